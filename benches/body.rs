@@ -5,9 +5,13 @@ extern crate test;
 
 use delay_timer::entity::SharedHeader;
 use delay_timer::prelude::*;
+use delay_timer::timer::executor_pool::ExecutorPool;
 use delay_timer::timer::timer_core::{Timer, TimerEvent};
 
+use std::sync::Arc;
+
 use futures::executor::block_on;
+use futures::future::join_all;
 use smol::channel::unbounded;
 use test::Bencher;
 
@@ -66,3 +70,33 @@ fn bench_try_wait(b: &mut Bencher) {
 fn bench_timestamp(b: &mut Bencher) {
     b.iter(|| timestamp());
 }
+
+// A batch of instances of a few tasks, each of them sums the large state its task captured.
+fn bench_executor_pool(b: &mut Bencher, dispatch: Dispatch) {
+    let executor_pool = ExecutorPool::new("bench", 4, dispatch);
+    let states: Vec<Arc<Vec<u64>>> = (0..8).map(|_| Arc::new(vec![1; 1 << 16])).collect();
+
+    b.iter(|| {
+        block_on(join_all((0..64u64).map(|i| {
+            let state = states[(i % 8) as usize].clone();
+            executor_pool.run_job(i % 8, move || async move {
+                test::black_box(state.iter().sum::<u64>());
+            })
+        })))
+    });
+}
+
+#[bench]
+fn bench_executor_pool_work_stealing(b: &mut Bencher) {
+    bench_executor_pool(b, Dispatch::WorkStealing);
+}
+
+#[bench]
+fn bench_executor_pool_round_robin(b: &mut Bencher) {
+    bench_executor_pool(b, Dispatch::RoundRobin);
+}
+
+#[bench]
+fn bench_executor_pool_sticky_by_task(b: &mut Bencher) {
+    bench_executor_pool(b, Dispatch::StickyByTask);
+}
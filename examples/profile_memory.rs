@@ -45,12 +45,7 @@ fn main() {
 
     sleep(Duration::from_secs(25));
 
-    for _ in 0..capacity {
-        if let Some(mut task_builder) = task_builder_vec.pop() {
-            task_builder.free();
-        }
-    }
-
+    // The cron-expressions are owned by the builders, they are released with them.
     drop(task_builder_vec);
 
     dbg!("after drop");
@@ -11,39 +11,63 @@
 
 use super::timer::{
     event_handle::{EventHandle, EventHandleBuilder},
-    task::{Task, TaskMark},
-    timer_core::{Timer, TimerEvent, DEFAULT_TIMER_SLOT_COUNT},
+    executor_pool::ExecutorPool,
+    job_queue::load_jobs,
+    memory::MemoryAttribution,
+    task::{SerialQueue, Task, TaskMark},
+    tenant::Tenants,
+    timer_core::{complete_fire_state, marked_fire_state, TimerEvent, DEFAULT_TIMER_SLOT_COUNT},
+    watchdog::{spawn_timer_core, SafeStructFailureFn, Watchdog},
     Slot,
 };
+use crate::planner::{decide, FireDecision};
 use crate::prelude::*;
-use crate::timer::runtime_trace::task_instance::task_instance_chain_pair;
+use crate::timer::delay::Delays;
+use crate::timer::diagnostics::{self, TaskProfile};
+use crate::timer::handoff::save_handoff;
+use crate::timer::reconcile::{ReconcileReport, RoutineRegistry, TaskConfig};
+use crate::timer::view::SchedulerView;
+use crate::timer::runtime_trace::child_processes::ChildProcesses;
+use crate::timer::runtime_trace::future_tracker::FutureTracker;
+use crate::timer::audit::{AuditOperation, AuditTrail, AuditedConfig};
+use crate::utils::secrets::EnvSecrets;
+use crate::timer::runtime_trace::task_instance::{
+    merged_instance_stream_pair, task_instance_chain_pair,
+};
 
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::atomic::{AtomicBool, AtomicU64};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread::Builder;
 use std::time::SystemTime;
 
+use event_listener::Event;
 use futures::executor::block_on;
+use once_cell::sync::OnceCell;
+use smol::channel::bounded;
 use snowflake::SnowflakeIdGenerator;
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::runtime::{Builder as TokioBuilder, Runtime};
 
 cfg_status_report!(
-    use crate::utils::status_report::StatusReporter;
+    use crate::utils::status_report::{write_history, EventLog, StatusReporter};
+    use std::io::{self, Write};
 );
 
 // Set it. Motivation to move forward.
 pub(crate) type SharedMotivation = Arc<AtomicBool>;
 // Global IdGenerator.
-pub(crate) type SharedIdGenerator = Arc<AsyncMutex<SnowflakeIdGenerator>>;
+pub(crate) type SharedIdGenerator = Arc<AsyncMutex<RecordIdGenerator>>;
 // Global Timestamp.
 pub(crate) type GlobalTime = Arc<AtomicU64>;
 // Shared task-wheel for operate.
 pub(crate) type SharedTaskWheel = Arc<DashMap<u64, Slot>>;
 // The slot currently used for storing global tasks.
 pub(crate) type SharedTaskFlagMap = Arc<DashMap<u64, TaskMark>>;
+// The serial queues by name.
+pub(crate) type SharedSerialQueues = Arc<DashMap<String, SerialQueue>>;
 
 /// Global sencond hand.
 #[derive(Debug, Clone, Default)]
@@ -64,6 +88,65 @@ impl SencondHand {
     }
 }
 
+// The layout of the snowflake ids, the milliseconds, the machine-id, the node-id and the sequence.
+const TIMESTAMP_SHIFT: u32 = 22;
+const MACHINE_ID_SHIFT: u32 = 17;
+const NODE_ID_SHIFT: u32 = 12;
+const SEQUENCE_MASK: i64 = (1 << NODE_ID_SHIFT) - 1;
+
+/// Generator of record ids, snowflake ids that never go backwards.
+///
+/// Every id is bigger than all ids generated before it,
+/// even if the system clock is adjusted backwards,
+/// so the record ids of a task are strictly increasing.
+#[derive(Debug)]
+pub(crate) struct RecordIdGenerator {
+    inner: SnowflakeIdGenerator,
+    last_id: i64,
+}
+
+impl RecordIdGenerator {
+    pub(crate) fn new(machine_id: i32, node_id: i32) -> Self {
+        RecordIdGenerator {
+            inner: SnowflakeIdGenerator::new(machine_id, node_id),
+            last_id: 0,
+        }
+    }
+
+    pub(crate) fn set_conf(&mut self, machine_id: i32, node_id: i32) {
+        self.inner.machine_id = machine_id;
+        self.inner.node_id = node_id;
+    }
+
+    pub(crate) fn generate(&mut self) -> i64 {
+        let id = self.inner.real_time_generate();
+        let id = if id > self.last_id {
+            id
+        } else {
+            self.next_after_last()
+        };
+        self.last_id = id;
+        id
+    }
+
+    // The id after the last one, next in the sequence of its millisecond,
+    // or first of the next millisecond once the sequence has run out,
+    // so the machine-id and node-id are never overwritten.
+    fn next_after_last(&self) -> i64 {
+        let worker = ((self.inner.machine_id as i64) << MACHINE_ID_SHIFT)
+            | ((self.inner.node_id as i64) << NODE_ID_SHIFT);
+        let millis = self.last_id >> TIMESTAMP_SHIFT;
+        let sequence = self.last_id & SEQUENCE_MASK;
+
+        let id = (millis << TIMESTAMP_SHIFT) | worker | (sequence + 1);
+        if sequence < SEQUENCE_MASK && id > self.last_id {
+            id
+        } else {
+            ((millis + 1) << TIMESTAMP_SHIFT) | worker
+        }
+    }
+}
+
 /// Builds DelayTimer with custom configuration values.
 ///
 /// Methods can be chained in order to set the configuration values. The
@@ -81,9 +164,52 @@ pub struct DelayTimerBuilder {
     /// RuntimeInstance (Tokio | Smol)
     pub(crate) runtime_instance: RuntimeInstance,
     timer_event_channel: Option<(AsyncSender<TimerEvent>, AsyncReceiver<TimerEvent>)>,
+    /// Called when the timer-core loop can't be kept alive any more.
+    on_irrecoverable_failure: Option<SafeStructFailureFn>,
+    /// Hooks around the scheduling decisions.
+    observer: Option<Arc<dyn SchedulerObserver>>,
+    /// Decides on each instance right before it's spawned.
+    spawn_hook: Option<Arc<dyn SpawnHook>>,
+    /// The storage of the job state of the tasks (optional).
+    state_store: Option<Arc<dyn StateStore>>,
+    /// The quotas of the tenants.
+    tenant_quotas: HashMap<String, TenantQuota>,
+    /// The number of threads of each named executor pool, and how they take the instances.
+    executor_pools: HashMap<String, (usize, Dispatch)>,
+    /// Resolves the secrets of the tasks (optional).
+    secrets_provider: Option<Arc<dyn SecretsProvider>>,
+    /// Capacity of the audit trail of the control-plane operations (optional).
+    audit_log_capacity: Option<usize>,
+    /// Persists the audit trail (optional).
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    /// `machine_id` and `node_id` of the record-id generator.
+    id_generator_conf: Option<(i32, i32)>,
+    /// Spread the tasks that fire at second `0` within the minute.
+    auto_splay_seconds: bool,
+    /// What to do with the fires missed while the wheel wasn't ticking.
+    misfire_policy: MisfirePolicy,
+    /// Which fires of a `CountDown` task count against it.
+    count_down_policy: CountDownPolicy,
+    /// The window the first fires are spread over after the timer starts (optional).
+    startup_stagger: Option<Duration>,
+    /// Start the instances by precise timers, rather than at the tick of their second.
+    high_precision: bool,
+    /// Attribute the approximate memory of the tasks to their tenants.
+    memory_attribution: bool,
     /// Whether or not to enable the status-report
     #[cfg(feature = "status-report")]
     enable_status_report: bool,
+    /// Capacity of the ring-buffer of recent public events.
+    #[cfg(feature = "status-report")]
+    event_log_capacity: Option<usize>,
+    #[cfg(feature = "status-report")]
+    event_log: Option<EventLog>,
+    /// Which of the routine events of the tasks are reported, unless they set their own.
+    #[cfg(feature = "status-report")]
+    event_sampling: EventSampling,
+    /// The level every public event is logged at (optional).
+    #[cfg(feature = "status-report")]
+    log_events: Option<LogLevel>,
 }
 
 /// DelayTimer is an abstraction layer that helps users solve execution cycle synchronous/asynchronous tasks.
@@ -92,8 +218,298 @@ pub struct DelayTimer {
     #[allow(dead_code)]
     shared_header: SharedHeader,
     timer_event_sender: TimerEventSender,
+    // The configs last applied by `reconcile`, to tell what changed,
+    // `None` for the tasks it removed that may still be in the flag-map.
+    reconciled_configs: Arc<Mutex<HashMap<u64, Option<TaskConfig>>>>,
+    // Who the control-plane operations are made by, for the audit trail.
+    actor: Option<Arc<str>>,
     #[cfg(feature = "status-report")]
     status_reporter: Option<StatusReporter>,
+    #[cfg(feature = "status-report")]
+    event_log: Option<EventLog>,
+}
+
+/// A task registered by `DelayTimer::register_task`, which doesn't run until it's activated.
+#[derive(Clone, Debug)]
+pub struct RegisteredTask {
+    task_id: u64,
+    timer_event_sender: TimerEventSender,
+}
+
+impl RegisteredTask {
+    /// Get the id of the task.
+    pub fn task_id(&self) -> u64 {
+        self.task_id
+    }
+
+    /// Activate the task, its schedule starts from now.
+    pub fn activate(&self) -> Result<(), TaskError> {
+        Ok(self
+            .timer_event_sender
+            .try_send(TimerEvent::ActivateTask(self.task_id))?)
+    }
+}
+
+// How long `DelayTimer::hand_off` waits for the timer-core loop to stop.
+const DEFAULT_HAND_OFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How long `DelayTimer::shutdown` waits for the teardown of the tasks.
+const DEFAULT_TEARDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The task-ids of a namespace carry its tag in the high bits.
+const SCOPED_TASK_ID_BITS: u32 = 48;
+const SCOPED_TASK_ID_MASK: u64 = (1 << SCOPED_TASK_ID_BITS) - 1;
+
+/// A view of a `DelayTimer` confined to the tasks of a namespace, made by `DelayTimer::scoped`.
+///
+/// The task-ids given to it are local to the namespace, they're prefixed internally
+/// so that they can't collide with the ones of the application or of other namespaces.
+/// Local task-ids must fit in 48 bits.
+#[derive(Clone, Debug)]
+pub struct ScopedDelayTimer {
+    delay_timer: DelayTimer,
+    namespace: Arc<str>,
+    prefix: u64,
+}
+
+impl ScopedDelayTimer {
+    fn new(delay_timer: DelayTimer, namespace: &str) -> Self {
+        // FNV-1a, so that the prefix of a namespace is stable across runs.
+        let hash = namespace
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+        let tag = match (hash ^ (hash >> 16) ^ (hash >> 32) ^ (hash >> 48)) & 0xffff {
+            // Tag `0` is the application's own task-ids.
+            0 => 1,
+            tag => tag,
+        };
+
+        ScopedDelayTimer {
+            delay_timer,
+            namespace: namespace.into(),
+            prefix: tag << SCOPED_TASK_ID_BITS,
+        }
+    }
+
+    /// Get the namespace.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Get the task-id that a local task-id has in the `DelayTimer`,
+    /// as it shows in status reports and in the context of the running task.
+    pub fn scoped_task_id(&self, task_id: u64) -> Result<u64, TaskError> {
+        if task_id & !SCOPED_TASK_ID_MASK != 0 {
+            return Err(TaskError::TaskIdOutOfScope(task_id));
+        }
+        Ok(self.prefix | task_id)
+    }
+
+    /// Add a task of the namespace.
+    pub fn add_task(&self, task: Task) -> Result<(), TaskError> {
+        self.delay_timer.add_task(self.scope_task(task)?)
+    }
+
+    /// Add a task of the namespace,
+    /// and return a handle that can constantly take out new instances of the task.
+    pub fn insert_task(&self, task: Task) -> Result<TaskInstancesChain, TaskError> {
+        self.delay_timer.insert_task(self.scope_task(task)?)
+    }
+
+    /// Update a task of the namespace.
+    pub fn update_task(&self, task: Task) -> Result<(), TaskError> {
+        self.delay_timer.update_task(self.scope_task(task)?)
+    }
+
+    /// Remove a task of the namespace.
+    pub fn remove_task(&self, task_id: u64) -> Result<(), TaskError> {
+        self.delay_timer.remove_task(self.scoped_task_id(task_id)?)
+    }
+
+    /// Advance a task of the namespace.
+    pub fn advance_task(&self, task_id: u64) -> Result<AdvanceOutcome, TaskError> {
+        self.delay_timer.advance_task(self.scoped_task_id(task_id)?)
+    }
+
+    /// Get the local ids of the tasks of the namespace.
+    pub fn task_ids(&self) -> Vec<u64> {
+        let mut task_ids: Vec<u64> = self
+            .delay_timer
+            .shared_header
+            .task_flag_map
+            .iter()
+            .map(|task_mark| *task_mark.key())
+            .filter(|task_id| task_id & !SCOPED_TASK_ID_MASK == self.prefix)
+            .map(|task_id| task_id & SCOPED_TASK_ID_MASK)
+            .collect();
+        task_ids.sort_unstable();
+        task_ids
+    }
+
+    fn scope_task(&self, mut task: Task) -> Result<Task, TaskError> {
+        task.task_id = self.scoped_task_id(task.task_id)?;
+        Ok(task)
+    }
+}
+
+/// A snapshot of the scheduler's health, suitable for readiness/liveness probes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedulerHealth {
+    /// How many seconds the time-wheel lags behind the system clock.
+    pub wheel_lag: u64,
+    /// Number of events waiting in the internal event-channel.
+    pub event_channel_occupancy: usize,
+    /// Number of task instances currently running.
+    pub running_instances: u64,
+    /// Number of tasks registered in the timer.
+    pub tasks_registered: usize,
+    /// Timestamp (in seconds) of the last tick of the time-wheel.
+    pub last_tick_timestamp: u64,
+    /// Whether the timer-core loop is still running.
+    pub timer_core_alive: bool,
+    /// How many times the clock was found slightly behind the wheel and compensated,
+    /// rather than a tick going back in time or a fire being scheduled in the past,
+    /// e.g. over a leap second or with a coarse clock.
+    pub small_drift_compensated: u64,
+    /// How many times the timer-core loop woke up, it sleeps until the next fire is due
+    /// rather than ticking every second while no fire is, a minute at most.
+    pub wakeups: u64,
+    /// The approximate memory the tasks hold by tenant, the largest first,
+    /// empty unless `DelayTimerBuilder::enable_memory_attribution`.
+    pub memory_attribution: Vec<GroupMemory>,
+}
+
+impl SchedulerHealth {
+    pub(crate) fn new(shared_header: &SharedHeader, event_channel_occupancy: usize) -> Self {
+        let last_tick_timestamp = shared_header.global_time.load(Ordering::Acquire);
+        // The wheel of a sleeping timer-core loop isn't lagging until it's due to wake up.
+        let idle_until = shared_header.idle_until.load(Ordering::Acquire);
+        let running_instances = shared_header
+            .task_flag_map
+            .iter()
+            .map(|task_mark| task_mark.value().get_parallel_runnable_num())
+            .sum();
+
+        SchedulerHealth {
+            wheel_lag: timestamp().saturating_sub(last_tick_timestamp.max(idle_until)),
+            event_channel_occupancy,
+            running_instances,
+            tasks_registered: shared_header.task_flag_map.len(),
+            last_tick_timestamp,
+            timer_core_alive: shared_header.timer_core_alive.load(Ordering::Acquire),
+            small_drift_compensated: shared_header
+                .small_drift_compensated
+                .load(Ordering::Relaxed),
+            wakeups: shared_header.wakeups.load(Ordering::Relaxed),
+            memory_attribution: shared_header
+                .memory_attribution
+                .as_ref()
+                .map(|memory_attribution| memory_attribution.by_group())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// What the instances of a task did, see `DelayTimer::task_stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of the instances that ran to completion.
+    pub completed_instances: u64,
+    /// Number of them that reported they found nothing to do (`instance::COMPLETED_NOOP`).
+    pub noop_instances: u64,
+    /// The 95th percentile (seconds) of the durations of the recent instances,
+    /// once enough of them finished.
+    pub p95_duration: Option<u64>,
+}
+
+impl TaskStats {
+    pub(crate) fn new(task_mark: &TaskMark) -> Self {
+        let (completed_instances, noop_instances) = task_mark.get_completed_runs();
+
+        TaskStats {
+            completed_instances,
+            noop_instances,
+            p95_duration: task_mark.get_p95_duration(),
+        }
+    }
+}
+
+// Shuts the timer of a scope down when it's left, even by a panic.
+struct ScopeGuard(Option<DelayTimer>);
+
+impl ScopeGuard {
+    fn delay_timer(&self) -> &DelayTimer {
+        self.0.as_ref().expect("The timer of the scope is shut down.")
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        if let Some(delay_timer) = self.0.take() {
+            if let Err(e) = delay_timer.shutdown() {
+                error!("`scope`: {}", e);
+            }
+        }
+    }
+}
+
+/// What `DelayTimer::shutdown` stopped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Number of running task instances that were aborted.
+    pub aborted_instances: usize,
+    /// Number of the scheduler's background futures that were aborted
+    /// (resource recycling, readiness checks, delayed removals).
+    pub aborted_futures: usize,
+    /// Number of the tasks whose `teardown` was awaited.
+    pub torn_down_tasks: usize,
+    /// The tasks whose `teardown` didn't finish within `ShutdownOptions::teardown_timeout`.
+    pub unfinished_teardowns: Vec<u64>,
+    /// The pids of the processes the running process tasks had spawned,
+    /// killed or left running as `ShutdownOptions::child_processes` says.
+    pub child_processes: Vec<u32>,
+}
+
+/// How `DelayTimer::shutdown_with` stops the timer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownOptions {
+    child_processes: ChildProcessPolicy,
+    teardown_timeout: Option<Duration>,
+}
+
+/// What a shutdown does with the processes spawned by the running process tasks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChildProcessPolicy {
+    /// Kill them.
+    Kill,
+    /// Leave them running, their pids are reported.
+    #[default]
+    Detach,
+}
+
+impl ShutdownOptions {
+    /// Set what is done with the processes spawned by the running process tasks (optional),
+    /// `ChildProcessPolicy::Detach` by default.
+    pub fn child_processes(mut self, child_processes: ChildProcessPolicy) -> Self {
+        self.child_processes = child_processes;
+        self
+    }
+
+    /// Set how long the `teardown` of the tasks are awaited (optional), 5 seconds by default.
+    pub fn teardown_timeout(mut self, teardown_timeout: Duration) -> Self {
+        self.teardown_timeout = Some(teardown_timeout);
+        self
+    }
+
+    pub(crate) fn get_child_processes(&self) -> ChildProcessPolicy {
+        self.child_processes
+    }
+
+    pub(crate) fn get_teardown_timeout(&self) -> Duration {
+        self.teardown_timeout.unwrap_or(DEFAULT_TEARDOWN_TIMEOUT)
+    }
 }
 
 /// SharedHeader Store the core context of the runtime.
@@ -113,6 +529,59 @@ pub struct SharedHeader {
     pub(crate) runtime_instance: RuntimeInstance,
     // Unique id generator.
     pub(crate) id_generator: SharedIdGenerator,
+    // Whether the timer-core loop is running.
+    pub(crate) timer_core_alive: Arc<AtomicBool>,
+    // How many times a small drift of the clock was compensated.
+    pub(crate) small_drift_compensated: Arc<AtomicU64>,
+    // How many times the timer-core loop woke up.
+    pub(crate) wakeups: Arc<AtomicU64>,
+    // The time the sleeping timer-core loop wakes up at, `0` while it ticks.
+    pub(crate) idle_until: Arc<AtomicU64>,
+    // Wakes the sleeping timer-core loop up.
+    pub(crate) rearm: Arc<Event>,
+    // The delays woken up by the ticks of the wheel, see `DelayTimer::delay_for`.
+    pub(crate) delays: Arc<Delays>,
+    // Hooks around the scheduling decisions.
+    pub(crate) observer: Option<Arc<dyn SchedulerObserver>>,
+    // Decides on each instance right before it's spawned.
+    pub(crate) spawn_hook: Option<Arc<dyn SpawnHook>>,
+    // The storage of the job state of the tasks.
+    pub(crate) state_store: Arc<dyn StateStore>,
+    // The tasks of the tenants, and their quotas.
+    pub(crate) tenants: Arc<Tenants>,
+    // The named executor pools the tasks may be assigned to.
+    pub(crate) executor_pools: Arc<HashMap<String, ExecutorPool>>,
+    // Resolves the secrets of the tasks.
+    pub(crate) secrets_provider: Arc<dyn SecretsProvider>,
+    // The trail of the control-plane operations, if it's kept or persisted.
+    pub(crate) audit_trail: Option<AuditTrail>,
+    // Spread the tasks that fire at second `0` within the minute.
+    pub(crate) auto_splay_seconds: bool,
+    // What to do with the fires missed while the wheel wasn't ticking.
+    pub(crate) misfire_policy: MisfirePolicy,
+    // Which fires of a `CountDown` task count against it.
+    pub(crate) count_down_policy: CountDownPolicy,
+    // Which of the routine events of the tasks are reported, unless they set their own.
+    #[cfg(feature = "status-report")]
+    pub(crate) event_sampling: EventSampling,
+    // The time the timer started and the seconds the first fires are spread over (optional).
+    pub(crate) startup_stagger: Option<(u64, u64)>,
+    // Start the instances by precise timers, rather than at the tick of their second.
+    pub(crate) high_precision: bool,
+    // The approximate memory of the tasks, if it's attributed.
+    pub(crate) memory_attribution: Option<Arc<MemoryAttribution>>,
+    // The owners of the tasks inserted with an `OwnerToken`.
+    pub(crate) task_owners: Arc<DashMap<u64, OwnerToken>>,
+    // The tasks paused by `remove_task_delayed` and their deadlines, they keep their task-marks.
+    pub(crate) paused_tasks: Arc<DashMap<u64, u64>>,
+    // The serial queues the tasks run in.
+    pub(crate) serial_queues: SharedSerialQueues,
+    // The background futures spawned on the async-runtime.
+    pub(crate) future_tracker: FutureTracker,
+    // The processes spawned by the running instances of the process tasks.
+    pub(crate) child_processes: ChildProcesses,
+    // The read-only view of the timer, once one was taken.
+    pub(crate) scheduler_view: Arc<OnceCell<SchedulerView>>,
 }
 
 impl fmt::Debug for SharedHeader {
@@ -123,6 +592,7 @@ impl fmt::Debug for SharedHeader {
             .field(&self.shared_motivation)
             .field(&self.runtime_instance)
             .field(&self.id_generator)
+            .field(&self.timer_core_alive)
             .finish()
     }
 }
@@ -187,6 +657,22 @@ impl RuntimeInstance {
             .build()
             .ok()
     }
+
+    // Run `future` to its end on the calling thread, within the runtime.
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        match (self.kind, self.inner.as_ref()) {
+            (RuntimeKind::Tokio, Some(tokio_runtime)) => tokio_runtime.block_on(future),
+            _ => smol::block_on(future),
+        }
+    }
+}
+
+impl SharedHeader {
+    // The sleeping timer-core loop looks at the wheel again,
+    // for a task put in it, or for it to stop.
+    pub(crate) fn rearm_timer_core(&self) {
+        self.rearm.notify(usize::MAX);
+    }
 }
 
 impl Default for SharedHeader {
@@ -197,7 +683,32 @@ impl Default for SharedHeader {
         let global_time = Arc::new(AtomicU64::new(timestamp()));
         let shared_motivation = Arc::new(AtomicBool::new(true));
         let runtime_instance = RuntimeInstance::default();
-        let id_generator = Arc::new(AsyncMutex::new(SnowflakeIdGenerator::new(1, 1)));
+        let id_generator = Arc::new(AsyncMutex::new(RecordIdGenerator::new(1, 1)));
+        let timer_core_alive = Arc::new(AtomicBool::new(false));
+        let small_drift_compensated = Arc::new(AtomicU64::new(0));
+        let wakeups = Arc::new(AtomicU64::new(0));
+        let idle_until = Arc::new(AtomicU64::new(0));
+        let rearm = Arc::new(Event::new());
+        let delays = Arc::new(Delays::new(rearm.clone()));
+        let observer = None;
+        let spawn_hook = None;
+        let state_store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::default());
+        let tenants = Arc::new(Tenants::default());
+        let executor_pools = Arc::new(HashMap::new());
+        let secrets_provider: Arc<dyn SecretsProvider> = Arc::new(EnvSecrets);
+        let audit_trail = None;
+        let auto_splay_seconds = false;
+        let misfire_policy = MisfirePolicy::default();
+        let count_down_policy = CountDownPolicy::default();
+        let startup_stagger = None;
+        let high_precision = false;
+        let memory_attribution = None;
+        let task_owners = Arc::new(DashMap::new());
+        let paused_tasks = Arc::new(DashMap::new());
+        let serial_queues = Arc::new(DashMap::new());
+        let future_tracker = FutureTracker::default();
+        let child_processes = ChildProcesses::default();
+        let scheduler_view = Arc::new(OnceCell::new());
 
         SharedHeader {
             wheel_queue,
@@ -207,6 +718,33 @@ impl Default for SharedHeader {
             shared_motivation,
             runtime_instance,
             id_generator,
+            timer_core_alive,
+            small_drift_compensated,
+            wakeups,
+            idle_until,
+            rearm,
+            delays,
+            observer,
+            spawn_hook,
+            state_store,
+            tenants,
+            executor_pools,
+            secrets_provider,
+            audit_trail,
+            auto_splay_seconds,
+            misfire_policy,
+            count_down_policy,
+            #[cfg(feature = "status-report")]
+            event_sampling: EventSampling::default(),
+            startup_stagger,
+            high_precision,
+            memory_attribution,
+            task_owners,
+            paused_tasks,
+            serial_queues,
+            future_tracker,
+            child_processes,
+            scheduler_view,
         }
     }
 }
@@ -223,6 +761,37 @@ impl DelayTimerBuilder {
         self.init_delay_timer()
     }
 
+    /// Build DelayTimer and run `f` with it, the timer is shut down once `f` returns or panics.
+    ///
+    /// None of the tasks added in the scope fires afterwards,
+    /// and their running instances are aborted, see `DelayTimer::shutdown`.
+    pub fn scope<R>(self, f: impl FnOnce(&DelayTimer) -> R) -> R {
+        let scope_guard = ScopeGuard(Some(self.build()));
+
+        f(scope_guard.delay_timer())
+    }
+
+    /// The async variant of `scope`, `f` gets a handle of the timer
+    /// and the timer is shut down once the future it returns is over.
+    ///
+    /// The shutdown is awaited rather than blocked on,
+    /// only a panic or the drop of the scope shuts the timer down synchronously.
+    pub async fn scope_async<F, Fut, R>(self, f: F) -> R
+    where
+        F: FnOnce(DelayTimer) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let mut scope_guard = ScopeGuard(Some(self.build()));
+        let output = f(scope_guard.delay_timer().clone()).await;
+
+        if let Some(delay_timer) = scope_guard.0.take() {
+            if let Err(e) = delay_timer.shutdown_async(ShutdownOptions::default()).await {
+                error!("`scope_async`: {}", e);
+            }
+        }
+        output
+    }
+
     // Start the DelayTimer.
     fn lauch(&mut self, shared_header: SharedHeader) -> AnyResult<()> {
         let mut event_handle_builder = EventHandleBuilder::default();
@@ -237,44 +806,39 @@ impl DelayTimerBuilder {
             // TODO: init static reporter.
         }
 
+        #[cfg(feature = "status-report")]
+        if let Some(event_log) = self.get_event_log() {
+            event_handle_builder.event_log(event_log);
+        }
+
+        #[cfg(feature = "status-report")]
+        if let Some(level) = self.log_events {
+            event_handle_builder.log_events(level);
+        }
+
         let event_handle = event_handle_builder
             .build()
             .ok_or_else(|| anyhow!("Missing base component, can't initialize."))?;
 
-        match self.runtime_instance.kind {
-            RuntimeKind::Smol => self.assign_task(event_handle, shared_header),
+        self.assign_task(event_handle, shared_header.clone());
 
-            RuntimeKind::Tokio => self.assign_task_by_tokio(event_handle, shared_header),
-        };
+        self.run_watchdog(shared_header);
 
         Ok(())
     }
 
     fn assign_task(&mut self, event_handle: EventHandle, shared_header: SharedHeader) {
-        let timer = Timer::new(self.get_timer_event_sender(), shared_header);
-
-        self.run_async_schedule(timer);
-
-        self.run_event_handle(event_handle);
-    }
-
-    fn run_async_schedule(&self, mut timer: Timer) {
-        Builder::new()
-            .name("async_schedule".into())
-            .spawn(move || {
-                smol::block_on(async {
-                    debug!(" `async_schedule` start.");
-                    timer.async_schedule().await;
-                })
-            })
+        spawn_timer_core(self.get_timer_event_sender(), shared_header.clone())
             .expect("async_schedule can't start.");
+
+        self.run_event_handle(event_handle, shared_header);
     }
 
-    fn run_event_handle(&self, mut event_handle: EventHandle) {
+    fn run_event_handle(&self, mut event_handle: EventHandle, shared_header: SharedHeader) {
         Builder::new()
             .name("event_handle".into())
             .spawn(move || {
-                block_on(async {
+                shared_header.runtime_instance.block_on(async {
                     debug!(" `event_handle` start.");
                     event_handle.lauch().await;
                 })
@@ -282,6 +846,19 @@ impl DelayTimerBuilder {
             .expect("event_handle can't start.");
     }
 
+    fn run_watchdog(&mut self, shared_header: SharedHeader) {
+        let watchdog = Watchdog::new(
+            shared_header,
+            self.get_timer_event_sender(),
+            self.on_irrecoverable_failure.clone(),
+        );
+
+        Builder::new()
+            .name("watchdog".into())
+            .spawn(move || watchdog.run())
+            .expect("watchdog can't start.");
+    }
+
     fn init_delay_timer(&mut self) -> DelayTimer {
         if self.runtime_instance.kind == RuntimeKind::Tokio && self.runtime_instance.inner.is_none()
         {
@@ -290,9 +867,47 @@ impl DelayTimerBuilder {
 
         let shared_header = SharedHeader {
             runtime_instance: self.runtime_instance.clone(),
+            observer: self.observer.clone(),
+            spawn_hook: self.spawn_hook.clone(),
+            state_store: self
+                .state_store
+                .clone()
+                .unwrap_or_else(|| Arc::new(MemoryStateStore::default())),
+            tenants: Arc::new(Tenants::new(self.tenant_quotas.clone())),
+            executor_pools: Arc::new(
+                self.executor_pools
+                    .iter()
+                    .map(|(name, &(threads, dispatch))| {
+                        (name.clone(), ExecutorPool::new(name, threads, dispatch))
+                    })
+                    .collect(),
+            ),
+            secrets_provider: self
+                .secrets_provider
+                .clone()
+                .unwrap_or_else(|| Arc::new(EnvSecrets)),
+            audit_trail: (self.audit_log_capacity.is_some() || self.audit_sink.is_some()).then(
+                || AuditTrail::new(self.audit_log_capacity.unwrap_or(0), self.audit_sink.clone()),
+            ),
+            auto_splay_seconds: self.auto_splay_seconds,
+            misfire_policy: self.misfire_policy,
+            count_down_policy: self.count_down_policy,
+            #[cfg(feature = "status-report")]
+            event_sampling: self.event_sampling,
+            startup_stagger: self
+                .startup_stagger
+                .map(|window| (timestamp(), window.as_secs())),
+            high_precision: self.high_precision,
+            memory_attribution: self
+                .memory_attribution
+                .then(|| Arc::new(MemoryAttribution::default())),
             ..Default::default()
         };
 
+        if let Some((machine_id, node_id)) = self.id_generator_conf {
+            block_on(shared_header.id_generator.lock()).set_conf(machine_id, node_id);
+        }
+
         let timer_event_sender = self.get_timer_event_sender();
 
         self.lauch(shared_header.clone())
@@ -304,12 +919,18 @@ impl DelayTimerBuilder {
         if self.enable_status_report {
             status_reporter = Some(StatusReporter::new(self.get_status_report_receiver()));
         }
+        #[cfg(feature = "status-report")]
+        let event_log = self.get_event_log();
 
         DelayTimer {
             shared_header,
             timer_event_sender,
+            reconciled_configs: Arc::new(Mutex::new(HashMap::new())),
+            actor: None,
             #[cfg(feature = "status-report")]
             status_reporter,
+            #[cfg(feature = "status-report")]
+            event_log,
         }
     }
 
@@ -336,42 +957,306 @@ impl DelayTimer {
 
     /// Add a task in timer_core by event-channel.
     pub fn add_task(&self, task: Task) -> Result<(), TaskError> {
-        self.seed_timer_event(TimerEvent::AddTask(Box::new(task)))
+        self.check_owner(task.task_id, None)?;
+        self.admit_tenant(&task)?;
+        let (task_id, config) = (task.task_id, self.audited_config(&task));
+        let seed_result = self.seed_admitted_event(task_id, TimerEvent::AddTask(Box::new(task)));
+        self.audit(seed_result, AuditOperation::Add, task_id, config)
     }
 
     /// Add a task in timer_core by event-channel.
     /// But it will return a handle that can constantly take out new instances of the task.
     pub fn insert_task(&self, task: Task) -> Result<TaskInstancesChain, TaskError> {
+        self.check_owner(task.task_id, None)?;
+        self.admit_tenant(&task)?;
+        let (task_id, config) = (task.task_id, self.audited_config(&task));
+        let (mut task_instances_chain, task_instances_chain_maintainer) =
+            task_instance_chain_pair(task_id);
+        task_instances_chain.timer_event_sender = Some(self.timer_event_sender.clone());
+
+        let seed_result = self.seed_admitted_event(
+            task_id,
+            TimerEvent::InsertTask(Box::new(task), task_instances_chain_maintainer),
+        );
+        self.audit(seed_result, AuditOperation::Add, task_id, config)?;
+        Ok(task_instances_chain)
+    }
+
+    /// Attach a new chain of task run instances to a task that has been added.
+    ///
+    /// Use it to re-attach after the previous chain was closed or dropped,
+    /// a chain that is still attached will be replaced and then report `Expired`.
+    pub fn instance_chain(&self, task_id: u64) -> Result<TaskInstancesChain, TaskError> {
+        let (mut task_instances_chain, task_instances_chain_maintainer) =
+            task_instance_chain_pair(task_id);
+        task_instances_chain.timer_event_sender = Some(self.timer_event_sender.clone());
+
+        self.seed_timer_event(TimerEvent::AttachInstancesChain(
+            task_id,
+            task_instances_chain_maintainer,
+        ))?;
+        Ok(task_instances_chain)
+    }
+
+    /// Subscribe to the running instances of a task that has been added,
+    /// whether it was added by `add_task` or `insert_task`.
+    ///
+    /// Unlike `instance_chain`, the chains that are already attached are not affected,
+    /// every subscriber gets each new instance.
+    pub fn subscribe_instances(&self, task_id: u64) -> Result<TaskInstancesChain, TaskError> {
         let (mut task_instances_chain, task_instances_chain_maintainer) =
-            task_instance_chain_pair();
+            task_instance_chain_pair(task_id);
         task_instances_chain.timer_event_sender = Some(self.timer_event_sender.clone());
 
-        self.seed_timer_event(TimerEvent::InsertTask(
-            Box::new(task),
+        self.seed_timer_event(TimerEvent::SubscribeInstances(
+            task_id,
             task_instances_chain_maintainer,
         ))?;
         Ok(task_instances_chain)
     }
 
+    /// Subscribe to the running instances of several tasks at once.
+    ///
+    /// The returned stream yields `(task_id, TaskInstance)` in arrival order,
+    /// so a single supervisor loop can watch a whole task group.
+    ///
+    /// A task listed twice is subscribed to once. It waits for the timer to subscribe,
+    /// and if one of the tasks isn't in the timer, none is subscribed to.
+    pub fn subscribe_instances_multi(
+        &self,
+        task_ids: &[u64],
+    ) -> Result<MergedInstanceStream, TaskError> {
+        let mut seen_task_ids = HashSet::new();
+        let task_ids: Vec<u64> = task_ids
+            .iter()
+            .copied()
+            .filter(|task_id| seen_task_ids.insert(*task_id))
+            .collect();
+
+        let (mut merged_instance_stream, task_instances_chain_maintainers) =
+            merged_instance_stream_pair(&task_ids);
+        merged_instance_stream.timer_event_sender = Some(self.timer_event_sender.clone());
+
+        block_on(async {
+            let (report_sender, report_receiver) = bounded(1);
+            self.seed_timer_event(TimerEvent::SubscribeInstancesMulti(
+                task_ids
+                    .into_iter()
+                    .zip(task_instances_chain_maintainers)
+                    .collect(),
+                report_sender,
+            ))?;
+
+            report_receiver
+                .recv()
+                .await?
+                .map_err(TaskError::UnknownTask)
+        })?;
+
+        Ok(merged_instance_stream)
+    }
+
+    /// Register a task in timer_core by event-channel, it doesn't run until it's activated.
+    ///
+    /// Register the whole task set during startup,
+    /// and flip them on by `RegisteredTask::activate` once the application is ready.
+    pub fn register_task(&self, task: Task) -> Result<RegisteredTask, TaskError> {
+        self.check_owner(task.task_id, None)?;
+        let (task_id, config) = (task.task_id, self.audited_config(&task));
+        self.admit_tenant(&task)?;
+        let seed_result =
+            self.seed_admitted_event(task_id, TimerEvent::RegisterTask(Box::new(task)));
+        self.audit(seed_result, AuditOperation::Register, task_id, config)?;
+
+        Ok(RegisteredTask {
+            task_id,
+            timer_event_sender: self.timer_event_sender.clone(),
+        })
+    }
+
     /// Update a task in timer_core by event-channel.
     pub fn update_task(&self, task: Task) -> Result<(), TaskError> {
-        self.seed_timer_event(TimerEvent::UpdateTask(Box::new(task)))
+        self.check_owner(task.task_id, None)?;
+        self.admit_tenant(&task)?;
+        let (task_id, config) = (task.task_id, self.audited_config(&task));
+        let seed_result = self.seed_admitted_event(task_id, TimerEvent::UpdateTask(Box::new(task)));
+        self.audit(seed_result, AuditOperation::Update, task_id, config)
+    }
+
+    /// Pause a task in timer_core by event-channel, and remove it once `grace` is over,
+    /// unless `undo_remove` is called before.
+    pub fn remove_task_delayed(&self, task_id: u64, grace: Duration) -> Result<(), TaskError> {
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::RemoveTaskDelayed(task_id, grace));
+        self.audit(seed_result, AuditOperation::RemoveDelayed(grace), task_id, None)
+    }
+
+    /// Resume a task paused by `remove_task_delayed`, within its grace period.
+    ///
+    /// The fires that were due during the pause are skipped.
+    pub fn undo_remove(&self, task_id: u64) -> Result<(), TaskError> {
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::UndoRemove(task_id));
+        self.audit(seed_result, AuditOperation::UndoRemove, task_id, None)
+    }
+
+    /// Resume a task paused by its failure policy, see `TaskBuilder::set_failure_policy`.
+    ///
+    /// The fires that were due during the pause are skipped.
+    pub fn resume_task(&self, task_id: u64) -> Result<(), TaskError> {
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::UndoRemove(task_id));
+        self.audit(seed_result, AuditOperation::Resume, task_id, None)
     }
 
     /// Remove a task in timer_core by event-channel.
     pub fn remove_task(&self, task_id: u64) -> Result<(), TaskError> {
-        self.seed_timer_event(TimerEvent::RemoveTask(task_id))
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::RemoveTask(task_id));
+        self.audit(seed_result, AuditOperation::Remove, task_id, None)
     }
 
     /// Advance a task in timer_core by event-channel.
-    pub fn advance_task(&self, task_id: u64) -> Result<(), TaskError> {
-        self.seed_timer_event(TimerEvent::AdvanceTask(task_id))
+    ///
+    /// The pending fire runs right away and counts against the remaining runs,
+    /// see `advance_task_with_options` for a free extra run.
+    ///
+    /// The `AdvanceOutcome` tells whether the run starts, is queued or is refused,
+    /// a refused run leaves the pending fire as it is.
+    pub fn advance_task(&self, task_id: u64) -> Result<AdvanceOutcome, TaskError> {
+        self.advance_task_with_options(task_id, AdvanceOptions::default())
+    }
+
+    /// Advance a task in timer_core by event-channel, with `AdvanceOptions`.
+    pub fn advance_task_with_options(
+        &self,
+        task_id: u64,
+        advance_options: AdvanceOptions,
+    ) -> Result<AdvanceOutcome, TaskError> {
+        self.check_owner(task_id, None)?;
+        self.seed_advance(task_id, advance_options)
+    }
+
+    // The advance of a task whose owner has been checked.
+    fn seed_advance(
+        &self,
+        task_id: u64,
+        advance_options: AdvanceOptions,
+    ) -> Result<AdvanceOutcome, TaskError> {
+        let advance_outcome = self.foresee_advance(task_id, advance_options.is_jump_queue());
+        if let AdvanceOutcome::RejectedByPolicy(_) = advance_outcome {
+            return Ok(advance_outcome);
+        }
+
+        let seed_result = self.seed_timer_event(TimerEvent::AdvanceTask(task_id, advance_options));
+        self.audit(seed_result, AuditOperation::Advance, task_id, None)
+            .map(|_| advance_outcome)
+    }
+
+    /// Run a task now with `payload`, read back by `TaskContext::payload`,
+    /// so that one task serves parameterized runs, e.g. "re-sync customer X".
+    ///
+    /// It's a free extra run, the schedule of the task goes on as it is,
+    /// see `AdvanceOptions::payload` to choose otherwise.
+    pub fn run_task_now_with_payload(
+        &self,
+        task_id: u64,
+        payload: impl Into<Vec<u8>>,
+    ) -> Result<AdvanceOutcome, TaskError> {
+        self.advance_task_with_options(
+            task_id,
+            AdvanceOptions::default()
+                .consume_count(false)
+                .payload(payload),
+        )
+    }
+
+    /// Enqueue a run of a task with `payload` for `run_at`,
+    /// a delayed job alongside the schedule of the task, see `run_task_now_with_payload`.
+    ///
+    /// The jobs are kept by the `StateStore` of the timer, a persistent store
+    /// keeps them across restarts: they're picked up again once the task is added back.
+    /// A job due while the timer was down runs right away.
+    pub fn enqueue(
+        &self,
+        task_id: u64,
+        payload: impl Into<Vec<u8>>,
+        run_at: DateTime<Utc>,
+    ) -> Result<(), TaskError> {
+        self.check_owner(task_id, None)?;
+        let job = EnqueuedJob {
+            run_at: run_at.timestamp().max(0) as u64,
+            payload: payload.into(),
+        };
+        self.seed_timer_event(TimerEvent::EnqueueJob(task_id, job))
+    }
+
+    /// Get the runs enqueued for a task that aren't due yet, the earliest first.
+    pub fn enqueued_jobs(&self, task_id: u64) -> AnyResult<Vec<EnqueuedJob>> {
+        load_jobs(self.shared_header.state_store.as_ref(), task_id)
+    }
+
+    /// Advance a task in timer_core by event-channel,
+    /// the span of that run is linked to the remote `parent_context`.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `otel` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "otel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+    pub fn advance_task_with(
+        &self,
+        task_id: u64,
+        parent_context: opentelemetry::Context,
+    ) -> Result<AdvanceOutcome, TaskError> {
+        self.advance_task_with_options(
+            task_id,
+            AdvanceOptions::default().parent_context(parent_context),
+        )
     }
 
     /// Cancel a task in timer_core by event-channel.
     /// `Cancel` is for instances derived from the task running up.
     pub fn cancel_task(&self, task_id: u64, record_id: i64) -> Result<(), TaskError> {
-        self.seed_timer_event(TimerEvent::CancelTask(task_id, record_id))
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::CancelTask(
+            task_id,
+            record_id,
+            TerminationReason::Cancelled(CancelOriginator::DelayTimer, None),
+        ));
+        self.audit(seed_result, AuditOperation::Cancel(record_id), task_id, None)
+    }
+
+    /// Cancel a task in timer_core by event-channel, with a reason
+    /// that can be read back from `TaskInstance::termination_reason`.
+    pub fn cancel_task_with_reason(
+        &self,
+        task_id: u64,
+        record_id: i64,
+        reason: impl Into<String>,
+    ) -> Result<(), TaskError> {
+        self.check_owner(task_id, None)?;
+        let seed_result = self.seed_timer_event(TimerEvent::CancelTask(
+            task_id,
+            record_id,
+            TerminationReason::Cancelled(CancelOriginator::DelayTimer, Some(reason.into())),
+        ));
+        self.audit(seed_result, AuditOperation::Cancel(record_id), task_id, None)
+    }
+
+    /// A future that completes once `duration` is over, woken up by the wheel of the timer
+    /// rather than an OS timer of its own, for the applications with thousands of timeouts.
+    ///
+    /// The wheel ticks by the second, so the delay is rounded up to the next tick.
+    /// It completes only while the timer is running.
+    pub fn delay_for(&self, duration: Duration) -> DelayFuture {
+        Delays::delay_for(&self.shared_header.delays, duration)
+    }
+
+    // The delays of the timer, for the one behind `delay_for`.
+    pub(crate) fn delays(&self) -> Arc<Delays> {
+        self.shared_header.delays.clone()
     }
 
     /// Stop DelayTimer, running tasks are not affected.
@@ -379,68 +1264,603 @@ impl DelayTimer {
         self.seed_timer_event(TimerEvent::StopTimer)
     }
 
+    /// Stop DelayTimer, and abort the futures it spawned on the async-runtime:
+    /// the running instances of the tasks and its own background futures.
+    ///
+    /// Only the futures of the scheduler are aborted, the runtime is left running,
+    /// so that a runtime shared by `tokio_runtime_shared_by_custom` keeps running
+    /// the futures of the application. The aborted instances are cancelled with
+    /// the reason `shutdown`.
+    ///
+    /// The processes spawned by the running process tasks are left running,
+    /// see `shutdown_with` to kill them.
+    pub fn shutdown(&self) -> Result<ShutdownReport, TaskError> {
+        self.shutdown_with(ShutdownOptions::default())
+    }
+
+    /// `shutdown` as `shutdown_options` says.
+    pub fn shutdown_with(
+        &self,
+        shutdown_options: ShutdownOptions,
+    ) -> Result<ShutdownReport, TaskError> {
+        block_on(self.shutdown_async(shutdown_options))
+    }
+
+    // `shutdown` without blocking the calling thread, for the async scopes.
+    async fn shutdown_async(
+        &self,
+        shutdown_options: ShutdownOptions,
+    ) -> Result<ShutdownReport, TaskError> {
+        let (report_sender, report_receiver) = bounded(1);
+        self.seed_timer_event(TimerEvent::Shutdown(shutdown_options, report_sender))?;
+
+        Ok(report_receiver.recv().await?)
+    }
+
+    /// Hand the tasks off to the instance of the scheduler replacing this one,
+    /// e.g. on a deploy of a daemon, through the `StateStore` they share.
+    ///
+    /// The timer stops firing, the running instances keep running to completion,
+    /// and the next fire of each task waiting in the wheel is saved, for `take_over`
+    /// to go on from there: no fire is missed or run by both instances.
+    /// It blocks until the timer-core loop has stopped, a tick at most,
+    /// see `hand_off_with_timeout` to choose how long to wait.
+    pub fn hand_off(&self) -> AnyResult<HandoffSnapshot> {
+        self.hand_off_with_timeout(DEFAULT_HAND_OFF_TIMEOUT)
+    }
+
+    /// `hand_off`, failing with `SchedulerError::StopTimeOut` if the timer-core loop
+    /// hasn't stopped within `timeout`, e.g. while it's held up by a full event channel.
+    /// Nothing is saved then, the loop stops on its own later on.
+    pub fn hand_off_with_timeout(&self, timeout: Duration) -> AnyResult<HandoffSnapshot> {
+        self.seed_timer_event(TimerEvent::StopTimer)?;
+        if !block_on(self.timer_core_stopped(timeout)) {
+            return Err(SchedulerError::StopTimeOut(timeout).into());
+        }
+
+        // The task-marks are read before the wheel, so that no task-mark is locked while a slot is.
+        let slot_marks: Vec<(u64, u64)> = self
+            .shared_header
+            .task_flag_map
+            .iter()
+            .map(|task_mark| (*task_mark.key(), task_mark.value().get_slot_mark()))
+            .collect();
+        let mut next_fires: Vec<(u64, u64)> = slot_marks
+            .into_iter()
+            .filter_map(|(task_id, slot_mark)| {
+                let slot = self.shared_header.wheel_queue.get(&slot_mark)?;
+                let next_fire = slot.value().get_task(task_id)?.get_scheduled_time()?;
+                Some((task_id, next_fire))
+            })
+            .collect();
+        next_fires.sort_unstable();
+
+        for &(task_id, next_fire) in next_fires.iter() {
+            save_handoff(self.shared_header.state_store.as_ref(), task_id, next_fire)?;
+        }
+
+        Ok(HandoffSnapshot {
+            handed_off_at: timestamp(),
+            next_fires,
+        })
+    }
+
+    /// Take over from the instance of the scheduler that handed the tasks off by `hand_off`,
+    /// through the `StateStore` they share.
+    ///
+    /// The registered tasks are activated, see `register_task`: the ones handed off
+    /// go on from the next fire the other instance left, without a startup fire,
+    /// the others start from now. Returns the ids of the tasks handed off.
+    pub fn take_over(&self) -> AnyResult<Vec<u64>> {
+        block_on(async {
+            let (report_sender, report_receiver) = bounded(1);
+            self.seed_timer_event(TimerEvent::TakeOver(report_sender))?;
+
+            report_receiver.recv().await?
+        })
+    }
+
+    /// Run `f` with a new DelayTimer, that is shut down once `f` returns or panics,
+    /// see `DelayTimerBuilder::scope`.
+    ///
+    /// Not to be confused with `scoped`, which confines a timer to a namespace.
+    pub fn scope<R>(f: impl FnOnce(&DelayTimer) -> R) -> R {
+        DelayTimerBuilder::default().scope(f)
+    }
+
     /// Set internal id-generator for `machine_id` and `node_id`.
     /// Add a new api in the future to support passing a custom id generator.
     /// The id-generator is mainly used for binding unique record ids to internal events, for user collection, and for tracking task dynamics.
     pub fn update_id_generator_conf(&self, machine_id: i32, node_id: i32) {
         let mut id_generator = block_on(self.shared_header.id_generator.lock());
 
-        id_generator.machine_id = machine_id;
-        id_generator.node_id = node_id;
+        id_generator.set_conf(machine_id, node_id);
     }
 
-    /// Send a event to event-handle.
-    fn seed_timer_event(&self, event: TimerEvent) -> Result<(), TaskError> {
-        Ok(self.timer_event_sender.try_send(event)?)
+    /// Get a snapshot of the scheduler's health.
+    pub fn health(&self) -> SchedulerHealth {
+        SchedulerHealth::new(&self.shared_header, self.timer_event_sender.len())
     }
-}
 
-/// # Required features
-///
-/// This function requires the `tokio-support` feature of the `delay_timer`
-/// crate to be enabled.
-impl DelayTimerBuilder {
-    fn assign_task_by_tokio(&mut self, event_handle: EventHandle, shared_header: SharedHeader) {
-        self.run_async_schedule_by_tokio(shared_header.clone());
-        self.run_event_handle_by_tokio(event_handle, shared_header);
+    /// Get what the instances of a task did, `None` for a task the timer doesn't have.
+    pub fn task_stats(&self, task_id: u64) -> Option<TaskStats> {
+        let task_mark = self.shared_header.task_flag_map.get(&task_id)?;
+        Some(TaskStats::new(task_mark.value()))
     }
 
-    fn run_async_schedule_by_tokio(&mut self, shared_header: SharedHeader) {
-        let shared_header_by_timer = shared_header.clone();
-        let timer_event_sender = self.get_timer_event_sender();
+    /// Get a read-only view of the timer, for dashboards polling its tasks and stats.
+    ///
+    /// The view is shared by all the callers, and it's only kept up to date once one was taken.
+    pub fn view(&self) -> SchedulerView {
+        self.shared_header
+            .scheduler_view
+            .get_or_init(|| SchedulerView::new(&self.shared_header, self.timer_event_sender.len()))
+            .clone()
+    }
 
-        if let Some(ref tokio_runtime_ref) = shared_header.runtime_instance.inner {
-            let tokio_runtime = tokio_runtime_ref.clone();
-            Builder::new()
-                .name("async_schedule_tokio".into())
-                .spawn(move || {
-                    tokio_runtime.block_on(async {
-                        let mut timer = Timer::new(timer_event_sender, shared_header_by_timer);
-                        timer.async_schedule().await;
-                    })
-                })
-                .expect("async_schedule can't start.");
+    /// Look for misconfigurations in the tasks waiting in the timer,
+    /// see `analyze_tasks` to check a set of tasks before adding them.
+    ///
+    /// The expected running time of a task in a serial queue is its p95 duration once it's known.
+    /// Tasks that are paused, not yet activated, or being handled right now aren't looked at.
+    pub fn analyze(&self) -> Vec<Diagnostic> {
+        let now = Utc::now();
+        let p95_durations = self.p95_durations();
+        let mut profiles = Vec::new();
+        for slot in self.shared_header.wheel_queue.iter() {
+            for task in slot.value().tasks() {
+                let p95_duration = p95_durations.get(&task.task_id).copied();
+                profiles.push(TaskProfile::new(task, now, p95_duration));
+            }
+        }
+
+        diagnostics::analyze(profiles)
+    }
+
+    /// Forecast the load of the tasks waiting in the timer over the coming `window`,
+    /// second by second, see `LoadForecast`.
+    ///
+    /// Tasks that are paused, not yet activated, or being handled right now aren't counted.
+    pub fn forecast(&self, window: Duration) -> LoadForecast {
+        let start = timestamp();
+        let mut forecast = LoadForecast::new(start, window.as_secs());
+        let until = (start + window.as_secs()) as i64 - 1;
+        let p95_durations = self.p95_durations();
+
+        for slot in self.shared_header.wheel_queue.iter() {
+            for task in slot.value().tasks() {
+                let duration = p95_durations
+                    .get(&task.task_id)
+                    .copied()
+                    .or_else(|| task.get_maximum_running_time(0))
+                    .unwrap_or(0);
+                forecast.add_fires(&task.fires_between(start as i64 - 1, until), duration);
+            }
         }
+
+        forecast
+    }
+
+    // The p95 durations of the tasks that have one, taken before the wheel is walked,
+    // so that no task-mark is locked while a slot is.
+    fn p95_durations(&self) -> HashMap<u64, u64> {
+        self.shared_header
+            .task_flag_map
+            .iter()
+            .filter_map(|task_mark| {
+                Some((*task_mark.key(), task_mark.value().get_p95_duration()?))
+            })
+            .collect()
+    }
+
+    /// Get the first fire of a task after `t`, see `Task::next_fire_after`.
+    ///
+    /// `None` is returned for a task that isn't waiting in the wheel,
+    /// such as one that is paused, not yet activated, or being handled right now.
+    pub fn next_fire_after(&self, task_id: u64, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let slot_mark = self
+            .shared_header
+            .task_flag_map
+            .get(&task_id)?
+            .value()
+            .get_slot_mark();
+        let slot = self.shared_header.wheel_queue.get(&slot_mark)?;
+        slot.value().get_task(task_id)?.next_fire_after(t)
     }
 
-    fn run_event_handle_by_tokio(
+    /// Sync the registered tasks to the `desired` set.
+    ///
+    /// Tasks that aren't registered are added, tasks whose config differs from the one
+    /// last reconciled are updated, and registered tasks that aren't desired are removed,
+    /// including the ones added by `add_task`.
+    /// Nothing is changed if one of the configs refers to a routine missing in `registry`.
+    pub fn reconcile(
         &self,
-        mut event_handle: EventHandle,
-        shared_header: SharedHeader,
-    ) {
-        if let Some(ref tokio_runtime_ref) = shared_header.runtime_instance.inner {
-            let tokio_runtime = tokio_runtime_ref.clone();
-            Builder::new()
-                .name("event_handle_tokio".into())
-                .spawn(move || {
-                    tokio_runtime.block_on(async {
-                        event_handle.lauch().await;
-                    })
-                })
-                .expect("event_handle_handle_by_tokio can't start.");
+        desired: Vec<TaskConfig>,
+        registry: &RoutineRegistry,
+    ) -> Result<ReconcileReport, TaskError> {
+        let mut reconciled_configs = self
+            .reconciled_configs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // A task that was just added may not be in the flag-map yet,
+        // and one that was just removed may still be there.
+        let registered: HashSet<u64> = self
+            .shared_header
+            .task_flag_map
+            .iter()
+            .map(|task_mark| *task_mark.key())
+            .filter(|task_id| !matches!(reconciled_configs.get(task_id), Some(None)))
+            .chain(
+                reconciled_configs
+                    .iter()
+                    .filter(|(_, config)| config.is_some())
+                    .map(|(task_id, _)| *task_id),
+            )
+            .collect();
+
+        let mut report = ReconcileReport::default();
+        let mut changes = Vec::new();
+        for config in desired.iter() {
+            let task_id = config.task_id;
+
+            if !registered.contains(&task_id) {
+                changes.push((config.build(registry)?, false));
+                report.added.push(task_id);
+            } else if reconciled_configs.get(&task_id).and_then(Option::as_ref) != Some(config) {
+                changes.push((config.build(registry)?, true));
+                report.updated.push(task_id);
+            } else {
+                report.unchanged.push(task_id);
+            }
+        }
+
+        let desired_ids: HashSet<u64> = desired.iter().map(|config| config.task_id).collect();
+        report.removed = registered
+            .into_iter()
+            .filter(|task_id| !desired_ids.contains(task_id))
+            .collect();
+        report.removed.sort_unstable();
+
+        for (task, is_update) in changes {
+            if is_update {
+                self.update_task(task)?;
+            } else {
+                self.add_task(task)?;
+            }
+        }
+
+        for task_id in report.removed.iter().copied() {
+            self.remove_task(task_id)?;
+        }
+
+        let removed = reconciled_configs
+            .iter()
+            .filter(|(task_id, config)| {
+                config.is_none()
+                    && !desired_ids.contains(task_id)
+                    && self.shared_header.task_flag_map.contains_key(task_id)
+            })
+            .map(|(task_id, _)| *task_id)
+            .chain(report.removed.iter().copied())
+            .collect::<Vec<_>>();
+        *reconciled_configs = desired
+            .into_iter()
+            .map(|config| (config.task_id, Some(config)))
+            .chain(removed.into_iter().map(|task_id| (task_id, None)))
+            .collect();
+
+        Ok(report)
+    }
+
+    /// Take a snapshot of the timing wheel: the occupied slots,
+    /// how many of their tasks still have laps to wait, and the next fires.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `debug-dump` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "debug-dump")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-dump")))]
+    pub fn debug_dump(&self) -> WheelDump {
+        WheelDump::new(&self.shared_header)
+    }
+
+    /// Get a view of the timer confined to the tasks of `namespace`,
+    /// for libraries that share the timer with the application.
+    pub fn scoped(&self, namespace: &str) -> ScopedDelayTimer {
+        ScopedDelayTimer::new(self.clone(), namespace)
+    }
+
+    /// Add a task owned by `owner` in timer_core by event-channel.
+    ///
+    /// An owned task can only be changed by the `*_owned` methods with its owner,
+    /// and its task-id can't be reused by another owner until it's removed.
+    /// A task-id already in use can't be owned.
+    pub fn add_task_owned(&self, task: Task, owner: &OwnerToken) -> Result<(), TaskError> {
+        let task_id = task.task_id;
+        self.check_owner(task_id, Some(owner))?;
+        if self.shared_header.task_flag_map.contains_key(&task_id) {
+            return Err(TaskError::TaskIdInUse(task_id));
+        }
+        self.admit_tenant(&task)?;
+        self.shared_header
+            .task_owners
+            .insert(task_id, owner.clone());
+
+        let config = self.audited_config(&task);
+        let seed_result = self.seed_admitted_event(task_id, TimerEvent::AddTask(Box::new(task)));
+        if seed_result.is_err() {
+            self.shared_header.task_owners.remove(&task_id);
+        }
+        self.audit(seed_result, AuditOperation::Add, task_id, config)
+    }
+
+    /// Update a task owned by `owner` in timer_core by event-channel.
+    pub fn update_task_owned(&self, task: Task, owner: &OwnerToken) -> Result<(), TaskError> {
+        self.check_owner(task.task_id, Some(owner))?;
+        self.admit_tenant(&task)?;
+        let (task_id, config) = (task.task_id, self.audited_config(&task));
+        let seed_result = self.seed_admitted_event(task_id, TimerEvent::UpdateTask(Box::new(task)));
+        self.audit(seed_result, AuditOperation::Update, task_id, config)
+    }
+
+    /// Remove a task owned by `owner` in timer_core by event-channel.
+    pub fn remove_task_owned(&self, task_id: u64, owner: &OwnerToken) -> Result<(), TaskError> {
+        self.check_owner(task_id, Some(owner))?;
+        let seed_result = self.seed_timer_event(TimerEvent::RemoveTask(task_id));
+        self.audit(seed_result, AuditOperation::Remove, task_id, None)
+    }
+
+    /// Advance a task owned by `owner` in timer_core by event-channel, with `AdvanceOptions`.
+    pub fn advance_task_owned(
+        &self,
+        task_id: u64,
+        advance_options: AdvanceOptions,
+        owner: &OwnerToken,
+    ) -> Result<AdvanceOutcome, TaskError> {
+        self.check_owner(task_id, Some(owner))?;
+        self.seed_advance(task_id, advance_options)
+    }
+
+    /// Cancel an instance of a task owned by `owner` in timer_core by event-channel.
+    pub fn cancel_task_owned(
+        &self,
+        task_id: u64,
+        record_id: i64,
+        owner: &OwnerToken,
+    ) -> Result<(), TaskError> {
+        self.check_owner(task_id, Some(owner))?;
+        let seed_result = self.seed_timer_event(TimerEvent::CancelTask(
+            task_id,
+            record_id,
+            TerminationReason::Cancelled(CancelOriginator::DelayTimer, None),
+        ));
+        self.audit(
+            seed_result,
+            AuditOperation::Cancel(record_id),
+            task_id,
+            None,
+        )
+    }
+
+    /// Get a handle of the timer whose control-plane operations are recorded
+    /// as made by `actor` in the audit trail, see `DelayTimerBuilder::enable_audit_log`.
+    pub fn with_actor(&self, actor: &str) -> DelayTimer {
+        DelayTimer {
+            actor: Some(actor.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Get the recorded control-plane operations, oldest first.
+    ///
+    /// Only the most recent ones fit in the trail, see `DelayTimerBuilder::enable_audit_log`;
+    /// without it, nothing is kept and an empty `Vec` is returned.
+    pub fn audit_log(&self) -> Vec<AuditRecord> {
+        self.shared_header
+            .audit_trail
+            .as_ref()
+            .map(AuditTrail::records)
+            .unwrap_or_default()
+    }
+
+    /// Get what `tenant` uses: its tasks and their running instances, with its quota.
+    pub fn tenant_usage(&self, tenant: &str) -> TenantUsage {
+        self.shared_header
+            .tenants
+            .usage(tenant, &self.shared_header.task_flag_map)
+    }
+
+    /// Get the ids of the tasks owned by `owner`.
+    pub fn tasks_owned_by(&self, owner: &OwnerToken) -> Vec<u64> {
+        let mut task_ids: Vec<u64> = self
+            .shared_header
+            .task_owners
+            .iter()
+            .filter(|task_owner| task_owner.value() == owner)
+            .map(|task_owner| *task_owner.key())
+            .collect();
+        task_ids.sort_unstable();
+        task_ids
+    }
+
+    // An owned task can only be touched by its owner, `None` is no owner.
+    fn check_owner(&self, task_id: u64, owner: Option<&OwnerToken>) -> Result<(), TaskError> {
+        match self.shared_header.task_owners.get(&task_id) {
+            Some(task_owner) if Some(task_owner.value()) != owner => {
+                Err(TaskError::NotOwner(task_id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Whether the timer-core loop stops within `timeout`, it's woken up on `rearm` as it stops.
+    async fn timer_core_stopped(&self, timeout: Duration) -> bool {
+        let stopped = async {
+            loop {
+                // It listens before looking, so that the loop stopping meanwhile isn't missed.
+                let rearmed = self.shared_header.rearm.listen();
+                if !self.shared_header.timer_core_alive.load(Ordering::Acquire) {
+                    return true;
+                }
+                rearmed.await;
+            }
+        };
+        let timed_out = async {
+            smol::Timer::after(timeout).await;
+            false
+        };
+        smol::future::or(stopped, timed_out).await
+    }
+
+    // What becomes of a manual run of the task, judged like the timer-core judges a fire.
+    // A task still on its way to the event-handle, or whose pending fire is being handled,
+    // can't be looked at, the run goes ahead as it used to.
+    fn foresee_advance(&self, task_id: u64, jump_queue: bool) -> AdvanceOutcome {
+        let slot_mark = match self.shared_header.task_flag_map.get(&task_id) {
+            Some(task_mark) => task_mark.value().get_slot_mark(),
+            None => return AdvanceOutcome::Advanced,
+        };
+
+        let timestamp = self.shared_header.global_time.load(Ordering::Acquire);
+        // The task-marks are read before the slot is looked at again, they're locked before the wheel.
+        let tenant = self
+            .shared_header
+            .wheel_queue
+            .get(&slot_mark)
+            .and_then(|slot| {
+                slot.value()
+                    .get_task(task_id)
+                    .map(|task| task.tenant.clone())
+            });
+        let tenant = match tenant {
+            Some(tenant) => tenant,
+            None => return AdvanceOutcome::Advanced,
+        };
+        let fire_state = marked_fire_state(
+            &self.shared_header,
+            task_id,
+            tenant.as_deref(),
+            timestamp,
+            None,
+        );
+
+        let slot = match self.shared_header.wheel_queue.get(&slot_mark) {
+            Some(slot) => slot,
+            None => return AdvanceOutcome::Advanced,
+        };
+        let task = match slot.value().get_task(task_id) {
+            Some(task) => task,
+            None => return AdvanceOutcome::Advanced,
+        };
+
+        let mut fire_state = complete_fire_state(&self.shared_header, task, timestamp, fire_state);
+        // A run jumping the queue is held up by the running instances only.
+        if jump_queue {
+            fire_state.serial_queue_blocked =
+                task.serial_queue.as_ref().is_some_and(|serial_queue| {
+                    self.shared_header
+                        .serial_queues
+                        .get(serial_queue)
+                        .is_some_and(|serial_queue| serial_queue.is_busy())
+                });
+            fire_state.semaphore_exhausted = task
+                .semaphore
+                .as_ref()
+                .is_some_and(|semaphore| semaphore.available_permits() == 0);
+        }
+        match decide(task, timestamp, &fire_state) {
+            FireDecision::Run => AdvanceOutcome::Advanced,
+            FireDecision::Queue if fire_state.serial_queue_blocked => {
+                let ahead = task.serial_queue.as_ref().and_then(|serial_queue| {
+                    self.shared_header
+                        .serial_queues
+                        .get(serial_queue)
+                        .map(|serial_queue| {
+                            if jump_queue {
+                                serial_queue.held()
+                            } else {
+                                serial_queue.ahead_of(task_id)
+                            }
+                        })
+                });
+                AdvanceOutcome::QueuedBehind(ahead.unwrap_or_default())
+            }
+            FireDecision::Queue if fire_state.semaphore_exhausted => {
+                let ahead = task.semaphore.as_ref().map(|semaphore| {
+                    if jump_queue {
+                        semaphore.held()
+                    } else {
+                        semaphore.ahead_of(task_id)
+                    }
+                });
+                AdvanceOutcome::QueuedBehind(ahead.unwrap_or_default())
+            }
+            FireDecision::Queue => AdvanceOutcome::QueuedBehind(fire_state.running_instances),
+            FireDecision::Skip(skip_reason) => AdvanceOutcome::RejectedByPolicy(skip_reason),
         }
     }
 
+    // The configuration of `task` for the audit trail, if there's one.
+    fn audited_config(&self, task: &Task) -> Option<AuditedConfig> {
+        self.shared_header
+            .audit_trail
+            .as_ref()
+            .map(|_| AuditedConfig::new(task))
+    }
+
+    // Record a control-plane operation in the audit trail, once it went through.
+    fn audit<T>(
+        &self,
+        result: Result<T, TaskError>,
+        operation: AuditOperation,
+        task_id: u64,
+        config: Option<AuditedConfig>,
+    ) -> Result<T, TaskError> {
+        if let (Ok(_), Some(audit_trail)) = (result.as_ref(), self.shared_header.audit_trail.as_ref()) {
+            audit_trail.record(self.actor.as_deref(), operation, task_id, config);
+        }
+        result
+    }
+
+    // The task counts towards the quota of its tenant from now on,
+    // and its memory is attributed to the tenant.
+    fn admit_tenant(&self, task: &Task) -> Result<(), TaskError> {
+        self.shared_header
+            .tenants
+            .admit(task.task_id, task.tenant.as_deref())?;
+        if let Some(memory_attribution) = self.shared_header.memory_attribution.as_ref() {
+            memory_attribution.record(task);
+        }
+        Ok(())
+    }
+
+    // The event of a task admitted by `admit_tenant` doesn't reach the event-handle
+    // once the timer is gone, so the task no longer counts towards its tenant.
+    fn seed_admitted_event(&self, task_id: u64, event: TimerEvent) -> Result<(), TaskError> {
+        let seed_result = self.seed_timer_event(event);
+        if seed_result.is_err() {
+            self.shared_header.tenants.release(task_id);
+            if let Some(memory_attribution) = self.shared_header.memory_attribution.as_ref() {
+                memory_attribution.release(task_id);
+            }
+        }
+        seed_result
+    }
+
+    /// Send a event to event-handle.
+    fn seed_timer_event(&self, event: TimerEvent) -> Result<(), TaskError> {
+        Ok(self.timer_event_sender.try_send(event)?)
+    }
+}
+
+/// # Required features
+///
+/// This function requires the `tokio-support` feature of the `delay_timer`
+/// crate to be enabled.
+impl DelayTimerBuilder {
     /// With this API, `DelayTimer` use default `Smol-Runtime` is generated internally.
     pub fn smol_runtime_by_default(mut self) -> Self {
         self.runtime_instance.kind = RuntimeKind::Smol;
@@ -473,6 +1893,187 @@ impl DelayTimerBuilder {
 
         self
     }
+
+    /// Set `machine_id` and `node_id` of the record-id generator, both in `0..32`.
+    ///
+    /// Record ids are snowflake ids, give every node of a cluster its own pair
+    /// so that the ids don't collide across nodes and can be used as keys in external systems.
+    /// Either of them out of `0..32` would spill into the bits of the other, so it's rejected.
+    pub fn id_generator_conf(
+        mut self,
+        machine_id: i32,
+        node_id: i32,
+    ) -> Result<Self, TimerBuilderError> {
+        for (name, id) in [("machine_id", machine_id), ("node_id", node_id)] {
+            if !(0..32).contains(&id) {
+                return Err(TimerBuilderError::IdGeneratorConfOutOfRange(name, id));
+            }
+        }
+
+        self.id_generator_conf = Some((machine_id, node_id));
+        Ok(self)
+    }
+
+    /// Spread the tasks whose cron second field is `0` across the minute.
+    ///
+    /// Each of them gets a stable second offset derived from its task-id,
+    /// so that a large fleet of minutely tasks doesn't all fire at :00.
+    /// A task opts out by `TaskBuilder::set_auto_splay_opt_out`.
+    pub fn auto_splay_seconds(mut self, auto_splay_seconds: bool) -> Self {
+        self.auto_splay_seconds = auto_splay_seconds;
+        self
+    }
+
+    /// Set what the timer does with the fires that were due while it was not ticking.
+    ///
+    /// When the clock jumps forward (e.g. a laptop wakes up from sleep),
+    /// the wheel is resynced instead of firing a burst of stale ticks,
+    /// and `SystemResumedAfter` is emitted.
+    /// The same applies to a timer-core loop restarted by the watchdog.
+    pub fn misfire_policy(mut self, misfire_policy: MisfirePolicy) -> Self {
+        self.misfire_policy = misfire_policy;
+        self
+    }
+
+    /// Set which fires of the `CountDown` tasks count against them.
+    ///
+    /// By default only the fires that run an instance count,
+    /// the fires skipped (e.g. by the exclusion schedule, or while the task was paused) don't.
+    /// The instances and the `FinishTask` events carry both the time their fire was
+    /// scheduled for and the time they started at.
+    pub fn count_down_policy(mut self, count_down_policy: CountDownPolicy) -> Self {
+        self.count_down_policy = count_down_policy;
+        self
+    }
+
+    /// Set up an executor pool of `threads` threads named `name` (optional),
+    /// the tasks assigned to it by `TaskBuilder::set_pool` run their instances there,
+    /// e.g. `executor_pool("heavy", 2)` so that heavyweight jobs can't saturate the runtime
+    /// the lightweight, latency-sensitive ones run on.
+    ///
+    /// Each thread runs many async instances at once, a blocking instance holds its thread up.
+    /// The first thread free to take an instance takes it, see `executor_pool_with_dispatch`.
+    pub fn executor_pool(self, name: impl Into<String>, threads: usize) -> Self {
+        self.executor_pool_with_dispatch(name, threads, Dispatch::default())
+    }
+
+    /// Set up an executor pool (optional), see `executor_pool`,
+    /// whose threads take the instances as `dispatch` says.
+    pub fn executor_pool_with_dispatch(
+        mut self,
+        name: impl Into<String>,
+        threads: usize,
+        dispatch: Dispatch,
+    ) -> Self {
+        self.executor_pools.insert(name.into(), (threads, dispatch));
+        self
+    }
+
+    /// Spread the first fires of the tasks over `window` after the timer starts.
+    ///
+    /// When thousands of tasks are inserted at boot, their first fires can align.
+    /// A first fire due within the window is delayed to a stable point of it derived from
+    /// the task-id, the fires due until then are collapsed into it. The fires after it
+    /// keep their scheduled times, and so do the tasks inserted once the window is over.
+    pub fn startup_stagger(mut self, window: Duration) -> Self {
+        self.startup_stagger = Some(window);
+        self
+    }
+
+    /// Start the instances within a few milliseconds of their scheduled second.
+    ///
+    /// The wheel ticks once a second, at no particular point of it, so an instance
+    /// normally starts anywhere within its second. In high-precision mode every fire
+    /// is taken from the wheel a tick ahead of time, and its instance is armed with a
+    /// precise timer for the scheduled second, see `TaskBuilder::set_dispatch_compensation`.
+    /// The fires further away are still left to the wheel.
+    pub fn high_precision(mut self, high_precision: bool) -> Self {
+        self.high_precision = high_precision;
+        self
+    }
+
+    /// Attribute the approximate memory the tasks hold to their tenants,
+    /// reported by `SchedulerHealth::memory_attribution`.
+    ///
+    /// The size of a task is taken once it's added, by its `TaskBuilder::set_memory_sizer`
+    /// or else guessed from the task and the state its routine captured,
+    /// which helps to tell which group grows when tens of thousands of closures are registered.
+    pub fn enable_memory_attribution(mut self) -> Self {
+        self.memory_attribution = true;
+        self
+    }
+
+    /// Set the observer of the internal scheduling decisions.
+    pub fn set_scheduler_observer<O: SchedulerObserver>(mut self, observer: O) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Set the hook deciding on each instance right before it's spawned,
+    /// so that the instances can be vetoed or handed an extension by a bespoke policy.
+    pub fn set_spawn_hook<H: SpawnHook>(mut self, spawn_hook: H) -> Self {
+        self.spawn_hook = Some(Arc::new(spawn_hook));
+        self
+    }
+
+    /// Set the storage of the job state the tasks keep between runs,
+    /// see `TaskContext::state_get`. The state is kept in memory by default.
+    pub fn set_state_store<S: StateStore>(mut self, state_store: S) -> Self {
+        self.state_store = Some(Arc::new(state_store));
+        self
+    }
+
+    /// Set the provider resolving the `SecretString`s of the tasks when their instances run,
+    /// see `TaskContext::resolve_secret`. They're environment variables by default, see `EnvSecrets`.
+    pub fn set_secrets_provider<P: SecretsProvider>(mut self, secrets_provider: P) -> Self {
+        self.secrets_provider = Some(Arc::new(secrets_provider));
+        self
+    }
+
+    /// Keep the most recent `capacity` control-plane operations in an audit trail,
+    /// read by `DelayTimer::audit_log`.
+    pub fn enable_audit_log(mut self, capacity: usize) -> Self {
+        self.audit_log_capacity = Some(capacity);
+        self
+    }
+
+    /// Set the sink persisting the audit trail of the control-plane operations,
+    /// whether or not it's kept by `enable_audit_log`.
+    pub fn set_audit_sink<S: AuditSink>(mut self, audit_sink: S) -> Self {
+        self.audit_sink = Some(Arc::new(audit_sink));
+        self
+    }
+
+    /// Set the quota of `tenant`, for the tasks labelled with it by `TaskBuilder::set_tenant`.
+    ///
+    /// Adding or updating a task beyond `max_tasks` fails with `TaskError::TenantQuotaExceeded`,
+    /// and the fires beyond `max_concurrent_instances` are skipped.
+    pub fn tenant_quota(
+        mut self,
+        tenant: &str,
+        max_tasks: MaxTasks,
+        max_concurrent_instances: MaxConcurrentInstances,
+    ) -> Self {
+        self.tenant_quotas.insert(
+            tenant.to_string(),
+            TenantQuota {
+                max_tasks: max_tasks.0,
+                max_concurrent_instances: max_concurrent_instances.0,
+            },
+        );
+        self
+    }
+
+    /// Set the callback for the truly fatal case,
+    /// the timer-core loop died and can't be restarted, so no task is scheduled any more.
+    pub fn set_on_irrecoverable_failure<F>(mut self, on_irrecoverable_failure: F) -> Self
+    where
+        F: Fn(&SchedulerError) + 'static + Send + Sync,
+    {
+        self.on_irrecoverable_failure =
+            Some(SafeStructFailureFn(Arc::new(on_irrecoverable_failure)));
+        self
+    }
 }
 
 cfg_status_report!(
@@ -488,13 +2089,46 @@ cfg_status_report!(
             self
         }
 
-        fn get_status_report_sender(&mut self) -> AsyncSender<PublicEvent> {
+        /// Keep the most recent `capacity` public events in a ring-buffer,
+        /// so that a consumer attaching late can read them through `DelayTimer::replay_events`.
+        pub fn enable_event_log(mut self, capacity: usize) -> Self {
+            self.event_log_capacity = Some(capacity);
+            self
+        }
+
+        /// Log every public event at `level` under the `delay_timer::events` target,
+        /// as `event=<name> task_id=<id> record_id=<id> detail=<detail>`,
+        /// without having to consume the `StatusReporter`.
+        ///
+        /// The routine events dropped by the `EventSampling` of their task are logged all the same.
+        pub fn log_events(mut self, level: LogLevel) -> Self {
+            self.log_events = Some(level);
+            self
+        }
+
+        /// Set which of the routine events of the tasks are reported,
+        /// for the tasks that don't set their own by `TaskBuilder::set_event_sampling`.
+        pub fn event_sampling(mut self, event_sampling: EventSampling) -> Self {
+            self.event_sampling = event_sampling;
+            self
+        }
+
+        fn get_event_log(&mut self) -> Option<EventLog> {
+            let capacity = self.event_log_capacity?;
+            Some(
+                self.event_log
+                    .get_or_insert_with(|| EventLog::new(capacity))
+                    .clone(),
+            )
+        }
+
+        fn get_status_report_sender(&mut self) -> AsyncSender<(u64, PublicEvent)> {
             GLOBAL_STATUS_REPORTER
                 .0
                 .clone()
         }
 
-        fn get_status_report_receiver(&mut self) -> AsyncReceiver<PublicEvent> {
+        fn get_status_report_receiver(&mut self) -> AsyncReceiver<(u64, PublicEvent)> {
             GLOBAL_STATUS_REPORTER
                 .1
                 .clone()
@@ -517,6 +2151,39 @@ cfg_status_report!(
 
             Err(channel::TryRecvError::Closed.into())
         }
+
+        /// Read the recorded public events that occurred at or after `since` (timestamp in seconds),
+        /// oldest first.
+        ///
+        /// Only the most recent events fit in the log, see `DelayTimerBuilder::enable_event_log`;
+        /// without it, nothing is recorded and an empty `Vec` is returned.
+        pub fn replay_events(&self, since: u64) -> Vec<PublicEvent> {
+            self.event_log
+                .as_ref()
+                .map(|event_log| event_log.replay(since))
+                .unwrap_or_default()
+        }
+
+        /// Write the recorded public events of a task, or of every task with `None`,
+        /// to `writer` as CSV or JSON lines, oldest first.
+        ///
+        /// Every record has the fields `time,event,task_id,record_id,detail`.
+        /// The history is the one kept by `DelayTimerBuilder::enable_event_log`,
+        /// without it, nothing but the CSV header is written.
+        pub fn export_history(
+            &self,
+            task_id: Option<u64>,
+            format: HistoryFormat,
+            writer: impl Write,
+        ) -> io::Result<()> {
+            let history = self
+                .event_log
+                .as_ref()
+                .map(|event_log| event_log.history(task_id))
+                .unwrap_or_default();
+
+            write_history(&history, format, writer)
+        }
     }
 
 );
@@ -544,3 +2211,28 @@ pub fn timestamp_micros() -> u128 {
         Err(_) => panic!("SystemTime before UNIX EPOCH!"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_record_id_generator() {
+        use super::RecordIdGenerator;
+
+        let mut id_generator = RecordIdGenerator::new(1, 1);
+        let first_id = id_generator.generate();
+
+        let worker = (1 << 17) | (1 << 12);
+        let millis = first_id >> 22;
+
+        // As if the system clock went backwards.
+        id_generator.last_id = ((millis + 1_000) << 22) | worker;
+        let second_id = id_generator.generate();
+        assert_eq!(second_id, ((millis + 1_000) << 22) | worker | 1);
+        assert!(id_generator.generate() > second_id);
+
+        // Once the sequence of the millisecond has run out, the next millisecond is taken.
+        id_generator.last_id = ((millis + 2_000) << 22) | worker | 0xFFF;
+        assert_eq!(id_generator.generate(), ((millis + 2_001) << 22) | worker);
+    }
+}
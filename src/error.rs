@@ -2,6 +2,8 @@
 
 use crate::prelude::*;
 
+use std::fmt;
+
 /// Error enumeration for `Task`-related operations.
 #[derive(Error, Debug)]
 pub enum TaskError {
@@ -14,6 +16,72 @@ pub enum TaskError {
     /// Task event get failed.
     #[error("Task event get failed.")]
     DisGetEvent(#[from] channel::TryRecvError),
+    /// The event-handle didn't report back.
+    #[error("Task event channel exception.")]
+    InternalChannelAnomaly(#[from] channel::RecvError),
+    /// No routine is registered under the name.
+    #[error("The routine `{0}` is not registered.")]
+    UnregisteredRoutine(String),
+    /// The task belongs to another owner.
+    #[error("The task {0} belongs to another owner.")]
+    NotOwner(u64),
+    /// The task-id is already taken by another task.
+    #[error("The task-id {0} is already in use.")]
+    TaskIdInUse(u64),
+    /// No task with the task-id is in the timer.
+    #[error("The task {0} is not in the timer.")]
+    UnknownTask(u64),
+    /// The task-id doesn't fit in the id space of a namespace.
+    #[error("The task-id {0} is out of the id space of the namespace.")]
+    TaskIdOutOfScope(u64),
+    /// The tenant of the task already has as many tasks as its quota allows.
+    #[error("The tenant `{0}` is out of its task quota.")]
+    TenantQuotaExceeded(String),
+    /// The schedule of the task has no fire.
+    #[error("The task {0} has no scheduled fire.")]
+    NoScheduledFire(u64),
+    /// A field of the `TaskBuilder` can't be built into a task.
+    #[error("Invalid {field}: {message}{}", did_you_mean(.suggestion))]
+    InvalidField {
+        /// The offending field.
+        field: BuilderField,
+        /// What is wrong with it.
+        message: String,
+        /// A corrected value, e.g. for a typo in a cron-expression (optional).
+        suggestion: Option<String>,
+    },
+}
+
+/// The fields of a `TaskBuilder` that can make building a task fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuilderField {
+    /// The frequency set by `set_frequency` and its shortcuts.
+    Frequency,
+    /// The frequency set by `set_frequency_by_candy`.
+    CandyFrequency,
+    /// The timeout set by `set_maximum_running_time`.
+    MaximumRunningTime,
+    /// The threshold set by `set_runtime_warning_threshold`.
+    RuntimeWarningThreshold,
+}
+
+impl fmt::Display for BuilderField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = match self {
+            BuilderField::Frequency => "frequency",
+            BuilderField::CandyFrequency => "candy frequency",
+            BuilderField::MaximumRunningTime => "maximum running time",
+            BuilderField::RuntimeWarningThreshold => "runtime warning threshold",
+        };
+        f.write_str(field)
+    }
+}
+
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|suggestion| format!(" (did you mean `{}`?)", suggestion))
+        .unwrap_or_default()
 }
 
 /// Error enumeration for `TaskInstance`-related operations.
@@ -40,6 +108,15 @@ pub enum TaskInstanceError {
     /// Running instance of the task is no longer maintained.
     #[error("Running instance of the task is no longer maintained.")]
     Expired,
+    /// Waiting for the next instance timeout.
+    #[error("Waiting for the next instance timeout.")]
+    DisNextTimeOut,
+    /// The instance has no process that can be signaled.
+    #[error("The instance has no process that can be signaled.")]
+    WithoutProcess,
+    /// Sending the signal to the process of the instance failed.
+    #[error("Sending the signal to the process failed.")]
+    DisSignal(#[source] std::io::Error),
 }
 
 /// Error enumeration for Cron expression parsing.
@@ -54,6 +131,37 @@ pub enum FrequencyAnalyzeError {
     /// The initialization time is wrong.
     #[error("The initialization time is wrong.")]
     DisInitTime,
+    /// A composite frequency was given without any cron expression.
+    #[error("No cron expression was given.")]
+    EmptyExpression,
+    /// The string names none of the `CandyCron` shortcuts.
+    #[error("`{0}` is not a candy cron.")]
+    UnknownCandy(String),
+    /// The string isn't a `CandyFrequency`, as in `once @daily` or `countdown 3 0 0 * * * * *`.
+    #[error("`{0}` is not a candy frequency.")]
+    MalformedCandyFrequency(String),
+}
+
+/// Error enumeration for the supervision of the timer-core loop.
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    /// The timer-core loop kept dying, it's not restarted any more.
+    #[error("The timer-core loop died after {0} restarts, giving up.")]
+    RestartsExhausted(u32),
+    /// The thread of the timer-core loop can't be spawned.
+    #[error("The timer-core loop can't be restarted.")]
+    RestartFailed(#[from] std::io::Error),
+    /// The timer-core loop didn't stop in time for `DelayTimer::hand_off`.
+    #[error("The timer-core loop didn't stop within {0:?}.")]
+    StopTimeOut(Duration),
+}
+
+/// Error enumeration for the configuration of a `DelayTimerBuilder`.
+#[derive(Error, Debug)]
+pub enum TimerBuilderError {
+    /// The `machine_id` or the `node_id` of the record-id generator is out of `0..32`.
+    #[error("The {0} `{1}` of the record-id generator is out of 0..32.")]
+    IdGeneratorConfOutOfRange(&'static str, i32),
 }
 
 /// Error enumeration for Command parsing & Child Execute.
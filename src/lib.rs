@@ -241,10 +241,14 @@
 pub mod macros;
 pub mod entity;
 pub mod error;
+pub mod planner;
 pub mod prelude;
+pub mod sync;
+pub mod testing;
 pub mod timer;
 pub mod utils;
 
 pub use anyhow;
 pub use cron_clock;
 pub use snowflake;
+pub use timer::delay::{delay_for, DelayFuture, DelaySource, SharedWheel};
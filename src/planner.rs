@@ -0,0 +1,327 @@
+//! Planner
+//! The scheduling decisions of the timer-core, free of runtimes, channels and clocks.
+//!
+//! `Planner` tells which tasks of a set fire at a given time, and what becomes of
+//! each fire, so that a schedule configuration can be unit tested without sleeping.
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+
+/// What becomes of a due fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FireDecision {
+    /// A new instance of the task runs.
+    Run,
+    /// The fire is held back, it's retried on the next tick.
+    Queue,
+    /// The fire doesn't run.
+    Skip(SkipReason),
+}
+
+/// The state of a task that a decision depends on, besides its schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FireState {
+    /// How many instances of the task are running.
+    pub running_instances: u64,
+    /// Whether the execution budget of the task is exhausted.
+    pub budget_exhausted: bool,
+    /// The time the pending fire was first held back, if it's queued.
+    pub queued_since: Option<u64>,
+    /// The time the last instance of the task ended, if any.
+    pub last_finish_time: Option<u64>,
+    /// Whether the serial queue of the task is busy, or other tasks are ahead in it.
+    pub serial_queue_blocked: bool,
+    /// Whether the circuit breaker of the task is open.
+    pub circuit_open: bool,
+    /// Whether the tenant of the task runs as many instances as its quota allows.
+    pub tenant_saturated: bool,
+    /// Whether the semaphore of the task has no permit for the pending fire.
+    pub semaphore_exhausted: bool,
+    /// Whether the process named by the pidfile of the task is still alive.
+    pub single_instance_held: bool,
+}
+
+/// Decide what becomes of the fire of `task` due at `timestamp`.
+pub fn decide(task: &Task, timestamp: u64, fire_state: &FireState) -> FireDecision {
+    if task.is_excluded(timestamp) {
+        return FireDecision::Skip(SkipReason::Exclusion);
+    }
+
+    if task.budget.is_some() && fire_state.budget_exhausted {
+        return FireDecision::Skip(SkipReason::BudgetExceeded);
+    }
+
+    if fire_state.circuit_open {
+        return FireDecision::Skip(SkipReason::CircuitOpen);
+    }
+
+    if fire_state.tenant_saturated {
+        return FireDecision::Skip(SkipReason::TenantQuota);
+    }
+
+    if fire_state.single_instance_held {
+        return FireDecision::Skip(SkipReason::SingleInstanceHeld);
+    }
+
+    if fire_state.serial_queue_blocked || fire_state.semaphore_exhausted {
+        return hold_back(task, timestamp, fire_state);
+    }
+
+    // A supervised task restarts once its instance has ended.
+    if task.is_keep_alive() {
+        return if fire_state.running_instances == 0
+            && task.is_restartable(timestamp, fire_state.last_finish_time)
+        {
+            FireDecision::Run
+        } else {
+            FireDecision::Queue
+        };
+    }
+
+    match task.maximum_parallel_runnable_num {
+        Some(maximum_parallel_runnable_num)
+            if fire_state.running_instances >= maximum_parallel_runnable_num =>
+        {
+            // The oldest running instances are cancelled to make room for it.
+            if task.replace_on_overlap {
+                return FireDecision::Run;
+            }
+
+            match task.max_queue_delay {
+                Some(_) => hold_back(task, timestamp, fire_state),
+                None => FireDecision::Skip(SkipReason::MaximumParallelRunnable),
+            }
+        }
+        _ => FireDecision::Run,
+    }
+}
+
+// The fire is queued until `max_queue_delay` is over, if the task has one.
+pub(crate) fn hold_back(task: &Task, timestamp: u64, fire_state: &FireState) -> FireDecision {
+    let queued_since = fire_state.queued_since.unwrap_or(timestamp);
+    match task.max_queue_delay {
+        Some(max_queue_delay)
+            if timestamp.saturating_sub(queued_since) > max_queue_delay.as_secs() =>
+        {
+            FireDecision::Skip(SkipReason::QueueDelayExceeded)
+        }
+        _ => FireDecision::Queue,
+    }
+}
+
+/// A due fire and what became of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fire {
+    /// The id of the task.
+    pub task_id: u64,
+    /// The time the fire was scheduled for.
+    pub scheduled_time: u64,
+    /// The time the fire was handled.
+    pub timestamp: u64,
+    /// What became of the fire.
+    pub decision: FireDecision,
+}
+
+#[derive(Debug)]
+struct PlannedTask {
+    task: Task,
+    due_time: u64,
+    fire_state: FireState,
+}
+
+/// Plans the fires of a set of tasks, tick by tick, the way the timer-core does.
+///
+/// Nothing runs, the decisions are only reported.
+/// Instances don't finish on their own either, the state they leave
+/// is set by `Planner::set_fire_state`.
+#[derive(Debug, Default)]
+pub struct Planner {
+    tasks: BTreeMap<u64, PlannedTask>,
+}
+
+impl Planner {
+    /// New a empty Planner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a task at `timestamp`, a task with the same id is replaced.
+    ///
+    /// The fires its schedule has before `timestamp` are dropped.
+    pub fn add_task(&mut self, mut task: Task, timestamp: u64) -> Result<(), TaskError> {
+        let due_time = if task.is_run_on_startup() {
+            timestamp
+        } else {
+            task.get_next_exec_timestamp();
+            task.skip_missed_fires(timestamp);
+            task.get_scheduled_time()
+                .filter(|&due_time| due_time >= timestamp)
+                .ok_or(TaskError::NoScheduledFire(task.task_id))?
+        };
+
+        self.tasks.insert(
+            task.task_id,
+            PlannedTask {
+                task,
+                due_time,
+                fire_state: FireState::default(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a task, returning it.
+    pub fn remove_task(&mut self, task_id: u64) -> Option<Task> {
+        self.tasks
+            .remove(&task_id)
+            .map(|planned_task| planned_task.task)
+    }
+
+    /// Set the state the decisions of the task depend on.
+    ///
+    /// The time its pending fire was queued since is tracked by the planner, it's ignored here.
+    pub fn set_fire_state(&mut self, task_id: u64, fire_state: FireState) {
+        if let Some(planned_task) = self.tasks.get_mut(&task_id) {
+            planned_task.fire_state = fire_state;
+        }
+    }
+
+    /// Get the ids of the planned tasks.
+    pub fn task_ids(&self) -> Vec<u64> {
+        self.tasks.keys().copied().collect()
+    }
+
+    /// Get the earliest time a fire is due, if any task is left.
+    pub fn next_due_time(&self) -> Option<u64> {
+        self.tasks
+            .values()
+            .map(|planned_task| planned_task.due_time)
+            .min()
+    }
+
+    /// Handle the tick at `timestamp`, every task due at or before it fires once.
+    ///
+    /// Tasks that have no fire left afterwards are dropped.
+    pub fn tick(&mut self, timestamp: u64) -> Vec<Fire> {
+        let mut fires = Vec::new();
+        let mut finished = Vec::new();
+
+        for (task_id, planned_task) in self
+            .tasks
+            .iter_mut()
+            .filter(|(_, planned_task)| planned_task.due_time <= timestamp)
+        {
+            let task = &mut planned_task.task;
+            let scheduled_time = task.get_scheduled_time().unwrap_or(planned_task.due_time);
+            let startup_run = task.take_run_on_startup();
+            let extra_run = task.take_extra_run();
+            let queued_since = task.take_queued_since();
+
+            let fire_state = FireState {
+                queued_since,
+                ..planned_task.fire_state
+            };
+            let decision = decide(task, timestamp, &fire_state);
+            fires.push(Fire {
+                task_id: *task_id,
+                scheduled_time,
+                timestamp,
+                decision,
+            });
+
+            match decision {
+                FireDecision::Queue => {
+                    task.queue_pending_fire(
+                        queued_since.unwrap_or(timestamp),
+                        startup_run,
+                        extra_run,
+                    );
+                    planned_task.due_time = timestamp + 1;
+                    continue;
+                }
+                FireDecision::Run => {
+                    task.record_start(timestamp);
+                    if !(startup_run || extra_run || task.down_count_and_set_vaild()) {
+                        finished.push(*task_id);
+                        continue;
+                    }
+                }
+                FireDecision::Skip(_) => {}
+            }
+
+            match task.get_next_exec_timestamp() {
+                Some(due_time) => planned_task.due_time = due_time,
+                None => finished.push(*task_id),
+            }
+        }
+
+        for task_id in finished {
+            self.tasks.remove(&task_id);
+        }
+        fires
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fire, FireDecision, FireState, Planner};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_planner() -> anyhow::Result<()> {
+        let mut planner = Planner::new();
+
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(10)
+            .set_task_id(1)
+            .set_maximum_parallel_runnable_num(1)
+            .set_max_queue_delay(Duration::from_secs(1))
+            .spawn_async_routine(|| async {})?;
+        planner.add_task(task, timestamp())?;
+
+        let due_time = planner.next_due_time().unwrap();
+        assert!(planner.tick(due_time - 1).is_empty());
+
+        // The task is busy, its fire waits for `max_queue_delay` and is then dropped.
+        planner.set_fire_state(
+            1,
+            FireState {
+                running_instances: 1,
+                ..FireState::default()
+            },
+        );
+        assert_eq!(
+            planner.tick(due_time),
+            vec![Fire {
+                task_id: 1,
+                scheduled_time: due_time,
+                timestamp: due_time,
+                decision: FireDecision::Queue,
+            }]
+        );
+        assert_eq!(planner.tick(due_time + 1)[0].decision, FireDecision::Queue);
+        assert_eq!(
+            planner.tick(due_time + 2)[0].decision,
+            FireDecision::Skip(SkipReason::QueueDelayExceeded)
+        );
+        assert_eq!(planner.next_due_time(), Some(due_time + 10));
+
+        planner.set_fire_state(1, FireState::default());
+        assert_eq!(planner.tick(due_time + 10)[0].decision, FireDecision::Run);
+        assert_eq!(planner.next_due_time(), Some(due_time + 20));
+
+        // A task is dropped once it has no fire left.
+        let task = TaskBuilder::default()
+            .set_frequency_once_by_seconds(5)
+            .set_task_id(2)
+            .spawn_async_routine(|| async {})?;
+        planner.add_task(task, timestamp())?;
+        let fires = planner.tick(due_time + 20);
+        assert!(fires
+            .iter()
+            .any(|fire| fire.task_id == 2 && fire.decision == FireDecision::Run));
+        assert_eq!(planner.task_ids(), vec![1]);
+
+        Ok(())
+    }
+}
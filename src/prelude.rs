@@ -10,16 +10,54 @@
 //!
 //! The prelude may grow over time as additional items see ubiquitous use.
 
-pub use crate::entity::{timestamp, timestamp_micros, DelayTimer, DelayTimerBuilder};
+pub use crate::entity::{
+    timestamp, timestamp_micros, ChildProcessPolicy, DelayTimer, DelayTimerBuilder, RegisteredTask,
+    SchedulerHealth, ScopedDelayTimer, ShutdownOptions, ShutdownReport, TaskStats,
+};
 pub use crate::error::*;
+pub use crate::sync::{Fairness, TaskSemaphore};
+pub use crate::timer::audit::{AuditOperation, AuditRecord, AuditSink, AuditedConfig};
+#[cfg(feature = "chaos")]
+pub use crate::timer::chaos::Chaos;
+pub use crate::timer::circuit_breaker::{CircuitBreaker, CircuitState};
+pub use crate::timer::dedicated_thread::ThreadOptions;
+pub use crate::timer::delay::{delay_for, DelayFuture, DelaySource, SharedWheel};
+pub use crate::timer::diagnostics::{analyze_tasks, Diagnostic};
+pub use crate::timer::dst::{AmbiguousTime, DstPolicy, NonexistentTime};
+pub use crate::timer::executor_pool::Dispatch;
+pub use crate::timer::forecast::LoadForecast;
+pub use crate::timer::handoff::HandoffSnapshot;
+pub use crate::timer::observer::{SchedulerObserver, SkipReason};
+pub use crate::timer::ownership::OwnerToken;
+pub use crate::timer::reconcile::{ReconcileReport, RoutineRegistry, TaskConfig};
+pub use crate::timer::runtime_trace::cancellation::CancellationToken;
+pub use crate::timer::runtime_trace::heartbeat::StallPolicy;
 pub use crate::timer::runtime_trace::state::instance;
 pub use crate::timer::runtime_trace::task_handle::DelayTaskHandler;
-pub use crate::timer::runtime_trace::task_instance::{Instance, TaskInstance, TaskInstancesChain};
+pub use crate::timer::runtime_trace::task_instance::{
+    AbortedInstance, CancelOriginator, CancellationPath, Instance, MergedInstanceStream,
+    TaskInstance, TaskInstancesChain, TerminationReason,
+};
+pub use crate::timer::runtime_trace::task_logger::{TaskLogger, DEFAULT_TASK_LOG_TARGET};
+pub use crate::timer::spawn_hook::{SpawnDecision, SpawnHook, TaskMeta};
+pub use crate::timer::job_queue::EnqueuedJob;
+pub use crate::timer::job_state::{MemoryStateStore, StateStore};
+pub use crate::timer::memory::GroupMemory;
+pub use crate::utils::secrets::{SecretString, SecretValue, SecretsProvider};
 pub use crate::timer::task::TaskContext;
 pub use crate::timer::task::{
-    FrequencyCronStr as Frequency, ScheduleIteratorTimeZone, Task, TaskBuilder,
+    AdvanceOptions, AdvanceOutcome, Alignment, Budget, EventSampling, Execution, Failure,
+    FrequencyCronStr as Frequency, NotReadyPolicy, Readiness, RuntimeWarningThreshold,
+    ScheduleIteratorTimeZone, SchedulePolicy, SchedulePolicyClone, Task, TaskBuilder,
+    TaskFrequency,
+};
+pub use crate::timer::tenant::{MaxConcurrentInstances, MaxTasks, TenantQuota, TenantUsage};
+pub use crate::timer::view::{SchedulerSnapshot, SchedulerView, TaskView};
+pub use crate::timer::timer_core::{
+    CountDownPolicy, FinishOutput, FinishTaskBody, MisfirePolicy, TimerEvent,
 };
-pub use crate::timer::timer_core::{FinishOutput, FinishTaskBody, TimerEvent};
+#[cfg(feature = "debug-dump")]
+pub use crate::timer::wheel_dump::{SlotDump, WheelDump};
 
 pub use crate::utils::convenience::cron_expression_grammatical_candy::{
     CandyCron, CandyCronStr, CandyFrequency,
@@ -27,9 +65,12 @@ pub use crate::utils::convenience::cron_expression_grammatical_candy::{
 pub use crate::utils::convenience::functions::{
     create_default_delay_task_handler, create_delay_task_handler,
 };
+pub use crate::utils::runtime_adapter::{RuntimeAdapter, SmolAdapter, TokioAdapter};
 
 pub use anyhow::{anyhow, Result as AnyResult};
+pub use chrono::{DateTime, Weekday};
 pub use cron_clock::{self, error as cron_error, FixedOffset, Local, TimeZone, Utc};
+pub use log::Level as LogLevel;
 pub use smol::channel;
 pub use smol::future as future_lite;
 pub use smol::spawn as async_spawn_by_smol;
@@ -50,7 +91,7 @@ pub(crate) use crate::timer::task::Routine;
 
 pub(crate) use crate::utils::parse::shell_command::{ChildGuard, ChildGuardList, ChildUnify};
 pub(crate) use dashmap::DashMap;
-pub(crate) use log::{debug, error, info, trace};
+pub(crate) use log::{debug, error, info, trace, warn};
 pub(crate) use smol::channel::{unbounded, Receiver as AsyncReceiver, Sender as AsyncSender};
 pub(crate) use smol::future::yield_now;
 pub(crate) use smol::lock::Mutex as AsyncMutex;
@@ -74,8 +115,9 @@ pub use tokio::task::{
 pub use tokio::time::sleep as sleep_by_tokio;
 
 cfg_status_report!(
-    pub use crate::utils::status_report::PublicEvent;
+    pub use crate::utils::status_report::{HistoryFormat, PublicEvent};
     pub(crate) use crate::utils::status_report::GLOBAL_STATUS_REPORTER;
+    pub(crate) use crate::utils::status_report::log_public_event;
 );
 
 #[cfg(target_family = "unix")]
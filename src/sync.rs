@@ -0,0 +1,184 @@
+//! Sync
+//! Primitives shared by tasks to coordinate their instances,
+//! e.g. a pool of permits bounding how many of them run at once.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A pool of permits attached to tasks by `TaskBuilder::set_semaphore`,
+/// at most as many instances of the attached tasks as there are permits run at once,
+/// e.g. "max 4 concurrent backups" across all the backup tasks.
+///
+/// A fire finding no free permit is held back and retried on the next tick,
+/// until `max_queue_delay` is over if the task has one.
+/// The permit is given back once the instance ends, is cancelled or times out.
+///
+/// Clones share the same permits.
+#[derive(Debug, Clone)]
+pub struct TaskSemaphore {
+    permits: usize,
+    fairness: Fairness,
+    state: Arc<Mutex<SemaphoreState>>,
+}
+
+/// How a freed permit is handed over to the fires held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fairness {
+    /// The fires held back the longest go first, the fires coming after them wait their turn.
+    #[default]
+    Fifo,
+    /// The first fire to find a free permit takes it,
+    /// a task firing often may keep the others waiting.
+    Barging,
+}
+
+#[derive(Debug, Default)]
+struct SemaphoreState {
+    // The permits taken by running instances.
+    held: usize,
+    // The tasks whose pending fire is held back, first come first served.
+    waiting: VecDeque<u64>,
+}
+
+// A permit taken by a running instance, it's given back once dropped.
+#[derive(Debug)]
+pub(crate) struct SemaphorePermit(TaskSemaphore);
+
+impl Drop for SemaphorePermit {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.held = state.held.saturating_sub(1);
+    }
+}
+
+impl TaskSemaphore {
+    /// New a semaphore with `permits` permits, handed over first come first served.
+    pub fn new(permits: usize) -> Self {
+        TaskSemaphore {
+            permits,
+            fairness: Fairness::default(),
+            state: Arc::new(Mutex::new(SemaphoreState::default())),
+        }
+    }
+
+    /// Set how a freed permit is handed over (optional), `Fairness::Fifo` by default.
+    pub fn fairness(mut self, fairness: Fairness) -> Self {
+        self.fairness = fairness;
+        self
+    }
+
+    /// Get the number of permits.
+    pub fn permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Get the number of permits no instance holds.
+    pub fn available_permits(&self) -> usize {
+        self.permits.saturating_sub(self.lock().held)
+    }
+
+    // Whether the pending fire of the task may take a permit now.
+    pub(crate) fn allows(&self, task_id: u64) -> bool {
+        let state = self.lock();
+        let available = self.permits.saturating_sub(state.held);
+        match self.fairness {
+            Fairness::Fifo => Self::position(&state, task_id) < available,
+            Fairness::Barging => available > 0,
+        }
+    }
+
+    // How many running instances hold a permit.
+    pub(crate) fn held(&self) -> u64 {
+        self.lock().held as u64
+    }
+
+    // How many fires come before the pending fire of the task, the running instances included.
+    pub(crate) fn ahead_of(&self, task_id: u64) -> u64 {
+        let state = self.lock();
+        (state.held + Self::position(&state, task_id)) as u64
+    }
+
+    // The pending fire of the task is held back, it keeps its place in the line.
+    pub(crate) fn wait(&self, task_id: u64) {
+        let mut state = self.lock();
+        if !state.waiting.contains(&task_id) {
+            state.waiting.push_back(task_id);
+        }
+    }
+
+    // The pending fire of the task goes to the front of the line.
+    pub(crate) fn jump(&self, task_id: u64) {
+        let mut state = self.lock();
+        state.waiting.retain(|&waiting_id| waiting_id != task_id);
+        state.waiting.push_front(task_id);
+    }
+
+    pub(crate) fn leave(&self, task_id: u64) {
+        self.lock()
+            .waiting
+            .retain(|&waiting_id| waiting_id != task_id);
+    }
+
+    pub(crate) fn acquire(&self, task_id: u64) -> SemaphorePermit {
+        let mut state = self.lock();
+        state.waiting.retain(|&waiting_id| waiting_id != task_id);
+        state.held += 1;
+        SemaphorePermit(self.clone())
+    }
+
+    fn position(state: &SemaphoreState, task_id: u64) -> usize {
+        state
+            .waiting
+            .iter()
+            .position(|&waiting_id| waiting_id == task_id)
+            .unwrap_or(state.waiting.len())
+    }
+
+    // The state stays consistent even if a holder panicked.
+    fn lock(&self) -> MutexGuard<'_, SemaphoreState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fairness, TaskSemaphore};
+
+    #[test]
+    fn test_task_semaphore() {
+        let semaphore = TaskSemaphore::new(2);
+        let shared = semaphore.clone();
+
+        let first = semaphore.acquire(1);
+        assert!(shared.allows(2));
+        let second = shared.acquire(2);
+        assert_eq!(semaphore.available_permits(), 0);
+        assert!(!semaphore.allows(3));
+
+        // The fires held back go first come first served.
+        semaphore.wait(3);
+        semaphore.wait(4);
+        assert_eq!(semaphore.ahead_of(4), 3);
+        drop(first);
+        assert!(!semaphore.allows(4));
+        assert!(semaphore.allows(3));
+        let _third = semaphore.acquire(3);
+        drop(second);
+        assert!(semaphore.allows(4));
+        assert!(!semaphore.allows(5));
+
+        // A manual run jumping the queue goes ahead of the fires held back.
+        semaphore.jump(6);
+        assert_eq!(semaphore.ahead_of(6), 1);
+        assert_eq!(semaphore.held(), 1);
+        assert!(semaphore.allows(6));
+        assert!(!semaphore.allows(4));
+
+        let barging = TaskSemaphore::new(1).fairness(Fairness::Barging);
+        barging.wait(1);
+        assert!(barging.allows(2));
+        let _permit = barging.acquire(2);
+        assert!(!barging.allows(1));
+    }
+}
@@ -0,0 +1,321 @@
+//! Testing
+//! Simulation of the fires of a task set across a time range, for asserting
+//! scheduling behavior in test suites without timing-sensitive sleeps.
+//!
+//! With the `status-report` feature, the public events of a running timer can be
+//! collected by an `EventCollector` and asserted on by `assert_fired!` and `assert_not_fired!`.
+use crate::planner::{FireDecision, FireState, Planner};
+use crate::prelude::*;
+use crate::timer::TaskMark;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+#[cfg(feature = "status-report")]
+use crate::utils::status_report::StatusReporter;
+#[cfg(feature = "status-report")]
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+#[cfg(feature = "status-report")]
+use std::time::Instant;
+
+/// A task to simulate, and how long its instances are assumed to run.
+#[derive(Debug)]
+pub struct SimulatedTask {
+    /// The task.
+    pub task: Task,
+    /// How long each instance runs, in seconds,
+    /// it's cut short by the `maximum_running_time` of the task.
+    pub run_time: u64,
+}
+
+impl From<Task> for SimulatedTask {
+    fn from(task: Task) -> Self {
+        SimulatedTask { task, run_time: 0 }
+    }
+}
+
+/// A fire the scheduler would produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirePlan {
+    /// The id of the task.
+    pub task_id: u64,
+    /// The time the fire was scheduled for.
+    pub scheduled_time: u64,
+    /// The time the fire runs, or is skipped, later than scheduled if it was queued.
+    pub fire_time: u64,
+    /// Why the fire doesn't run, `None` if it runs.
+    pub skipped_reason: Option<SkipReason>,
+}
+
+/// Simulate the fires of `configs` from `from` to `to` (timestamp-seconds, inclusive).
+///
+/// The overlap policies, budgets and exclusion schedules of the tasks are respected.
+/// The schedule of a task starts when it's built,
+/// so `from` should not be earlier than the tasks.
+/// A task with no fire in the range produces nothing.
+pub fn simulate<C: Into<SimulatedTask>>(
+    configs: impl IntoIterator<Item = C>,
+    from: u64,
+    to: u64,
+) -> Vec<FirePlan> {
+    let mut planner = Planner::new();
+    let mut simulated: HashMap<u64, (TaskMark, u64, Option<u64>)> = HashMap::new();
+
+    for config in configs {
+        let SimulatedTask { task, run_time } = config.into();
+        let task_id = task.task_id;
+        let maximum_running_time = task.get_maximum_running_time(0);
+
+        let mut task_mark = TaskMark::default();
+        task_mark.set_task_id(task_id).set_budget(task.budget);
+
+        if planner.add_task(task, from).is_ok() {
+            simulated.insert(task_id, (task_mark, run_time, maximum_running_time));
+        }
+    }
+
+    let mut fire_plans = Vec::new();
+    // The running instances as `(end_time, task_id, start_time)`, the earliest end first.
+    let mut running = BinaryHeap::new();
+
+    while let Some(timestamp) = planner.next_due_time().filter(|&due_time| due_time <= to) {
+        while let Some(&Reverse((end_time, task_id, start_time))) = running.peek() {
+            if end_time > timestamp {
+                break;
+            }
+            running.pop();
+
+            if let Some((task_mark, ..)) = simulated.get_mut(&task_id) {
+                task_mark.dec_parallel_runnable_num();
+                task_mark.set_last_finish_time(end_time);
+                task_mark.record_budget_usage(start_time, end_time);
+            }
+        }
+
+        for (task_id, (task_mark, ..)) in simulated.iter() {
+            planner.set_fire_state(
+                *task_id,
+                FireState {
+                    running_instances: task_mark.get_parallel_runnable_num(),
+                    budget_exhausted: task_mark.is_budget_exhausted(timestamp),
+                    queued_since: None,
+                    last_finish_time: task_mark.get_last_finish_time(),
+                    serial_queue_blocked: false,
+                    circuit_open: false,
+                    tenant_saturated: false,
+                    semaphore_exhausted: false,
+                    single_instance_held: false,
+                },
+            );
+        }
+
+        for fire in planner.tick(timestamp) {
+            let skipped_reason = match fire.decision {
+                FireDecision::Queue => continue,
+                FireDecision::Run => None,
+                FireDecision::Skip(skip_reason) => Some(skip_reason),
+            };
+
+            if skipped_reason.is_none() {
+                if let Some((task_mark, run_time, maximum_running_time)) =
+                    simulated.get_mut(&fire.task_id)
+                {
+                    let run_time = maximum_running_time.map_or(*run_time, |maximum_running_time| {
+                        (*run_time).min(maximum_running_time)
+                    });
+
+                    task_mark.inc_parallel_runnable_num();
+                    running.push(Reverse((timestamp + run_time, fire.task_id, timestamp)));
+                }
+            }
+
+            fire_plans.push(FirePlan {
+                task_id: fire.task_id,
+                scheduled_time: fire.scheduled_time,
+                fire_time: fire.timestamp,
+                skipped_reason,
+            });
+        }
+    }
+
+    fire_plans
+}
+
+cfg_status_report!(
+    /// Drains the public events of a `StatusReporter` into a `Vec`,
+    /// each with the time it was collected at, relative to the creation of the collector.
+    ///
+    /// The events are drained by a background thread, which exits
+    /// once the collector is dropped or the reporter is closed.
+    #[derive(Debug)]
+    pub struct EventCollector {
+        inner: Arc<CollectedEvents>,
+    }
+
+    #[derive(Debug, Default)]
+    struct CollectedEvents {
+        events: Mutex<Vec<(Duration, PublicEvent)>>,
+        new_event: Condvar,
+    }
+
+    impl EventCollector {
+        /// New a collector draining `status_reporter`, see `DelayTimer::take_status_reporter`.
+        pub fn new(status_reporter: StatusReporter) -> Self {
+            let inner = Arc::new(CollectedEvents::default());
+            let collected_events = Arc::downgrade(&inner);
+            let start = Instant::now();
+
+            std::thread::Builder::new()
+                .name("event_collector".into())
+                .spawn(move || drain(status_reporter, collected_events, start))
+                .expect("event_collector can't start.");
+
+            EventCollector { inner }
+        }
+
+        /// Get the events collected so far, oldest first.
+        pub fn events(&self) -> Vec<(Duration, PublicEvent)> {
+            self.inner.lock().clone()
+        }
+
+        /// Get how many instances of `task_id` started so far.
+        pub fn fire_count(&self, task_id: u64) -> usize {
+            fire_count(&self.inner.lock(), task_id)
+        }
+
+        /// Wait until `condition` holds for the events collected so far, for at most `within`.
+        ///
+        /// Whether `condition` holds is returned.
+        pub fn wait_until(
+            &self,
+            within: Duration,
+            mut condition: impl FnMut(&[(Duration, PublicEvent)]) -> bool,
+        ) -> bool {
+            let deadline = Instant::now() + within;
+            let mut events = self.inner.lock();
+
+            loop {
+                if condition(&events) {
+                    return true;
+                }
+
+                let now = Instant::now();
+                if now >= deadline {
+                    return false;
+                }
+                events = self
+                    .inner
+                    .new_event
+                    .wait_timeout(events, deadline - now)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .0;
+            }
+        }
+
+        /// Wait until `task_id` fired `times` times in all, for at most `within`.
+        ///
+        /// The number of fires so far is returned if it falls short.
+        pub fn wait_for_fires(&self, task_id: u64, times: usize, within: Duration) -> Result<(), usize> {
+            if self.wait_until(within, |events| fire_count(events, task_id) >= times) {
+                return Ok(());
+            }
+            Err(self.fire_count(task_id))
+        }
+
+        /// Wait for `within`, and check `task_id` didn't fire meanwhile.
+        ///
+        /// The number of fires meanwhile is returned if there are any.
+        pub fn expect_no_fire(&self, task_id: u64, within: Duration) -> Result<(), usize> {
+            let fired = self.fire_count(task_id);
+            if self.wait_until(within, |events| fire_count(events, task_id) > fired) {
+                return Err(self.fire_count(task_id) - fired);
+            }
+            Ok(())
+        }
+    }
+
+    impl CollectedEvents {
+        // The events stay usable even if a test panicked while holding them.
+        fn lock(&self) -> MutexGuard<'_, Vec<(Duration, PublicEvent)>> {
+            self.events
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+
+    fn drain(status_reporter: StatusReporter, collected_events: Weak<CollectedEvents>, start: Instant) {
+        while let Ok(public_event) = status_reporter.next_public_event_with_wait() {
+            let collected_events = match collected_events.upgrade() {
+                Some(collected_events) => collected_events,
+                None => return,
+            };
+
+            collected_events.lock().push((start.elapsed(), public_event));
+            collected_events.new_event.notify_all();
+        }
+    }
+
+    fn fire_count(events: &[(Duration, PublicEvent)], task_id: u64) -> usize {
+        events
+            .iter()
+            .filter(|(_, public_event)| {
+                matches!(public_event, PublicEvent::RunningTask(id, _) if *id == task_id)
+            })
+            .count()
+    }
+);
+
+/// Assert that a task fires, `times` times in all (at least once by default),
+/// within a `Duration` from now, according to an `EventCollector`.
+///
+/// ```ignore
+/// assert_fired!(collector, task_id, times = 3, within = Duration::from_secs(5));
+/// ```
+///
+/// # Required features
+///
+/// This macro requires the `status-report` feature of the `delay_timer`
+/// crate to be enabled.
+#[cfg(feature = "status-report")]
+#[cfg_attr(docsrs, doc(cfg(feature = "status-report")))]
+#[macro_export]
+macro_rules! assert_fired {
+    ($collector:expr, $task_id:expr, times = $times:expr, within = $within:expr $(,)?) => {{
+        let (task_id, times, within): (u64, usize, std::time::Duration) = ($task_id, $times, $within);
+        if let Err(fired) = $crate::testing::EventCollector::wait_for_fires(&$collector, task_id, times, within) {
+            panic!(
+                "assertion failed: task {} fired {} times, expected {} within {:?}",
+                task_id, fired, times, within
+            );
+        }
+    }};
+    ($collector:expr, $task_id:expr, within = $within:expr $(,)?) => {
+        $crate::assert_fired!($collector, $task_id, times = 1, within = $within)
+    };
+}
+
+/// Assert that a task doesn't fire within a `Duration` from now, according to an `EventCollector`.
+///
+/// The assertion waits for the whole `Duration`.
+///
+/// ```ignore
+/// assert_not_fired!(collector, task_id, within = Duration::from_secs(2));
+/// ```
+///
+/// # Required features
+///
+/// This macro requires the `status-report` feature of the `delay_timer`
+/// crate to be enabled.
+#[cfg(feature = "status-report")]
+#[cfg_attr(docsrs, doc(cfg(feature = "status-report")))]
+#[macro_export]
+macro_rules! assert_not_fired {
+    ($collector:expr, $task_id:expr, within = $within:expr $(,)?) => {{
+        let (task_id, within): (u64, std::time::Duration) = ($task_id, $within);
+        if let Err(fired) = $crate::testing::EventCollector::expect_no_fire(&$collector, task_id, within) {
+            panic!(
+                "assertion failed: task {} fired {} times, expected none within {:?}",
+                task_id, fired, within
+            );
+        }
+    }};
+}
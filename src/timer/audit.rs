@@ -0,0 +1,157 @@
+//! Audit
+//! The trail of the control-plane operations made on a timer: who changed which task,
+//! how and when, along with the configuration it had before.
+use crate::prelude::*;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Pointer};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A control-plane operation on a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    /// `add_task`, `insert_task` and their owned variants.
+    Add,
+    /// `register_task`.
+    Register,
+    /// `update_task` and its owned variant.
+    Update,
+    /// `remove_task` and its owned variant.
+    Remove,
+    /// `remove_task_delayed`, with the grace period.
+    RemoveDelayed(Duration),
+    /// `undo_remove`.
+    UndoRemove,
+    /// `resume_task`.
+    Resume,
+    /// `advance_task` and its variants.
+    Advance,
+    /// `cancel_task` of the instance with the record-id.
+    Cancel(i64),
+}
+
+/// The configuration of a task, as recorded in the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditedConfig {
+    /// The frequency, see `TaskFrequency`.
+    pub frequency: String,
+    /// Maximum execution time (optional).
+    pub maximum_running_time: Option<u64>,
+    /// The maximum number of parallel runs (optional).
+    pub maximum_parallel_runnable_num: Option<u64>,
+    /// The tenant (optional).
+    pub tenant: Option<String>,
+}
+
+impl AuditedConfig {
+    pub(crate) fn new(task: &Task) -> Self {
+        AuditedConfig {
+            frequency: task.frequency().to_string(),
+            maximum_running_time: task.get_maximum_running_time(0),
+            maximum_parallel_runnable_num: task.maximum_parallel_runnable_num,
+            tenant: task.tenant.clone(),
+        }
+    }
+}
+
+/// An entry of the audit trail, see `DelayTimer::audit_log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// The time (timestamp-seconds) of the operation.
+    pub time: u64,
+    /// Who made it, set by `DelayTimer::with_actor` (optional).
+    pub actor: Option<String>,
+    /// The operation.
+    pub operation: AuditOperation,
+    /// The task it was made on.
+    pub task_id: u64,
+    /// The configuration it gave the task, for `Add`, `Register` and `Update`.
+    pub config: Option<AuditedConfig>,
+    /// The configuration the task had before, if it was added through the audited timer.
+    pub previous_config: Option<AuditedConfig>,
+}
+
+/// Persists the audit trail, set by `DelayTimerBuilder::set_audit_sink`.
+///
+/// It's called inline by the control-plane operations, it should be cheap and must not block.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Persist `audit_record`.
+    fn record(&self, audit_record: &AuditRecord);
+}
+
+impl fmt::Debug for dyn AuditSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// The recent audit records, and the last recorded configuration of each task.
+#[derive(Debug, Clone)]
+pub(crate) struct AuditTrail {
+    inner: Arc<Mutex<AuditTrailInner>>,
+    sink: Option<Arc<dyn AuditSink>>,
+}
+
+#[derive(Debug)]
+struct AuditTrailInner {
+    capacity: usize,
+    records: VecDeque<AuditRecord>,
+    configs: HashMap<u64, AuditedConfig>,
+}
+
+impl AuditTrail {
+    pub(crate) fn new(capacity: usize, sink: Option<Arc<dyn AuditSink>>) -> Self {
+        let inner = Arc::new(Mutex::new(AuditTrailInner {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+            configs: HashMap::new(),
+        }));
+        AuditTrail { inner, sink }
+    }
+
+    pub(crate) fn record(
+        &self,
+        actor: Option<&str>,
+        operation: AuditOperation,
+        task_id: u64,
+        config: Option<AuditedConfig>,
+    ) {
+        let mut inner = self.lock();
+        let previous_config = match (operation, config.as_ref()) {
+            (_, Some(config)) => inner.configs.insert(task_id, config.clone()),
+            (AuditOperation::Remove, None) => inner.configs.remove(&task_id),
+            _ => inner.configs.get(&task_id).cloned(),
+        };
+
+        let audit_record = AuditRecord {
+            time: timestamp(),
+            actor: actor.map(str::to_string),
+            operation,
+            task_id,
+            config,
+            previous_config,
+        };
+        if let Some(sink) = self.sink.as_ref() {
+            sink.record(&audit_record);
+        }
+
+        if inner.capacity == 0 {
+            return;
+        }
+        if inner.records.len() == inner.capacity {
+            inner.records.pop_front();
+        }
+        inner.records.push_back(audit_record);
+    }
+
+    pub(crate) fn records(&self) -> Vec<AuditRecord> {
+        self.lock().records.iter().cloned().collect()
+    }
+
+    // The trail stays usable even if a holder panicked.
+    fn lock(&self) -> MutexGuard<'_, AuditTrailInner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
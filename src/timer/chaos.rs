@@ -0,0 +1,96 @@
+//! Chaos
+//! Faults injected into the running instances of a task, so that the retry and
+//! alerting configuration of an application can be verified in staging.
+use std::time::Duration;
+
+/// Faults injected into a percentage of the running instances of a task,
+/// set by `TaskBuilder::set_chaos`.
+///
+/// An instance gets at most one fault, so the percentages should add up to 100 at most.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Chaos {
+    delay_percent: u8,
+    delay: Duration,
+    panic_percent: u8,
+    timeout_percent: u8,
+}
+
+/// A fault injected into a running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChaosFault {
+    /// The instance finishes `Duration` later.
+    Delay(Duration),
+    /// The instance panics instead of finishing.
+    Panic,
+    /// The instance is timed out as soon as it's spawned.
+    Timeout,
+}
+
+impl Chaos {
+    /// Delay the finish of `percent` of the instances by `delay`.
+    pub fn delay(mut self, percent: u8, delay: Duration) -> Self {
+        self.delay_percent = percent.min(100);
+        self.delay = delay;
+        self
+    }
+
+    /// Panic `percent` of the instances, after the routine returns.
+    pub fn panic(mut self, percent: u8) -> Self {
+        self.panic_percent = percent.min(100);
+        self
+    }
+
+    /// Time out `percent` of the instances right after they're spawned,
+    /// the way the scheduler does once `maximum_running_time` is over.
+    pub fn timeout(mut self, percent: u8) -> Self {
+        self.timeout_percent = percent.min(100);
+        self
+    }
+
+    // Roll the dice for a new instance.
+    pub(crate) fn pick(&self) -> Option<ChaosFault> {
+        let roll = fastrand::u8(0..100);
+
+        if roll < self.panic_percent {
+            return Some(ChaosFault::Panic);
+        }
+
+        let roll = roll - self.panic_percent;
+        if roll < self.timeout_percent {
+            return Some(ChaosFault::Timeout);
+        }
+
+        let roll = roll - self.timeout_percent;
+        if roll < self.delay_percent {
+            return Some(ChaosFault::Delay(self.delay));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chaos, ChaosFault};
+    use std::time::Duration;
+
+    #[test]
+    fn test_pick() {
+        assert_eq!(Chaos::default().pick(), None);
+        assert_eq!(Chaos::default().panic(100).pick(), Some(ChaosFault::Panic));
+        assert_eq!(
+            Chaos::default().timeout(100).pick(),
+            Some(ChaosFault::Timeout)
+        );
+        assert_eq!(
+            Chaos::default().delay(100, Duration::from_secs(1)).pick(),
+            Some(ChaosFault::Delay(Duration::from_secs(1)))
+        );
+
+        let chaos = Chaos::default()
+            .panic(20)
+            .timeout(30)
+            .delay(50, Duration::from_secs(1));
+        assert!((0..1000).all(|_| chaos.pick().is_some()));
+    }
+}
@@ -0,0 +1,151 @@
+//! Circuit breaker
+//! A breaker shared by the tasks hitting the same dependency, so that a struggling
+//! downstream isn't hammered by a whole fleet of pollers.
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A breaker attached to tasks by `TaskBuilder::set_circuit_breaker`.
+///
+/// The failures of any attached task count towards tripping it,
+/// once it's open the fires of all the attached tasks are skipped.
+/// When `cool_down` is over a single fire goes through as a probe,
+/// its success closes the breaker and its failure opens it again.
+///
+/// Clones share the same breaker.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u64,
+    cool_down: Duration,
+    state: Arc<Mutex<BreakerState>>,
+}
+
+/// The state of a `CircuitBreaker`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// The fires go through.
+    Closed,
+    /// The fires are skipped.
+    Open,
+    /// A probe is running, the other fires are skipped until it ends.
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakerState {
+    Closed { consecutive_failures: u64 },
+    // Both carry the time (timestamp-seconds) the next probe may go.
+    // A probe that never reports, e.g. a cancelled one, is followed by another.
+    Open { until: u64 },
+    HalfOpen { until: u64 },
+}
+
+impl CircuitBreaker {
+    /// New a closed breaker, tripped by `failure_threshold` failures in a row
+    /// and probing again `cool_down` after it's tripped.
+    pub fn new(failure_threshold: u64, cool_down: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            cool_down,
+            state: Arc::new(Mutex::new(BreakerState::Closed {
+                consecutive_failures: 0,
+            })),
+        }
+    }
+
+    /// Get the current state of the breaker.
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            BreakerState::Closed { .. } => CircuitState::Closed,
+            BreakerState::Open { .. } => CircuitState::Open,
+            BreakerState::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Close the breaker by hand, forgetting the failures so far.
+    pub fn reset(&self) {
+        *self.lock() = BreakerState::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    // Whether a fire at `timestamp` may go through.
+    pub(crate) fn allows(&self, timestamp: u64) -> bool {
+        match *self.lock() {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } | BreakerState::HalfOpen { until } => timestamp >= until,
+        }
+    }
+
+    // A fire went through, it's the probe if the breaker isn't closed.
+    pub(crate) fn on_fire(&self, timestamp: u64) {
+        let mut state = self.lock();
+        if !matches!(*state, BreakerState::Closed { .. }) {
+            *state = BreakerState::HalfOpen {
+                until: timestamp + self.cool_down.as_secs(),
+            };
+        }
+    }
+
+    // The outcome of an ended instance of an attached task.
+    pub(crate) fn record_outcome(&self, failed: bool, timestamp: u64) {
+        let mut state = self.lock();
+        *state = match *state {
+            // The instances started before the breaker was tripped don't sway it.
+            BreakerState::Open { .. } => return,
+            _ if !failed => BreakerState::Closed {
+                consecutive_failures: 0,
+            },
+            BreakerState::Closed {
+                consecutive_failures,
+            } if consecutive_failures + 1 < self.failure_threshold => BreakerState::Closed {
+                consecutive_failures: consecutive_failures + 1,
+            },
+            _ => BreakerState::Open {
+                until: timestamp + self.cool_down.as_secs(),
+            },
+        };
+    }
+
+    // The state stays consistent even if a holder panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitBreaker, CircuitState};
+    use std::time::Duration;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let circuit_breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+        let shared = circuit_breaker.clone();
+
+        circuit_breaker.record_outcome(true, 100);
+        circuit_breaker.record_outcome(false, 101);
+        circuit_breaker.record_outcome(true, 102);
+        assert_eq!(shared.state(), CircuitState::Closed);
+
+        shared.record_outcome(true, 103);
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+        assert!(!circuit_breaker.allows(112));
+
+        // The cool-down is over, a single probe goes through.
+        assert!(circuit_breaker.allows(113));
+        circuit_breaker.on_fire(113);
+        assert_eq!(circuit_breaker.state(), CircuitState::HalfOpen);
+        assert!(!shared.allows(114));
+
+        circuit_breaker.record_outcome(true, 115);
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+        assert!(!circuit_breaker.allows(124));
+
+        circuit_breaker.on_fire(125);
+        circuit_breaker.record_outcome(false, 126);
+        assert_eq!(circuit_breaker.state(), CircuitState::Closed);
+        assert!(shared.allows(126));
+    }
+}
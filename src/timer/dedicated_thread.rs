@@ -0,0 +1,241 @@
+//! Dedicated thread
+//! The pool of OS threads running the instances of the tasks set to `Execution::DedicatedThread`,
+//! each thread runs one instance at a time, away from the async workers.
+//!
+//! The priority and CPU affinity of a thread can be set for the instances of a task,
+//! see `ThreadOptions`.
+use super::local_worker::{abortable_job, LocalJob};
+use crate::prelude::*;
+
+use std::io;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+use std::thread::Builder;
+
+use once_cell::sync::Lazy;
+use smol::channel::unbounded;
+use smol::future::FutureExt;
+use tokio::runtime::Handle as TokioHandle;
+
+// A thread with no instance to run for that long exits.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Scheduling of the dedicated threads running the instances of a task,
+/// set by `TaskBuilder::set_thread_options`.
+///
+/// They're only supported on Linux, elsewhere they're ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThreadOptions {
+    /// Niceness of the thread, from -20 (most favorable) to 19 (least favorable),
+    /// a niceness below the current one usually requires privileges.
+    pub nice: Option<i32>,
+    /// The CPUs the thread is pinned to.
+    pub cpu_affinity: Option<Vec<usize>>,
+}
+
+// The scheduling of a thread before `ThreadOptions` were applied to it.
+#[derive(Debug)]
+struct ThreadSetup {
+    #[cfg(target_os = "linux")]
+    nice: Option<i32>,
+    #[cfg(target_os = "linux")]
+    cpu_set: Option<libc::cpu_set_t>,
+}
+
+impl ThreadOptions {
+    // Failing to apply an option is logged, the instance runs anyway.
+    #[cfg(target_os = "linux")]
+    fn apply(&self) -> ThreadSetup {
+        let tid = current_tid();
+        let mut thread_setup = ThreadSetup {
+            nice: None,
+            cpu_set: None,
+        };
+
+        if let Some(nice) = self.nice {
+            // Safety: the calls only affect the calling thread.
+            let previous_nice = unsafe { libc::getpriority(libc::PRIO_PROCESS as _, tid) };
+            match cvt(unsafe { libc::setpriority(libc::PRIO_PROCESS as _, tid, nice) }) {
+                Ok(()) => thread_setup.nice = Some(previous_nice),
+                Err(e) => warn!("`ThreadOptions::apply`, can't set nice {}: {}", nice, e),
+            }
+        }
+
+        if let Some(cpu_affinity) = self.cpu_affinity.as_ref() {
+            // Safety: `cpu_set_t` is a plain bit-mask, and the calls only affect the calling thread.
+            unsafe {
+                let mut previous_cpu_set: libc::cpu_set_t = std::mem::zeroed();
+                let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+                for &cpu in cpu_affinity {
+                    if cpu < libc::CPU_SETSIZE as usize {
+                        libc::CPU_SET(cpu, &mut cpu_set);
+                    }
+                }
+
+                let cpu_set_size = std::mem::size_of::<libc::cpu_set_t>();
+                let applied = cvt(libc::sched_getaffinity(0, cpu_set_size, &mut previous_cpu_set))
+                    .and_then(|_| cvt(libc::sched_setaffinity(0, cpu_set_size, &cpu_set)));
+                match applied {
+                    Ok(()) => thread_setup.cpu_set = Some(previous_cpu_set),
+                    Err(e) => warn!(
+                        "`ThreadOptions::apply`, can't set cpu-affinity {:?}: {}",
+                        cpu_affinity, e
+                    ),
+                }
+            }
+        }
+
+        thread_setup
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn apply(&self) -> ThreadSetup {
+        warn!("`ThreadOptions` are only supported on Linux, they're ignored.");
+        ThreadSetup {}
+    }
+}
+
+impl ThreadSetup {
+    // Put the thread back as it was, so that it can run the instances of other tasks.
+    #[cfg(target_os = "linux")]
+    fn restore(self) -> io::Result<()> {
+        // Safety: the calls only affect the calling thread.
+        unsafe {
+            if let Some(nice) = self.nice {
+                cvt(libc::setpriority(libc::PRIO_PROCESS as _, current_tid(), nice))?;
+            }
+
+            if let Some(cpu_set) = self.cpu_set {
+                let cpu_set_size = std::mem::size_of::<libc::cpu_set_t>();
+                cvt(libc::sched_setaffinity(0, cpu_set_size, &cpu_set))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn restore(self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// On Linux, the priority of a thread is set through its thread-id.
+#[cfg(target_os = "linux")]
+fn current_tid() -> libc::id_t {
+    // Safety: `gettid` can't fail.
+    unsafe { libc::syscall(libc::SYS_gettid) as libc::id_t }
+}
+
+#[cfg(target_os = "linux")]
+fn cvt(ret: libc::c_int) -> io::Result<()> {
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// An instance to run on a dedicated thread.
+struct DedicatedJob {
+    thread_options: Option<ThreadOptions>,
+    // The context of the tokio runtime the instance was spawned on, if any, is entered while it runs.
+    tokio_handle: Option<TokioHandle>,
+    local_job: LocalJob,
+}
+
+static DEDICATED_THREADS: Lazy<DedicatedThreads> = Lazy::new(|| {
+    let (job_sender, job_receiver) = unbounded();
+
+    DedicatedThreads {
+        job_sender,
+        job_receiver,
+        idle_threads: AtomicUsize::new(0),
+    }
+});
+
+struct DedicatedThreads {
+    job_sender: AsyncSender<DedicatedJob>,
+    job_receiver: AsyncReceiver<DedicatedJob>,
+    // The threads waiting for a job that no job was sent for yet.
+    idle_threads: AtomicUsize,
+}
+
+impl DedicatedThreads {
+    // A new thread is started when none is idle.
+    fn submit(&'static self, dedicated_job: DedicatedJob) -> AnyResult<()> {
+        if self.take_idle_thread().is_none() {
+            self.spawn_thread()?;
+        }
+
+        self.job_sender
+            .try_send(dedicated_job)
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    fn take_idle_thread(&self) -> Option<usize> {
+        self.idle_threads
+            .fetch_update(AcqRel, Acquire, |idle_threads| idle_threads.checked_sub(1))
+            .ok()
+    }
+
+    fn spawn_thread(&'static self) -> AnyResult<()> {
+        Builder::new()
+            .name("dedicated_instance".into())
+            .spawn(move || loop {
+                let next_job = async { self.job_receiver.recv().await.ok() };
+                let idle_timeout = async {
+                    AsyncTimer::after(IDLE_TIMEOUT).await;
+                    None
+                };
+                let dedicated_job = smol::block_on(next_job.or(idle_timeout));
+
+                match dedicated_job {
+                    Some(dedicated_job) => {
+                        let thread_setup =
+                            dedicated_job.thread_options.as_ref().map(ThreadOptions::apply);
+                        {
+                            let _tokio_context =
+                                dedicated_job.tokio_handle.as_ref().map(TokioHandle::enter);
+                            smol::block_on((dedicated_job.local_job)());
+                        }
+
+                        // A thread that can't be put back as it was isn't reused.
+                        if let Err(e) = thread_setup.map_or(Ok(()), ThreadSetup::restore) {
+                            warn!("`dedicated_instance` exits, it can't be restored: {}", e);
+                            break;
+                        }
+                        self.idle_threads.fetch_add(1, AcqRel);
+                    }
+
+                    // A job may have been sent for this thread meanwhile, it stays then.
+                    None if self.take_idle_thread().is_some() => break,
+                    None => {}
+                }
+            })?;
+
+        Ok(())
+    }
+}
+
+// Run the future made by `instance` on a dedicated thread, until it's over or the returned future is dropped.
+//
+// The context of the tokio runtime it's called from, if any, is entered by the thread,
+// so that the instance can use the tokio timers and IO.
+pub(crate) async fn run_on_dedicated_thread(
+    instance: LocalJob,
+    thread_options: Option<ThreadOptions>,
+) {
+    let (local_job, _abort_on_drop, finish_receiver) = abortable_job(instance);
+    let dedicated_job = DedicatedJob {
+        thread_options,
+        tokio_handle: TokioHandle::try_current().ok(),
+        local_job,
+    };
+
+    if let Err(e) = DEDICATED_THREADS.submit(dedicated_job) {
+        error!("`run_on_dedicated_thread`: {}", e);
+        return;
+    }
+
+    finish_receiver.await.ok();
+}
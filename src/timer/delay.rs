@@ -0,0 +1,222 @@
+//! Delay
+//! Ad-hoc timeouts woken up by the ticks of the wheel,
+//! for the applications with so many of them that an OS timer each is too costly,
+//! see `DelayTimer::delay_for` and `delay_for`,
+//! or `DelaySource` to be generic over where the delays come from.
+use crate::prelude::*;
+
+use event_listener::Event;
+use once_cell::sync::Lazy;
+
+use std::collections::BTreeMap;
+use std::mem;
+use std::pin::Pin;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+// The delays of the timer behind `delay_for`, started on its first call.
+// The timer-core loop keeps running once the timer is dropped.
+static DEFAULT_DELAYS: Lazy<Arc<Delays>> = Lazy::new(|| {
+    DelayTimerBuilder::default()
+        .smol_runtime_by_default()
+        .build()
+        .delays()
+});
+
+/// A future that completes once `duration` is over,
+/// woken up by the wheel of a timer shared by the whole process.
+///
+/// The wheel ticks by the second, so the delay is rounded up to the next tick:
+/// it never completes early, but it may complete up to a second late.
+/// See `DelayTimer::delay_for` to wake it up by the wheel of a given timer.
+pub fn delay_for(duration: Duration) -> DelayFuture {
+    Delays::delay_for(&DEFAULT_DELAYS, duration)
+}
+
+/// Where the delays come from, in place of an OS timer each as `futures-timer` has it,
+/// for the code taking timeouts to be written once for any of them.
+///
+/// It's implemented for `DelayTimer`, woken up by its wheel, and for `SharedWheel`,
+/// woken up by the wheel shared by the whole process.
+/// An application can implement it on top of another timer, e.g. to keep precise timeouts in tests.
+pub trait DelaySource {
+    /// The future that completes once the delay is over.
+    type Delay: Future<Output = ()> + Send + 'static;
+
+    /// A future that completes once `duration` is over.
+    fn delay_for(&self, duration: Duration) -> Self::Delay;
+}
+
+impl DelaySource for DelayTimer {
+    type Delay = DelayFuture;
+
+    fn delay_for(&self, duration: Duration) -> DelayFuture {
+        DelayTimer::delay_for(self, duration)
+    }
+}
+
+/// The wheel shared by the whole process, the one behind `delay_for`, as a `DelaySource`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SharedWheel;
+
+impl DelaySource for SharedWheel {
+    type Delay = DelayFuture;
+
+    fn delay_for(&self, duration: Duration) -> DelayFuture {
+        delay_for(duration)
+    }
+}
+
+/// A future that completes once its delay is over, see `delay_for`.
+///
+/// Dropping it before it completes takes it out of the wheel.
+#[derive(Debug)]
+pub struct DelayFuture {
+    delays: Arc<Delays>,
+    key: (u64, u64),
+    state: Arc<DelayState>,
+}
+
+impl Future for DelayFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.state.fired.load(Acquire) {
+            return Poll::Ready(());
+        }
+
+        *self.state.lock_waker() = Some(cx.waker().clone());
+        // It may fire while the waker is put in place.
+        if self.state.fired.load(Acquire) {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for DelayFuture {
+    fn drop(&mut self) {
+        if !self.state.fired.load(Acquire) {
+            self.delays.lock_pending().remove(&self.key);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DelayState {
+    fired: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl DelayState {
+    fn fire(&self) {
+        self.fired.store(true, Release);
+        if let Some(waker) = self.lock_waker().take() {
+            waker.wake();
+        }
+    }
+
+    fn lock_waker(&self) -> MutexGuard<'_, Option<Waker>> {
+        self.waker
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Delays {
+    // The pending delays by the second they are due at, and an id.
+    pending: Mutex<BTreeMap<(u64, u64), Arc<DelayState>>>,
+    next_id: AtomicU64,
+    // Wakes the sleeping timer-core loop up, for a delay due before it wakes up.
+    rearm: Arc<Event>,
+}
+
+impl Delays {
+    pub(crate) fn new(rearm: Arc<Event>) -> Self {
+        Delays {
+            pending: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(0),
+            rearm,
+        }
+    }
+
+    pub(crate) fn delay_for(delays: &Arc<Delays>, duration: Duration) -> DelayFuture {
+        let due_micros = timestamp_micros() + duration.as_micros();
+        let due = ((due_micros + 999_999) / 1_000_000) as u64;
+        let key = (due, delays.next_id.fetch_add(1, Relaxed));
+        let state = Arc::new(DelayState::default());
+
+        delays.lock_pending().insert(key, state.clone());
+        delays.rearm.notify(usize::MAX);
+
+        DelayFuture {
+            delays: delays.clone(),
+            key,
+            state,
+        }
+    }
+
+    // Wake up the delays due by `timestamp`.
+    pub(crate) fn wake_due(&self, timestamp: u64) {
+        let due = {
+            let mut pending = self.lock_pending();
+            let later = pending.split_off(&(timestamp + 1, 0));
+            mem::replace(&mut *pending, later)
+        };
+
+        for state in due.into_values() {
+            state.fire();
+        }
+    }
+
+    // The ticks until the next delay is due, `u64::MAX` if none is pending.
+    pub(crate) fn ticks_until_next(&self, timestamp: u64) -> u64 {
+        self.lock_pending()
+            .keys()
+            .next()
+            .map_or(u64::MAX, |(due, _)| due.saturating_sub(timestamp).max(1))
+    }
+
+    // The pending delays stay consistent even if a holder panicked.
+    fn lock_pending(&self) -> MutexGuard<'_, BTreeMap<(u64, u64), Arc<DelayState>>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Delays;
+    use crate::prelude::*;
+
+    use event_listener::Event;
+    use futures::FutureExt;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn test_wake_due() {
+        let delays = Arc::new(Delays::new(Arc::new(Event::new())));
+        let now = timestamp();
+        let mut first = Delays::delay_for(&delays, Duration::from_secs(2));
+        let second = Delays::delay_for(&delays, Duration::from_secs(30));
+        assert!(delays.ticks_until_next(now) >= 2);
+        assert!((&mut first).now_or_never().is_none());
+
+        // The delay is rounded up, it isn't due a tick early.
+        delays.wake_due(now + 1);
+        assert!((&mut first).now_or_never().is_none());
+
+        delays.wake_due(now + 4);
+        assert!(first.now_or_never().is_some());
+        assert_eq!(delays.lock_pending().len(), 1);
+
+        drop(second);
+        assert!(delays.lock_pending().is_empty());
+        assert_eq!(delays.ticks_until_next(now), u64::MAX);
+    }
+}
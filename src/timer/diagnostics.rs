@@ -0,0 +1,271 @@
+//! Diagnostics
+//! Warnings about the configurations of tasks that won't behave as they read,
+//! found by `DelayTimer::analyze` or `analyze_tasks` before the tasks get to run.
+use crate::prelude::*;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// How many upcoming fires are walked to tell the interval of a schedule.
+const SAMPLED_FIRES: usize = 8;
+const ONE_HOUR: u64 = 3600;
+
+/// A misconfiguration of one or more tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// The instances may run longer than the fires are apart,
+    /// while `maximum_parallel_runnable_num` keeps them from overlapping,
+    /// so the fires meanwhile are skipped or queued.
+    RunsOutlastInterval {
+        /// The id of the task.
+        task_id: u64,
+        /// The maximum running time of an instance, in seconds.
+        maximum_running_time: u64,
+        /// The shortest time between two fires, in seconds.
+        interval: u64,
+        /// How many instances may run at once.
+        maximum_parallel_runnable_num: u64,
+    },
+    /// Several tasks have the same id, only one of them is kept by the timer.
+    DuplicateTaskId(u64),
+    /// The schedule of the task has no fire ahead,
+    /// e.g. a cron-expression for the 30th of February.
+    NeverFires(u64),
+    /// The tasks of a serial queue take longer to run than the time between their fires,
+    /// so the queue grows without bound.
+    SerialQueueBacklog {
+        /// The name of the queue.
+        queue: String,
+        /// The tasks running in the queue.
+        task_ids: Vec<u64>,
+        /// The seconds of runs the fires of an hour ask of the queue, more than the hour has.
+        run_seconds_per_hour: u64,
+    },
+}
+
+impl Diagnostic {
+    /// The ids of the tasks the diagnostic is about.
+    pub fn task_ids(&self) -> Vec<u64> {
+        match self {
+            Diagnostic::RunsOutlastInterval { task_id, .. }
+            | Diagnostic::DuplicateTaskId(task_id)
+            | Diagnostic::NeverFires(task_id) => vec![*task_id],
+            Diagnostic::SerialQueueBacklog { task_ids, .. } => task_ids.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::RunsOutlastInterval {
+                task_id,
+                maximum_running_time,
+                interval,
+                maximum_parallel_runnable_num,
+            } => write!(
+                f,
+                "task {} may run for {}s while its fires are {}s apart, with at most {} instance(s) at once",
+                task_id, maximum_running_time, interval, maximum_parallel_runnable_num
+            ),
+            Diagnostic::DuplicateTaskId(task_id) => {
+                write!(f, "task id {} is used by several tasks", task_id)
+            }
+            Diagnostic::NeverFires(task_id) => write!(f, "task {} never fires", task_id),
+            Diagnostic::SerialQueueBacklog {
+                queue,
+                task_ids,
+                run_seconds_per_hour,
+            } => write!(
+                f,
+                "serial queue `{}` is asked for {}s of runs per hour by tasks {:?}",
+                queue, run_seconds_per_hour, task_ids
+            ),
+        }
+    }
+}
+
+// What the analysis needs to know of a task, taken while the task is at hand.
+#[derive(Debug, Clone)]
+pub(crate) struct TaskProfile {
+    task_id: u64,
+    never_fires: bool,
+    interval: Option<u64>,
+    maximum_running_time: Option<u64>,
+    // How long an instance is expected to run, the maximum running time if nothing better is known.
+    expected_running_time: Option<u64>,
+    maximum_parallel_runnable_num: Option<u64>,
+    serial_queue: Option<String>,
+}
+
+impl TaskProfile {
+    pub(crate) fn new(task: &Task, now: DateTime<Utc>, p95_duration: Option<u64>) -> Self {
+        let maximum_running_time = task.get_maximum_running_time(0);
+        let mut fires = Vec::with_capacity(SAMPLED_FIRES);
+        let mut after = now;
+        while fires.len() < SAMPLED_FIRES {
+            match task.next_fire_after(after) {
+                Some(fire) => {
+                    fires.push(fire.timestamp());
+                    after = fire;
+                }
+                None => break,
+            }
+        }
+
+        TaskProfile {
+            task_id: task.task_id,
+            // A supervised task has no schedule to tell.
+            never_fires: fires.is_empty() && !task.is_keep_alive(),
+            interval: fires
+                .windows(2)
+                .map(|window| (window[1] - window[0]) as u64)
+                .min(),
+            maximum_running_time,
+            expected_running_time: p95_duration.or(maximum_running_time),
+            maximum_parallel_runnable_num: task.maximum_parallel_runnable_num,
+            serial_queue: task.serial_queue.clone(),
+        }
+    }
+}
+
+/// Look for misconfigurations in a set of tasks, before they're added to a timer.
+///
+/// For the tasks a timer already has, see `DelayTimer::analyze`.
+pub fn analyze_tasks<'a>(tasks: impl IntoIterator<Item = &'a Task>) -> Vec<Diagnostic> {
+    let now = Utc::now();
+    analyze(
+        tasks
+            .into_iter()
+            .map(|task| TaskProfile::new(task, now, None))
+            .collect(),
+    )
+}
+
+pub(crate) fn analyze(profiles: Vec<TaskProfile>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut task_id_count: BTreeMap<u64, usize> = BTreeMap::new();
+    for profile in profiles.iter() {
+        *task_id_count.entry(profile.task_id).or_default() += 1;
+    }
+    diagnostics.extend(
+        task_id_count
+            .into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|(task_id, _)| Diagnostic::DuplicateTaskId(task_id)),
+    );
+
+    for profile in profiles.iter() {
+        if profile.never_fires {
+            diagnostics.push(Diagnostic::NeverFires(profile.task_id));
+        }
+
+        if let (Some(maximum_running_time), Some(interval), Some(maximum_parallel_runnable_num)) = (
+            profile.maximum_running_time,
+            profile.interval,
+            profile.maximum_parallel_runnable_num,
+        ) {
+            if maximum_running_time > interval * maximum_parallel_runnable_num {
+                diagnostics.push(Diagnostic::RunsOutlastInterval {
+                    task_id: profile.task_id,
+                    maximum_running_time,
+                    interval,
+                    maximum_parallel_runnable_num,
+                });
+            }
+        }
+    }
+
+    let mut serial_queues: HashMap<&str, Vec<&TaskProfile>> = HashMap::new();
+    for profile in profiles.iter() {
+        if let Some(queue) = profile.serial_queue.as_deref() {
+            serial_queues.entry(queue).or_default().push(profile);
+        }
+    }
+    let mut backlogs: Vec<Diagnostic> = serial_queues
+        .into_iter()
+        .filter_map(|(queue, profiles)| {
+            // The runs of a task without an interval don't recur, they don't hold the queue up for good.
+            let run_seconds_per_hour = profiles
+                .iter()
+                .filter_map(|profile| {
+                    Some(profile.expected_running_time? * ONE_HOUR / profile.interval?)
+                })
+                .sum::<u64>();
+            if run_seconds_per_hour <= ONE_HOUR {
+                return None;
+            }
+
+            let mut task_ids: Vec<u64> = profiles.iter().map(|profile| profile.task_id).collect();
+            task_ids.sort_unstable();
+            Some(Diagnostic::SerialQueueBacklog {
+                queue: queue.to_string(),
+                task_ids,
+                run_seconds_per_hour,
+            })
+        })
+        .collect();
+    backlogs.sort_by(|a, b| a.task_ids().cmp(&b.task_ids()));
+    diagnostics.extend(backlogs);
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_tasks, Diagnostic};
+    use crate::prelude::*;
+
+    fn task(task_id: u64, seconds: u64) -> TaskBuilder<'static> {
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_task_id(task_id)
+            .set_frequency_repeated_by_seconds(seconds);
+        task_builder
+    }
+
+    #[test]
+    fn test_analyze_tasks() -> AnyResult<()> {
+        let overlapping = task(1, 10)
+            .set_maximum_running_time(30)
+            .set_maximum_parallel_runnable_num(1)
+            .spawn_routine(|| {})?;
+        let duplicate = task(1, 60).spawn_routine(|| {})?;
+        let never_fires = TaskBuilder::default()
+            .set_task_id(2)
+            .set_frequency_repeated_by_cron_str("0 0 0 30 2 *")
+            .spawn_routine(|| {})?;
+        let queued = task(3, 10)
+            .set_maximum_running_time(6)
+            .set_serial_queue("reports")
+            .spawn_routine(|| {})?;
+        let other_queued = task(4, 20)
+            .set_maximum_running_time(10)
+            .set_serial_queue("reports")
+            .spawn_routine(|| {})?;
+
+        let diagnostics =
+            analyze_tasks(&[overlapping, duplicate, never_fires, queued, other_queued]);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::DuplicateTaskId(1),
+                Diagnostic::RunsOutlastInterval {
+                    task_id: 1,
+                    maximum_running_time: 30,
+                    interval: 10,
+                    maximum_parallel_runnable_num: 1,
+                },
+                Diagnostic::NeverFires(2),
+                Diagnostic::SerialQueueBacklog {
+                    queue: "reports".to_string(),
+                    task_ids: vec![3, 4],
+                    run_seconds_per_hour: 3960,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+//! Daylight saving
+//! What becomes of the fires of a cron-expression evaluated in the local time zone,
+//! whose wall-clock time is skipped or repeated by a daylight-saving transition.
+use crate::prelude::*;
+
+use std::collections::BTreeSet;
+
+use chrono::{LocalResult, NaiveDateTime, Timelike};
+use cron_clock::Schedule;
+
+// Daylight-saving gaps last a few hours at most, a wall-clock time past that is no gap.
+const MAX_GAP_MINUTES: u32 = 24 * 60;
+
+/// What becomes of a fire at a wall-clock time that's skipped when the clocks spring forward.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum NonexistentTime {
+    /// The fire is dropped.
+    Skip,
+    /// The fire runs at the first valid time after the gap,
+    /// the fires within the same gap run once.
+    RunAtNextValidTime,
+}
+
+/// What becomes of a fire at a wall-clock time that occurs twice when the clocks fall back.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum AmbiguousTime {
+    /// The fire runs at the first occurrence only.
+    RunOnce,
+    /// The fire runs at both occurrences.
+    RunTwice,
+}
+
+/// How the fires of a cron-expression evaluated in the local time zone
+/// go through the daylight-saving transitions, see `TaskBuilder::set_dst_policy`.
+///
+/// By default a skipped time runs at the next valid time, and a repeated time runs once.
+/// UTC and fixed offsets have no transitions, the policy doesn't apply to them.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct DstPolicy {
+    /// The fires at a wall-clock time skipped by spring-forward.
+    pub nonexistent: NonexistentTime,
+    /// The fires at a wall-clock time repeated by fall-back.
+    pub ambiguous: AmbiguousTime,
+}
+
+impl Default for DstPolicy {
+    fn default() -> Self {
+        DstPolicy {
+            nonexistent: NonexistentTime::RunAtNextValidTime,
+            ambiguous: AmbiguousTime::RunOnce,
+        }
+    }
+}
+
+// Walks the cron-expression on the wall clock of `time_zone`,
+// and turns each wall-clock time into the fires the policy asks for.
+#[derive(Debug, Clone)]
+pub(crate) struct DstSchedule<Z: TimeZone> {
+    schedule: Schedule,
+    time_zone: Z,
+    dst_policy: DstPolicy,
+    // The last wall-clock time taken from the schedule.
+    cursor: NaiveDateTime,
+    // The fires computed ahead, the second occurrence of a repeated time
+    // is due after the first occurrences of the wall-clock times following it.
+    queued: BTreeSet<i64>,
+    // The fires to compute are all later than it.
+    horizon: Option<i64>,
+    // The last fire yielded.
+    last_fire: Option<i64>,
+    exhausted: bool,
+}
+
+impl<Z: TimeZone> DstSchedule<Z> {
+    pub(crate) fn new(schedule: Schedule, time_zone: Z, dst_policy: DstPolicy) -> Self {
+        let cursor = Utc::now().with_timezone(&time_zone).naive_local();
+
+        DstSchedule {
+            schedule,
+            time_zone,
+            dst_policy,
+            cursor,
+            queued: BTreeSet::new(),
+            horizon: None,
+            last_fire: None,
+            exhausted: false,
+        }
+    }
+
+    // Start over from now, like `ScheduleIteratorOwned::refresh_previous_datetime`.
+    pub(crate) fn refresh(&mut self) {
+        *self = DstSchedule::new(self.schedule.clone(), self.time_zone.clone(), self.dst_policy);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn starting_at(mut self, cursor: NaiveDateTime) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    pub(crate) fn next(&mut self) -> Option<i64> {
+        loop {
+            match self.queued.iter().next().copied() {
+                Some(fire)
+                    if self.exhausted || self.horizon.is_some_and(|horizon| fire <= horizon) =>
+                {
+                    self.queued.remove(&fire);
+                    if self.last_fire < Some(fire) {
+                        self.last_fire = Some(fire);
+                        return Some(fire);
+                    }
+                }
+                _ if self.exhausted => return None,
+                _ => self.take_wall_clock_time(),
+            }
+        }
+    }
+
+    fn take_wall_clock_time(&mut self) {
+        // The wall-clock times are walked in UTC, which has no transitions.
+        let wall_clock_time = match self
+            .schedule
+            .after(&Utc.from_utc_datetime(&self.cursor))
+            .next()
+        {
+            Some(wall_clock_time) => wall_clock_time.naive_utc(),
+            None => {
+                self.exhausted = true;
+                return;
+            }
+        };
+        self.cursor = wall_clock_time;
+
+        match self.time_zone.from_local_datetime(&wall_clock_time) {
+            LocalResult::Single(fire) => self.queue(fire.timestamp()),
+            LocalResult::Ambiguous(earliest, latest) => {
+                self.queue(earliest.timestamp());
+                if self.dst_policy.ambiguous == AmbiguousTime::RunTwice {
+                    self.queued.insert(latest.timestamp());
+                }
+            }
+            LocalResult::None => {
+                if self.dst_policy.nonexistent == NonexistentTime::RunAtNextValidTime {
+                    if let Some(fire) = self.end_of_gap(wall_clock_time) {
+                        self.queue(fire);
+                    }
+                }
+            }
+        }
+    }
+
+    fn queue(&mut self, fire: i64) {
+        self.queued.insert(fire);
+        self.horizon = Some(fire);
+    }
+
+    // The first valid time after the gap `wall_clock_time` falls in,
+    // the transitions are on the minute.
+    fn end_of_gap(&self, wall_clock_time: NaiveDateTime) -> Option<i64> {
+        let mut candidate = wall_clock_time.with_second(0)?;
+        for _ in 0..MAX_GAP_MINUTES {
+            candidate += chrono::Duration::minutes(1);
+            if let Some(fire) = self.time_zone.from_local_datetime(&candidate).earliest() {
+                return Some(fire.timestamp());
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AmbiguousTime, DstPolicy, DstSchedule, NonexistentTime};
+    use crate::prelude::*;
+
+    use std::str::FromStr;
+
+    use chrono::{LocalResult, NaiveDate, NaiveDateTime, Offset};
+    use cron_clock::Schedule;
+
+    // A time zone at UTC+1, and UTC+2 from 2021-03-28 02:00 to 2021-10-31 03:00 (wall clock),
+    // like central Europe.
+    #[derive(Debug, Clone, Copy)]
+    struct Cet;
+
+    #[derive(Debug, Clone, Copy)]
+    struct CetOffset(i32);
+
+    impl Offset for CetOffset {
+        fn fix(&self) -> FixedOffset {
+            FixedOffset::east_opt(self.0).unwrap()
+        }
+    }
+
+    const WINTER: i32 = 3600;
+    const SUMMER: i32 = 7200;
+
+    fn wall_clock(day: u32, month: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2021, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    impl TimeZone for Cet {
+        type Offset = CetOffset;
+
+        fn from_offset(_offset: &CetOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<CetOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<CetOffset> {
+            if *local >= wall_clock(28, 3, 2, 0) && *local < wall_clock(28, 3, 3, 0) {
+                LocalResult::None
+            } else if *local >= wall_clock(31, 10, 2, 0) && *local < wall_clock(31, 10, 3, 0) {
+                LocalResult::Ambiguous(CetOffset(SUMMER), CetOffset(WINTER))
+            } else if *local >= wall_clock(28, 3, 3, 0) && *local < wall_clock(31, 10, 3, 0) {
+                LocalResult::Single(CetOffset(SUMMER))
+            } else {
+                LocalResult::Single(CetOffset(WINTER))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> CetOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> CetOffset {
+            if *utc >= wall_clock(28, 3, 1, 0) && *utc < wall_clock(31, 10, 1, 0) {
+                CetOffset(SUMMER)
+            } else {
+                CetOffset(WINTER)
+            }
+        }
+    }
+
+    // The fires as UTC wall-clock times `(day, hour, minute)`.
+    fn fires(
+        cron_expression: &str,
+        dst_policy: DstPolicy,
+        from: NaiveDateTime,
+        count: usize,
+    ) -> Vec<(u32, u32, u32)> {
+        use chrono::{Datelike, Timelike};
+
+        let schedule = Schedule::from_str(cron_expression).unwrap();
+        let mut dst_schedule = DstSchedule::new(schedule, Cet, dst_policy).starting_at(from);
+        (0..count)
+            .map(|_| {
+                let fire = Utc.timestamp_opt(dst_schedule.next().unwrap(), 0).unwrap();
+                (fire.day(), fire.hour(), fire.minute())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spring_forward() {
+        let from = wall_clock(28, 3, 1, 0);
+        let every_half_hour = "0 15/30 * * * *";
+
+        // 01:15 and 01:45 CET, then 02:15 and 02:45 don't exist, 03:15 CEST.
+        let skip = DstPolicy {
+            nonexistent: NonexistentTime::Skip,
+            ..Default::default()
+        };
+        assert_eq!(
+            fires(every_half_hour, skip, from, 3),
+            vec![(28, 0, 15), (28, 0, 45), (28, 1, 15)]
+        );
+        assert_eq!(fires("0 30 2 * * *", skip, from, 1), vec![(29, 0, 30)]);
+
+        // Both missing fires run once, at 03:00 CEST (01:00 UTC).
+        assert_eq!(
+            fires(every_half_hour, DstPolicy::default(), from, 4),
+            vec![(28, 0, 15), (28, 0, 45), (28, 1, 0), (28, 1, 15)]
+        );
+        assert_eq!(
+            fires("0 30 2 * * *", DstPolicy::default(), from, 2),
+            vec![(28, 1, 0), (29, 0, 30)]
+        );
+    }
+
+    #[test]
+    fn test_fall_back() {
+        let from = wall_clock(31, 10, 1, 45);
+        let every_half_hour = "0 0/30 * * * *";
+
+        // 02:00 and 02:30 CEST, then 03:00 CET.
+        assert_eq!(
+            fires(every_half_hour, DstPolicy::default(), from, 3),
+            vec![(31, 0, 0), (31, 0, 30), (31, 2, 0)]
+        );
+
+        // 02:00 and 02:30 CEST, 02:00 and 02:30 CET, then 03:00 CET.
+        let twice = DstPolicy {
+            ambiguous: AmbiguousTime::RunTwice,
+            ..Default::default()
+        };
+        assert_eq!(
+            fires(every_half_hour, twice, from, 5),
+            vec![(31, 0, 0), (31, 0, 30), (31, 1, 0), (31, 1, 30), (31, 2, 0)]
+        );
+        assert_eq!(
+            fires("0 30 2 * * *", twice, from, 3),
+            vec![(31, 0, 30), (31, 1, 30), (1, 1, 30)]
+        );
+    }
+}
@@ -8,21 +8,35 @@
 //! 2. A communication center for internal and external workers.
 
 pub(crate) use super::super::entity::{SharedHeader, SharedTaskWheel};
+use super::fire_record::load_last_fire;
+use super::handoff::take_handoff;
+use super::job_queue::{load_jobs, save_jobs};
 use super::runtime_trace::sweeper::{RecycleUnit, RecyclingBins};
 use super::runtime_trace::task_handle::TaskTrace;
+use super::task::{
+    spread_offset, ReadinessState, SafeStructAbortedFn, SafeStructInitFn, SafeStructTeardownFn,
+};
+use super::timer_core::{missed_fire_horizon, ticks_until};
 pub(crate) use super::timer_core::{TimerEvent, DEFAULT_TIMER_SLOT_COUNT};
 use super::{Slot, Task, TaskMark};
 use crate::prelude::*;
 
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::atomic::Ordering::{Acquire, Release};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
+use futures::future::join_all;
 use smol::channel::unbounded;
 
+// How long a `FinishTask` waits for the `AppendTaskHandle` of its instance, in seconds.
+const EARLY_FINISH_TIMEOUT_SECONDS: u64 = 60;
+
 cfg_status_report!(
     use std::convert::TryFrom;
-    type StatusReportSender = Option<AsyncSender<PublicEvent>>;
+    use crate::utils::status_report::EventLog;
+    type StatusReportSender = Option<AsyncSender<(u64, PublicEvent)>>;
 );
 #[derive(Debug, Default, Clone)]
 pub(crate) struct EventHandleBuilder {
@@ -34,6 +48,10 @@ pub(crate) struct EventHandleBuilder {
     #[warn(dead_code)]
     #[cfg(feature = "status-report")]
     pub(crate) status_report_sender: StatusReportSender,
+    #[cfg(feature = "status-report")]
+    pub(crate) event_log: Option<EventLog>,
+    #[cfg(feature = "status-report")]
+    pub(crate) log_events: Option<LogLevel>,
 }
 
 impl EventHandleBuilder {
@@ -59,22 +77,38 @@ impl EventHandleBuilder {
         let task_trace = TaskTrace::default();
         let shared_header = self.shared_header?;
 
+        let timer_event_sender = self.timer_event_sender?;
         let sub_wokers = SubWorkers::new(
-            self.timer_event_sender?,
+            timer_event_sender.clone(),
             shared_header.runtime_instance.kind,
         );
 
         let timer_event_receiver = self.timer_event_receiver?;
         #[cfg(feature = "status-report")]
         let status_report_sender = self.status_report_sender;
+        #[cfg(feature = "status-report")]
+        let event_log = self.event_log;
+        #[cfg(feature = "status-report")]
+        let log_events = self.log_events;
 
         Some(EventHandle {
             shared_header,
             task_trace,
             timer_event_receiver,
+            timer_event_sender,
             #[cfg(feature = "status-report")]
             status_report_sender,
+            #[cfg(feature = "status-report")]
+            event_log,
+            #[cfg(feature = "status-report")]
+            log_events,
+            #[cfg(feature = "status-report")]
+            event_sequence: 0,
             sub_wokers,
+            inactive_tasks: HashMap::new(),
+            tombstones: HashMap::new(),
+            last_appended_record_id: 0,
+            early_finishes: HashMap::new(),
         })
     }
 }
@@ -102,10 +136,29 @@ pub(crate) struct EventHandle {
     pub(crate) task_trace: TaskTrace,
     //The core of the event recipient, dealing with the global event.
     pub(crate) timer_event_receiver: TimerEventReceiver,
+    //Event sender, for the events EventHandle sends itself later.
+    pub(crate) timer_event_sender: TimerEventSender,
     #[cfg(feature = "status-report")]
     pub(crate) status_report_sender: StatusReportSender,
+    //Ring-buffer of recent public events, for consumers that attach late.
+    #[cfg(feature = "status-report")]
+    pub(crate) event_log: Option<EventLog>,
+    //The level public events are logged at, if they are.
+    #[cfg(feature = "status-report")]
+    pub(crate) log_events: Option<LogLevel>,
+    //The sequence number of the last public event reported.
+    #[cfg(feature = "status-report")]
+    pub(crate) event_sequence: u64,
     //The sub-workers of EventHandle.
     pub(crate) sub_wokers: SubWorkers,
+    //Tasks that are registered, but not activated yet.
+    pub(crate) inactive_tasks: HashMap<u64, Box<Task>>,
+    //Tasks paused by a delayed removal, with the time they are removed at.
+    pub(crate) tombstones: HashMap<u64, (Task, u64)>,
+    //The record-id of the last instance appended, record-ids are handed out in increasing order.
+    pub(crate) last_appended_record_id: i64,
+    //Instances that finished before they were appended, by record-id.
+    pub(crate) early_finishes: HashMap<i64, FinishTaskBody>,
 }
 
 /// These sub-workers are the left and right arms of `EventHandle`
@@ -122,28 +175,12 @@ pub(crate) struct RecyclingBinWorker {
 
 impl EventHandle {
     fn recycling_task(&mut self) {
-        async_spawn_by_smol(
-            self.sub_wokers
-                .recycling_bin_woker
-                .inner
-                .clone()
-                .add_recycle_unit(),
-        )
-        .detach();
-        async_spawn_by_smol(self.sub_wokers.recycling_bin_woker.inner.clone().recycle()).detach();
-    }
-
-    // `async_spawn_by_tokio` 'must be called from the context of Tokio runtime configured
-    // with either `basic_scheduler` or `threaded_scheduler`'.
-    fn recycling_task_by_tokio(&mut self) {
-        async_spawn_by_tokio(
-            self.sub_wokers
-                .recycling_bin_woker
-                .inner
-                .clone()
-                .add_recycle_unit(),
-        );
-        async_spawn_by_tokio(self.sub_wokers.recycling_bin_woker.inner.clone().recycle());
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let future_tracker = &self.shared_header.future_tracker;
+        let recycling_bins = &self.sub_wokers.recycling_bin_woker.inner;
+
+        runtime_kind.spawn(future_tracker.track(recycling_bins.clone().add_recycle_unit()));
+        runtime_kind.spawn(future_tracker.track(recycling_bins.clone().recycle()));
     }
 
     // handle all event.
@@ -154,19 +191,16 @@ impl EventHandle {
     }
 
     fn init_sub_workers(&mut self) {
-        let runtime_kind = self.shared_header.runtime_instance.kind;
-
-        match runtime_kind {
-            RuntimeKind::Smol => self.recycling_task(),
-
-            RuntimeKind::Tokio => self.recycling_task_by_tokio(),
-        };
+        self.recycling_task();
     }
 
     async fn handle_event(&mut self) {
-        // Turn on `feature` and have `status_report_sender` go this piece of logic.
+        // Turn on `feature` and have `status_report_sender`, `event_log` or `log_events` go this piece of logic.
         #[cfg(feature = "status-report")]
-        if let Some(status_report_sender) = self.status_report_sender.take() {
+        if self.status_report_sender.is_some()
+            || self.event_log.is_some()
+            || self.log_events.is_some()
+        {
             while let Ok(event) = self.timer_event_receiver.recv().await {
                 let public_event_result = PublicEvent::try_from(&event);
 
@@ -175,10 +209,13 @@ impl EventHandle {
                 match dispatch_result {
                     Ok(event_sync_mark) if event_sync_mark => {
                         if let Ok(public_event) = public_event_result {
-                            status_report_sender
-                                .send(public_event)
-                                .await
-                                .unwrap_or_else(|e| error!("event sync error: {}", e));
+                            // The log has every event, the sampling is for the consumers.
+                            if let Some(level) = self.log_events {
+                                log_public_event(level, &public_event);
+                            }
+                            if self.sample_public_event(&public_event) {
+                                self.report_public_event(public_event).await;
+                            }
                         }
                     }
                     Err(e) => {
@@ -186,6 +223,7 @@ impl EventHandle {
                     }
                     _ => {}
                 }
+                self.refresh_view();
             }
             return;
         }
@@ -196,6 +234,18 @@ impl EventHandle {
                 .await
                 .map_err(|e| error!("{}", e))
                 .ok();
+            self.refresh_view();
+        }
+    }
+
+    // Replace the snapshot of the view once the events at hand are handled, if a view was taken.
+    fn refresh_view(&self) {
+        if !self.timer_event_receiver.is_empty() {
+            return;
+        }
+
+        if let Some(scheduler_view) = self.shared_header.scheduler_view.get() {
+            scheduler_view.refresh(&self.shared_header, 0);
         }
     }
 
@@ -205,37 +255,232 @@ impl EventHandle {
         match event {
             TimerEvent::StopTimer => {
                 self.shared_header.shared_motivation.store(false, Release);
+                self.shared_header.rearm_timer_core();
                 Ok(true)
             }
 
-            TimerEvent::AddTask(task) => self.add_task(task).map(|task_mark| {
-                self.record_task_mark(task_mark);
-                true
-            }),
+            TimerEvent::Shutdown(shutdown_options, report_sender) => {
+                let mut shutdown_report = self.shutdown(shutdown_options).await;
+                let (torn_down_tasks, unfinished_teardowns) = self
+                    .teardown_tasks(shutdown_options.get_teardown_timeout())
+                    .await;
+                shutdown_report.torn_down_tasks = torn_down_tasks;
+                shutdown_report.unfinished_teardowns = unfinished_teardowns;
+                report_sender
+                    .try_send(shutdown_report)
+                    .unwrap_or_else(|e| error!("`shutdown`: {}", e));
+                Ok(false)
+            }
+
+            TimerEvent::AddTask(task) => {
+                let task_id = task.task_id;
+                self.add_task(task)
+                    .map(|task_mark| {
+                        self.record_task_mark(task_mark);
+                        true
+                    })
+                    .map_err(|e| {
+                        self.release_unseated_task(task_id);
+                        e
+                    })
+            }
+
+            TimerEvent::RegisterTask(task) => {
+                self.inactive_tasks.insert(task.task_id, task);
+                Ok(false)
+            }
+
+            TimerEvent::ActivateTask(task_id) => {
+                let task = self.inactive_tasks.remove(&task_id).ok_or_else(|| {
+                    anyhow!(
+                        "Fn : `activate_task`, No registered task found (task-id: {} )",
+                        task_id
+                    )
+                })?;
+
+                self.add_task(task)
+                    .map(|task_mark| {
+                        self.record_task_mark(task_mark);
+                        true
+                    })
+                    .map_err(|e| {
+                        self.release_unseated_task(task_id);
+                        e
+                    })
+            }
+
+            TimerEvent::TakeOver(report_sender) => {
+                report_sender
+                    .try_send(self.take_over())
+                    .unwrap_or_else(|e| error!("`take_over`: {}", e));
+                Ok(false)
+            }
 
             TimerEvent::InsertTask(task, task_instances_chain_maintainer) => {
-                self.add_task(task).map(|mut task_mark| {
-                    task_mark.set_task_instances_chain_maintainer(task_instances_chain_maintainer);
-                    self.record_task_mark(task_mark);
-                    true
-                })
+                let task_id = task.task_id;
+                self.add_task(task)
+                    .map(|mut task_mark| {
+                        task_mark
+                            .set_task_instances_chain_maintainer(task_instances_chain_maintainer);
+                        self.record_task_mark(task_mark);
+                        true
+                    })
+                    .map_err(|e| {
+                        self.release_unseated_task(task_id);
+                        e
+                    })
+            }
+
+            TimerEvent::AttachInstancesChain(task_id, task_instances_chain_maintainer) => self
+                .attach_instances_chain(task_id, task_instances_chain_maintainer)
+                .map(|_| false),
+
+            TimerEvent::SubscribeInstances(task_id, task_instances_chain_maintainer) => self
+                .subscribe_instances(task_id, task_instances_chain_maintainer)
+                .map(|_| false),
+
+            TimerEvent::SubscribeInstancesMulti(
+                task_instances_chain_maintainers,
+                report_sender,
+            ) => {
+                let unknown_task_id = task_instances_chain_maintainers
+                    .iter()
+                    .map(|(task_id, _)| *task_id)
+                    .find(|task_id| !self.shared_header.task_flag_map.contains_key(task_id));
+
+                let report = match unknown_task_id {
+                    Some(task_id) => Err(task_id),
+                    None => {
+                        for (task_id, task_instances_chain_maintainer) in
+                            task_instances_chain_maintainers
+                        {
+                            self.subscribe_instances(task_id, task_instances_chain_maintainer)?;
+                        }
+                        Ok(())
+                    }
+                };
+                report_sender
+                    .try_send(report)
+                    .unwrap_or_else(|e| error!("`subscribe_instances_multi`: {}", e));
+                Ok(false)
+            }
+
+            TimerEvent::DetachInstancesChain(task_id) => {
+                if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+                    task_mark
+                        .value_mut()
+                        .detach_task_instances_chain_maintainer();
+                }
+                Ok(false)
+            }
+
+            TimerEvent::TaskExhausted(task_id) => {
+                if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+                    task_mark.value_mut().set_exhausted();
+                }
+                self.close_exhausted_instances_chains(task_id);
+                Ok(false)
+            }
+
+            TimerEvent::SchedulerRestarted(_) => {
+                self.reinit_tasks();
+                Ok(true)
+            }
+
+            TimerEvent::TaskInitialized(task_id, resource) => {
+                if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+                    task_mark.value_mut().set_resource(resource);
+                }
+                Ok(false)
+            }
+
+            TimerEvent::BudgetExceeded(_)
+            | TimerEvent::SkippedByExclusion(_)
+            | TimerEvent::SystemResumedAfter(_)
+            | TimerEvent::StaleFireDropped(_)
+            | TimerEvent::SkippedNotReady(_)
+            | TimerEvent::SpawnVetoed(_)
+            | TimerEvent::SkippedByCircuitBreaker(_)
+            | TimerEvent::SkippedByTenantQuota(_)
+            | TimerEvent::SkippedBySingleInstanceGuard(_)
+            | TimerEvent::InstanceStalled(_, _) => Ok(true),
+
+            TimerEvent::RunningLong(task_id, record_id) => {
+                Ok(self.task_trace.contains(task_id, record_id))
+            }
+
+            TimerEvent::ReadinessChecked(task_id, ready) => {
+                if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+                    let task_mark = task_mark.value_mut();
+
+                    // A report that comes after the fire has given up on it is stale.
+                    if let ReadinessState::Checking(_) = task_mark.get_readiness() {
+                        task_mark.set_readiness(if ready {
+                            ReadinessState::Ready
+                        } else {
+                            ReadinessState::NotReady(timestamp())
+                        });
+                    }
+                }
+                Ok(false)
             }
 
             TimerEvent::UpdateTask(task) => {
-                self.update_task(task).await;
+                let task_id = task.task_id;
+                if self.update_task(task).await.is_none() {
+                    self.release_unseated_task(task_id);
+                }
                 Ok(true)
             }
 
-            TimerEvent::AdvanceTask(task_id) => self.advance_task(task_id).await.map(|_| true),
+            TimerEvent::AdvanceTask(task_id, advance_options) => self
+                .advance_task(task_id, advance_options)
+                .await
+                .map(|_| true),
+
+            TimerEvent::RemoveTask(task_id) => self.discard_task(task_id).await,
+            TimerEvent::RemoveTaskDelayed(task_id, grace) => {
+                self.remove_task_delayed(task_id, grace).map(|_| false)
+            }
+
+            TimerEvent::UndoRemove(task_id) => self.undo_remove(task_id).map(|_| false),
 
-            TimerEvent::RemoveTask(task_id) => {
-                let remove_result = self.remove_task(task_id).await.map(|_| true);
+            TimerEvent::ExpireTombstone(task_id) => Ok(self.expire_tombstone(task_id)),
 
-                self.shared_header.task_flag_map.remove(&task_id);
-                remove_result
+            TimerEvent::EnqueueJob(task_id, job) => self.enqueue_job(task_id, job).map(|_| false),
+
+            TimerEvent::RunEnqueuedJob(task_id) => {
+                self.run_enqueued_job(task_id).await.map(|_| false)
             }
-            TimerEvent::CancelTask(task_id, record_id) => {
-                self.cancel_task::<true>(task_id, record_id, state::instance::CANCELLED)
+
+            TimerEvent::TaskQuarantined(task_id, failure) => match failure {
+                Failure::KeepRunning => Ok(false),
+                // A quarantined task stays paused until it's resumed.
+                Failure::PauseAfter(_) => self.pause_task(task_id, u64::MAX).map(|_| true),
+                Failure::RemoveAfter(_) => self.discard_task(task_id).await,
+            },
+
+            TimerEvent::CancelTask(task_id, record_id, termination_reason) => {
+                self.cancel_task::<true>(
+                    task_id,
+                    record_id,
+                    state::instance::CANCELLED,
+                    termination_reason,
+                )
+                .await
+            }
+
+            TimerEvent::ReplaceInstances(task_id, count) => {
+                for record_id in self.task_trace.oldest_instances(task_id, count as usize) {
+                    self.cancel_task::<false>(
+                        task_id,
+                        record_id,
+                        state::instance::CANCELLED,
+                        TerminationReason::Replaced,
+                    )
+                    .await?;
+                }
+                Ok(false)
             }
 
             // FIXED:
@@ -247,18 +492,43 @@ impl EventHandle {
             // Ok(_) & Err(_) for Result, means whether the processing is successful or not.
             // `bool` means whether to synchronize the event to external.
             TimerEvent::TimeoutTask(task_id, record_id) => {
-                self.cancel_task::<false>(task_id, record_id, state::instance::TIMEOUT)
+                self.cancel_task::<false>(
+                    task_id,
+                    record_id,
+                    state::instance::TIMEOUT,
+                    TerminationReason::Timeout,
+                )
+                .await
             }
 
             TimerEvent::AppendTaskHandle(task_id, delay_task_handler_box) => {
+                let record_id = delay_task_handler_box.get_record_id();
                 self.maintain_task_status(task_id, delay_task_handler_box)
                     .await;
+                self.last_appended_record_id = self.last_appended_record_id.max(record_id);
+
+                // Handled again after the `RunningTask` event of the instance.
+                if let Some(finish_task_body) = self.early_finishes.remove(&record_id) {
+                    self.timer_event_sender
+                        .try_send(TimerEvent::FinishTask(finish_task_body))
+                        .unwrap_or_else(|e| error!("`event_dispatch`: {}", e));
+                }
+                self.expire_early_finishes();
                 Ok(true)
             }
 
-            TimerEvent::FinishTask(FinishTaskBody {
-                task_id, record_id, ..
-            }) => self.finish_task(task_id, record_id),
+            // An instance may finish before the timer-core has appended it,
+            // then its `FinishTask` waits for its `AppendTaskHandle`, so that they're reported in order.
+            TimerEvent::FinishTask(finish_task_body)
+                if finish_task_body.record_id > self.last_appended_record_id =>
+            {
+                self.expire_early_finishes();
+                self.early_finishes
+                    .insert(finish_task_body.record_id, finish_task_body);
+                Ok(false)
+            }
+
+            TimerEvent::FinishTask(finish_task_body) => self.finish_task(finish_task_body),
         }
     }
 
@@ -272,21 +542,67 @@ impl EventHandle {
     }
 
     // Add task to wheel_queue  slot
-    fn add_task(&mut self, mut task: Box<Task>) -> AnyResult<TaskMark> {
-        let second_hand = self.shared_header.second_hand.current_second_hand();
+    fn add_task(&mut self, task: Box<Task>) -> AnyResult<TaskMark> {
+        self.add_task_resumed_at(task, 0)
+    }
 
-        let exec_time: u64 = task
-            .get_next_exec_timestamp()
-            .ok_or_else(|| anyhow!("can't get_next_exec_timestamp in {}", &task.task_id))?;
+    // The tick, from the start of the wheel, the task is put in the wheel at for its fire at `exec_time`,
+    // ahead of it by the dispatch lead of the task.
+    fn time_seed(&self, task: &Task, exec_time: u64, timestamp: u64, second_hand: u64) -> u64 {
+        ticks_until(&self.shared_header, exec_time, timestamp)
+            .map(|step| step.saturating_sub(task.dispatch_lead()))
+            .unwrap_or(task.task_id % DEFAULT_TIMER_SLOT_COUNT)
+            + second_hand
+            + 1
+    }
+
+    // Add a task whose fires before `resume_at` ran elsewhere.
+    fn add_task_resumed_at(&mut self, mut task: Box<Task>, resume_at: u64) -> AnyResult<TaskMark> {
+        let second_hand = self.shared_header.second_hand.current_second_hand();
 
         let timestamp = self.shared_header.global_time.load(Acquire);
 
+        if self.shared_header.auto_splay_seconds {
+            task.apply_auto_splay();
+        }
+        if self.shared_header.high_precision {
+            task.apply_high_precision();
+        }
+
+        // While the timer warms up, the first fires are spread over the stagger window.
+        let stagger_target = self
+            .shared_header
+            .startup_stagger
+            .map(|(started_at, window)| started_at + spread_offset(task.task_id, window));
+
+        // The scheduled fires are left untouched for the startup fire,
+        // the first of them is taken after it runs.
+        let exec_time: u64 = if task.is_run_on_startup() {
+            stagger_target.map_or(timestamp, |target| target.max(timestamp))
+        } else {
+            task.get_next_exec_timestamp()
+                .ok_or_else(|| anyhow!("can't get_next_exec_timestamp in {}", &task.task_id))?;
+
+            // A task that runs each scheduled time at most once doesn't run the last one again.
+            let last_fire = if task.at_most_once {
+                load_last_fire(self.shared_header.state_store.as_ref(), task.task_id)?
+            } else {
+                None
+            };
+            let resume_at = last_fire.map_or(resume_at, |last_fire| resume_at.max(last_fire + 1));
+
+            // A registered task drops the fires it missed before being activated.
+            let horizon = missed_fire_horizon(&self.shared_header, timestamp);
+            task.skip_missed_fires(horizon.max(resume_at));
+            if let Some(target) = stagger_target {
+                task.stagger_pending_fire(target);
+            }
+            task.get_scheduled_time()
+                .ok_or_else(|| anyhow!("can't get_next_exec_timestamp in {}", &task.task_id))?
+        };
+
         // Put task on next slot.
-        let time_seed: u64 = exec_time
-            .checked_sub(timestamp)
-            .unwrap_or(task.task_id % DEFAULT_TIMER_SLOT_COUNT)
-            + second_hand
-            + 1;
+        let time_seed = self.time_seed(&task, exec_time, timestamp, second_hand);
         let slot_seed: u64 = time_seed % DEFAULT_TIMER_SLOT_COUNT;
 
         let cylinder_line = time_seed / DEFAULT_TIMER_SLOT_COUNT;
@@ -294,15 +610,34 @@ impl EventHandle {
 
         // copy task_id
         let task_id = task.task_id;
+        let on_aborted = task.on_aborted.clone();
+        let budget = task.budget;
+        let failure_policy = task.failure_policy;
+        let exclude_noop_durations = task.exclude_noop_durations;
+        let circuit_breaker = task.circuit_breaker.clone();
+        #[cfg(feature = "status-report")]
+        let event_sampling = task.event_sampling;
+        if let Some(init) = task.init.as_ref() {
+            self.spawn_task_init(task_id, init);
+        }
+        self.restore_job_queue(task_id);
         if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_seed) {
             slot.value_mut().add_task(*task);
         }
+        self.shared_header.rearm_timer_core();
 
         let mut task_mart = TaskMark::default();
         task_mart
             .set_task_id(task_id)
             .set_slot_mark(slot_seed)
-            .set_parallel_runnable_num(0);
+            .set_parallel_runnable_num(0)
+            .set_on_aborted(on_aborted)
+            .set_budget(budget)
+            .set_failure_policy(failure_policy)
+            .set_exclude_noop_durations(exclude_noop_durations)
+            .set_circuit_breaker(circuit_breaker);
+        #[cfg(feature = "status-report")]
+        task_mart.set_event_sampling(event_sampling);
         debug!(
             "task-id: {} , next-exec-timestamp: {}, slot-seed: {}, cylinder-line: {}",
             task_id, exec_time, slot_seed, cylinder_line
@@ -311,6 +646,35 @@ impl EventHandle {
         Ok(task_mart)
     }
 
+    // Activate the registered tasks, those handed off by another instance
+    // go on from the next fire it left, without a startup fire of their own.
+    fn take_over(&mut self) -> AnyResult<Vec<u64>> {
+        let mut task_ids: Vec<u64> = self.inactive_tasks.keys().copied().collect();
+        task_ids.sort_unstable();
+
+        let mut handed_off = Vec::new();
+        for task_id in task_ids {
+            let next_fire = take_handoff(self.shared_header.state_store.as_ref(), task_id)?;
+            let mut task = match self.inactive_tasks.remove(&task_id) {
+                Some(task) => task,
+                None => continue,
+            };
+
+            let resume_at = match next_fire {
+                Some(next_fire) => {
+                    task.take_run_on_startup();
+                    handed_off.push(task_id);
+                    next_fire
+                }
+                None => 0,
+            };
+            let task_mark = self.add_task_resumed_at(task, resume_at)?;
+            self.record_task_mark(task_mark);
+        }
+
+        Ok(handed_off)
+    }
+
     // for record task-mark.
     pub(crate) fn record_task_mark(&mut self, task_mark: TaskMark) {
         self.shared_header
@@ -318,11 +682,89 @@ impl EventHandle {
             .insert(task_mark.task_id, task_mark);
     }
 
+    // Attach a chain to a task that has been registered,
+    // an existing chain will be replaced and marked as abandoned.
+    pub(crate) fn attach_instances_chain(
+        &mut self,
+        task_id: u64,
+        task_instances_chain_maintainer: TaskInstancesChainMaintainer,
+    ) -> Result<()> {
+        let mut task_mark = self
+            .shared_header
+            .task_flag_map
+            .get_mut(&task_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Fn : `attach_instances_chain`, No task-mark found (task-id: {} )",
+                    task_id
+                )
+            })?;
+
+        task_mark
+            .value_mut()
+            .set_task_instances_chain_maintainer(task_instances_chain_maintainer);
+        drop(task_mark);
+
+        // A task that doesn't fire anymore closes the chain right away.
+        self.close_exhausted_instances_chains(task_id);
+        Ok(())
+    }
+
+    // Subscribe a chain to a task that has been registered,
+    // the chains already attached to the task are not affected.
+    pub(crate) fn subscribe_instances(
+        &mut self,
+        task_id: u64,
+        task_instances_chain_maintainer: TaskInstancesChainMaintainer,
+    ) -> Result<()> {
+        let mut task_mark = self
+            .shared_header
+            .task_flag_map
+            .get_mut(&task_id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Fn : `subscribe_instances`, No task-mark found (task-id: {} )",
+                    task_id
+                )
+            })?;
+
+        task_mark
+            .value_mut()
+            .add_task_instances_chain_subscriber(task_instances_chain_maintainer);
+        drop(task_mark);
+
+        // A task that doesn't fire anymore closes the chain right away.
+        self.close_exhausted_instances_chains(task_id);
+        Ok(())
+    }
+
     // for update task.
-    pub(crate) async fn update_task(&mut self, task: Box<Task>) -> Option<Task> {
-        let task_mark = self.shared_header.task_flag_map.get(&task.task_id)?;
+    pub(crate) async fn update_task(&mut self, mut task: Box<Task>) -> Option<Task> {
+        if self.shared_header.auto_splay_seconds {
+            task.apply_auto_splay();
+        }
+        if self.shared_header.high_precision {
+            task.apply_high_precision();
+        }
+
+        let mut task_mark = self.shared_header.task_flag_map.get_mut(&task.task_id)?;
+        if let Some(init) = task.init.as_ref() {
+            self.spawn_task_init(task.task_id, init);
+        }
 
         let slot_mark = task_mark.value().get_slot_mark();
+        task_mark
+            .value_mut()
+            .set_on_aborted(task.on_aborted.clone())
+            .set_budget(task.budget)
+            .set_failure_policy(task.failure_policy)
+            .set_exclude_noop_durations(task.exclude_noop_durations)
+            .set_circuit_breaker(task.circuit_breaker.clone());
+        #[cfg(feature = "status-report")]
+        task_mark
+            .value_mut()
+            .set_event_sampling(task.event_sampling);
+        drop(task_mark);
 
         if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_mark) {
             return slot.value_mut().update_task(*task);
@@ -332,7 +774,11 @@ impl EventHandle {
     }
 
     // Take the initiative to perform once Task.
-    pub(crate) async fn advance_task(&mut self, task_id: u64) -> Result<()> {
+    pub(crate) async fn advance_task(
+        &mut self,
+        task_id: u64,
+        mut advance_options: AdvanceOptions,
+    ) -> Result<()> {
         let task_mark = self
             .shared_header
             .task_flag_map
@@ -359,11 +805,22 @@ impl EventHandle {
             }
         };
         task.clear_cylinder_line();
+        if !advance_options.is_consume_count() {
+            task.set_extra_run();
+        }
+        if advance_options.is_jump_queue() {
+            self.jump_queues(&task);
+        }
+        task.set_advance_payload(advance_options.take_payload().map(Arc::from));
+        #[cfg(feature = "otel")]
+        task.set_advance_parent_context(advance_options.into_parent_context());
 
         let slot_seed = self.shared_header.second_hand.current_second_hand() + 1;
 
         if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_seed) {
             slot.value_mut().add_task(task);
+            drop(slot);
+            self.shared_header.rearm_timer_core();
             return Ok(());
         }
 
@@ -373,8 +830,174 @@ impl EventHandle {
         ))
     }
 
+    // Build the resource of the task in the background, it's reported by `TaskInitialized`.
+    fn spawn_task_init(&self, task_id: u64, init: &SafeStructInitFn) {
+        let initializing = (init.0)();
+        let timer_event_sender = self.timer_event_sender.clone();
+        let initializing = async move {
+            match initializing.await {
+                Ok(resource) => timer_event_sender
+                    .send(TimerEvent::TaskInitialized(task_id, resource))
+                    .await
+                    .unwrap_or_else(|e| error!("`spawn_task_init`: {}", e)),
+                Err(e) => error!("task-id: {}, `init` failed: {}", task_id, e),
+            }
+        };
+
+        let initializing = self.shared_header.future_tracker.track(initializing);
+        self.shared_header.runtime_instance.kind.spawn(initializing);
+    }
+
+    // The resources of the tasks are built afresh for the restarted timer-core loop.
+    fn reinit_tasks(&self) {
+        let inits: Vec<(u64, SafeStructInitFn)> = self
+            .shared_header
+            .wheel_queue
+            .iter()
+            .flat_map(|slot| {
+                slot.value()
+                    .tasks()
+                    .filter_map(|task| Some((task.task_id, task.init.clone()?)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        for (task_id, init) in inits.iter() {
+            self.spawn_task_init(*task_id, init);
+        }
+    }
+
+    // Release the resources of the removed task in the background,
+    // it isn't tracked so that a shutdown doesn't abort it halfway.
+    fn spawn_task_teardown(&self, task: &Task) {
+        let teardown = match task.teardown.as_ref() {
+            Some(teardown) => (teardown.0)(),
+            None => return,
+        };
+
+        self.shared_header.runtime_instance.kind.spawn(teardown);
+    }
+
+    // The resources of the tasks still there are released before the shutdown is reported,
+    // the tracked background futures being aborted by then.
+    async fn teardown_tasks(&self, teardown_timeout: Duration) -> (usize, Vec<u64>) {
+        let teardown_of = |task: &Task| {
+            task.teardown
+                .clone()
+                .map(|teardown| (task.task_id, teardown))
+        };
+        let mut teardowns: Vec<(u64, SafeStructTeardownFn)> = self
+            .shared_header
+            .wheel_queue
+            .iter()
+            .flat_map(|slot| {
+                slot.value()
+                    .tasks()
+                    .filter_map(teardown_of)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        teardowns.extend(
+            self.tombstones
+                .values()
+                .filter_map(|(task, _)| teardown_of(task)),
+        );
+        teardowns.extend(
+            self.inactive_tasks
+                .values()
+                .filter_map(|task| teardown_of(task)),
+        );
+
+        // Each teardown leaves the unfinished ones as it completes.
+        let unfinished: Mutex<Vec<u64>> =
+            Mutex::new(teardowns.iter().map(|(task_id, _)| *task_id).collect());
+        let torn_down = join_all(teardowns.iter().map(|(task_id, teardown)| {
+            let unfinished = &unfinished;
+            async move {
+                (teardown.0)().await;
+                unfinished
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .retain(|unfinished_task_id| unfinished_task_id != task_id);
+            }
+        }));
+
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        if runtime_kind
+            .timeout(teardown_timeout, torn_down)
+            .await
+            .is_none()
+        {
+            warn!(
+                "The teardown of some tasks didn't finish within {:?}.",
+                teardown_timeout
+            );
+        }
+
+        let unfinished = unfinished
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        (teardowns.len(), unfinished)
+    }
+
+    // The manual run is the next in line for the serial queue and the semaphore of the task.
+    fn jump_queues(&self, task: &Task) {
+        if let Some(serial_queue) = task.serial_queue.as_ref() {
+            self.shared_header
+                .serial_queues
+                .entry(serial_queue.clone())
+                .or_default()
+                .jump(task.task_id);
+        }
+        if let Some(semaphore) = task.semaphore.as_ref() {
+            semaphore.jump(task.task_id);
+        }
+    }
+
+    // A task admitted by the timer that's neither in the timer nor registered,
+    // after its add or update failed, no longer counts towards its tenant nor keeps its owner.
+    fn release_unseated_task(&self, task_id: u64) {
+        if self.shared_header.task_flag_map.contains_key(&task_id)
+            || self.inactive_tasks.contains_key(&task_id)
+        {
+            return;
+        }
+
+        self.shared_header.task_owners.remove(&task_id);
+        self.shared_header.tenants.release(task_id);
+        self.release_memory(task_id);
+    }
+
+    // The memory of a removed task is no longer attributed to its tenant.
+    fn release_memory(&self, task_id: u64) {
+        if let Some(memory_attribution) = self.shared_header.memory_attribution.as_ref() {
+            memory_attribution.release(task_id);
+        }
+    }
+
+    // The pending fire of a removed task no longer holds up the tasks behind it.
+    fn leave_serial_queues(&self, task_id: u64) {
+        for mut serial_queue in self.shared_header.serial_queues.iter_mut() {
+            serial_queue.value_mut().leave(task_id);
+        }
+    }
+
+    // The pending fire of a removed task no longer holds up the tasks waiting for a permit.
+    fn leave_semaphore(task: &Task) {
+        if let Some(semaphore) = task.semaphore.as_ref() {
+            semaphore.leave(task.task_id);
+        }
+    }
+
+    // An ended instance lets the next task of its serial queue run.
+    fn finish_serial_queues(&self, record_id: i64) {
+        for mut serial_queue in self.shared_header.serial_queues.iter_mut() {
+            serial_queue.value_mut().finish(record_id);
+        }
+    }
+
     // for remove task.
-    pub(crate) async fn remove_task(&mut self, task_id: u64) -> Result<()> {
+    pub(crate) async fn remove_task(&mut self, task_id: u64) -> Result<Task> {
         let task_mark = self
             .shared_header
             .task_flag_map
@@ -389,8 +1012,9 @@ impl EventHandle {
         let slot_mark = task_mark.value().get_slot_mark();
 
         if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_mark) {
-            slot.value_mut().remove_task(task_id);
-            return Ok(());
+            return slot.value_mut().remove_task(task_id).ok_or_else(|| {
+                anyhow!("Fn : `remove_task`, No task found (task-id: {} )", task_id)
+            });
         }
 
         Err(anyhow!(
@@ -399,6 +1023,312 @@ impl EventHandle {
         ))
     }
 
+    // The task, its chains and its task-mark are all dropped, then its resources are torn down.
+    async fn discard_task(&mut self, task_id: u64) -> Result<bool> {
+        self.leave_serial_queues(task_id);
+        self.early_finishes
+            .retain(|_, finish_task_body| finish_task_body.task_id != task_id);
+        if let Some(task) = self.inactive_tasks.remove(&task_id) {
+            self.spawn_task_teardown(&task);
+            self.shared_header.task_owners.remove(&task_id);
+            self.shared_header.tenants.release(task_id);
+            self.release_memory(task_id);
+            return Ok(true);
+        }
+
+        if let Some((task, _)) = self.tombstones.remove(&task_id) {
+            self.discard_paused_task(&task);
+            return Ok(true);
+        }
+
+        let remove_result = self.remove_task(task_id).await.map(|task| {
+            Self::leave_semaphore(&task);
+            self.spawn_task_teardown(&task);
+            true
+        });
+
+        self.shared_header.task_flag_map.remove(&task_id);
+        self.shared_header.task_owners.remove(&task_id);
+        self.shared_header.tenants.release(task_id);
+        self.release_memory(task_id);
+        // The sleeping timer-core loop looks again, the removed fire may have been the next one.
+        self.shared_header.rearm_timer_core();
+        remove_result
+    }
+
+    // The task leaves the wheel but keeps its task-mark, so it can be put back as it was.
+    // It's removed by `expire_tombstone` once the deadline is over.
+    fn pause_task(&mut self, task_id: u64, deadline: u64) -> Result<()> {
+        let slot_mark = self
+            .shared_header
+            .task_flag_map
+            .get(&task_id)
+            .map(|task_mark| task_mark.value().get_slot_mark())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Fn : `pause_task`, No task-mark found (task-id: {} )",
+                    task_id
+                )
+            })?;
+
+        let task = self
+            .shared_header
+            .wheel_queue
+            .get_mut(&slot_mark)
+            .and_then(|mut slot| slot.value_mut().remove_task(task_id))
+            .ok_or_else(|| {
+                anyhow!("Fn : `pause_task`, No task found (task-id: {} )", task_id)
+            })?;
+
+        Self::leave_semaphore(&task);
+        self.tombstones.insert(task_id, (task, deadline));
+        self.shared_header.paused_tasks.insert(task_id, deadline);
+        self.leave_serial_queues(task_id);
+        Ok(())
+    }
+
+    // The job is kept in the queue of the task, the earliest first, until it's due.
+    pub(crate) fn enqueue_job(&mut self, task_id: u64, job: EnqueuedJob) -> Result<()> {
+        if !self.shared_header.task_flag_map.contains_key(&task_id) {
+            return Err(anyhow!(
+                "Fn : `enqueue_job`, No task found (task-id: {} )",
+                task_id
+            ));
+        }
+
+        let state_store = self.shared_header.state_store.as_ref();
+        let mut jobs = load_jobs(state_store, task_id)?;
+        let run_at = job.run_at;
+        let position = jobs.partition_point(|queued_job| queued_job.run_at <= run_at);
+        jobs.insert(position, job);
+        save_jobs(state_store, task_id, &jobs)?;
+
+        self.wake_job_queue(task_id, run_at);
+        Ok(())
+    }
+
+    // Run the earliest due job of the task as a free extra run carrying its payload.
+    // It's retried a second later while the task can't take it,
+    // e.g. the payload of another manual run is still pending.
+    pub(crate) async fn run_enqueued_job(&mut self, task_id: u64) -> Result<()> {
+        let state_store = self.shared_header.state_store.clone();
+        let mut jobs = load_jobs(state_store.as_ref(), task_id)?;
+        if !jobs.first().is_some_and(|job| job.run_at <= timestamp()) {
+            return Ok(());
+        }
+
+        // The jobs of a removed task go with it.
+        if !self.shared_header.task_flag_map.contains_key(&task_id) {
+            return save_jobs(state_store.as_ref(), task_id, &[]);
+        }
+
+        if self.has_pending_payload(task_id) {
+            self.wake_job_queue(task_id, timestamp() + 1);
+            return Ok(());
+        }
+
+        let job = jobs.remove(0);
+        let advance_options = AdvanceOptions::default()
+            .consume_count(false)
+            .payload(job.payload);
+        if let Err(e) = self.advance_task(task_id, advance_options).await {
+            debug!("task-id: {}, the enqueued job waits: {}", task_id, e);
+            self.wake_job_queue(task_id, timestamp() + 1);
+            return Ok(());
+        }
+
+        save_jobs(state_store.as_ref(), task_id, &jobs)
+    }
+
+    fn has_pending_payload(&self, task_id: u64) -> bool {
+        let slot_mark = match self.shared_header.task_flag_map.get(&task_id) {
+            Some(task_mark) => task_mark.value().get_slot_mark(),
+            None => return false,
+        };
+
+        self.shared_header
+            .wheel_queue
+            .get(&slot_mark)
+            .and_then(|slot| slot.value().get_task(task_id).map(Task::has_advance_payload))
+            .unwrap_or(false)
+    }
+
+    // The jobs kept by a persistent store are woken for the task added again, e.g. after a restart.
+    fn restore_job_queue(&self, task_id: u64) {
+        match load_jobs(self.shared_header.state_store.as_ref(), task_id) {
+            Ok(jobs) => {
+                for job in jobs.iter() {
+                    self.wake_job_queue(task_id, job.run_at);
+                }
+            }
+            Err(e) => error!("task-id: {}, `restore_job_queue`: {}", task_id, e),
+        }
+    }
+
+    // Wake the queue of the task at `run_at` (timestamp-seconds), to run the job due then.
+    fn wake_job_queue(&self, task_id: u64, run_at: u64) {
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let timer_event_sender = self.timer_event_sender.clone();
+        let wake = async move {
+            let wait = (run_at as u128 * 1_000_000).saturating_sub(timestamp_micros());
+            runtime_kind.sleep(Duration::from_micros(wait as u64)).await;
+            timer_event_sender
+                .send(TimerEvent::RunEnqueuedJob(task_id))
+                .await
+                .unwrap_or_else(|e| error!("`wake_job_queue`: {}", e));
+        };
+
+        let wake = self.shared_header.future_tracker.track(wake);
+        self.shared_header.runtime_instance.kind.spawn(wake);
+    }
+
+    // The paused task is removed once `grace` is over, unless the removal is undone.
+    pub(crate) fn remove_task_delayed(&mut self, task_id: u64, grace: Duration) -> Result<()> {
+        self.pause_task(task_id, timestamp() + grace.as_secs())?;
+
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let timer_event_sender = self.timer_event_sender.clone();
+        let expiry = async move {
+            runtime_kind.sleep(grace).await;
+            timer_event_sender
+                .send(TimerEvent::ExpireTombstone(task_id))
+                .await
+                .unwrap_or_else(|e| error!("`remove_task_delayed`: {}", e));
+        };
+
+        let expiry = self.shared_header.future_tracker.track(expiry);
+        self.shared_header.runtime_instance.kind.spawn(expiry);
+
+        Ok(())
+    }
+
+    // Put the paused task back in the wheel, fires due during the pause are skipped,
+    // they count against a countdown according to the `CountDownPolicy`.
+    pub(crate) fn undo_remove(&mut self, task_id: u64) -> Result<()> {
+        let (mut task, _) = self.tombstones.remove(&task_id).ok_or_else(|| {
+            anyhow!(
+                "Fn : `undo_remove`, No removal to undo (task-id: {} )",
+                task_id
+            )
+        })?;
+        self.shared_header.paused_tasks.remove(&task_id);
+
+        let timestamp = self.shared_header.global_time.load(Acquire);
+        let second_hand = self.shared_header.second_hand.current_second_hand();
+        let horizon = missed_fire_horizon(&self.shared_header, timestamp);
+        match self.shared_header.count_down_policy {
+            CountDownPolicy::Executions => task.skip_missed_fires(horizon + 1),
+            CountDownPolicy::ScheduledFires => {
+                // The countdown ran out while the task was paused, it's exhausted
+                // the way the timer-core exhausts it, then the removal goes through.
+                if !task.count_missed_fires(horizon + 1) {
+                    if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id)
+                    {
+                        task_mark.value_mut().set_exhausted();
+                    }
+                    self.close_exhausted_instances_chains(task_id);
+                    self.discard_paused_task(&task);
+                    return Ok(());
+                }
+            }
+        }
+
+        let exec_time = task.get_scheduled_time().unwrap_or(timestamp);
+        let time_seed = self.time_seed(&task, exec_time, timestamp, second_hand);
+        let slot_seed = time_seed % DEFAULT_TIMER_SLOT_COUNT;
+        task.set_cylinder_line(time_seed / DEFAULT_TIMER_SLOT_COUNT);
+
+        if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_seed) {
+            slot.value_mut().add_task(task);
+        }
+        self.shared_header.rearm_timer_core();
+
+        if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+            task_mark.value_mut().set_slot_mark(slot_seed);
+        }
+
+        Ok(())
+    }
+
+    // The removal may have been undone, or the task paused again since.
+    pub(crate) fn expire_tombstone(&mut self, task_id: u64) -> bool {
+        let expired = self
+            .tombstones
+            .get(&task_id)
+            .map(|(_, deadline)| *deadline <= timestamp())
+            .unwrap_or(false);
+
+        if expired {
+            if let Some((task, _)) = self.tombstones.remove(&task_id) {
+                self.discard_paused_task(&task);
+            }
+        }
+        expired
+    }
+
+    // A task paused by `pause_task` is dropped for good, along with its task-mark.
+    fn discard_paused_task(&mut self, task: &Task) {
+        let task_id = task.task_id;
+        self.spawn_task_teardown(task);
+        self.shared_header.paused_tasks.remove(&task_id);
+        self.shared_header.task_flag_map.remove(&task_id);
+        self.shared_header.task_owners.remove(&task_id);
+        self.shared_header.tenants.release(task_id);
+        self.release_memory(task_id);
+        self.early_finishes
+            .retain(|_, finish_task_body| finish_task_body.task_id != task_id);
+    }
+
+    // An instance whose `AppendTaskHandle` didn't come in time was dropped before being appended,
+    // its `FinishTask` won't be handled.
+    fn expire_early_finishes(&mut self) {
+        let now = timestamp();
+        self.early_finishes.retain(|record_id, finish_task_body| {
+            let waiting = finish_task_body.finish_time + EARLY_FINISH_TIMEOUT_SECONDS > now;
+            if !waiting {
+                warn!(
+                    "task-id: {}, record-id: {} finished but was never appended.",
+                    finish_task_body.task_id, record_id
+                );
+            }
+            waiting
+        });
+    }
+
+    // The running instances are cancelled the way `cancel_task` does,
+    // so that their chains and `on_aborted` closures learn about it.
+    // Their processes are killed before, as the handles of the last ones go with them.
+    async fn shutdown(&mut self, shutdown_options: ShutdownOptions) -> ShutdownReport {
+        self.shared_header.shared_motivation.store(false, Release);
+        self.shared_header.rearm_timer_core();
+
+        let child_processes = match shutdown_options.get_child_processes() {
+            ChildProcessPolicy::Kill => self.shared_header.child_processes.kill_all(),
+            ChildProcessPolicy::Detach => self.shared_header.child_processes.pids(),
+        };
+
+        let instances = self.task_trace.instances();
+        for &(task_id, record_id) in instances.iter() {
+            self.cancel_task::<false>(
+                task_id,
+                record_id,
+                state::instance::CANCELLED,
+                TerminationReason::Shutdown,
+            )
+            .await
+            .map_err(|e| error!("`shutdown`: {}", e))
+            .ok();
+        }
+
+        ShutdownReport {
+            aborted_instances: instances.len(),
+            aborted_futures: self.shared_header.future_tracker.abort_all(),
+            torn_down_tasks: 0,
+            unfinished_teardowns: Vec::new(),
+            child_processes,
+        }
+    }
+
     // The `INITIATIVE` mark indicates whether the cancellation was initiated by an outside party.
 
     // `INITIATIVE` = true
@@ -409,35 +1339,64 @@ impl EventHandle {
     // Passive cancellation at runtime (e.g., timeout) indicates that
     // The task instance has completed or has been actively cancelled,
     // And no error logging is required.
-    pub(crate) fn cancel_task<const INITIATIVE: bool>(
+    pub(crate) async fn cancel_task<const INITIATIVE: bool>(
         &mut self,
         task_id: u64,
         record_id: i64,
         state: usize,
+        termination_reason: TerminationReason,
     ) -> Result<bool> {
         // The cancellation operation is executed first, and then the outside world is notified of the cancellation event.
         // If the operation object does not exist in the middle, it should return early.
 
-        let quit_result = self.task_trace.quit_one_task_handler(task_id, record_id);
-
-        if quit_result.is_err() {
-            if INITIATIVE {
-                quit_result?;
-            } else {
-                return Ok(false);
-            }
-        }
+        let task_handler_box = match self.task_trace.quit_one_task_handler(task_id, record_id) {
+            Ok(task_handler_box) => task_handler_box,
+            Err(e) if INITIATIVE => return Err(e),
+            Err(_) => return Ok(false),
+        };
+        self.finish_serial_queues(record_id);
 
         if let Some(mut task_mark_ref_mut) = self.shared_header.task_flag_map.get_mut(&task_id) {
             let task_mark = task_mark_ref_mut.value_mut();
 
             task_mark.dec_parallel_runnable_num();
+            task_mark.set_last_finish_time(timestamp());
+            task_mark.record_budget_usage(task_handler_box.get_start_time(), timestamp());
+
+            // A cancelled instance neither failed nor succeeded.
+            let tripped_failure = if state == state::instance::TIMEOUT {
+                task_mark.record_outcome(true)
+            } else {
+                None
+            };
 
-            if task_mark.task_instances_chain_maintainer.is_some() {
+            if task_mark.has_task_instances_chain_maintainer() {
                 // Here the user can be notified that the task instance has disappeared via `Instance`.
-                task_mark.notify_cancel_finish(record_id, state)?;
+                task_mark.notify_cancel_finish(
+                    record_id,
+                    state,
+                    Some(termination_reason.clone()),
+                )?;
             }
 
+            let on_aborted = task_mark.get_on_aborted();
+            drop(task_mark_ref_mut);
+            self.close_exhausted_instances_chains(task_id);
+
+            if let Some(failure) = tripped_failure {
+                self.quarantine_task(task_id, failure);
+            }
+
+            let aborted_instance = AbortedInstance {
+                task_id,
+                record_id,
+                termination_reason,
+                cancellation_observed: task_handler_box.get_cancellation_token().is_observed(),
+            };
+            let token_handed_out = task_handler_box.is_token_handed_out();
+            self.audit_aborted_instance(aborted_instance, token_handed_out, on_aborted)
+                .await;
+
             return Ok(true);
         }
 
@@ -453,19 +1412,101 @@ impl EventHandle {
         }
     }
 
-    pub(crate) fn finish_task(&mut self, task_id: u64, record_id: i64) -> Result<bool> {
+    // A routine that never looked at the token it was handed
+    // was stopped at an arbitrary await point,
+    // any lock or connection it held was released without its cooperation.
+    // The routines without a `TaskContext` have no token to look at, they aren't reported.
+    async fn audit_aborted_instance(
+        &mut self,
+        aborted_instance: AbortedInstance,
+        token_handed_out: bool,
+        on_aborted: Option<SafeStructAbortedFn>,
+    ) {
+        if token_handed_out && !aborted_instance.cancellation_observed {
+            warn!(
+                "task-id: {}, record-id: {} was aborted while it may hold a guard.",
+                aborted_instance.task_id, aborted_instance.record_id
+            );
+
+            // It's logged like the events reported by `handle_event`.
+            #[cfg(feature = "status-report")]
+            {
+                let public_event = PublicEvent::AbortedWhileHoldingGuard(
+                    aborted_instance.task_id,
+                    aborted_instance.record_id,
+                );
+
+                if let Some(level) = self.log_events {
+                    log_public_event(level, &public_event);
+                }
+                self.report_public_event(public_event).await;
+            }
+        }
+
+        // A panicking closure doesn't take the event-handle down with it.
+        if let Some(on_aborted) = on_aborted {
+            let (task_id, record_id) = (aborted_instance.task_id, aborted_instance.record_id);
+            if catch_unwind(AssertUnwindSafe(|| (on_aborted.0)(aborted_instance))).is_err() {
+                error!(
+                    "task-id: {}, record-id: {}, the `on_aborted` closure panicked.",
+                    task_id, record_id
+                );
+            }
+        }
+    }
+
+    pub(crate) fn finish_task(&mut self, finish_task_body: FinishTaskBody) -> Result<bool> {
+        let FinishTaskBody {
+            task_id, record_id, ..
+        } = finish_task_body;
+
+        let failed = match finish_task_body.finish_output {
+            Some(FinishOutput::ProcessOutput(ref output)) => !output.status.success(),
+            Some(FinishOutput::ExceptionOutput(_)) => true,
+            None => false,
+        };
+
         // `task-handler` should exit first regardless of whether `task_mark_ref_mut` exists or not.
-        self.task_trace.quit_one_task_handler(task_id, record_id)?;
+        let task_handler_box = self.task_trace.quit_one_task_handler(task_id, record_id)?;
+        self.finish_serial_queues(record_id);
 
         if let Some(mut task_mark_ref_mut) = self.shared_header.task_flag_map.get_mut(&task_id) {
             let task_mark = task_mark_ref_mut.value_mut();
+            task_mark.record_budget_usage(task_handler_box.get_start_time(), timestamp());
+            task_mark.record_completion(
+                timestamp().saturating_sub(task_handler_box.get_start_time()),
+                finish_task_body.no_work,
+            );
+
+            if task_mark.has_task_instances_chain_maintainer() {
+                if let Some(pid) = finish_task_body.pid {
+                    task_mark.set_instance_pid(record_id, pid);
+                }
+
+                #[cfg(feature = "json-result")]
+                if let Some(result) = finish_task_body.result {
+                    task_mark.set_instance_result(record_id, result);
+                }
 
-            if task_mark.task_instances_chain_maintainer.is_some() {
                 // Here the user can be notified that the task instance has disappeared via `Instance`.
-                task_mark.notify_cancel_finish(record_id, state::instance::COMPLETED)?;
+                let state = if finish_task_body.no_work {
+                    state::instance::COMPLETED_NOOP
+                } else {
+                    state::instance::COMPLETED
+                };
+                task_mark.notify_cancel_finish(record_id, state, None)?;
             }
 
             task_mark.dec_parallel_runnable_num();
+            task_mark.set_last_finish_time(timestamp());
+
+            let tripped_failure = task_mark.record_outcome(failed);
+            drop(task_mark_ref_mut);
+            self.close_exhausted_instances_chains(task_id);
+
+            if let Some(failure) = tripped_failure {
+                self.quarantine_task(task_id, failure);
+            }
 
             return Ok(true);
         }
@@ -478,6 +1519,34 @@ impl EventHandle {
         Ok(true)
     }
 
+    // The chains of a task that doesn't fire anymore are closed once its last instance is over,
+    // so that their consumers stop waiting for the next one.
+    fn close_exhausted_instances_chains(&self, task_id: u64) {
+        if self.task_trace.is_running(task_id) {
+            return;
+        }
+
+        if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+            let task_mark = task_mark.value_mut();
+            if task_mark.is_exhausted() {
+                task_mark.close_task_instances_chains();
+            }
+        }
+    }
+
+    // The task is paused or removed once the event at hand is handled,
+    // the `TaskQuarantined` event is reported then.
+    fn quarantine_task(&self, task_id: u64, failure: Failure) {
+        warn!(
+            "task-id: {} kept failing, it's quarantined by {:?}.",
+            task_id, failure
+        );
+
+        self.timer_event_sender
+            .try_send(TimerEvent::TaskQuarantined(task_id, failure))
+            .unwrap_or_else(|e| error!("`quarantine_task`: {}", e));
+    }
+
     pub(crate) async fn maintain_task_status(
         &mut self,
         task_id: u64,
@@ -485,19 +1554,26 @@ impl EventHandle {
     ) {
         {
             if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
-                let task_instances_chain_maintainer_option =
-                    task_mark.value_mut().get_task_instances_chain_maintainer();
+                let task_mark = task_mark.value_mut();
 
-                if let Some(task_instances_chain_maintainer) =
-                    task_instances_chain_maintainer_option
-                {
+                if task_mark.has_task_instances_chain_maintainer() {
                     let instance = Instance::default()
                         .set_task_id(task_id)
-                        .set_record_id(delay_task_handler_box.get_record_id());
-
-                    task_instances_chain_maintainer
-                        .push_instance(instance)
-                        .await;
+                        .set_record_id(delay_task_handler_box.get_record_id())
+                        .set_scheduled_for(delay_task_handler_box.get_scheduled_time())
+                        .set_started_at(delay_task_handler_box.get_start_time())
+                        .set_cancellation_token(
+                            delay_task_handler_box.get_cancellation_token().clone(),
+                        )
+                        .set_running_pid(delay_task_handler_box.get_running_pid().clone());
+
+                    for task_instances_chain_maintainer in
+                        task_mark.get_task_instances_chain_maintainers()
+                    {
+                        task_instances_chain_maintainer
+                            .push_instance(instance.clone())
+                            .await;
+                    }
                 }
             } else {
                 error!("Missing task_mark for task_id : {}", task_id)
@@ -532,11 +1608,73 @@ cfg_status_report!(
 impl EventHandleBuilder {
     pub(crate) fn status_report_sender(
         &mut self,
-        status_report_sender: AsyncSender<PublicEvent>,
+        status_report_sender: AsyncSender<(u64, PublicEvent)>,
     ) -> &mut Self {
         self.status_report_sender = Some(status_report_sender);
         self
     }
+
+    pub(crate) fn event_log(&mut self, event_log: EventLog) -> &mut Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    pub(crate) fn log_events(&mut self, level: LogLevel) -> &mut Self {
+        self.log_events = Some(level);
+        self
+    }
+}
+
+impl EventHandle {
+    // Whether the event passes the sampling of its task, the failures always do.
+    fn sample_public_event(&self, public_event: &PublicEvent) -> bool {
+        let record_id = match public_event.get_record_id() {
+            Some(record_id) => record_id,
+            None => return true,
+        };
+        let mut task_mark = match self
+            .shared_header
+            .task_flag_map
+            .get_mut(&public_event.get_task_id())
+        {
+            Some(task_mark) => task_mark,
+            None => return true,
+        };
+
+        let completion = match public_event {
+            PublicEvent::RunningTask(_, _) => false,
+            PublicEvent::FinishTask(finish_task_body) if !finish_task_body.is_failed() => true,
+            PublicEvent::FinishTask(_)
+            | PublicEvent::TimeoutTask(_, _)
+            | PublicEvent::CancelTask(_, _, _) => {
+                task_mark.value_mut().forget_unsampled_record(record_id);
+                return true;
+            }
+            _ => return true,
+        };
+
+        task_mark
+            .value_mut()
+            .sample_event(record_id, completion, self.shared_header.event_sampling)
+    }
+
+    // The events are reported in the order they're handled, each with the next sequence number,
+    // so that a consumer can tell when some were lost.
+    async fn report_public_event(&mut self, public_event: PublicEvent) {
+        self.event_sequence += 1;
+        let sequence = self.event_sequence;
+
+        if let Some(event_log) = self.event_log.as_ref() {
+            event_log.record(public_event.clone());
+        }
+
+        if let Some(status_report_sender) = self.status_report_sender.as_ref() {
+            status_report_sender
+                .send((sequence, public_event))
+                .await
+                .unwrap_or_else(|e| error!("event sync error: {}", e));
+        }
+    }
 }
 );
 
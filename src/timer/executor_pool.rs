@@ -0,0 +1,119 @@
+//! Executor pool
+//! The named pools of threads set up by `DelayTimerBuilder::executor_pool`,
+//! running the instances of the tasks assigned to them by `TaskBuilder::set_pool`,
+//! so that heavyweight jobs can't saturate the workers of the latency-sensitive ones.
+use super::local_worker::{abortable_job, LocalFuture, LocalJob};
+use crate::prelude::*;
+
+use std::future::Future;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+use std::thread::Builder;
+
+use smol::channel::unbounded;
+use smol::future::FutureExt;
+use tokio::runtime::Builder as TokioBuilder;
+use tokio::task::{spawn_local, LocalSet};
+
+/// How the instances are handed to the threads of an executor pool,
+/// see `DelayTimerBuilder::executor_pool_with_dispatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dispatch {
+    /// The first thread free to take an instance takes it,
+    /// a thread held up by a blocking instance leaves the others to the rest.
+    #[default]
+    WorkStealing,
+    /// The threads take the instances in turn.
+    RoundRobin,
+    /// The instances of a task always run on the same thread, picked by the task-id,
+    /// so that the state the task captured stays warm in the caches of that core.
+    StickyByTask,
+}
+
+/// A pool of threads, each of them runs many instances at once on a current-thread runtime.
+///
+/// The threads exit once the pool and its clones are dropped.
+#[derive(Debug, Clone)]
+pub struct ExecutorPool {
+    dispatch: Dispatch,
+    // Taken by the first free thread.
+    shared_sender: AsyncSender<LocalJob>,
+    // Taken by the thread they belong to.
+    thread_senders: Arc<Vec<AsyncSender<LocalJob>>>,
+    next_thread: Arc<AtomicUsize>,
+}
+
+impl ExecutorPool {
+    /// New a pool of `threads` threads (one at least) named after `name`.
+    pub fn new(name: &str, threads: usize, dispatch: Dispatch) -> Self {
+        let (shared_sender, shared_receiver) = unbounded::<LocalJob>();
+
+        let thread_senders = (0..threads.max(1))
+            .map(|i| {
+                let (thread_sender, thread_receiver) = unbounded::<LocalJob>();
+                let shared_receiver = shared_receiver.clone();
+                Builder::new()
+                    .name(format!("pool-{}-{}", name, i))
+                    .spawn(move || {
+                        let runtime = TokioBuilder::new_current_thread()
+                            .enable_all()
+                            .build()
+                            .expect("executor_pool can't build its runtime.");
+
+                        LocalSet::new().block_on(&runtime, async move {
+                            while let Ok(local_job) =
+                                thread_receiver.recv().or(shared_receiver.recv()).await
+                            {
+                                spawn_local(local_job());
+                            }
+                        });
+                    })
+                    .expect("executor_pool can't start.");
+                thread_sender
+            })
+            .collect();
+
+        ExecutorPool {
+            dispatch,
+            shared_sender,
+            thread_senders: Arc::new(thread_senders),
+            next_thread: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Run the future made by `job` on the pool as an instance of the task `task_id`,
+    /// until it's over or the returned future is dropped.
+    pub async fn run_job<F, U>(&self, task_id: u64, job: F)
+    where
+        F: FnOnce() -> U + Send + 'static,
+        U: Future<Output = ()> + 'static,
+    {
+        self.run(
+            task_id,
+            Box::new(move || -> LocalFuture { Box::pin(job()) }),
+        )
+        .await
+    }
+
+    pub(crate) async fn run(&self, task_id: u64, instance: LocalJob) {
+        let (local_job, _abort_on_drop, finish_receiver) = abortable_job(instance);
+
+        let sender = match self.dispatch {
+            Dispatch::WorkStealing => &self.shared_sender,
+            Dispatch::RoundRobin => {
+                let next_thread = self.next_thread.fetch_add(1, Relaxed);
+                &self.thread_senders[next_thread % self.thread_senders.len()]
+            }
+            Dispatch::StickyByTask => {
+                &self.thread_senders[(task_id % self.thread_senders.len() as u64) as usize]
+            }
+        };
+        if let Err(e) = sender.send(local_job).await {
+            error!("`ExecutorPool::run`: {}", e);
+            return;
+        }
+
+        finish_receiver.await.ok();
+    }
+}
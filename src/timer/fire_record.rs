@@ -0,0 +1,53 @@
+//! Fire record
+//! The scheduled time each task set by `TaskBuilder::set_at_most_once` last fired for,
+//! kept in the `StateStore` of the timer, so that a restart doesn't run it again.
+use crate::prelude::*;
+
+// The key the last fire of a task is kept under, among the job state of the task.
+const LAST_FIRE_KEY: &str = "delay_timer.last_fire";
+
+pub(crate) fn save_last_fire(
+    state_store: &dyn StateStore,
+    task_id: u64,
+    scheduled_time: u64,
+) -> AnyResult<()> {
+    state_store.set(task_id, LAST_FIRE_KEY, scheduled_time.to_string())
+}
+
+// The scheduled time the task last fired for, if it ever fired.
+pub(crate) fn load_last_fire(state_store: &dyn StateStore, task_id: u64) -> AnyResult<Option<u64>> {
+    state_store
+        .get(task_id, LAST_FIRE_KEY)?
+        .map(|scheduled_time| {
+            scheduled_time.parse().map_err(|_| {
+                anyhow!(
+                    "The last fire `{}` of task {} is malformed.",
+                    scheduled_time,
+                    task_id
+                )
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_last_fire, save_last_fire};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_last_fire() -> AnyResult<()> {
+        let state_store = MemoryStateStore::default();
+        assert_eq!(load_last_fire(&state_store, 1)?, None);
+
+        save_last_fire(&state_store, 1, 10_800)?;
+        save_last_fire(&state_store, 1, 97_200)?;
+        assert_eq!(load_last_fire(&state_store, 1)?, Some(97_200));
+        assert_eq!(load_last_fire(&state_store, 2)?, None);
+
+        state_store.set(1, super::LAST_FIRE_KEY, "03:00".to_string())?;
+        assert!(load_last_fire(&state_store, 1).is_err());
+
+        Ok(())
+    }
+}
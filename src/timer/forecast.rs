@@ -0,0 +1,96 @@
+//! Forecast
+//! The load the registered schedules put on the timer over a coming window,
+//! so that a crowded second shows up before it's reached, see `DelayTimer::forecast`.
+
+/// The fires expected in each second of a window, and how many instances run meanwhile.
+///
+/// Every fire is assumed to run, for the p95 duration of the task once it's known,
+/// its maximum running time otherwise, and at least a second.
+/// What depends on the state of the timer at the time (budgets, overlap policies, readiness) isn't accounted for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadForecast {
+    /// The time (timestamp-seconds) the window starts at.
+    pub start: u64,
+    /// How many fires are expected in each second of the window, from `start` on.
+    pub fires_per_second: Vec<u32>,
+    /// How many instances are expected to run in each second of the window, from `start` on.
+    pub concurrency: Vec<u32>,
+}
+
+impl LoadForecast {
+    pub(crate) fn new(start: u64, seconds: u64) -> Self {
+        LoadForecast {
+            start,
+            fires_per_second: vec![0; seconds as usize],
+            concurrency: vec![0; seconds as usize],
+        }
+    }
+
+    // Count the fires of a task, each running for `duration` seconds.
+    // The fires outside the window are left out, the runs are cut at its end.
+    pub(crate) fn add_fires(&mut self, fires: &[u64], duration: u64) {
+        let seconds = self.fires_per_second.len() as u64;
+        for &fire in fires {
+            let offset = match fire.checked_sub(self.start).filter(|&offset| offset < seconds) {
+                Some(offset) => offset,
+                None => continue,
+            };
+
+            self.fires_per_second[offset as usize] += 1;
+            let end = (offset + duration.max(1)).min(seconds);
+            for running in self.concurrency[offset as usize..end as usize].iter_mut() {
+                *running += 1;
+            }
+        }
+    }
+
+    /// How many fires are expected over the window.
+    pub fn total_fires(&self) -> u64 {
+        self.fires_per_second
+            .iter()
+            .map(|&fires| fires as u64)
+            .sum()
+    }
+
+    /// The second with the most fires, as `(time, fires)`, the earliest of equals.
+    ///
+    /// `None` for an empty window.
+    pub fn peak_fires(&self) -> Option<(u64, u32)> {
+        peak(self.start, &self.fires_per_second)
+    }
+
+    /// The second with the most instances running, as `(time, instances)`, the earliest of equals.
+    ///
+    /// `None` for an empty window.
+    pub fn peak_concurrency(&self) -> Option<(u64, u32)> {
+        peak(self.start, &self.concurrency)
+    }
+}
+
+fn peak(start: u64, counts: &[u32]) -> Option<(u64, u32)> {
+    counts
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|&(_, &count)| count)
+        .map(|(offset, &count)| (start + offset as u64, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoadForecast;
+
+    #[test]
+    fn test_load_forecast() {
+        let mut forecast = LoadForecast::new(100, 10);
+        forecast.add_fires(&[100, 104, 108, 112], 3);
+        forecast.add_fires(&[99, 104], 0);
+
+        assert_eq!(forecast.fires_per_second, vec![1, 0, 0, 0, 2, 0, 0, 0, 1, 0]);
+        assert_eq!(forecast.concurrency, vec![1, 1, 1, 0, 2, 1, 1, 0, 1, 1]);
+        assert_eq!(forecast.total_fires(), 4);
+        assert_eq!(forecast.peak_fires(), Some((104, 2)));
+        assert_eq!(forecast.peak_concurrency(), Some((104, 2)));
+        assert_eq!(LoadForecast::new(100, 0).peak_fires(), None);
+    }
+}
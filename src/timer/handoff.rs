@@ -0,0 +1,61 @@
+//! Handoff
+//! The fires of the tasks handed from an instance of the scheduler to the one replacing it,
+//! through the `StateStore` they share, so that a deploy neither misses nor repeats a fire.
+use crate::prelude::*;
+
+// The key the handoff of a task is kept under, among the job state of the task.
+const HANDOFF_KEY: &str = "delay_timer.handoff";
+
+/// What an instance left for the one taking over, see `DelayTimer::hand_off`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandoffSnapshot {
+    /// The time (timestamp-seconds) the instance stopped firing at.
+    pub handed_off_at: u64,
+    /// The tasks, by id, with the time (timestamp-seconds) of the next fire left to run.
+    pub next_fires: Vec<(u64, u64)>,
+}
+
+pub(crate) fn save_handoff(
+    state_store: &dyn StateStore,
+    task_id: u64,
+    next_fire: u64,
+) -> AnyResult<()> {
+    state_store.set(task_id, HANDOFF_KEY, next_fire.to_string())
+}
+
+// The next fire handed off for the task, if there's one, it's taken once.
+pub(crate) fn take_handoff(state_store: &dyn StateStore, task_id: u64) -> AnyResult<Option<u64>> {
+    let next_fire = match state_store.get(task_id, HANDOFF_KEY)? {
+        // The store can't remove a key, an empty handoff is none.
+        Some(next_fire) if !next_fire.is_empty() => next_fire,
+        _ => return Ok(None),
+    };
+    let next_fire = next_fire
+        .parse()
+        .map_err(|_| anyhow!("The handoff `{}` of task {} is malformed.", next_fire, task_id))?;
+
+    state_store.set(task_id, HANDOFF_KEY, String::new())?;
+    Ok(Some(next_fire))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{save_handoff, take_handoff};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_take_handoff() -> AnyResult<()> {
+        let state_store = MemoryStateStore::default();
+        assert_eq!(take_handoff(&state_store, 1)?, None);
+
+        save_handoff(&state_store, 1, 120)?;
+        assert_eq!(take_handoff(&state_store, 2)?, None);
+        assert_eq!(take_handoff(&state_store, 1)?, Some(120));
+        assert_eq!(take_handoff(&state_store, 1)?, None);
+
+        state_store.set(1, super::HANDOFF_KEY, "soon".to_string())?;
+        assert!(take_handoff(&state_store, 1).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Job queue
+//! The parameterized runs enqueued for a later time by `DelayTimer::enqueue`,
+//! kept per task in the `StateStore` of the timer, so that a persistent store
+//! keeps them across restarts.
+use crate::prelude::*;
+
+use std::fmt::Write;
+
+// The key the queue of a task is kept under, among the job state of the task.
+const JOB_QUEUE_KEY: &str = "delay_timer.job_queue";
+
+/// A run enqueued by `DelayTimer::enqueue`, waiting for its time to come.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnqueuedJob {
+    /// The time (timestamp-seconds) it runs at.
+    pub run_at: u64,
+    /// The payload handed to it, see `TaskContext::payload`.
+    pub payload: Vec<u8>,
+}
+
+// The jobs enqueued for the task, the earliest first.
+pub(crate) fn load_jobs(state_store: &dyn StateStore, task_id: u64) -> AnyResult<Vec<EnqueuedJob>> {
+    let jobs = match state_store.get(task_id, JOB_QUEUE_KEY)? {
+        Some(jobs) => jobs,
+        None => return Ok(Vec::new()),
+    };
+
+    jobs.lines().map(decode_job).collect()
+}
+
+pub(crate) fn save_jobs(
+    state_store: &dyn StateStore,
+    task_id: u64,
+    jobs: &[EnqueuedJob],
+) -> AnyResult<()> {
+    let jobs = jobs.iter().map(encode_job).collect::<Vec<_>>().join("\n");
+    state_store.set(task_id, JOB_QUEUE_KEY, jobs)
+}
+
+// A job per line, as `<run_at> <payload in hex>`.
+fn encode_job(job: &EnqueuedJob) -> String {
+    let mut line = format!("{} ", job.run_at);
+    for byte in job.payload.iter() {
+        // Writing to a `String` doesn't fail.
+        let _ = write!(line, "{:02x}", byte);
+    }
+    line
+}
+
+fn decode_job(line: &str) -> AnyResult<EnqueuedJob> {
+    let malformed = || anyhow!("The enqueued job `{}` is malformed.", line);
+
+    let (run_at, payload) = line.split_once(' ').ok_or_else(malformed)?;
+    if payload.len() % 2 != 0 {
+        return Err(malformed());
+    }
+    let payload = (0..payload.len())
+        .step_by(2)
+        .map(|i| {
+            payload
+                .get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(malformed)
+        })
+        .collect::<AnyResult<Vec<u8>>>()?;
+
+    Ok(EnqueuedJob {
+        run_at: run_at.parse().map_err(|_| malformed())?,
+        payload,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_jobs, save_jobs, EnqueuedJob};
+    use crate::prelude::{MemoryStateStore, StateStore};
+
+    #[test]
+    fn test_job_queue() -> anyhow::Result<()> {
+        let state_store = MemoryStateStore::default();
+        assert!(load_jobs(&state_store, 1)?.is_empty());
+
+        let jobs = vec![
+            EnqueuedJob {
+                run_at: 10,
+                payload: b"customer-42".to_vec(),
+            },
+            EnqueuedJob {
+                run_at: 20,
+                payload: Vec::new(),
+            },
+        ];
+        save_jobs(&state_store, 1, &jobs)?;
+        assert_eq!(load_jobs(&state_store, 1)?, jobs);
+        assert!(load_jobs(&state_store, 2)?.is_empty());
+
+        state_store.set(1, super::JOB_QUEUE_KEY, "10 4".to_string())?;
+        assert!(load_jobs(&state_store, 1).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+//! Job state
+//! A tiny key-value store per task, through which incremental jobs keep
+//! their cursors and watermarks from one run to the next.
+use crate::prelude::*;
+
+use std::fmt::{self, Pointer};
+
+/// The storage behind `TaskContext::state_get` and `TaskContext::state_set`,
+/// set by `DelayTimerBuilder::set_state_store`.
+///
+/// The keys are scoped to the task, so that tasks don't see each other's state.
+/// It's called from the running instances, a slow store holds them up.
+pub trait StateStore: Send + Sync + 'static {
+    /// Get the value of `key` for `task_id`, `None` if it was never set.
+    fn get(&self, task_id: u64, key: &str) -> AnyResult<Option<String>>;
+
+    /// Set the value of `key` for `task_id`.
+    fn set(&self, task_id: u64, key: &str, value: String) -> AnyResult<()>;
+}
+
+impl fmt::Debug for dyn StateStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// The default `StateStore`, which keeps the state in memory,
+/// so it's lost when the process exits.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    inner: DashMap<(u64, String), String>,
+}
+
+impl StateStore for MemoryStateStore {
+    fn get(&self, task_id: u64, key: &str) -> AnyResult<Option<String>> {
+        Ok(self
+            .inner
+            .get(&(task_id, key.to_string()))
+            .map(|value| value.clone()))
+    }
+
+    fn set(&self, task_id: u64, key: &str, value: String) -> AnyResult<()> {
+        self.inner.insert((task_id, key.to_string()), value);
+        Ok(())
+    }
+}
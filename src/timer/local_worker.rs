@@ -0,0 +1,94 @@
+//! Local worker
+//! The thread running the `!Send` futures of the local routines,
+//! on a current-thread tokio runtime with a `LocalSet`.
+use crate::prelude::*;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::thread::Builder;
+
+use futures::channel::oneshot;
+use futures::future::{AbortHandle, Abortable};
+use once_cell::sync::Lazy;
+use smol::channel::unbounded;
+use tokio::runtime::Builder as TokioBuilder;
+use tokio::task::{spawn_local, LocalSet};
+
+/// A future made by the thread it's sent to, so it never leaves that thread.
+pub(crate) type LocalJob = Box<dyn FnOnce() -> LocalFuture + Send>;
+pub(crate) type LocalFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+// Started by the first local routine that runs.
+static LOCAL_WORKER: Lazy<AsyncSender<LocalJob>> = Lazy::new(|| {
+    let (job_sender, job_receiver) = unbounded::<LocalJob>();
+
+    Builder::new()
+        .name("local_worker".into())
+        .spawn(move || {
+            let runtime = TokioBuilder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("local_worker can't build its runtime.");
+
+            LocalSet::new().block_on(&runtime, async move {
+                debug!(" `local_worker` start.");
+                while let Ok(local_job) = job_receiver.recv().await {
+                    spawn_local(local_job());
+                }
+            });
+        })
+        .expect("local_worker can't start.");
+
+    job_sender
+});
+
+// Aborts the local future once the instance is dropped, e.g. cancelled or timed out.
+pub(crate) struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Make `local_job` abortable through the returned guard,
+// the receiver learns whether it ran to completion.
+pub(crate) fn abortable_job(
+    local_job: LocalJob,
+) -> (LocalJob, AbortOnDrop, oneshot::Receiver<()>) {
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let (finish_sender, finish_receiver) = oneshot::channel();
+
+    let local_job: LocalJob = Box::new(move || -> LocalFuture {
+        Box::pin(async move {
+            if Abortable::new(local_job(), abort_registration).await.is_ok() {
+                finish_sender.send(()).ok();
+            }
+        })
+    });
+
+    (local_job, AbortOnDrop(abort_handle), finish_receiver)
+}
+
+// Run the future made by `routine` on the local worker, until it's over or the returned future is dropped.
+//
+// Whether the future ran to completion is returned.
+pub(crate) async fn run_on_local_worker<F, U>(routine: F) -> bool
+where
+    F: FnOnce() -> U + Send + 'static,
+    U: Future + 'static,
+{
+    let local_job: LocalJob = Box::new(move || -> LocalFuture {
+        Box::pin(async move {
+            routine().await;
+        })
+    });
+    let (local_job, _abort_on_drop, finish_receiver) = abortable_job(local_job);
+
+    if let Err(e) = LOCAL_WORKER.send(local_job).await {
+        error!("`run_on_local_worker`: {}", e);
+        return false;
+    }
+
+    finish_receiver.await.is_ok()
+}
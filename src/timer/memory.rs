@@ -0,0 +1,108 @@
+//! Memory
+//! The approximate memory the tasks hold, attributed to their tenants,
+//! for the timers registering so many closures that it's worth watching,
+//! see `DelayTimerBuilder::enable_memory_attribution`.
+use crate::prelude::*;
+
+use std::collections::HashMap;
+
+/// The memory held by the tasks of a group, see `SchedulerHealth::memory_attribution`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupMemory {
+    /// The tenant of the tasks, `None` for the tasks without one.
+    pub tenant: Option<String>,
+    /// The tasks of the group.
+    pub tasks: usize,
+    /// The approximate bytes the tasks hold,
+    /// as told by `TaskBuilder::set_memory_sizer` or guessed from the tasks.
+    pub approximate_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MemoryAttribution {
+    // The tenant and the approximate size of each task, taken once it's added.
+    sizes: DashMap<u64, (Option<String>, usize)>,
+}
+
+impl MemoryAttribution {
+    pub(crate) fn record(&self, task: &Task) {
+        self.sizes.insert(
+            task.task_id,
+            (task.tenant.clone(), task.approximate_memory()),
+        );
+    }
+
+    // The task is gone.
+    pub(crate) fn release(&self, task_id: u64) {
+        self.sizes.remove(&task_id);
+    }
+
+    // The groups, the largest first.
+    pub(crate) fn by_group(&self) -> Vec<GroupMemory> {
+        let mut groups: HashMap<Option<String>, GroupMemory> = HashMap::new();
+        for size in self.sizes.iter() {
+            let (tenant, bytes) = size.value();
+            let group = groups.entry(tenant.clone()).or_insert_with(|| GroupMemory {
+                tenant: tenant.clone(),
+                ..Default::default()
+            });
+            group.tasks += 1;
+            group.approximate_bytes += bytes;
+        }
+
+        let mut groups: Vec<GroupMemory> = groups.into_values().collect();
+        groups.sort_unstable_by(|a, b| {
+            b.approximate_bytes
+                .cmp(&a.approximate_bytes)
+                .then_with(|| a.tenant.cmp(&b.tenant))
+        });
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GroupMemory, MemoryAttribution};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_memory_by_group() -> AnyResult<()> {
+        let memory_attribution = MemoryAttribution::default();
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_seconds(1);
+
+        for task_id in 1..=3 {
+            let task = task_builder
+                .set_task_id(task_id)
+                .set_tenant("acme")
+                .set_memory_sizer(|| 1024)
+                .spawn_async_routine(|| async {})?;
+            memory_attribution.record(&task);
+        }
+        let captured = vec![0u8; 16];
+        let task = TaskBuilder::default()
+            .set_task_id(4)
+            .set_frequency_repeated_by_seconds(1)
+            .spawn_async_routine(move || {
+                let captured = captured.clone();
+                async move { drop(captured) }
+            })?;
+        memory_attribution.record(&task);
+
+        memory_attribution.release(3);
+        let groups = memory_attribution.by_group();
+        assert_eq!(
+            groups[0],
+            GroupMemory {
+                tenant: Some("acme".to_string()),
+                tasks: 2,
+                approximate_bytes: 2048,
+            }
+        );
+        assert_eq!(groups[1].tenant, None);
+        assert_eq!(groups[1].tasks, 1);
+        assert!(groups[1].approximate_bytes > 0);
+
+        Ok(())
+    }
+}
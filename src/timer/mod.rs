@@ -1,12 +1,41 @@
 //! timer is the core module of the library , it can provide an API for task building ,
 //! task scheduling , event handling , resource recovery .
 
+#[cfg(feature = "chaos")]
+#[cfg_attr(docsrs, doc(cfg(feature = "chaos")))]
+pub mod chaos;
+pub mod audit;
+pub mod circuit_breaker;
+pub mod dedicated_thread;
+pub mod delay;
+pub mod diagnostics;
+pub mod dst;
+pub mod executor_pool;
+pub mod forecast;
+pub mod handoff;
+pub mod job_queue;
+pub mod job_state;
+pub mod memory;
+pub mod observer;
+pub mod ownership;
+pub mod reconcile;
+pub mod spawn_hook;
 pub mod task;
+pub mod tenant;
 pub mod timer_core;
+pub mod view;
+#[cfg(feature = "debug-dump")]
+#[cfg_attr(docsrs, doc(cfg(feature = "debug-dump")))]
+pub mod wheel_dump;
 
 pub(crate) mod event_handle;
+pub(crate) mod fire_record;
+pub(crate) mod local_worker;
+#[cfg(feature = "otel")]
+pub(crate) mod otel;
 pub(crate) mod runtime_trace;
 pub(crate) mod slot;
+pub(crate) mod watchdog;
 
 pub(crate) use slot::Slot;
 pub(crate) use task::{Task, TaskMark};
@@ -0,0 +1,54 @@
+//! Observer
+//! Hooks around the internal scheduling decisions, for deep debugging and custom metrics.
+use std::fmt::{self, Pointer};
+use std::time::Duration;
+
+/// Observer of the internal scheduling decisions of the timer-core.
+///
+/// Every hook does nothing by default, implement the ones of interest.
+/// Hooks run inline on the timer-core loop, they should be cheap and must not block.
+pub trait SchedulerObserver: Send + Sync + 'static {
+    /// A tick of the time-wheel started on `second_hand`.
+    fn on_tick_started(&self, _second_hand: u64, _timestamp: u64) {}
+
+    /// The tasks due on `second_hand` were all processed.
+    fn on_slot_drained(&self, _second_hand: u64, _task_count: usize) {}
+
+    /// A due fire of the task didn't run.
+    fn on_task_skipped(&self, _task_id: u64, _reason: SkipReason) {}
+
+    /// A new running instance of the task was spawned.
+    fn on_instance_spawned(&self, _task_id: u64, _record_id: i64) {}
+
+    /// An event was delivered to the event-handle, after waiting for `latency`.
+    fn on_channel_send(&self, _latency: Duration) {}
+}
+
+impl fmt::Debug for dyn SchedulerObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// The policy that made the timer-core skip a due fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The fire matches the exclusion schedule of the task.
+    Exclusion,
+    /// The execution budget of the task is exhausted.
+    BudgetExceeded,
+    /// The task already runs `maximum_parallel_runnable_num` instances.
+    MaximumParallelRunnable,
+    /// The fire was queued for longer than the `max_queue_delay` of the task.
+    QueueDelayExceeded,
+    /// The readiness check of the task hasn't passed.
+    NotReady,
+    /// The `SpawnHook` of the timer vetoed the instance.
+    Vetoed,
+    /// The circuit breaker of the task is open.
+    CircuitOpen,
+    /// The tenant of the task runs as many instances as its quota allows.
+    TenantQuota,
+    /// The process named by the pidfile of the task is still alive.
+    SingleInstanceHeld,
+}
@@ -0,0 +1,53 @@
+//! Otel
+//! OpenTelemetry span of each running instance, so scheduled jobs show up in distributed traces.
+use super::task::Task;
+
+use opentelemetry::global;
+use opentelemetry::trace::{SpanKind, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+
+// The name of the tracer, spans are got from the global tracer-provider.
+const TRACER_NAME: &str = "delay_timer";
+
+/// Start the span of a running instance of `task`.
+///
+/// Its parent is the context given to `advance_task_with` for that run,
+/// or else the one given at registration, or else the current context.
+/// The span ends when the last clone of the returned context is dropped,
+/// that is, when the instance finishes or is aborted.
+pub(crate) fn start_instance_span(
+    task: &mut Task,
+    record_id: i64,
+    scheduled_time: u64,
+    timestamp: u64,
+) -> Context {
+    let parent_context = task
+        .take_advance_parent_context()
+        .or_else(|| task.otel_parent_context.clone())
+        .unwrap_or_else(Context::current);
+
+    let mut attributes = vec![
+        KeyValue::new("code.function", task.routine.0.name()),
+        KeyValue::new(
+            "scheduling.delay",
+            timestamp.saturating_sub(scheduled_time) as i64,
+        ),
+        KeyValue::new("delay_timer.task_id", task.task_id as i64),
+        KeyValue::new("delay_timer.record_id", record_id),
+    ];
+    if let Some(cron_expression) = task.cron_expression() {
+        attributes.push(KeyValue::new(
+            "scheduling.cron",
+            cron_expression.to_string(),
+        ));
+    }
+
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder(format!("delay_timer.task-{}", task.task_id))
+        .with_kind(SpanKind::Internal)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, &parent_context);
+
+    parent_context.with_span(span)
+}
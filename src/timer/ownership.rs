@@ -0,0 +1,31 @@
+//! Ownership
+//! Tasks inserted by one component of an application can't be clobbered by another one.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+static NEXT_OWNER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Token of the owner of tasks.
+///
+/// Every token made by `OwnerToken::new` is distinct, even with the same name,
+/// only its clones are recognized as the same owner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OwnerToken {
+    id: u64,
+    name: Arc<str>,
+}
+
+impl OwnerToken {
+    /// Make a new token, `name` only serves diagnostics.
+    pub fn new(name: &str) -> Self {
+        OwnerToken {
+            id: NEXT_OWNER_ID.fetch_add(1, Ordering::Relaxed),
+            name: name.into(),
+        }
+    }
+
+    /// Get the name of the owner.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,119 @@
+//! Reconcile
+//! Declarative description of tasks, so that a whole desired set can be synced
+//! into a `DelayTimer` by `DelayTimer::reconcile`.
+use super::task::{Task, TaskBuilder};
+use crate::prelude::*;
+
+use std::collections::HashMap;
+use std::fmt::{self, Pointer};
+use std::sync::Arc;
+
+type SafeArcRoutineSpawner =
+    Arc<dyn Fn(&TaskBuilder<'_>) -> Result<Task, TaskError> + 'static + Send + Sync>;
+
+#[derive(Clone)]
+struct SafeStructRoutineSpawner(SafeArcRoutineSpawner);
+impl fmt::Debug for SafeStructRoutineSpawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// The desired state of a task.
+///
+/// The routine can't be declared, it's referred to by its name in a `RoutineRegistry`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TaskConfig {
+    /// Task_id should unique.
+    pub task_id: u64,
+    /// The cron expression the task repeats by.
+    pub cron_expression: String,
+    /// The name the routine is registered by.
+    pub routine: String,
+    /// Maximum execution time (optional).
+    pub maximum_running_time: Option<u64>,
+    /// The maximum number of parallel runs (optional).
+    pub maximum_parallel_runnable_num: Option<u64>,
+}
+
+impl TaskConfig {
+    pub(crate) fn build(&self, registry: &RoutineRegistry) -> Result<Task, TaskError> {
+        let spawner = registry
+            .routines
+            .get(&self.routine)
+            .ok_or_else(|| TaskError::UnregisteredRoutine(self.routine.clone()))?;
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_task_id(self.task_id)
+            .set_frequency_repeated_by_cron_str(&self.cron_expression);
+
+        if let Some(maximum_running_time) = self.maximum_running_time {
+            task_builder.set_maximum_running_time(maximum_running_time);
+        }
+
+        if let Some(maximum_parallel_runnable_num) = self.maximum_parallel_runnable_num {
+            task_builder.set_maximum_parallel_runnable_num(maximum_parallel_runnable_num);
+        }
+
+        (spawner.0)(&task_builder)
+    }
+}
+
+/// Routines by name, for `TaskConfig`s to refer to.
+#[derive(Debug, Clone, Default)]
+pub struct RoutineRegistry {
+    routines: HashMap<String, SafeStructRoutineSpawner>,
+}
+
+impl RoutineRegistry {
+    /// Register an async-routine under `name`.
+    pub fn register_async_routine<F, U>(&mut self, name: impl Into<String>, routine: F) -> &mut Self
+    where
+        F: Fn() -> U + 'static + Send + Sync + Clone,
+        U: std::future::Future + 'static + Send,
+    {
+        let spawner: SafeArcRoutineSpawner =
+            Arc::new(move |task_builder| task_builder.spawn_async_routine(routine.clone()));
+        self.routines
+            .insert(name.into(), SafeStructRoutineSpawner(spawner));
+        self
+    }
+
+    /// Register a sync-routine under `name`.
+    pub fn register_routine<F>(&mut self, name: impl Into<String>, routine: F) -> &mut Self
+    where
+        F: Fn() + 'static + Send + Sync + Clone,
+    {
+        let spawner: SafeArcRoutineSpawner =
+            Arc::new(move |task_builder| task_builder.spawn_routine(routine.clone()));
+        self.routines
+            .insert(name.into(), SafeStructRoutineSpawner(spawner));
+        self
+    }
+
+    /// Whether a routine is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.routines.contains_key(name)
+    }
+}
+
+/// The changes `DelayTimer::reconcile` made, by task-id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Tasks that weren't registered.
+    pub added: Vec<u64>,
+    /// Tasks whose config changed.
+    pub updated: Vec<u64>,
+    /// Tasks that aren't desired any more.
+    pub removed: Vec<u64>,
+    /// Tasks that were left as they were.
+    pub unchanged: Vec<u64>,
+}
+
+impl ReconcileReport {
+    /// Whether nothing was changed.
+    pub fn is_noop(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
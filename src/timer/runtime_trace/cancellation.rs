@@ -0,0 +1,94 @@
+//! Cooperative cancellation of the running task instance.
+//!
+//! The token is shared between the `TaskContext` handed to the routine and the
+//! `TaskInstance` held by users, a routine that observes it can wind down on its own
+//! before being hard-aborted.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use event_listener::Event;
+
+/// Token for cooperative cancellation of a running task instance.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    inner: Arc<CancellationTokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationTokenInner {
+    cancelled: AtomicBool,
+    // Set once the routine looks at the token.
+    observed: AtomicBool,
+    event: Event,
+}
+
+impl CancellationToken {
+    /// Whether the cancellation has been requested.
+    ///
+    /// It's a plain read, the routine checks the token by `observe`.
+    #[inline(always)]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Whether the cancellation has been requested, checked by the routine:
+    /// an instance whose routine observed its token isn't reported as aborted
+    /// while it may hold a guard, see `AbortedInstance::cancellation_observed`.
+    #[inline(always)]
+    pub fn observe(&self) -> bool {
+        self.inner.observed.store(true, Ordering::Release);
+        self.is_cancelled()
+    }
+
+    /// Async-await until the cancellation is requested, the routine observes the token.
+    pub async fn cancelled(&self) {
+        loop {
+            if self.observe() {
+                return;
+            }
+
+            let listener = self.inner.event.listen();
+
+            // Check again after registering the listener,
+            // otherwise a notification in between would be missed.
+            if self.is_cancelled() {
+                return;
+            }
+
+            listener.await;
+        }
+    }
+
+    /// Whether the routine has ever checked or awaited the token.
+    #[inline(always)]
+    pub(crate) fn is_observed(&self) -> bool {
+        self.inner.observed.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::AcqRel) {
+            self.inner.event.notify(usize::MAX);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+
+    #[test]
+    fn test_observe() {
+        let cancellation_token = CancellationToken::default();
+
+        // Reading the token doesn't count as the routine observing it.
+        assert!(!cancellation_token.is_cancelled());
+        assert!(!cancellation_token.is_observed());
+
+        assert!(!cancellation_token.observe());
+        assert!(cancellation_token.is_observed());
+
+        cancellation_token.cancel();
+        assert!(cancellation_token.is_cancelled());
+        assert!(cancellation_token.observe());
+    }
+}
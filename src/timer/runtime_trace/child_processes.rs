@@ -0,0 +1,93 @@
+//! Child processes
+//! The registry of the processes spawned by the running instances of the process tasks,
+//! so that a shutdown can tell which of them it would leave behind, and kill them.
+use crate::prelude::*;
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ChildProcesses {
+    // The pid of each process, and the task it was spawned by.
+    inner: Arc<DashMap<u32, u64>>,
+}
+
+// The processes of an instance, they leave the registry once it's over.
+#[derive(Debug)]
+pub(crate) struct TrackedChildProcesses {
+    child_processes: ChildProcesses,
+    pids: Vec<u32>,
+}
+
+impl Drop for TrackedChildProcesses {
+    fn drop(&mut self) {
+        for pid in self.pids.iter() {
+            self.child_processes.inner.remove(pid);
+        }
+    }
+}
+
+impl ChildProcesses {
+    pub(crate) fn track(&self, task_id: u64, pids: Vec<u32>) -> TrackedChildProcesses {
+        for &pid in pids.iter() {
+            self.inner.insert(pid, task_id);
+        }
+
+        TrackedChildProcesses {
+            child_processes: self.clone(),
+            pids,
+        }
+    }
+
+    // The pids of the processes still registered, the lowest first.
+    pub(crate) fn pids(&self) -> Vec<u32> {
+        let mut pids: Vec<u32> = self.inner.iter().map(|entry| *entry.key()).collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    // Kill the processes still registered, and tell which of them they were.
+    pub(crate) fn kill_all(&self) -> Vec<u32> {
+        let pids = self.pids();
+        for &pid in pids.iter() {
+            if let Err(e) = kill(pid) {
+                error!("`ChildProcesses::kill_all` (pid: {}): {}", pid, e);
+            }
+        }
+        pids
+    }
+}
+
+#[cfg(unix)]
+fn kill(pid: u32) -> std::io::Result<()> {
+    match unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(_pid: u32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "Killing a process by its pid is only supported on unix.",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChildProcesses;
+
+    #[test]
+    fn test_child_processes() {
+        let child_processes = ChildProcesses::default();
+
+        let tracked = child_processes.track(1, vec![42, 7]);
+        let _other = child_processes.track(2, vec![9]);
+        assert_eq!(child_processes.pids(), vec![7, 9, 42]);
+
+        drop(tracked);
+        assert_eq!(child_processes.pids(), vec![9]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Future tracker
+//! The registry of the background futures the scheduler spawns on the async-runtime,
+//! so that a shutdown aborts exactly these and leaves the rest of a shared runtime alone.
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use futures::future::{abortable, AbortHandle};
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FutureTracker {
+    inner: Arc<FutureTrackerInner>,
+}
+
+#[derive(Debug, Default)]
+struct FutureTrackerInner {
+    next_id: AtomicU64,
+    // Set by `abort_all`, the futures tracked later are aborted right away.
+    closed: AtomicBool,
+    abort_handles: DashMap<u64, AbortHandle>,
+}
+
+impl FutureTracker {
+    // Wrap `future` before spawning it, it leaves the registry once it's over.
+    pub(crate) fn track<F>(&self, future: F) -> impl Future<Output = ()> + Send + 'static
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (future, abort_handle) = abortable(future);
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        self.inner.abort_handles.insert(id, abort_handle.clone());
+
+        // Checked after the insertion, so that a concurrent `abort_all` can't miss it.
+        if self.inner.closed.load(Ordering::Acquire) {
+            abort_handle.abort();
+        }
+
+        let inner = self.inner.clone();
+        async move {
+            future.await.ok();
+            inner.abort_handles.remove(&id);
+        }
+    }
+
+    // Abort the futures that are still running, and tell how many they were.
+    pub(crate) fn abort_all(&self) -> usize {
+        self.inner.closed.store(true, Ordering::Release);
+
+        let ids: Vec<u64> = self
+            .inner
+            .abort_handles
+            .iter()
+            .map(|abort_handle| *abort_handle.key())
+            .collect();
+
+        ids.into_iter()
+            .filter_map(|id| self.inner.abort_handles.remove(&id))
+            .map(|(_, abort_handle)| abort_handle.abort())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FutureTracker;
+    use smol::Timer;
+    use std::time::Duration;
+
+    #[test]
+    fn test_abort_all() {
+        let future_tracker = FutureTracker::default();
+
+        let finished = smol::spawn(future_tracker.track(async {}));
+        let pending = smol::spawn(future_tracker.track(async {
+            Timer::after(Duration::from_secs(60)).await;
+        }));
+        smol::block_on(finished);
+
+        // The finished future has left the registry.
+        assert_eq!(future_tracker.abort_all(), 1);
+        smol::block_on(pending);
+
+        // Tracked after the shutdown, it never runs.
+        smol::block_on(future_tracker.track(async {
+            Timer::after(Duration::from_secs(60)).await;
+        }));
+        assert_eq!(future_tracker.abort_all(), 0);
+    }
+}
@@ -0,0 +1,94 @@
+//! Heartbeats of the running task instance.
+//!
+//! The routine beats through `TaskContext::heartbeat`, a watcher running beside it
+//! reports the instance as stalled once a beat is overdue,
+//! and winds down as soon as the routine (and so its context) is dropped.
+use crate::prelude::*;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+/// What becomes of an instance that missed its heartbeat, see `TaskBuilder::set_heartbeat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StallPolicy {
+    /// An `InstanceStalled` event is reported, the instance keeps running.
+    #[default]
+    Report,
+    /// An `InstanceStalled` event is reported, then the instance times out
+    /// the way it does once `maximum_running_time` is over.
+    Timeout,
+}
+
+#[derive(Debug)]
+pub(crate) struct Heartbeat {
+    started: Instant,
+    // Milliseconds since `started`.
+    last_beat: AtomicU64,
+}
+
+impl Heartbeat {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Heartbeat {
+            started: Instant::now(),
+            last_beat: AtomicU64::new(0),
+        })
+    }
+
+    pub(crate) fn beat(&self) {
+        self.last_beat
+            .store(self.started.elapsed().as_millis() as u64, Ordering::Release);
+    }
+
+    // The time of the last beat (milliseconds since `started`), and how long ago it was.
+    fn last_beat(&self) -> (u64, Duration) {
+        let last_beat = self.last_beat.load(Ordering::Acquire);
+        let since_last_beat = self
+            .started
+            .elapsed()
+            .saturating_sub(Duration::from_millis(last_beat));
+        (last_beat, since_last_beat)
+    }
+}
+
+// Watch the beats of the instance, each overdue one is reported once.
+pub(crate) async fn watch_heartbeat(
+    heartbeat: Weak<Heartbeat>,
+    interval: Duration,
+    stall_policy: StallPolicy,
+    task_id: u64,
+    record_id: i64,
+    timer_event_sender: TimerEventSender,
+) {
+    let mut wait = interval;
+    let mut reported_beat = None;
+    loop {
+        AsyncTimer::after(wait).await;
+
+        let (last_beat, since_last_beat) = match heartbeat.upgrade() {
+            Some(heartbeat) => heartbeat.last_beat(),
+            None => return,
+        };
+        if since_last_beat < interval {
+            wait = interval - since_last_beat;
+            continue;
+        }
+        wait = interval;
+        if reported_beat == Some(last_beat) {
+            continue;
+        }
+        reported_beat = Some(last_beat);
+
+        timer_event_sender
+            .send(TimerEvent::InstanceStalled(task_id, record_id))
+            .await
+            .unwrap_or_else(|e| error!("`watch_heartbeat`: {}", e));
+        if stall_policy == StallPolicy::Timeout {
+            timer_event_sender
+                .send(TimerEvent::TimeoutTask(task_id, record_id))
+                .await
+                .unwrap_or_else(|e| error!("`watch_heartbeat`: {}", e));
+            return;
+        }
+    }
+}
@@ -14,8 +14,13 @@
 //! 2. If the task is not set `max-running-time`, it will be automatically recycled when it finishes running.
 //! 3. The internal-task-handle, which holds the execution handle of the running task,
 //! gives lib the support to exit the task at any time.
+pub(crate) mod cancellation;
+pub(crate) mod child_processes;
+pub(crate) mod future_tracker;
+pub(crate) mod heartbeat;
 pub(crate) mod state;
 pub(crate) mod sweeper;
 
 pub mod task_handle;
 pub mod task_instance;
+pub mod task_logger;
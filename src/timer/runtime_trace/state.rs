@@ -11,6 +11,9 @@ pub mod instance {
 
     /// Set if the task has been Timeout.
     pub const TIMEOUT: usize = 1 << 4;
+
+    /// Set if the task has been completed, but reported it found nothing to do.
+    pub const COMPLETED_NOOP: usize = 1 << 5;
 }
 
 pub(crate) mod instance_chain {
@@ -166,15 +166,7 @@ impl RecyclingBins {
 
     pub(crate) async fn yield_for_while(&self, duration: Option<Duration>) {
         let duration = duration.unwrap_or_else(|| Duration::from_secs(3));
-        match self.runtime_kind {
-            RuntimeKind::Smol => {
-                AsyncTimer::after(duration).await;
-            }
-
-            RuntimeKind::Tokio => {
-                sleep_by_tokio(duration).await;
-            }
-        }
+        self.runtime_kind.sleep(duration).await;
     }
 }
 
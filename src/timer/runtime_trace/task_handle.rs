@@ -1,7 +1,9 @@
 //! Internal-task-handle
 //! The internal-task-handle, which holds the execution handle of the running task,
 //! gives lib the support to exit the task at any time.
+use super::task_instance::RunningPid;
 use crate::prelude::*;
+use crate::sync::SemaphorePermit;
 
 use std::collections::{HashMap, LinkedList};
 use std::fmt::{self, Debug, Formatter, Pointer};
@@ -25,6 +27,48 @@ impl TaskTrace {
             .push_back(task_handler_box);
     }
 
+    pub(crate) fn contains(&self, task_id: u64, record_id: i64) -> bool {
+        self.inner
+            .get(&task_id)
+            .is_some_and(|task_handler_box_list| {
+                task_handler_box_list
+                    .iter()
+                    .any(|task_handler_box| task_handler_box.record_id == record_id)
+            })
+    }
+
+    pub(crate) fn is_running(&self, task_id: u64) -> bool {
+        self.inner
+            .get(&task_id)
+            .is_some_and(|task_handler_box_list| !task_handler_box_list.is_empty())
+    }
+
+    // The record-ids of the `count` oldest running instances of the task.
+    pub(crate) fn oldest_instances(&self, task_id: u64, count: usize) -> Vec<i64> {
+        self.inner
+            .get(&task_id)
+            .map(|task_handler_box_list| {
+                task_handler_box_list
+                    .iter()
+                    .take(count)
+                    .map(|task_handler_box| task_handler_box.record_id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // The running instances, as `(task_id, record_id)`.
+    pub(crate) fn instances(&self) -> Vec<(u64, i64)> {
+        self.inner
+            .iter()
+            .flat_map(|(task_id, task_handler_box_list)| {
+                task_handler_box_list
+                    .iter()
+                    .map(move |task_handler_box| (*task_id, task_handler_box.record_id))
+            })
+            .collect()
+    }
+
     #[allow(dead_code)]
     pub(crate) fn clear(self) {
         for (_task_id, task_handler_box_list) in self.inner.into_iter() {
@@ -34,6 +78,8 @@ impl TaskTrace {
         }
     }
 
+    // The quit handler box is returned (its handler has been taken),
+    // so that the caller can still read what it recorded about the instance.
     // linkedlist is ordered by record_id, if input record_id is small than linkedlist first record_id
     // that is no task_handler can cancel  or record_id bigger than last record_id.
     // one record_id may be used for many handler.
@@ -41,7 +87,11 @@ impl TaskTrace {
     //TODO: One stable cfg-flag， One nightly cfg-flag .
 
     #[cfg(RUSTC_IS_NIGHTLY)]
-    pub(crate) fn quit_one_task_handler(&mut self, task_id: u64, record_id: i64) -> Result<()> {
+    pub(crate) fn quit_one_task_handler(
+        &mut self,
+        task_id: u64,
+        record_id: i64,
+    ) -> Result<DelayTaskHandlerBox> {
         let task_handler_list = self.inner.get_mut(&task_id).ok_or_else(|| {
             anyhow!(
                 "Fn : `quit_one_task_handler`, No task-handler-list found (task-id: {}, record-id: {} )",
@@ -75,16 +125,24 @@ impl TaskTrace {
         }
 
         // remove current task_handler_box.
-        list_mut_cursor
-            .remove_current()
-            .map(|mut task_handler_box| task_handler_box.quit())
-            .transpose()?;
+        let mut task_handler_box = list_mut_cursor.remove_current().ok_or_else(|| {
+            anyhow!(
+                "Fn : `quit_one_task_handler`, No task_handler found (task-id: {}, record-id: {} )",
+                task_id,
+                record_id
+            )
+        })?;
+        task_handler_box.quit()?;
 
-        Ok(())
+        Ok(task_handler_box)
     }
 
     #[cfg(not(RUSTC_IS_NIGHTLY))]
-    pub(crate) fn quit_one_task_handler(&mut self, task_id: u64, record_id: i64) -> Result<()> {
+    pub(crate) fn quit_one_task_handler(
+        &mut self,
+        task_id: u64,
+        record_id: i64,
+    ) -> Result<DelayTaskHandlerBox> {
         let task_handler_list = self.inner.get_mut(&task_id).ok_or_else(|| {
             anyhow!(
                 "Fn : `quit_one_task_handler`, No task-handler-list found (task-id: {} )",
@@ -114,7 +172,8 @@ impl TaskTrace {
 
         task_handler_list.append(&mut has_remove_element_list);
 
-        remove_element.quit()
+        remove_element.quit()?;
+        Ok(remove_element)
     }
 }
 
@@ -153,11 +212,20 @@ pub struct DelayTaskHandlerBox {
     task_id: u64,
     ///Globally unique ID.
     record_id: i64,
+    ///the time its fire was scheduled for.
+    scheduled_time: u64,
     ///it's start_time.
-    #[allow(dead_code)]
     start_time: u64,
     ///it's end_time.
     end_time: Option<u64>,
+    ///Token shared with the routine for cooperative cancellation.
+    cancellation_token: CancellationToken,
+    ///Whether the routine was handed the token, through its `TaskContext`.
+    token_handed_out: bool,
+    ///The pid of the process launched by the routine, shared with its context.
+    running_pid: RunningPid,
+    ///The permit of the task semaphore, given back once the box is dropped.
+    semaphore_permit: Option<SemaphorePermit>,
 }
 
 impl Drop for DelayTaskHandlerBox {
@@ -173,12 +241,17 @@ impl Drop for DelayTaskHandlerBox {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug)]
 pub(crate) struct DelayTaskHandlerBoxBuilder {
     task_id: u64,
     record_id: i64,
+    scheduled_time: u64,
     start_time: u64,
     end_time: Option<u64>,
+    cancellation_token: CancellationToken,
+    token_handed_out: bool,
+    running_pid: RunningPid,
+    semaphore_permit: Option<SemaphorePermit>,
 }
 
 impl DelayTaskHandlerBoxBuilder {
@@ -194,6 +267,12 @@ impl DelayTaskHandlerBoxBuilder {
         self
     }
 
+    #[inline(always)]
+    pub fn set_scheduled_time(mut self, scheduled_time: u64) -> Self {
+        self.scheduled_time = scheduled_time;
+        self
+    }
+
     #[inline(always)]
     pub fn set_start_time(mut self, start_time: u64) -> Self {
         self.start_time = start_time;
@@ -206,14 +285,46 @@ impl DelayTaskHandlerBoxBuilder {
         self
     }
 
+    #[inline(always)]
+    pub fn set_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_token_handed_out(mut self, token_handed_out: bool) -> Self {
+        self.token_handed_out = token_handed_out;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_running_pid(mut self, running_pid: RunningPid) -> Self {
+        self.running_pid = running_pid;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_semaphore_permit(
+        mut self,
+        semaphore_permit: Option<SemaphorePermit>,
+    ) -> Self {
+        self.semaphore_permit = semaphore_permit;
+        self
+    }
+
     pub fn spawn(self, task_handler: Box<dyn DelayTaskHandler>) -> DelayTaskHandlerBox {
         let task_handler = SafeStructBoxedDelayTaskHandler(task_handler);
         DelayTaskHandlerBox {
             task_handler: Some(task_handler),
             task_id: self.task_id,
             record_id: self.record_id,
+            scheduled_time: self.scheduled_time,
             start_time: self.start_time,
             end_time: self.end_time,
+            cancellation_token: self.cancellation_token,
+            token_handed_out: self.token_handed_out,
+            running_pid: self.running_pid,
+            semaphore_permit: self.semaphore_permit,
         }
     }
 }
@@ -235,11 +346,36 @@ impl DelayTaskHandlerBox {
         self.record_id
     }
 
+    #[inline(always)]
+    pub fn get_scheduled_time(&self) -> u64 {
+        self.scheduled_time
+    }
+
+    #[inline(always)]
+    pub fn get_start_time(&self) -> u64 {
+        self.start_time
+    }
+
     #[inline(always)]
     pub fn get_end_time(&self) -> Option<u64> {
         self.end_time
     }
 
+    #[inline(always)]
+    pub fn get_cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_token_handed_out(&self) -> bool {
+        self.token_handed_out
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_running_pid(&self) -> &RunningPid {
+        &self.running_pid
+    }
+
     fn quit(&mut self) -> Result<()> {
         if let Some(task_handler) = self.task_handler.take() {
             return task_handler.get_inner().quit();
@@ -1,13 +1,18 @@
 use crate::prelude::*;
 
 use std::collections::LinkedList;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 
 use event_listener::Event;
 use future_lite::block_on;
-use smol::channel::{unbounded, Receiver, Sender};
+use futures::stream::Stream;
+use once_cell::sync::OnceCell;
+use smol::channel::{unbounded, Receiver, Sender, TryRecvError};
+use smol::future::or;
 
 /// instance of task running.
 #[derive(Debug, Default, Clone)]
@@ -18,6 +23,31 @@ pub struct Instance {
     task_id: u64,
     /// The id of task running record.
     record_id: i64,
+    /// The time (timestamp-seconds) the fire was scheduled for.
+    scheduled_for: u64,
+    /// The time (timestamp-seconds) the instance started at.
+    started_at: u64,
+    /// The token shared with the routine of the running instance.
+    cancellation_token: CancellationToken,
+    /// The pid of the process the running instance launched.
+    running_pid: RunningPid,
+}
+
+/// The pid of the process a running instance launched,
+/// shared by the context of the instance and its `Instance`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RunningPid(Arc<OnceCell<u32>>);
+
+impl RunningPid {
+    #[inline(always)]
+    pub(crate) fn set(&self, pid: u32) {
+        self.0.set(pid).ok();
+    }
+
+    #[inline(always)]
+    pub(crate) fn get(&self) -> Option<u32> {
+        self.0.get().copied()
+    }
 }
 
 /// Public instance of task running.
@@ -25,6 +55,53 @@ pub struct Instance {
 pub struct TaskInstance {
     pub(crate) instance: Instance,
     pub(crate) timer_event_sender: TimerEventSender,
+    /// The reason carried by the cancellation of this handle.
+    pub(crate) cancel_reason: Option<String>,
+}
+
+/// Why a running instance of the task was terminated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// Cancelled through a public api, with the optional reason given by the caller.
+    Cancelled(CancelOriginator, Option<String>),
+    /// The instance ran over the `maximum_running_time` of the task.
+    Timeout,
+    /// The timer was shut down while the instance was running.
+    Shutdown,
+    /// A new fire of the task replaced the instance, see `TaskBuilder::set_replace_on_overlap`.
+    Replaced,
+}
+
+/// A running instance that was hard-aborted, handed to the `on_aborted` closure of the task.
+#[derive(Debug, Clone)]
+pub struct AbortedInstance {
+    /// The id of task.
+    pub task_id: u64,
+    /// The id of the aborted running instance.
+    pub record_id: i64,
+    /// Why the instance was aborted.
+    pub termination_reason: TerminationReason,
+    /// Whether the routine ever looked at its cancellation token.
+    /// If not, it was stopped at an arbitrary await point and may have been holding a guard.
+    pub cancellation_observed: bool,
+}
+
+/// Which path `TaskInstance::cancel_graceful` took to stop the running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancellationPath {
+    /// The routine observed the cancellation token and stopped within the grace period.
+    Cooperative,
+    /// The grace period elapsed and the instance was hard-aborted.
+    Aborted,
+}
+
+/// Who triggered the cancellation of a running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelOriginator {
+    /// Cancelled through `TaskInstance`.
+    TaskInstance,
+    /// Cancelled through `DelayTimer::cancel_task`.
+    DelayTimer,
 }
 
 #[derive(Debug)]
@@ -33,23 +110,41 @@ pub(crate) struct InstanceHeader {
     event: Event,
     /// The state of inner taskInstance.
     state: AtomicUsize,
+    /// Why the inner taskInstance was terminated, it is set at most once.
+    termination_reason: OnceCell<TerminationReason>,
+    /// The result of the inner taskInstance, it is set at most once.
+    #[cfg(feature = "json-result")]
+    result: OnceCell<serde_json::Value>,
+    /// The pid of the process launched by the inner taskInstance, it is set at most once.
+    pid: OnceCell<u32>,
 }
 
 impl Default for InstanceHeader {
     fn default() -> Self {
         let event = Event::new();
         let state = AtomicUsize::new(state::instance::RUNNING);
-
-        InstanceHeader { event, state }
+        let termination_reason = OnceCell::new();
+
+        InstanceHeader {
+            event,
+            state,
+            termination_reason,
+            #[cfg(feature = "json-result")]
+            result: OnceCell::new(),
+            pid: OnceCell::new(),
+        }
     }
 }
 
-pub(crate) fn task_instance_chain_pair() -> (TaskInstancesChain, TaskInstancesChainMaintainer) {
+pub(crate) fn task_instance_chain_pair(
+    task_id: u64,
+) -> (TaskInstancesChain, TaskInstancesChainMaintainer) {
     let (inner_sender, inner_receiver) = unbounded::<Instance>();
     let inner_state = Arc::new(AtomicUsize::new(state::instance_chain::LIVING));
     let inner_list = LinkedList::new();
 
     let chain = TaskInstancesChain {
+        task_id,
         inner_receiver,
         inner_state: inner_state.clone(),
         timer_event_sender: None,
@@ -63,15 +158,61 @@ pub(crate) fn task_instance_chain_pair() -> (TaskInstancesChain, TaskInstancesCh
     (chain, chain_maintainer)
 }
 
+// All maintainers share one sender, so instances of different tasks
+// are received in the order they arrive.
+pub(crate) fn merged_instance_stream_pair(
+    task_ids: &[u64],
+) -> (MergedInstanceStream, Vec<TaskInstancesChainMaintainer>) {
+    let (inner_sender, inner_receiver) = unbounded::<Instance>();
+
+    let mut inner_states = Vec::with_capacity(task_ids.len());
+    let mut chain_maintainers = Vec::with_capacity(task_ids.len());
+
+    for task_id in task_ids {
+        let inner_state = Arc::new(AtomicUsize::new(state::instance_chain::LIVING));
+
+        inner_states.push((*task_id, inner_state.clone()));
+        chain_maintainers.push(TaskInstancesChainMaintainer {
+            inner_sender: inner_sender.clone(),
+            inner_state,
+            inner_list: LinkedList::new(),
+        });
+    }
+
+    let stream = MergedInstanceStream {
+        inner_receiver,
+        inner_states,
+        timer_event_sender: None,
+    };
+
+    (stream, chain_maintainers)
+}
+
 /// Chain of task run instances.
 /// For User access to Running-Task's instance.
+///
+/// When the chain is closed or dropped, the scheduler stops routing instances to it.
+/// A new chain can be attached at any time via `DelayTimer::instance_chain`.
 #[derive(Debug)]
 pub struct TaskInstancesChain {
+    pub(crate) task_id: u64,
     pub(crate) inner_receiver: Receiver<Instance>,
     pub(crate) inner_state: Arc<AtomicUsize>,
     pub(crate) timer_event_sender: Option<TimerEventSender>,
 }
 
+/// Merged chain of run instances of several tasks.
+/// Yields `(task_id, TaskInstance)` in the order the instances arrive,
+/// so that a single loop can watch a whole group of tasks.
+///
+/// It also implements `Stream`, the stream ends when the event sender is missing.
+#[derive(Debug)]
+pub struct MergedInstanceStream {
+    pub(crate) inner_receiver: Receiver<Instance>,
+    pub(crate) inner_states: Vec<(u64, Arc<AtomicUsize>)>,
+    pub(crate) timer_event_sender: Option<TimerEventSender>,
+}
+
 /// Chain of task run instances.
 /// For inner maintain to Running-Task's instance.
 #[derive(Debug)]
@@ -82,7 +223,6 @@ pub struct TaskInstancesChainMaintainer {
 }
 
 impl Instance {
-    #[allow(dead_code)]
     #[inline(always)]
     pub(crate) fn get_task_id(&self) -> u64 {
         self.task_id
@@ -105,6 +245,33 @@ impl Instance {
         self
     }
 
+    #[inline(always)]
+    pub(crate) fn set_scheduled_for(mut self, scheduled_for: u64) -> Instance {
+        self.scheduled_for = scheduled_for;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_started_at(mut self, started_at: u64) -> Instance {
+        self.started_at = started_at;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_cancellation_token(
+        mut self,
+        cancellation_token: CancellationToken,
+    ) -> Instance {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_running_pid(mut self, running_pid: RunningPid) -> Instance {
+        self.running_pid = running_pid;
+        self
+    }
+
     #[inline(always)]
     pub(crate) fn set_state(&self, state: usize) {
         self.header.state.store(state, Ordering::Release);
@@ -116,23 +283,89 @@ impl Instance {
         self.header.state.load(Ordering::Acquire)
     }
 
+    /// Get the time (timestamp-seconds) the fire of the Instance was scheduled for.
+    #[inline(always)]
+    pub fn scheduled_for(&self) -> u64 {
+        self.scheduled_for
+    }
+
+    /// Get the time (timestamp-seconds) the Instance started at,
+    /// later than `scheduled_for` if the fire was queued or advanced.
+    #[inline(always)]
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    /// Get the reason why the Instance was terminated,
+    /// `None` if it is still running or has been completed.
+    #[inline(always)]
+    pub fn termination_reason(&self) -> Option<TerminationReason> {
+        self.header.termination_reason.get().cloned()
+    }
+
+    /// Get the result of the Instance,
+    /// `None` until it's completed with a result.
+    #[cfg(feature = "json-result")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-result")))]
+    #[inline(always)]
+    pub fn result(&self) -> Option<serde_json::Value> {
+        self.header.result.get().cloned()
+    }
+
+    // The result is set before the state, like the termination reason.
+    #[cfg(feature = "json-result")]
+    #[inline(always)]
+    pub(crate) fn set_result(&self, result: serde_json::Value) {
+        self.header.result.set(result).ok();
+    }
+
+    /// Get the pid of the process launched by the Instance,
+    /// while it's running, or once it's completed if the process was detached.
+    #[inline(always)]
+    pub fn pid(&self) -> Option<u32> {
+        // The process of an instance that's over has been reaped, its pid may be reused.
+        if self.get_state() == state::instance::RUNNING {
+            return self.running_pid.get();
+        }
+        self.header.pid.get().copied()
+    }
+
+    // The pid is set before the state, like the result.
     #[inline(always)]
-    pub(crate) fn notify_cancel_finish(&self, state: usize) {
+    pub(crate) fn set_pid(&self, pid: u32) {
+        self.header.pid.set(pid).ok();
+    }
+
+    // The reason is set before the state,
+    // so that it is visible as soon as the state changes.
+    #[inline(always)]
+    pub(crate) fn notify_cancel_finish(
+        &self,
+        state: usize,
+        termination_reason: Option<TerminationReason>,
+    ) {
+        if let Some(termination_reason) = termination_reason {
+            self.header.termination_reason.set(termination_reason).ok();
+        }
+
         self.set_state(state);
         self.header.event.notify(usize::MAX);
     }
 }
 
 impl TaskInstance {
-    #[allow(dead_code)]
+    /// Get the task-id of the instance.
     #[inline(always)]
-    pub(crate) fn get_task_id(&self) -> u64 {
+    pub fn get_task_id(&self) -> u64 {
         self.instance.get_task_id()
     }
 
-    #[allow(dead_code)]
+    /// Get the record-id of the instance.
+    ///
+    /// Record ids of a task are strictly increasing, and unique across the nodes
+    /// whose `DelayTimerBuilder::id_generator_conf` differ.
     #[inline(always)]
-    pub(crate) fn get_record_id(&self) -> i64 {
+    pub fn get_record_id(&self) -> i64 {
         self.instance.get_record_id()
     }
 
@@ -142,6 +375,66 @@ impl TaskInstance {
         self.instance.get_state()
     }
 
+    /// Get the time (timestamp-seconds) the fire of the instance was scheduled for.
+    #[inline(always)]
+    pub fn scheduled_for(&self) -> u64 {
+        self.instance.scheduled_for()
+    }
+
+    /// Get the time (timestamp-seconds) the instance started at,
+    /// later than `scheduled_for` if the fire was queued or advanced.
+    #[inline(always)]
+    pub fn started_at(&self) -> u64 {
+        self.instance.started_at()
+    }
+
+    /// Get the reason why the instance was terminated (cancelled or timeout),
+    /// `None` if it is still running or has been completed.
+    #[inline(always)]
+    pub fn termination_reason(&self) -> Option<TerminationReason> {
+        self.instance.termination_reason()
+    }
+
+    /// Get the result of the instance, parsed from the stdout of a process task
+    /// (see `ProcessTaskBuilder::set_json_result`), `None` until it's completed with a result.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `json-result` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "json-result")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-result")))]
+    #[inline(always)]
+    pub fn result(&self) -> Option<serde_json::Value> {
+        self.instance.result()
+    }
+
+    /// Get the pid of the last process launched by the instance of a process task,
+    /// while it's running, or once it's completed if the process was detached
+    /// (see `ProcessTaskBuilder::detach`).
+    #[inline(always)]
+    pub fn pid(&self) -> Option<u32> {
+        self.instance.pid()
+    }
+
+    /// Send `signal` (e.g. `libc::SIGUSR1`) to the last process launched by the instance,
+    /// the one whose pid is `TaskInstance::pid`.
+    ///
+    /// Unlike a cancellation, the instance is left to the process,
+    /// which may e.g. reload its configuration and carry on.
+    /// Signals are only supported on unix.
+    pub fn signal(&self, signal: i32) -> Result<(), TaskInstanceError> {
+        let pid = self.pid().ok_or(TaskInstanceError::WithoutProcess)?;
+        send_signal(pid, signal).map_err(TaskInstanceError::DisSignal)
+    }
+
+    /// Set the reason carried by the following cancellation of this handle.
+    #[inline(always)]
+    pub fn set_cancel_reason(&mut self, reason: impl Into<String>) -> &mut Self {
+        self.cancel_reason = Some(reason.into());
+        self
+    }
+
     /// Cancel the currently running task instance and block the thread to wait.
     #[inline(always)]
     pub fn cancel_with_wait(&self) -> Result<InstanceState, TaskInstanceError> {
@@ -169,6 +462,37 @@ impl TaskInstance {
             .ok_or(TaskInstanceError::DisCancelTimeOut)
     }
 
+    /// Cancel the currently running task instance gracefully and block the thread to wait.
+    ///
+    /// The cancellation token of the instance is triggered first,
+    /// if the routine doesn't stop within `grace` the instance is hard-aborted.
+    /// Routines that never look at the token (e.g. shell commands) always take the `Aborted` path.
+    pub fn cancel_graceful(&self, grace: Duration) -> Result<CancellationPath, TaskInstanceError> {
+        if self.get_state() != state::instance::RUNNING {
+            return Err(TaskInstanceError::DisCancel);
+        }
+
+        let listener = self.instance.header.event.listen();
+        self.instance.cancellation_token.cancel();
+
+        if self.get_state() != state::instance::RUNNING || listener.wait_timeout(grace) {
+            return Ok(CancellationPath::Cooperative);
+        }
+
+        let listener = self.instance.header.event.listen();
+        match self.cancel() {
+            Ok(()) => {}
+            // The routine stopped right after the grace period.
+            Err(TaskInstanceError::DisCancel) => return Ok(CancellationPath::Cooperative),
+            Err(e) => return Err(e),
+        }
+
+        if self.get_state() == state::instance::RUNNING {
+            listener.wait();
+        }
+        Ok(CancellationPath::Aborted)
+    }
+
     /// Cancel the currently running task instance and async-await it.
     #[inline(always)]
     pub async fn cancel_with_async_wait(&self) -> Result<InstanceState, TaskInstanceError> {
@@ -187,6 +511,10 @@ impl TaskInstance {
         Ok(self.timer_event_sender.try_send(TimerEvent::CancelTask(
             self.instance.task_id,
             self.instance.record_id,
+            TerminationReason::Cancelled(
+                CancelOriginator::TaskInstance,
+                self.cancel_reason.clone(),
+            ),
         ))?)
     }
 }
@@ -199,6 +527,25 @@ impl TaskInstancesChainMaintainer {
             .map_or((), |_| {});
         self.inner_list.push_back(instance);
     }
+
+    pub(crate) fn get_instance(&self, record_id: i64) -> Option<&Instance> {
+        self.inner_list
+            .iter()
+            .find(|d| d.get_record_id() == record_id)
+    }
+
+    pub(crate) fn remove_instance(&mut self, record_id: i64) -> Option<Instance> {
+        let index = self
+            .inner_list
+            .iter()
+            .position(|d| d.get_record_id() == record_id)?;
+
+        let mut has_remove_instance_list = self.inner_list.split_off(index);
+        let remove_instance = has_remove_instance_list.pop_front();
+        self.inner_list.append(&mut has_remove_instance_list);
+
+        remove_instance
+    }
 }
 impl TaskInstancesChain {
     /// Non-blocking get the next task instance.
@@ -208,42 +555,87 @@ impl TaskInstancesChain {
         Ok(self
             .inner_receiver
             .try_recv()
+            .map_err(expired_once_closed)
             .map(|instance| TaskInstance {
                 instance,
                 timer_event_sender,
+                cancel_reason: None,
             })?)
     }
 
     /// Blocking get the next task instance.
+    ///
+    /// Once the task is removed, or its countdown is over and its last instance has ended,
+    /// it returns `TaskInstanceError::Expired` instead of blocking forever.
     pub fn next_with_wait(&self) -> Result<TaskInstance, TaskInstanceError> {
-        let timer_event_sender = self.get_timer_event_sender()?;
-
-        let instance = block_on(self.inner_receiver.recv())?;
+        block_on(self.next_with_async_wait())
+    }
 
-        Ok(TaskInstance {
-            instance,
-            timer_event_sender,
-        })
+    /// Blocking get the next task instance, for at most `timeout`,
+    /// so that a supervisor loop can't hang on a task that doesn't fire.
+    pub fn next_with_timeout(&self, timeout: Duration) -> Result<TaskInstance, TaskInstanceError> {
+        block_on(or(self.next_with_async_wait(), async {
+            AsyncTimer::after(timeout).await;
+            Err(TaskInstanceError::DisNextTimeOut)
+        }))
     }
 
-    /// Async-await get the next task instance.
+    /// Async-await get the next task instance, see `next_with_wait`.
     pub async fn next_with_async_wait(&self) -> Result<TaskInstance, TaskInstanceError> {
         let timer_event_sender = self.get_timer_event_sender()?;
 
-        let instance = self.inner_receiver.recv().await?;
+        let instance = self
+            .inner_receiver
+            .recv()
+            .await
+            .map_err(|_| TaskInstanceError::Expired)?;
 
         Ok(TaskInstance {
             instance,
             timer_event_sender,
+            cancel_reason: None,
         })
     }
 
+    /// Close the chain and tell the scheduler to stop routing instances of the task to it.
+    ///
+    /// Dropping the chain has the same effect,
+    /// `close` just makes it explicit and reports whether the scheduler was notified.
+    pub fn close(self) -> Result<(), TaskInstanceError> {
+        self.detach()
+    }
+
     /// Get state of TaskInstancesChain.
     #[inline(always)]
     fn get_state(&self) -> InstanceState {
         self.inner_state.load(Ordering::Acquire)
     }
 
+    // Only a living chain needs to notify the scheduler,
+    // an abandoned chain no longer has a maintainer on the other side.
+    fn detach(&self) -> Result<(), TaskInstanceError> {
+        let detached = self
+            .inner_state
+            .compare_exchange(
+                state::instance_chain::LIVING,
+                state::instance_chain::DROPPED,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok();
+
+        if !detached {
+            return Ok(());
+        }
+
+        let timer_event_sender = self
+            .timer_event_sender
+            .as_ref()
+            .ok_or(TaskInstanceError::MisEventSender)?;
+
+        Ok(timer_event_sender.try_send(TimerEvent::DetachInstancesChain(self.task_id))?)
+    }
+
     fn get_timer_event_sender(&self) -> Result<Sender<TimerEvent>, TaskInstanceError> {
         if self.get_state() == state::instance_chain::ABANDONED {
             return Err(TaskInstanceError::Expired);
@@ -255,10 +647,177 @@ impl TaskInstancesChain {
     }
 }
 
+impl MergedInstanceStream {
+    /// Non-blocking get the next task instance.
+    pub fn next(&self) -> Result<(u64, TaskInstance), TaskInstanceError> {
+        let timer_event_sender = self.get_timer_event_sender()?;
+        let instance = self.inner_receiver.try_recv().map_err(expired_once_closed)?;
+
+        Ok(Self::task_instance(instance, timer_event_sender))
+    }
+
+    /// Blocking get the next task instance.
+    ///
+    /// Once none of the tasks fires anymore, it returns `TaskInstanceError::Expired`
+    /// instead of blocking forever.
+    pub fn next_with_wait(&self) -> Result<(u64, TaskInstance), TaskInstanceError> {
+        block_on(self.next_with_async_wait())
+    }
+
+    /// Blocking get the next task instance, for at most `timeout`.
+    pub fn next_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(u64, TaskInstance), TaskInstanceError> {
+        block_on(or(self.next_with_async_wait(), async {
+            AsyncTimer::after(timeout).await;
+            Err(TaskInstanceError::DisNextTimeOut)
+        }))
+    }
+
+    /// Async-await get the next task instance, see `next_with_wait`.
+    pub async fn next_with_async_wait(&self) -> Result<(u64, TaskInstance), TaskInstanceError> {
+        let timer_event_sender = self.get_timer_event_sender()?;
+        let instance = self
+            .inner_receiver
+            .recv()
+            .await
+            .map_err(|_| TaskInstanceError::Expired)?;
+
+        Ok(Self::task_instance(instance, timer_event_sender))
+    }
+
+    /// The ids of the tasks that are still routed to the stream.
+    pub fn task_ids(&self) -> Vec<u64> {
+        self.inner_states
+            .iter()
+            .filter(|(_, s)| s.load(Ordering::Acquire) == state::instance_chain::LIVING)
+            .map(|(task_id, _)| *task_id)
+            .collect()
+    }
+
+    /// Close the stream and tell the scheduler to stop routing instances of the tasks to it.
+    pub fn close(self) -> Result<(), TaskInstanceError> {
+        self.detach()
+    }
+
+    #[inline(always)]
+    fn task_instance(
+        instance: Instance,
+        timer_event_sender: TimerEventSender,
+    ) -> (u64, TaskInstance) {
+        let task_id = instance.get_task_id();
+
+        (
+            task_id,
+            TaskInstance {
+                instance,
+                timer_event_sender,
+                cancel_reason: None,
+            },
+        )
+    }
+
+    // The stream expires only if all tasks are no longer maintained.
+    fn get_timer_event_sender(&self) -> Result<Sender<TimerEvent>, TaskInstanceError> {
+        if self
+            .inner_states
+            .iter()
+            .all(|(_, s)| s.load(Ordering::Acquire) == state::instance_chain::ABANDONED)
+        {
+            return Err(TaskInstanceError::Expired);
+        }
+
+        self.timer_event_sender
+            .clone()
+            .ok_or(TaskInstanceError::MisEventSender)
+    }
+
+    // Every subscription is detached, a failure to tell the scheduler doesn't stop the others,
+    // the first one is returned.
+    fn detach(&self) -> Result<(), TaskInstanceError> {
+        let mut detach_result = Ok(());
+
+        for (task_id, inner_state) in self.inner_states.iter() {
+            let detached = inner_state
+                .compare_exchange(
+                    state::instance_chain::LIVING,
+                    state::instance_chain::DROPPED,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok();
+
+            if !detached {
+                continue;
+            }
+
+            let sent = self
+                .timer_event_sender
+                .as_ref()
+                .ok_or(TaskInstanceError::MisEventSender)
+                .and_then(|timer_event_sender| {
+                    Ok(timer_event_sender.try_send(TimerEvent::DetachInstancesChain(*task_id))?)
+                });
+            if detach_result.is_ok() {
+                detach_result = sent;
+            }
+        }
+
+        detach_result
+    }
+}
+
+// The channel is closed once all its maintainers are dropped, the chain has expired then.
+fn expired_once_closed(try_recv_error: TryRecvError) -> TaskInstanceError {
+    match try_recv_error {
+        TryRecvError::Closed => TaskInstanceError::Expired,
+        try_recv_error => try_recv_error.into(),
+    }
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: i32) -> std::io::Result<()> {
+    match unsafe { libc::kill(pid as libc::pid_t, signal) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(unix))]
+fn send_signal(_pid: u32, _signal: i32) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "Signaling a process is only supported on unix.",
+    ))
+}
+
+impl Stream for MergedInstanceStream {
+    type Item = (u64, TaskInstance);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let timer_event_sender = match self.timer_event_sender.clone() {
+            Some(timer_event_sender) => timer_event_sender,
+            None => return Poll::Ready(None),
+        };
+
+        Pin::new(&mut self.inner_receiver)
+            .poll_next(cx)
+            .map(|instance| instance.map(|i| Self::task_instance(i, timer_event_sender)))
+    }
+}
+
+impl Drop for MergedInstanceStream {
+    fn drop(&mut self) {
+        self.detach()
+            .unwrap_or_else(|e| debug!(" `MergedInstanceStream::drop` : {}", e));
+    }
+}
+
 impl Drop for TaskInstancesChain {
     fn drop(&mut self) {
-        self.inner_state
-            .store(state::instance_chain::DROPPED, Ordering::Release);
+        self.detach()
+            .unwrap_or_else(|e| debug!(" `TaskInstancesChain::drop` : {}", e));
     }
 }
 
@@ -0,0 +1,97 @@
+//! Logging of the running task instance.
+//!
+//! The logger is handed to the routine through `TaskContext::logger`, every record
+//! is tagged with the task-id and record-id, and can be captured so that it comes
+//! back with the finish of the instance.
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use log::Level;
+
+/// The log target of task routines, unless the task sets its own.
+pub const DEFAULT_TASK_LOG_TARGET: &str = "delay_timer::task";
+
+/// Logger of a running task instance.
+#[derive(Debug, Default, Clone)]
+pub struct TaskLogger {
+    task_id: u64,
+    record_id: i64,
+    target: Option<&'static str>,
+    captured: Option<Arc<Mutex<Vec<String>>>>,
+}
+
+impl TaskLogger {
+    pub(crate) fn new(
+        task_id: u64,
+        record_id: i64,
+        target: Option<&'static str>,
+        capture: bool,
+    ) -> Self {
+        TaskLogger {
+            task_id,
+            record_id,
+            target,
+            captured: capture.then(Default::default),
+        }
+    }
+
+    /// Log `message` at `level`.
+    pub fn log(&self, level: Level, message: impl Display) {
+        let target = self.target.unwrap_or(DEFAULT_TASK_LOG_TARGET);
+        log::log!(
+            target: target,
+            level,
+            "task-id: {}, record-id: {}, {}",
+            self.task_id,
+            self.record_id,
+            message
+        );
+
+        if let Some(captured) = self.captured.as_ref() {
+            captured
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(format!("{} {}", level, message));
+        }
+    }
+
+    /// Log `message` at the error level.
+    #[inline(always)]
+    pub fn error(&self, message: impl Display) {
+        self.log(Level::Error, message);
+    }
+
+    /// Log `message` at the warn level.
+    #[inline(always)]
+    pub fn warn(&self, message: impl Display) {
+        self.log(Level::Warn, message);
+    }
+
+    /// Log `message` at the info level.
+    #[inline(always)]
+    pub fn info(&self, message: impl Display) {
+        self.log(Level::Info, message);
+    }
+
+    /// Log `message` at the debug level.
+    #[inline(always)]
+    pub fn debug(&self, message: impl Display) {
+        self.log(Level::Debug, message);
+    }
+
+    /// Log `message` at the trace level.
+    #[inline(always)]
+    pub fn trace(&self, message: impl Display) {
+        self.log(Level::Trace, message);
+    }
+
+    // Take what was captured so far.
+    pub(crate) fn take_captured(&self) -> Vec<String> {
+        self.captured
+            .as_ref()
+            .map(|captured| {
+                std::mem::take(&mut *captured.lock().unwrap_or_else(|e| e.into_inner()))
+            })
+            .unwrap_or_default()
+    }
+}
@@ -38,6 +38,23 @@ impl Slot {
         self.task_map.remove(&task_id)
     }
 
+    pub(crate) fn contains_task(&self, task_id: u64) -> bool {
+        self.task_map.contains_key(&task_id)
+    }
+
+    pub(crate) fn get_task(&self, task_id: u64) -> Option<&Task> {
+        self.task_map.get(&task_id)
+    }
+
+    pub(crate) fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.task_map.values()
+    }
+
+    // Take all tasks out of the slot, used to re-seed the wheel.
+    pub(crate) fn drain_tasks(&mut self) -> Vec<Task> {
+        self.task_map.drain().map(|(_, task)| task).collect()
+    }
+
     // Check and reduce cylinder_line，
     // Returns a Vec. containing all task ids to be executed.(cylinder_line == 0)
     pub(crate) fn arrival_time_tasks(&mut self) -> Vec<u64> {
@@ -0,0 +1,48 @@
+//! Spawn hook
+//! A plug-in deciding on each instance right before it's spawned,
+//! for bespoke policies (tenant quotas, feature flags...) the timer doesn't have built in.
+use std::any::Any;
+use std::fmt::{self, Pointer};
+use std::sync::Arc;
+
+/// Decides on each new instance of the tasks, right before it's spawned.
+///
+/// It's consulted once the fire has passed the policies of the task
+/// (exclusion, budget, parallelism, readiness...).
+/// It runs inline on the timer-core loop, it should be cheap and must not block.
+pub trait SpawnHook: Send + Sync + 'static {
+    /// Decide whether the instance described by `task_meta` is spawned.
+    fn before_spawn(&self, task_meta: &TaskMeta) -> SpawnDecision;
+}
+
+impl fmt::Debug for dyn SpawnHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// The instance about to be spawned, as seen by `SpawnHook::before_spawn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskMeta {
+    /// The id of the task.
+    pub task_id: u64,
+    /// The id the instance would run with.
+    pub record_id: i64,
+    /// The time (timestamp-seconds) the fire is scheduled for.
+    pub scheduled_time: u64,
+    /// How many instances of the task are running.
+    pub running_instances: u64,
+    /// Whether it's an extra run (`set_run_on_startup`, `advance_task`) rather than a scheduled fire.
+    pub extra_run: bool,
+}
+
+/// What `SpawnHook::before_spawn` makes of an instance.
+#[derive(Debug, Clone)]
+pub enum SpawnDecision {
+    /// The instance is spawned.
+    Spawn,
+    /// The instance is spawned, the routine gets `extension` by `TaskContext::extension`.
+    SpawnWith(Arc<dyn Any + Send + Sync>),
+    /// The instance isn't spawned, the fire is skipped with a `SpawnVetoed` event.
+    Veto,
+}
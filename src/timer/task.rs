@@ -1,16 +1,41 @@
 //! Task
 //! It is a basic periodic task execution unit.
+use super::dst::DstSchedule;
+use super::local_worker::{run_on_local_worker, LocalFuture, LocalJob};
+use super::runtime_trace::child_processes::{ChildProcesses, TrackedChildProcesses};
+use super::runtime_trace::heartbeat::Heartbeat;
 use super::runtime_trace::task_handle::DelayTaskHandler;
+use super::runtime_trace::task_instance::RunningPid;
+use super::runtime_trace::task_logger::TaskLogger;
 use crate::prelude::*;
+use crate::utils::parse::shell_command::{CommandUnify, ProcessTaskBuilder};
 
+use std::any::Any;
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Pointer;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::str::FromStr;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
+use chrono::DateTime;
 use cron_clock::{Schedule, ScheduleIteratorOwned, Utc};
 use lru::LruCache;
+use smol::process::{Child as SmolChild, Command as SmolCommand};
+use tokio::process::{Child as TokioChild, Command as TokioCommand};
+
+#[cfg(feature = "otel")]
+use opentelemetry::Context as OtelContext;
+
+#[cfg(feature = "chaos")]
+use super::chaos::ChaosFault;
+
+#[cfg(feature = "status-report")]
+use std::collections::HashSet;
 
 // Parsing cache for cron expressions, stored with thread-local storage.
 thread_local!(static CRON_EXPRESSION_CACHE: RefCell<LruCache<ScheduleIteratorTimeZoneQuery, DelayTimerScheduleIteratorOwned>> = RefCell::new(LruCache::new(256)));
@@ -27,6 +52,125 @@ pub(crate) struct TaskMark {
     /// Chain of task run instances.
     /// For inner maintain to Running-Task's instance.
     pub(crate) task_instances_chain_maintainer: Option<TaskInstancesChainMaintainer>,
+    /// Chains subscribed after the task was registered.
+    pub(crate) task_instances_chain_subscribers: Vec<TaskInstancesChainMaintainer>,
+    /// Whether the countdown of the task is over, it doesn't fire anymore.
+    exhausted: bool,
+    /// Cleanup closure run after a hard abort.
+    on_aborted: Option<SafeStructAbortedFn>,
+    /// Execution budget of the task.
+    budget: Option<Budget>,
+    /// Runtime consumed in the current budget window.
+    budget_usage: BudgetUsage,
+    /// The time the last instance of the task ended.
+    last_finish_time: Option<u64>,
+    /// The readiness of the pending fire, for a task with a readiness check.
+    readiness: ReadinessState,
+    /// What becomes of the task once its instances keep failing.
+    failure_policy: Failure,
+    /// How many instances in a row failed or timed out.
+    consecutive_failures: u64,
+    /// Breaker shared with the tasks hitting the same dependency.
+    circuit_breaker: Option<CircuitBreaker>,
+    /// The resource built by the `init` of the task.
+    resource: Option<Arc<dyn Any + Send + Sync>>,
+    /// The durations (seconds) of the recent instances that finished, oldest first.
+    durations: VecDeque<u64>,
+    /// Whether the runs that found nothing to do are kept out of `durations`.
+    exclude_noop_durations: bool,
+    /// How many instances completed, and how many of them found nothing to do.
+    completed_runs: (u64, u64),
+    /// Which of the routine events of the task are reported (optional).
+    #[cfg(feature = "status-report")]
+    event_sampling: Option<EventSampling>,
+    /// How many `RunningTask` events the task had.
+    #[cfg(feature = "status-report")]
+    sampled_instances: u64,
+    /// The running instances whose `RunningTask` event wasn't reported.
+    #[cfg(feature = "status-report")]
+    unsampled_records: HashSet<i64>,
+}
+
+// The readiness of the pending fire of a task gated by a readiness check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum ReadinessState {
+    #[default]
+    Unchecked,
+    // Being checked since the time.
+    Checking(u64),
+    Ready,
+    // Found not ready at the time.
+    NotReady(u64),
+}
+
+// The tasks sharing a serial queue, only one instance of them runs at a time.
+#[derive(Default, Debug)]
+pub(crate) struct SerialQueue {
+    // The running instance, as `(task_id, record_id)`.
+    running: Option<(u64, i64)>,
+    // The tasks whose pending fire waits for its turn, first come first served.
+    waiting: VecDeque<u64>,
+}
+
+impl SerialQueue {
+    // Whether the pending fire of the task may run now.
+    pub(crate) fn is_turn_of(&self, task_id: u64) -> bool {
+        self.running.is_none() && self.waiting.front().is_none_or(|&front| front == task_id)
+    }
+
+    // The pending fire of the task is held back, it keeps its place in the line.
+    pub(crate) fn wait(&mut self, task_id: u64) {
+        if !self.waiting.contains(&task_id) {
+            self.waiting.push_back(task_id);
+        }
+    }
+
+    // The pending fire of the task goes to the front of the line.
+    pub(crate) fn jump(&mut self, task_id: u64) {
+        self.leave(task_id);
+        self.waiting.push_front(task_id);
+    }
+
+    pub(crate) fn is_busy(&self) -> bool {
+        self.running.is_some()
+    }
+
+    // How many running instances hold the queue, one at most.
+    pub(crate) fn held(&self) -> u64 {
+        self.running.is_some() as u64
+    }
+
+    pub(crate) fn leave(&mut self, task_id: u64) {
+        self.waiting.retain(|&waiting_id| waiting_id != task_id);
+    }
+
+    // How many tasks come before the pending fire of the task, the running one included.
+    pub(crate) fn ahead_of(&self, task_id: u64) -> u64 {
+        let waiting = self
+            .waiting
+            .iter()
+            .position(|&waiting_id| waiting_id == task_id)
+            .unwrap_or(self.waiting.len());
+        self.running.is_some() as u64 + waiting as u64
+    }
+
+    pub(crate) fn start(&mut self, task_id: u64, record_id: i64) {
+        self.leave(task_id);
+        self.running = Some((task_id, record_id));
+    }
+
+    pub(crate) fn finish(&mut self, record_id: i64) {
+        if matches!(self.running, Some((_, running_id)) if running_id == record_id) {
+            self.running = None;
+        }
+    }
+}
+
+// The window is a whole hour, counted from the unix epoch.
+#[derive(Default, Debug, Clone, Copy)]
+struct BudgetUsage {
+    window: u64,
+    consumed_seconds: u64,
 }
 
 impl TaskMark {
@@ -47,6 +191,18 @@ impl TaskMark {
         self
     }
 
+    #[inline(always)]
+    pub(crate) fn set_on_aborted(&mut self, on_aborted: Option<SafeStructAbortedFn>) -> &mut Self {
+        self.on_aborted = on_aborted;
+        self
+    }
+
+    // The closure is cloned out, so that it runs without holding the `task_flag_map` entry.
+    #[inline(always)]
+    pub(crate) fn get_on_aborted(&self) -> Option<SafeStructAbortedFn> {
+        self.on_aborted.clone()
+    }
+
     #[inline(always)]
     pub(crate) fn get_parallel_runnable_num(&self) -> u64 {
         self.parallel_runnable_num
@@ -78,6 +234,159 @@ impl TaskMark {
         self.set_parallel_runnable_num(parallel_runnable_num);
     }
 
+    #[inline(always)]
+    pub(crate) fn get_last_finish_time(&self) -> Option<u64> {
+        self.last_finish_time
+    }
+
+    // Count a completed instance, and keep its duration unless it's excluded.
+    pub(crate) fn record_completion(&mut self, duration: u64, no_work: bool) {
+        self.completed_runs.0 += 1;
+        if no_work {
+            self.completed_runs.1 += 1;
+            if self.exclude_noop_durations {
+                return;
+            }
+        }
+        self.record_duration(duration);
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_completed_runs(&self) -> (u64, u64) {
+        self.completed_runs
+    }
+
+    pub(crate) fn record_duration(&mut self, duration: u64) {
+        if self.durations.len() == DURATION_HISTORY {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+    }
+
+    // The 95th percentile of the recent durations, once there are enough of them.
+    pub(crate) fn get_p95_duration(&self) -> Option<u64> {
+        if self.durations.len() < MIN_DURATION_SAMPLES {
+            return None;
+        }
+
+        let mut durations: Vec<u64> = self.durations.iter().copied().collect();
+        durations.sort_unstable();
+        let rank = (durations.len() * 95).div_ceil(100);
+        Some(durations[rank - 1])
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_last_finish_time(&mut self, finish_time: u64) -> &mut Self {
+        self.last_finish_time = Some(finish_time);
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_readiness(&self) -> ReadinessState {
+        self.readiness
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_readiness(&mut self, readiness: ReadinessState) -> &mut Self {
+        self.readiness = readiness;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_failure_policy(&mut self, failure_policy: Failure) -> &mut Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_exclude_noop_durations(&mut self, exclude_noop_durations: bool) -> &mut Self {
+        self.exclude_noop_durations = exclude_noop_durations;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_circuit_breaker(
+        &mut self,
+        circuit_breaker: Option<CircuitBreaker>,
+    ) -> &mut Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    #[cfg(feature = "status-report")]
+    #[inline(always)]
+    pub(crate) fn set_event_sampling(
+        &mut self,
+        event_sampling: Option<EventSampling>,
+    ) -> &mut Self {
+        self.event_sampling = event_sampling;
+        self
+    }
+
+    // Whether a routine event of the instance is reported,
+    // its successful `FinishTask` goes along with its `RunningTask`.
+    #[cfg(feature = "status-report")]
+    pub(crate) fn sample_event(
+        &mut self,
+        record_id: i64,
+        completion: bool,
+        default_sampling: EventSampling,
+    ) -> bool {
+        if completion {
+            return !self.unsampled_records.remove(&record_id);
+        }
+
+        self.sampled_instances += 1;
+        let passes = self
+            .event_sampling
+            .unwrap_or(default_sampling)
+            .passes(self.sampled_instances);
+        if !passes {
+            self.unsampled_records.insert(record_id);
+        }
+        passes
+    }
+
+    // The instance ended otherwise, its events are always reported.
+    #[cfg(feature = "status-report")]
+    pub(crate) fn forget_unsampled_record(&mut self, record_id: i64) {
+        self.unsampled_records.remove(&record_id);
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_resource(&self) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.resource.clone()
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_resource(&mut self, resource: Arc<dyn Any + Send + Sync>) -> &mut Self {
+        self.resource = Some(resource);
+        self
+    }
+
+    // Count the outcome of an ended instance, a success breaks the streak of failures.
+    // The failure policy is returned when the failure trips it.
+    pub(crate) fn record_outcome(&mut self, failed: bool) -> Option<Failure> {
+        if let Some(circuit_breaker) = self.circuit_breaker.as_ref() {
+            circuit_breaker.record_outcome(failed, timestamp());
+        }
+
+        if !failed {
+            self.consecutive_failures = 0;
+            return None;
+        }
+
+        self.consecutive_failures += 1;
+        let max_failures = self.failure_policy.max_consecutive_failures()?;
+        if self.consecutive_failures < max_failures {
+            return None;
+        }
+
+        // A resumed task gets a fresh streak.
+        self.consecutive_failures = 0;
+        Some(self.failure_policy)
+    }
+
     #[inline(always)]
     pub(crate) fn set_task_instances_chain_maintainer(
         &mut self,
@@ -87,69 +396,274 @@ impl TaskMark {
         self
     }
 
-    pub(crate) fn get_task_instances_chain_maintainer(
+    #[inline(always)]
+    pub(crate) fn add_task_instances_chain_subscriber(
         &mut self,
-    ) -> Option<&mut TaskInstancesChainMaintainer> {
-        let state = self
+        task_instances_chain_maintainer: TaskInstancesChainMaintainer,
+    ) -> &mut Self {
+        self.task_instances_chain_subscribers
+            .push(task_instances_chain_maintainer);
+        self
+    }
+
+    // Only a chain that has been closed by the user is detached,
+    // a chain attached later should not be affected by an earlier close.
+    #[inline(always)]
+    pub(crate) fn detach_task_instances_chain_maintainer(&mut self) {
+        let is_dropped = |c: &TaskInstancesChainMaintainer| {
+            c.inner_state.load(Ordering::Acquire) == state::instance_chain::DROPPED
+        };
+
+        if self
             .task_instances_chain_maintainer
             .as_ref()
-            .map(|c| c.inner_state.load(Ordering::Acquire));
-
-        if state == Some(state::instance_chain::DROPPED) {
+            .is_some_and(is_dropped)
+        {
             self.task_instances_chain_maintainer = None;
         }
 
-        self.task_instances_chain_maintainer.as_mut()
+        self.task_instances_chain_subscribers
+            .retain(|c| !is_dropped(c));
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_exhausted(&mut self) -> &mut Self {
+        self.exhausted = true;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    // Dropping the maintainers closes the chains, their consumers stop waiting.
+    pub(crate) fn close_task_instances_chains(&mut self) {
+        self.task_instances_chain_maintainer = None;
+        self.task_instances_chain_subscribers.clear();
+    }
+
+    #[inline(always)]
+    pub(crate) fn has_task_instances_chain_maintainer(&self) -> bool {
+        self.task_instances_chain_maintainer.is_some()
+            || !self.task_instances_chain_subscribers.is_empty()
+    }
+
+    // All the chains that are still living, the one from `insert_task` comes first.
+    pub(crate) fn get_task_instances_chain_maintainers(
+        &mut self,
+    ) -> impl Iterator<Item = &mut TaskInstancesChainMaintainer> {
+        self.detach_task_instances_chain_maintainer();
+
+        self.task_instances_chain_maintainer
+            .iter_mut()
+            .chain(self.task_instances_chain_subscribers.iter_mut())
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_budget(&mut self, budget: Option<Budget>) -> &mut Self {
+        self.budget = budget;
+        self
+    }
+
+    // Instances that run across windows are charged to the window they finish in.
+    pub(crate) fn record_budget_usage(&mut self, start_time: u64, end_time: u64) {
+        if self.budget.is_none() {
+            return;
+        }
+
+        let window = end_time / ONE_HOUR;
+        if self.budget_usage.window != window {
+            self.budget_usage = BudgetUsage {
+                window,
+                consumed_seconds: 0,
+            };
+        }
+
+        self.budget_usage.consumed_seconds += end_time.saturating_sub(start_time);
+    }
+
+    pub(crate) fn is_budget_exhausted(&self, timestamp: u64) -> bool {
+        match self.budget {
+            Some(budget) => {
+                self.budget_usage.window == timestamp / ONE_HOUR
+                    && self.budget_usage.consumed_seconds >= budget.max_total_runtime_per_hour
+            }
+            None => false,
+        }
+    }
+
+    // Every chain holds a copy of the instance, setting the pid on one is enough.
+    pub(crate) fn set_instance_pid(&mut self, record_id: i64, pid: u32) {
+        if let Some(instance) = self.get_task_instances_chain_maintainers().find_map(
+            |task_instances_chain_maintainer| {
+                task_instances_chain_maintainer.get_instance(record_id)
+            },
+        ) {
+            instance.set_pid(pid);
+        }
+    }
+
+    // Every chain holds a copy of the instance, setting the result on one is enough.
+    #[cfg(feature = "json-result")]
+    pub(crate) fn set_instance_result(&mut self, record_id: i64, result: serde_json::Value) {
+        if let Some(instance) = self.get_task_instances_chain_maintainers().find_map(
+            |task_instances_chain_maintainer| {
+                task_instances_chain_maintainer.get_instance(record_id)
+            },
+        ) {
+            instance.set_result(result);
+        }
     }
 
     pub(crate) fn notify_cancel_finish(
         &mut self,
         record_id: i64,
         state: usize,
+        termination_reason: Option<TerminationReason>,
     ) -> AnyResult<Instance> {
-        let task_instances_chain_maintainer = self.get_task_instances_chain_maintainer().ok_or_else(|| {
+        let mut remove_instance = None;
+
+        // Every chain holds a copy of the instance, they share the same header.
+        for task_instances_chain_maintainer in self.get_task_instances_chain_maintainers() {
+            if let Some(instance) = task_instances_chain_maintainer.remove_instance(record_id) {
+                remove_instance.get_or_insert(instance);
+            }
+        }
+
+        let remove_instance = remove_instance.ok_or_else(|| {
             anyhow!(
-                "Fn : `notify_cancel_finish`, No task-instances-chain-maintainer found (record-id: {} , state : {} )",
+                "Fn : `notify_cancel_finish`, No task-handle found in chains (record-id: {} , state : {} )",
                 record_id, state
             )
         })?;
 
-        let index = task_instances_chain_maintainer
-            .inner_list
-            .iter()
-            .position(|d| d.get_record_id() == record_id)
-            .ok_or_else(|| anyhow!("No task-handle-index found ( record-id: {} )", record_id))?;
-
-        let mut has_remove_instance_list =
-            task_instances_chain_maintainer.inner_list.split_off(index);
-
-        let remove_instance = has_remove_instance_list
-            .pop_front()
-            .ok_or_else(|| anyhow!("No task-handle found in list ( record-id: {} )", record_id))?;
-
-        task_instances_chain_maintainer
-            .inner_list
-            .append(&mut has_remove_instance_list);
-
-        remove_instance.notify_cancel_finish(state);
+        remove_instance.notify_cancel_finish(state, termination_reason);
 
         Ok(remove_instance)
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub(crate) enum FrequencyUnify<'a> {
     FrequencyCronStr(FrequencyCronStr<'a>),
+    FrequencyCandyStr(FrequencyCandyStr),
     FrequencySeconds(FrequencySeconds),
 }
 
+// The cron-expression given by `set_frequency_by_candy`, owned by the builder,
+// the clones of the builder and the tasks built from it share it.
+#[derive(Debug, Clone)]
+pub(crate) enum FrequencyCandyStr {
+    Once(Arc<str>),
+    Repeated(Arc<str>),
+    CountDown(u64, Arc<str>),
+}
+
 impl<'a> Default for FrequencyUnify<'a> {
     fn default() -> FrequencyUnify<'a> {
         FrequencyUnify::FrequencySeconds(FrequencySeconds::default())
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+// An owned copy of `FrequencyUnify`, kept by the task to rebuild its builder.
+#[derive(Debug, Clone)]
+enum FrequencyTemplate {
+    Once(Arc<str>),
+    Repeated(Arc<str>),
+    CountDown(u64, Arc<str>),
+    Multi(Vec<Arc<str>>),
+    Except {
+        include: Arc<str>,
+        exclude: Arc<str>,
+    },
+    Custom(Box<dyn SchedulePolicy>),
+    KeepAlive {
+        restart_delay: Duration,
+        max_restarts_per_hour: u64,
+    },
+    Seconds(FrequencySeconds),
+}
+
+impl<'a> From<&FrequencyUnify<'a>> for FrequencyTemplate {
+    fn from(value: &FrequencyUnify<'a>) -> Self {
+        match value {
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(s)) => {
+                FrequencyTemplate::Once((*s).into())
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(s)) => {
+                FrequencyTemplate::Repeated((*s).into())
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(count, s)) => {
+                FrequencyTemplate::CountDown(*count, (*s).into())
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Multi(expressions)) => {
+                FrequencyTemplate::Multi(expressions.iter().map(|s| (*s).into()).collect())
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Except { include, exclude }) => {
+                FrequencyTemplate::Except {
+                    include: (*include).into(),
+                    exclude: (*exclude).into(),
+                }
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Custom(policy)) => {
+                FrequencyTemplate::Custom(policy.clone())
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::KeepAlive {
+                restart_delay,
+                max_restarts_per_hour,
+            }) => FrequencyTemplate::KeepAlive {
+                restart_delay: *restart_delay,
+                max_restarts_per_hour: *max_restarts_per_hour,
+            },
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Once(s)) => {
+                FrequencyTemplate::Once(s.clone())
+            }
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Repeated(s)) => {
+                FrequencyTemplate::Repeated(s.clone())
+            }
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::CountDown(count, s)) => {
+                FrequencyTemplate::CountDown(*count, s.clone())
+            }
+            FrequencyUnify::FrequencySeconds(frequency) => FrequencyTemplate::Seconds(*frequency),
+        }
+    }
+}
+
+impl FrequencyTemplate {
+    fn as_frequency_unify(&self) -> FrequencyUnify<'_> {
+        match self {
+            FrequencyTemplate::Once(s) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(s))
+            }
+            FrequencyTemplate::Repeated(s) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(s))
+            }
+            FrequencyTemplate::CountDown(count, s) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(*count, s))
+            }
+            FrequencyTemplate::Multi(expressions) => FrequencyUnify::FrequencyCronStr(
+                FrequencyCronStr::Multi(expressions.iter().map(|s| &**s).collect()),
+            ),
+            FrequencyTemplate::Except { include, exclude } => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Except { include, exclude })
+            }
+            FrequencyTemplate::Custom(policy) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Custom(policy.clone()))
+            }
+            FrequencyTemplate::KeepAlive {
+                restart_delay,
+                max_restarts_per_hour,
+            } => FrequencyUnify::FrequencyCronStr(FrequencyCronStr::KeepAlive {
+                restart_delay: *restart_delay,
+                max_restarts_per_hour: *max_restarts_per_hour,
+            }),
+            FrequencyTemplate::Seconds(frequency) => FrequencyUnify::FrequencySeconds(*frequency),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 /// Enumerated values of repeating types based on the string of cron-expression.
 pub enum FrequencyCronStr<'a> {
     /// Repeat once.
@@ -158,274 +672,1117 @@ pub enum FrequencyCronStr<'a> {
     Repeated(&'a str),
     /// Type of countdown.
     CountDown(u64, &'a str),
+    /// Repeat at the union of several cron-expressions,
+    /// coincident times of different expressions fire only once.
+    Multi(Vec<&'a str>),
+    /// Repeat per the `include` cron-expression,
+    /// except the fires that also match the `exclude` cron-expression.
+    Except {
+        /// The cron-expression the task runs by.
+        include: &'a str,
+        /// The cron-expression of the times to skip.
+        exclude: &'a str,
+    },
+    /// Repeat as long as the custom policy yields the next fire time.
+    Custom(Box<dyn SchedulePolicy>),
+    /// Supervise the routine: it's restarted whenever its instance ends,
+    /// so that a sidecar process is kept running.
+    KeepAlive {
+        /// How long to wait after the instance ended before restarting it.
+        restart_delay: Duration,
+        /// How many times it's restarted within an hour at most,
+        /// further restarts wait for the hour to slide by.
+        max_restarts_per_hour: u64,
+    },
 }
 
-#[derive(Debug, Copy, Clone)]
-/// Enumerated values of repeating types based on the number of seconds.
-pub(crate) enum FrequencySeconds {
-    /// Repeat once.
-    Once(u64),
-    /// Repeat ad infinitum.
-    Repeated(u64),
-    /// Type of countdown.
-    CountDown(u64, u64),
-}
+impl<'a> FrequencyCronStr<'a> {
+    /// The `CandyFrequency` of the same schedule, where there's one.
+    ///
+    /// It's `None` for the composite frequencies,
+    /// and for a countdown longer than `CandyFrequency::CountDown` holds.
+    pub fn to_candy(&self) -> Option<CandyFrequency<CandyCronStr>> {
+        let candy = |expression: &str| CandyCronStr(expression.to_owned());
+        match self {
+            FrequencyCronStr::Once(expression) => Some(CandyFrequency::Once(candy(*expression))),
+            FrequencyCronStr::Repeated(expression) => {
+                Some(CandyFrequency::Repeated(candy(*expression)))
+            }
+            FrequencyCronStr::CountDown(count, expression) => u32::try_from(*count)
+                .ok()
+                .map(|count| CandyFrequency::CountDown(count, candy(*expression))),
+            FrequencyCronStr::Multi(_)
+            | FrequencyCronStr::Except { .. }
+            | FrequencyCronStr::Custom(_)
+            | FrequencyCronStr::KeepAlive { .. } => None,
+        }
+    }
 
-impl<'a> Default for FrequencyCronStr<'a> {
-    fn default() -> FrequencyCronStr<'a> {
-        FrequencyCronStr::Once("@minutely")
+    // Whether every fire of the expression is at second `0`.
+    fn is_second_field_zero(&self) -> bool {
+        match self {
+            FrequencyCronStr::Once(expression)
+            | FrequencyCronStr::Repeated(expression)
+            | FrequencyCronStr::CountDown(_, expression)
+            | FrequencyCronStr::Except {
+                include: expression,
+                ..
+            } => is_second_field_zero(expression),
+            FrequencyCronStr::Multi(expressions) => {
+                !expressions.is_empty() && expressions.iter().all(|e| is_second_field_zero(e))
+            }
+            FrequencyCronStr::Custom(_) | FrequencyCronStr::KeepAlive { .. } => false,
+        }
     }
 }
 
-impl Default for FrequencySeconds {
-    fn default() -> FrequencySeconds {
-        FrequencySeconds::Once(ONE_MINUTE)
+// The seconds come first in the cron-expression, the `@` shortcuts fire at second `0`.
+fn is_second_field_zero(expression: &str) -> bool {
+    let expression = expression.trim();
+    if expression.starts_with('@') {
+        return expression != "@secondly";
     }
-}
 
-/// Iterator for task internal control of execution time.
-#[derive(Debug, Clone)]
-pub(crate) enum FrequencyInner {
-    /// Unlimited repetition types for cron-expression.
-    CronExpressionRepeated(DelayTimerScheduleIteratorOwned),
-    /// Type of countdown for cron-expression.
-    CronExpressionCountDown(u64, DelayTimerScheduleIteratorOwned),
-    /// Unlimited repetition types for seconds-duration.
-    SecondsRepeated(SecondsState),
-    /// Type of countdown for SecondsState.
-    /// SecondsCountDown(count_down, SecondsState)
-    SecondsCountDown(u64, SecondsState),
+    expression.split_whitespace().next() == Some("0")
 }
 
-impl<'a> TryFrom<(FrequencyUnify<'a>, ScheduleIteratorTimeZone)> for FrequencyInner {
-    type Error = FrequencyAnalyzeError;
-
-    fn try_from(
-        (frequency, time_zone): (FrequencyUnify<'_>, ScheduleIteratorTimeZone),
-    ) -> Result<FrequencyInner, Self::Error> {
-        let frequency_inner = match frequency {
-            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(cron_str)) => {
-                let task_schedule =
-                    DelayTimerScheduleIteratorOwned::analyze_cron_expression(time_zone, cron_str)?;
-
-                FrequencyInner::CronExpressionCountDown(1, task_schedule)
+impl<'a> FrequencyUnify<'a> {
+    // The candy cron-expression is lent as a plain one, the other frequencies are cloned.
+    fn to_borrowed(&self) -> FrequencyUnify<'_> {
+        match self {
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Once(s)) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(s))
             }
-            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(cron_str)) => {
-                let task_schedule =
-                    DelayTimerScheduleIteratorOwned::analyze_cron_expression(time_zone, cron_str)?;
-
-                FrequencyInner::CronExpressionRepeated(task_schedule)
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Repeated(s)) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(s))
             }
-            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(count_down, cron_str)) => {
-                let task_schedule =
-                    DelayTimerScheduleIteratorOwned::analyze_cron_expression(time_zone, cron_str)?;
-
-                FrequencyInner::CronExpressionCountDown(count_down as u64, task_schedule)
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::CountDown(count, s)) => {
+                FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(*count, s))
             }
+            frequency => frequency.clone(),
+        }
+    }
 
-            FrequencyUnify::FrequencySeconds(FrequencySeconds::Once(seconds)) => {
-                if seconds == 0 {
-                    return Err(FrequencyAnalyzeError::DisInitTime);
-                }
+    fn is_second_field_zero(&self) -> bool {
+        match self.to_borrowed() {
+            FrequencyUnify::FrequencyCronStr(frequency) => frequency.is_second_field_zero(),
+            _ => false,
+        }
+    }
 
-                let seconds_state: SecondsState =
-                    ((timestamp() + seconds)..).step_by(seconds as usize);
-                FrequencyInner::SecondsCountDown(1, seconds_state)
+    // The cron-expressions the frequency is made of.
+    fn cron_expressions(&self) -> Vec<&str> {
+        match self {
+            FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Once(expression))
+            | FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::Repeated(expression))
+            | FrequencyUnify::FrequencyCandyStr(FrequencyCandyStr::CountDown(_, expression)) => {
+                vec![&**expression]
             }
-            FrequencyUnify::FrequencySeconds(FrequencySeconds::Repeated(seconds)) => {
-                if seconds == 0 {
-                    return Err(FrequencyAnalyzeError::DisInitTime);
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(expression))
+            | FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(expression))
+            | FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(_, expression)) => {
+                vec![*expression]
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Multi(expressions)) => {
+                expressions.clone()
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Except { include, exclude }) => {
+                vec![*include, *exclude]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+const CRON_SHORTCUTS: [&str; 7] = [
+    "@yearly",
+    "@monthly",
+    "@weekly",
+    "@daily",
+    "@hourly",
+    "@minutely",
+    "@secondly",
+];
+
+const CRON_NAMES: [&str; 19] = [
+    "SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "JAN", "FEB", "MAR", "APR", "MAY", "JUN",
+    "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+// A parsable cron-expression close to `expression`: a shortcut or a name with a typo fixed,
+// or the seconds field added to a five-fields expression.
+pub(crate) fn suggest_cron_expression(expression: &str) -> Option<String> {
+    let expression = expression.trim();
+    let closest = |word: &str, candidates: &[&'static str], max_distance: usize| {
+        candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    edit_distance(&word.to_uppercase(), &candidate.to_uppercase()),
+                    candidate,
+                )
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| *candidate)
+    };
+
+    let suggestion = if expression.starts_with('@') {
+        closest(expression, &CRON_SHORTCUTS, 2)?.to_string()
+    } else {
+        let mut fields: Vec<String> = expression
+            .split_whitespace()
+            .map(|field| {
+                let mut fixed = String::new();
+                let mut word = String::new();
+                for c in field.chars().chain(std::iter::once(' ')) {
+                    if c.is_ascii_alphabetic() {
+                        word.push(c);
+                        continue;
+                    }
+
+                    // Full names are parsed too, only the three-letter ones are corrected.
+                    if word.len() == 3 && closest(&word, &CRON_NAMES, 0).is_none() {
+                        fixed.push_str(closest(&word, &CRON_NAMES, 1).unwrap_or(&word));
+                    } else {
+                        fixed.push_str(&word);
+                    }
+                    word.clear();
+                    if c != ' ' {
+                        fixed.push(c);
+                    }
                 }
+                fixed
+            })
+            .collect();
 
-                let seconds_state: SecondsState =
-                    ((timestamp() + seconds)..).step_by(seconds as usize);
+        if fields.len() == 5 {
+            fields.insert(0, "0".to_string());
+        }
+        fields.join(" ")
+    };
 
-                FrequencyInner::SecondsRepeated(seconds_state)
-            }
-            FrequencyUnify::FrequencySeconds(FrequencySeconds::CountDown(count_down, seconds)) => {
-                if seconds == 0 {
-                    return Err(FrequencyAnalyzeError::DisInitTime);
-                }
+    Some(suggestion).filter(|suggestion| Schedule::from_str(suggestion).is_ok())
+}
 
-                let seconds_state: SecondsState =
-                    ((timestamp() + seconds)..).step_by(seconds as usize);
-                FrequencyInner::SecondsCountDown(count_down, seconds_state)
-            }
-        };
+// The edit distance between `a` and `b`, a swap of adjacent letters counts as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
 
-        Ok(frequency_inner)
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
     }
+
+    distances[a.len()][b.len()]
 }
 
-/// Set the time zone for the time of the expression iteration.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-pub enum ScheduleIteratorTimeZone {
-    /// Utc specifies the UTC time zone. It is most efficient.
-    Utc,
-    /// Local specifies the system local time zone.
-    Local,
-    /// FixedOffset specifies an arbitrary, fixed time zone such as UTC+09:00 or UTC-10:30. This often results from the parsed textual date and time. Since it stores the most information and does not depend on the system environment, you would want to normalize other TimeZones into this type.
-    FixedOffset(FixedOffset),
+// A stable offset within the minute, derived from the task-id.
+pub(crate) fn splay_offset(task_id: u64) -> u64 {
+    spread_offset(task_id, 60)
 }
 
-#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
-pub(crate) struct ScheduleIteratorTimeZoneQuery {
-    time_zone: ScheduleIteratorTimeZone,
-    cron_expression: String,
+// A stable offset in `0..range`, derived from the task-id (splitmix64).
+pub(crate) fn spread_offset(task_id: u64, range: u64) -> u64 {
+    let mut x = task_id.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (x ^ (x >> 31)) % range.max(1)
 }
 
-impl ScheduleIteratorTimeZone {
-    fn get_fixed_offset(&self) -> AnyResult<FixedOffset> {
-        match self {
-            ScheduleIteratorTimeZone::FixedOffset(offset) => Ok(*offset),
-            _ => Err(anyhow!("No variant of FixedOffset.")),
-        }
+/// Custom scheduling policy, for schedules that cron-expressions can't describe
+/// (e.g. Fibonacci backoff, market calendars, data-dependent intervals).
+///
+/// Wrap it by `Frequency::Custom`, the task runs until `next_after` returns `None`.
+/// Implementors only need to derive `Clone`, `SchedulePolicyClone` is implemented for them.
+pub trait SchedulePolicy: SchedulePolicyClone + Send + Sync + 'static {
+    /// Get the next fire time after `previous`, the time the previous fire was scheduled for,
+    /// or the time the task is built at for the first fire.
+    ///
+    /// The schedule goes on from the scheduled times, so a fire running late doesn't shift it.
+    fn next_after(&mut self, previous: DateTime<Utc>) -> Option<DateTime<Utc>>;
+}
+
+/// Cloning support for boxed `SchedulePolicy`,
+/// so that a `TaskBuilder` holding a custom policy can still be cloned.
+pub trait SchedulePolicyClone {
+    /// Clone the policy into a new box.
+    fn clone_box(&self) -> Box<dyn SchedulePolicy>;
+}
+
+impl<T: SchedulePolicy + Clone> SchedulePolicyClone for T {
+    fn clone_box(&self) -> Box<dyn SchedulePolicy> {
+        Box::new(self.clone())
     }
 }
 
-impl Default for ScheduleIteratorTimeZone {
-    fn default() -> Self {
-        ScheduleIteratorTimeZone::Local
+impl Clone for Box<dyn SchedulePolicy> {
+    fn clone(&self) -> Box<dyn SchedulePolicy> {
+        self.clone_box()
     }
 }
 
-/// The Cron-expression scheduling iterator enum.
-/// There are three variants.
-/// The declaration `enum` is to avoid the problems caused by generalized contagion and monomorphism.
-///
-//
-// Frequency<T> -> FrequencyInner<T> -> Task<T> -> Slot<T> -> Wheel<T> ....
-// Frequency<Utc> or Frequency<Local> caused Task<Utc> Task<Local>
-// The Wheel<T> must only exist one for delay-timer run ,
-// can't store two kind of task-type .
-//
-///
-/// The intention is to provide an api to the user to set the time zone of `ScheduleIteratorOwned` conveniently,
-/// if you use a generic that wraps its type need to add this generic parameter,
-/// and after the type will be inconsistent and can not be stored in the same container,
-/// so use enum to avoid these problems.
+impl fmt::Debug for dyn SchedulePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Enumerated values of repeating types based on the number of seconds.
+pub(crate) enum FrequencySeconds {
+    /// Repeat once.
+    Once(u64),
+    /// Repeat ad infinitum.
+    Repeated(u64),
+    /// Type of countdown.
+    CountDown(u64, u64),
+}
 
+/// The frequency a task was built with, as told by `Task::frequency`.
 #[derive(Debug, Clone)]
-pub(crate) enum DelayTimerScheduleIteratorOwned {
-    Utc(ScheduleIteratorOwned<Utc>),
-    Local(ScheduleIteratorOwned<Local>),
-    FixedOffset(ScheduleIteratorOwned<FixedOffset>),
+pub enum TaskFrequency<'a> {
+    /// Fired by cron-expressions, or by a custom policy.
+    Cron(FrequencyCronStr<'a>),
+    /// Fired every `interval`.
+    Interval {
+        /// The time between two fires.
+        interval: Duration,
+        /// How many times it fires in total, `None` for ever.
+        count: Option<u64>,
+    },
 }
 
-impl DelayTimerScheduleIteratorOwned {
-    pub(crate) fn new(
-        ScheduleIteratorTimeZoneQuery {
-            time_zone,
-            ref cron_expression,
-        }: ScheduleIteratorTimeZoneQuery,
-    ) -> Result<DelayTimerScheduleIteratorOwned, cron_error::Error> {
-        Ok(match time_zone {
-            ScheduleIteratorTimeZone::Utc => DelayTimerScheduleIteratorOwned::Utc(
-                Schedule::from_str(cron_expression)?.upcoming_owned(Utc),
-            ),
-            ScheduleIteratorTimeZone::Local => DelayTimerScheduleIteratorOwned::Local(
-                Schedule::from_str(cron_expression)?.upcoming_owned(Local),
+// The counts are left out, `Task::remaining_runs` tells them.
+impl<'a> fmt::Display for TaskFrequency<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskFrequency::Cron(FrequencyCronStr::Once(expression)) => {
+                write!(f, "once by `{}`", expression)
+            }
+            TaskFrequency::Cron(FrequencyCronStr::Repeated(expression))
+            | TaskFrequency::Cron(FrequencyCronStr::CountDown(_, expression)) => {
+                write!(f, "by `{}`", expression)
+            }
+            TaskFrequency::Cron(FrequencyCronStr::Multi(expressions)) => {
+                write!(f, "by ")?;
+                for (i, expression) in expressions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "`{}`", expression)?;
+                }
+                Ok(())
+            }
+            TaskFrequency::Cron(FrequencyCronStr::Except { include, exclude }) => {
+                write!(f, "by `{}` except `{}`", include, exclude)
+            }
+            TaskFrequency::Cron(FrequencyCronStr::Custom(_)) => write!(f, "by a custom policy"),
+            TaskFrequency::Cron(FrequencyCronStr::KeepAlive { restart_delay, .. }) => write!(
+                f,
+                "kept alive, restarted after {}",
+                format_seconds(restart_delay.as_secs())
             ),
-            ScheduleIteratorTimeZone::FixedOffset(fixed_offset) => {
-                DelayTimerScheduleIteratorOwned::FixedOffset(
-                    Schedule::from_str(cron_expression)?.upcoming_owned(fixed_offset),
-                )
+            TaskFrequency::Interval {
+                interval,
+                count: Some(1),
+            } => write!(f, "once after {}", format_seconds(interval.as_secs())),
+            TaskFrequency::Interval { interval, .. } => {
+                write!(f, "every {}", format_seconds(interval.as_secs()))
             }
+        }
+    }
+}
+
+// `5400` as `1h30m`.
+fn format_seconds(seconds: u64) -> String {
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+
+    [(ONE_DAY, "d"), (ONE_HOUR, "h"), (ONE_MINUTE, "m"), (1, "s")]
+        .iter()
+        .scan(seconds, |rest, &(unit, suffix)| {
+            let value = *rest / unit;
+            *rest %= unit;
+            Some((value, suffix))
         })
+        .filter(|&(value, _)| value > 0)
+        .map(|(value, suffix)| format!("{}{}", value, suffix))
+        .collect()
+}
+
+impl<'a> Default for FrequencyCronStr<'a> {
+    fn default() -> FrequencyCronStr<'a> {
+        FrequencyCronStr::Once("@minutely")
     }
+}
 
-    #[inline(always)]
-    pub(crate) fn refresh_previous_datetime(&mut self, time_zone: ScheduleIteratorTimeZone) {
-        match self {
-            Self::Utc(ref mut iterator) => iterator.refresh_previous_datetime(Utc),
-            Self::Local(ref mut iterator) => iterator.refresh_previous_datetime(Local),
+impl Default for FrequencySeconds {
+    fn default() -> FrequencySeconds {
+        FrequencySeconds::Once(ONE_MINUTE)
+    }
+}
 
-            Self::FixedOffset(ref mut iterator) => {
-                if let Ok(offset) = time_zone.get_fixed_offset() {
-                    iterator.refresh_previous_datetime(offset);
-                }
-            }
+/// Iterator for task internal control of execution time.
+#[derive(Debug, Clone)]
+pub(crate) enum FrequencyInner {
+    /// Unlimited repetition types for cron-expression.
+    CronExpressionRepeated(DelayTimerScheduleIteratorOwned),
+    /// Type of countdown for cron-expression.
+    CronExpressionCountDown(u64, DelayTimerScheduleIteratorOwned),
+    /// Unlimited repetition types for seconds-duration.
+    SecondsRepeated(SecondsState),
+    /// Type of countdown for SecondsState.
+    /// SecondsCountDown(count_down, SecondsState)
+    SecondsCountDown(u64, SecondsState),
+    /// Unlimited repetition types for the union of cron-expressions.
+    CronExpressionMultiRepeated(MultiScheduleIterator),
+    /// Unlimited repetition types for cron-expression, with an exclusion schedule.
+    CronExpressionExceptRepeated(DelayTimerScheduleIteratorOwned, ExclusionSchedule),
+    /// Unlimited repetition types for custom policy, until it yields nothing.
+    /// CustomRepeated(policy, the previous scheduled time)
+    CustomRepeated(Box<dyn SchedulePolicy>, Option<DateTime<Utc>>),
+    /// Unlimited restarts of the ended instance.
+    KeepAlive(KeepAliveState),
+}
+
+// The fire of a supervised task is due every second after the last start,
+// it's held back until the instance has ended and the restart is allowed.
+#[derive(Debug, Clone)]
+pub(crate) struct KeepAliveState {
+    restart_delay: u64,
+    max_restarts_per_hour: u64,
+    // The time of the last fire.
+    cursor: u64,
+    // The start times of the instances within the last hour.
+    start_times: VecDeque<u64>,
+}
+
+impl KeepAliveState {
+    fn new(restart_delay: Duration, max_restarts_per_hour: u64) -> KeepAliveState {
+        KeepAliveState {
+            restart_delay: restart_delay.as_secs(),
+            max_restarts_per_hour,
+            cursor: timestamp(),
+            start_times: VecDeque::new(),
         }
     }
 
-    #[inline(always)]
-    pub(crate) fn next(&mut self) -> Option<i64> {
-        match self {
-            Self::Utc(ref mut iterator) => iterator.next().map(|e| e.timestamp()),
-            Self::Local(ref mut iterator) => iterator.next().map(|e| e.timestamp()),
-            Self::FixedOffset(ref mut iterator) => iterator.next().map(|e| e.timestamp()),
-        }
+    fn next(&mut self) -> i64 {
+        self.cursor += 1;
+        self.cursor as i64
     }
 
-    // Analyze expressions, get cache.
-    fn analyze_cron_expression(
+    // The first start of the hour isn't a restart.
+    fn is_restartable(&self, timestamp: u64, last_finish_time: Option<u64>) -> bool {
+        let delay_elapsed = last_finish_time
+            .is_none_or(|finish_time| timestamp >= finish_time + self.restart_delay);
+        let starts = self
+            .start_times
+            .iter()
+            .filter(|&&start_time| start_time + ONE_HOUR > timestamp)
+            .count() as u64;
+
+        delay_elapsed && starts <= self.max_restarts_per_hour
+    }
+
+    fn record_start(&mut self, timestamp: u64) {
+        self.start_times
+            .retain(|&start_time| start_time + ONE_HOUR > timestamp);
+        self.start_times.push_back(timestamp);
+        self.cursor = self.cursor.max(timestamp);
+    }
+}
+
+// The exclusion is evaluated in the same time zone as the including expression.
+#[derive(Debug, Clone)]
+pub(crate) struct ExclusionSchedule {
+    schedule: Schedule,
+    time_zone: ScheduleIteratorTimeZone,
+}
+
+impl ExclusionSchedule {
+    fn new(
         time_zone: ScheduleIteratorTimeZone,
         cron_expression: &str,
-    ) -> Result<DelayTimerScheduleIteratorOwned, FrequencyAnalyzeError> {
-        let indiscriminate_expression = cron_expression.trim_matches(' ').to_owned();
-        let schedule_iterator_time_zone_query: ScheduleIteratorTimeZoneQuery =
-            ScheduleIteratorTimeZoneQuery {
-                cron_expression: indiscriminate_expression,
-                time_zone,
-            };
+    ) -> Result<ExclusionSchedule, FrequencyAnalyzeError> {
+        let schedule = Schedule::from_str(cron_expression.trim_matches(' '))?;
 
-        let analyze_result = CRON_EXPRESSION_CACHE.try_with(|expression_cache| {
-            let mut lru_cache = expression_cache.borrow_mut();
-            if let Some(schedule_iterator) = lru_cache.get(&schedule_iterator_time_zone_query) {
-                let mut schedule_iterator_copy = schedule_iterator.clone();
+        Ok(ExclusionSchedule {
+            schedule,
+            time_zone,
+        })
+    }
 
-                // Reset the internal base time to avoid expiration time during internal iterations.
-                schedule_iterator_copy.refresh_previous_datetime(time_zone);
+    fn includes(&self, timestamp: i64) -> bool {
+        let date_time = match Utc.timestamp_opt(timestamp, 0).single() {
+            Some(date_time) => date_time,
+            None => return false,
+        };
 
-                return Ok(schedule_iterator_copy);
+        match self.time_zone {
+            ScheduleIteratorTimeZone::Utc => self.schedule.includes(date_time),
+            ScheduleIteratorTimeZone::Local => {
+                self.schedule.includes(date_time.with_timezone(&Local))
+            }
+            ScheduleIteratorTimeZone::FixedOffset(offset) => {
+                self.schedule.includes(date_time.with_timezone(&offset))
             }
+        }
+    }
+}
 
-            let new_result =
-                DelayTimerScheduleIteratorOwned::new(schedule_iterator_time_zone_query.clone());
+// Each iterator keeps its next time peeked,
+// the earliest one is yielded and every iterator peeking the same time is advanced.
+#[derive(Debug, Clone)]
+pub(crate) struct MultiScheduleIterator {
+    iterators: Vec<(DelayTimerScheduleIteratorOwned, Option<i64>)>,
+}
 
-            new_result.map(|task_schedule| {
-                lru_cache.put(schedule_iterator_time_zone_query, task_schedule.clone());
-                task_schedule
+impl MultiScheduleIterator {
+    fn new(iterators: Vec<DelayTimerScheduleIteratorOwned>) -> MultiScheduleIterator {
+        let iterators = iterators
+            .into_iter()
+            .map(|mut iterator| {
+                let peeked = iterator.next();
+                (iterator, peeked)
             })
-        })?;
+            .collect();
 
-        Ok(analyze_result?)
+        MultiScheduleIterator { iterators }
     }
-}
 
-impl FrequencyInner {
-    // How many times the acquisition needs to be performed.
-    #[allow(dead_code)]
-    fn residual_time(&self) -> u64 {
-        match self {
-            FrequencyInner::CronExpressionRepeated(_) => u64::MAX,
-            FrequencyInner::SecondsRepeated(_) => u64::MAX,
-            FrequencyInner::CronExpressionCountDown(ref time, _) => *time,
-            FrequencyInner::SecondsCountDown(ref time, _) => *time,
+    fn next(&mut self) -> Option<i64> {
+        let next = self
+            .iterators
+            .iter()
+            .filter_map(|(_, peeked)| *peeked)
+            .min()?;
+
+        for (iterator, peeked) in self.iterators.iter_mut() {
+            if *peeked == Some(next) {
+                *peeked = iterator.next();
+            }
         }
+
+        Some(next)
     }
+}
 
-    fn next_alarm_timestamp(&mut self) -> Option<i64> {
+/// Wall-clock boundary the repeated interval is aligned to.
+///
+/// E.g. repeating every 15 minutes aligned to `Hour` fires at :00/:15/:30/:45,
+/// rather than relative to the time the task was inserted.
+/// Boundaries are counted in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Align to the start of the current minute.
+    Minute,
+    /// Align to the start of the current hour.
+    Hour,
+    /// Align to an arbitrary point in time.
+    Epoch(DateTime<Utc>),
+}
+
+impl Alignment {
+    fn anchor(&self, now: u64) -> i64 {
         match self {
-            FrequencyInner::CronExpressionCountDown(_, ref mut clock) => clock.next(),
-            FrequencyInner::CronExpressionRepeated(ref mut clock) => clock.next(),
-            FrequencyInner::SecondsRepeated(seconds_state) => {
-                seconds_state.next().map(|s| s as i64)
-            }
-            FrequencyInner::SecondsCountDown(_, seconds_state) => {
-                seconds_state.next().map(|s| s as i64)
-            }
+            Alignment::Minute => (now - now % ONE_MINUTE) as i64,
+            Alignment::Hour => (now - now % ONE_HOUR) as i64,
+            Alignment::Epoch(date_time) => date_time.timestamp(),
         }
     }
 
-    #[warn(unused_parens)]
-    fn down_count(&mut self) {
-        match self {
-            FrequencyInner::CronExpressionRepeated(_) => {}
-            FrequencyInner::SecondsRepeated(_) => {}
-            FrequencyInner::CronExpressionCountDown(ref mut exec_count, _) => *exec_count -= 1u64,
-            FrequencyInner::SecondsCountDown(count_down, _) => *count_down -= 1u64,
-        };
+    // The first boundary strictly after `now`.
+    fn first_fire(&self, now: u64, seconds: u64) -> u64 {
+        let anchor = self.anchor(now);
+        let seconds = seconds as i64;
+        let elapsed = now as i64 - anchor;
+
+        let steps = elapsed.div_euclid(seconds) + 1;
+        (anchor + steps * seconds) as u64
+    }
+}
+
+// The state starts from the first fire, then steps by `seconds`.
+fn seconds_state(seconds: u64, align_to: Option<Alignment>) -> SecondsState {
+    let now = timestamp();
+    let first_fire = match align_to {
+        Some(alignment) => alignment.first_fire(now, seconds),
+        None => now + seconds,
+    };
+
+    (first_fire..).step_by(seconds as usize)
+}
+
+impl<'a>
+    TryFrom<(
+        FrequencyUnify<'a>,
+        ScheduleIteratorTimeZone,
+        DstPolicy,
+        Option<Alignment>,
+    )> for FrequencyInner
+{
+    type Error = FrequencyAnalyzeError;
+
+    fn try_from(
+        (frequency, time_zone, dst_policy, align_to): (
+            FrequencyUnify<'_>,
+            ScheduleIteratorTimeZone,
+            DstPolicy,
+            Option<Alignment>,
+        ),
+    ) -> Result<FrequencyInner, Self::Error> {
+        let frequency_inner = match frequency {
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Once(cron_str)) => {
+                let task_schedule = DelayTimerScheduleIteratorOwned::analyze_cron_expression(
+                    time_zone, dst_policy, cron_str,
+                )?;
+
+                FrequencyInner::CronExpressionCountDown(1, task_schedule)
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Repeated(cron_str)) => {
+                let task_schedule = DelayTimerScheduleIteratorOwned::analyze_cron_expression(
+                    time_zone, dst_policy, cron_str,
+                )?;
+
+                FrequencyInner::CronExpressionRepeated(task_schedule)
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::CountDown(count_down, cron_str)) => {
+                let task_schedule = DelayTimerScheduleIteratorOwned::analyze_cron_expression(
+                    time_zone, dst_policy, cron_str,
+                )?;
+
+                FrequencyInner::CronExpressionCountDown(count_down as u64, task_schedule)
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Multi(cron_strs)) => {
+                if cron_strs.is_empty() {
+                    return Err(FrequencyAnalyzeError::EmptyExpression);
+                }
+
+                let task_schedules = cron_strs
+                    .into_iter()
+                    .map(|cron_str| {
+                        DelayTimerScheduleIteratorOwned::analyze_cron_expression(
+                            time_zone, dst_policy, cron_str,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                FrequencyInner::CronExpressionMultiRepeated(MultiScheduleIterator::new(
+                    task_schedules,
+                ))
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Except { include, exclude }) => {
+                let task_schedule = DelayTimerScheduleIteratorOwned::analyze_cron_expression(
+                    time_zone, dst_policy, include,
+                )?;
+                let exclusion_schedule = ExclusionSchedule::new(time_zone, exclude)?;
+
+                FrequencyInner::CronExpressionExceptRepeated(task_schedule, exclusion_schedule)
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::Custom(schedule_policy)) => {
+                FrequencyInner::CustomRepeated(schedule_policy, None)
+            }
+            FrequencyUnify::FrequencyCronStr(FrequencyCronStr::KeepAlive {
+                restart_delay,
+                max_restarts_per_hour,
+            }) => {
+                FrequencyInner::KeepAlive(KeepAliveState::new(restart_delay, max_restarts_per_hour))
+            }
+
+            FrequencyUnify::FrequencySeconds(FrequencySeconds::Once(seconds)) => {
+                if seconds == 0 {
+                    return Err(FrequencyAnalyzeError::DisInitTime);
+                }
+
+                let seconds_state: SecondsState =
+                    ((timestamp() + seconds)..).step_by(seconds as usize);
+                FrequencyInner::SecondsCountDown(1, seconds_state)
+            }
+            FrequencyUnify::FrequencySeconds(FrequencySeconds::Repeated(seconds)) => {
+                if seconds == 0 {
+                    return Err(FrequencyAnalyzeError::DisInitTime);
+                }
+
+                FrequencyInner::SecondsRepeated(seconds_state(seconds, align_to))
+            }
+            FrequencyUnify::FrequencySeconds(FrequencySeconds::CountDown(count_down, seconds)) => {
+                if seconds == 0 {
+                    return Err(FrequencyAnalyzeError::DisInitTime);
+                }
+
+                FrequencyInner::SecondsCountDown(count_down, seconds_state(seconds, align_to))
+            }
+            candy @ FrequencyUnify::FrequencyCandyStr(_) => {
+                return (candy.to_borrowed(), time_zone, dst_policy, align_to).try_into();
+            }
+        };
+
+        Ok(frequency_inner)
+    }
+}
+
+/// Execution budget of a recurring task.
+///
+/// Once the running instances of the task have consumed the budget in the current hour,
+/// the following fires of that hour are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    /// Total seconds the instances of the task can run in one hour.
+    pub max_total_runtime_per_hour: u64,
+}
+
+/// How the fires of a task are gated by its readiness check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    /// How long the check may take, a check that times out hasn't passed.
+    pub timeout: Duration,
+    /// What becomes of a fire whose check hasn't passed.
+    pub policy: NotReadyPolicy,
+}
+
+/// What becomes of a fire whose readiness check hasn't passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotReadyPolicy {
+    /// The fire is skipped, a `SkippedNotReady` event is emitted instead.
+    Skip,
+    /// The fire is held back and checked again after `retry_after`,
+    /// until the check passes or the `max_queue_delay` of the task is over.
+    Defer {
+        /// How long to wait before checking again.
+        retry_after: Duration,
+    },
+}
+
+/// When a running instance is reported by a `RunningLong` event,
+/// see `TaskBuilder::set_runtime_warning_threshold`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeWarningThreshold {
+    /// Once it has run that long.
+    After(Duration),
+    /// Once it has run that fraction of its maximum running time, e.g. `0.8`.
+    FractionOfTimeout(f64),
+}
+
+impl From<Duration> for RuntimeWarningThreshold {
+    fn from(duration: Duration) -> Self {
+        RuntimeWarningThreshold::After(duration)
+    }
+}
+
+impl From<f64> for RuntimeWarningThreshold {
+    fn from(fraction: f64) -> Self {
+        RuntimeWarningThreshold::FractionOfTimeout(fraction)
+    }
+}
+
+/// What becomes of a task whose instances keep failing, see `TaskBuilder::set_failure_policy`.
+///
+/// A failed instance is one that timed out, or a process that couldn't run or exited unsuccessfully.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// The task keeps running whatever happens.
+    KeepRunning,
+    /// The task is paused after that many failures in a row,
+    /// until it's resumed by `DelayTimer::resume_task`.
+    PauseAfter(u64),
+    /// The task is removed after that many failures in a row.
+    RemoveAfter(u64),
+}
+
+impl Failure {
+    // `None` for a policy that never trips.
+    fn max_consecutive_failures(&self) -> Option<u64> {
+        match *self {
+            Failure::KeepRunning => None,
+            Failure::PauseAfter(n) | Failure::RemoveAfter(n) => Some(n.max(1)),
+        }
+    }
+}
+
+impl Default for Failure {
+    fn default() -> Self {
+        Failure::KeepRunning
+    }
+}
+
+/// Which of the routine events of a task are reported, for the tasks firing so often
+/// that they would flood the status-report, see `TaskBuilder::set_event_sampling`.
+///
+/// It applies to the `RunningTask` and the successful `FinishTask` events,
+/// the failures and the other events are always reported.
+/// The `FinishTask` of an instance is reported if and only if its `RunningTask` is,
+/// so that the events of a reported instance are never dropped apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventSampling {
+    /// Every event is reported.
+    All,
+    /// The first instance, then every nth one.
+    EveryNth(u64),
+    /// Only the failures are reported.
+    FailuresOnly,
+}
+
+impl EventSampling {
+    // Whether the `nth` (from 1) event of its kind is reported.
+    pub(crate) fn passes(&self, nth: u64) -> bool {
+        match *self {
+            EventSampling::All => true,
+            EventSampling::EveryNth(n) => nth.saturating_sub(1) % n.max(1) == 0,
+            EventSampling::FailuresOnly => false,
+        }
+    }
+}
+
+impl Default for EventSampling {
+    fn default() -> Self {
+        EventSampling::All
+    }
+}
+
+/// Where the instances of a task run, see `TaskBuilder::set_execution_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    /// On the runtime of the timer, the sync routines on its blocking pool.
+    Runtime,
+    /// Each instance on an OS thread of its own, taken from a pool shared by the tasks in this mode.
+    ///
+    /// Blocking or FFI-heavy routines, async ones included,
+    /// can't hold up the async workers of the runtime then.
+    DedicatedThread,
+}
+
+impl Default for Execution {
+    fn default() -> Self {
+        Execution::Runtime
+    }
+}
+
+/// Options of a manual run triggered by `DelayTimer::advance_task_with_options`.
+#[derive(Debug, Clone)]
+pub struct AdvanceOptions {
+    consume_count: bool,
+    jump_queue: bool,
+    payload: Option<Vec<u8>>,
+    #[cfg(feature = "otel")]
+    parent_context: Option<OtelContext>,
+}
+
+impl Default for AdvanceOptions {
+    fn default() -> Self {
+        AdvanceOptions {
+            consume_count: true,
+            jump_queue: false,
+            payload: None,
+            #[cfg(feature = "otel")]
+            parent_context: None,
+        }
+    }
+}
+
+impl AdvanceOptions {
+    /// Whether the manual run counts against the remaining runs of the task (default `true`).
+    ///
+    /// When it does, the pending fire runs ahead of time and the schedule resumes after it.
+    /// When it doesn't, it is a free extra execution and the pending fire is kept.
+    pub fn consume_count(mut self, consume_count: bool) -> Self {
+        self.consume_count = consume_count;
+        self
+    }
+
+    /// Get whether the manual run counts against the remaining runs of the task.
+    pub fn is_consume_count(&self) -> bool {
+        self.consume_count
+    }
+
+    /// Whether the manual run goes to the front of the line (default `false`),
+    /// for the operator-triggered runs, e.g. during an incident.
+    ///
+    /// It's the next to run in the serial queue and the next to take a permit of the semaphore
+    /// of the task, ahead of the fires held back there. The running instances aren't disturbed,
+    /// the run is queued behind them only.
+    ///
+    /// It doesn't bypass any limit but the line: the budget, the circuit breaker and the other
+    /// policies of the task still apply to the run, as does the quota of its tenant.
+    pub fn jump_queue(mut self, jump_queue: bool) -> Self {
+        self.jump_queue = jump_queue;
+        self
+    }
+
+    /// Get whether the manual run goes to the front of the line.
+    pub fn is_jump_queue(&self) -> bool {
+        self.jump_queue
+    }
+
+    /// Hand `payload` to the manual run (optional), read back by `TaskContext::payload`,
+    /// e.g. the customer a "re-sync customer" run is about.
+    ///
+    /// The fires of the schedule run without one.
+    pub fn payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.payload = Some(payload.into());
+        self
+    }
+
+    pub(crate) fn take_payload(&mut self) -> Option<Vec<u8>> {
+        self.payload.take()
+    }
+
+    /// Link the span of the manual run to a remote parent context.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `otel` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "otel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+    pub fn parent_context(mut self, parent_context: OtelContext) -> Self {
+        self.parent_context = Some(parent_context);
+        self
+    }
+
+    #[cfg(feature = "otel")]
+    pub(crate) fn into_parent_context(self) -> Option<OtelContext> {
+        self.parent_context
+    }
+}
+
+/// What becomes of a manual run, as foreseen by `DelayTimer::advance_task`
+/// from the state of the task when it's called.
+///
+/// The readiness check of the task and the `SpawnHook` of the timer still have their say
+/// once the run is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvanceOutcome {
+    /// The run starts right away.
+    Advanced,
+    /// The run is queued behind that many running instances,
+    /// of the task or of its serial queue.
+    QueuedBehind(u64),
+    /// The run is refused by a policy of the task, its pending fire is left as it is.
+    RejectedByPolicy(SkipReason),
+}
+
+/// Set the time zone for the time of the expression iteration.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ScheduleIteratorTimeZone {
+    /// Utc specifies the UTC time zone. It is most efficient.
+    Utc,
+    /// Local specifies the system local time zone.
+    Local,
+    /// FixedOffset specifies an arbitrary, fixed time zone such as UTC+09:00 or UTC-10:30. This often results from the parsed textual date and time. Since it stores the most information and does not depend on the system environment, you would want to normalize other TimeZones into this type.
+    FixedOffset(FixedOffset),
+}
+
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub(crate) struct ScheduleIteratorTimeZoneQuery {
+    time_zone: ScheduleIteratorTimeZone,
+    dst_policy: DstPolicy,
+    cron_expression: String,
+}
+
+impl ScheduleIteratorTimeZone {
+    fn get_fixed_offset(&self) -> AnyResult<FixedOffset> {
+        match self {
+            ScheduleIteratorTimeZone::FixedOffset(offset) => Ok(*offset),
+            _ => Err(anyhow!("No variant of FixedOffset.")),
+        }
+    }
+}
+
+impl Default for ScheduleIteratorTimeZone {
+    fn default() -> Self {
+        ScheduleIteratorTimeZone::Local
+    }
+}
+
+/// The Cron-expression scheduling iterator enum.
+/// There are three variants.
+/// The declaration `enum` is to avoid the problems caused by generalized contagion and monomorphism.
+///
+//
+// Frequency<T> -> FrequencyInner<T> -> Task<T> -> Slot<T> -> Wheel<T> ....
+// Frequency<Utc> or Frequency<Local> caused Task<Utc> Task<Local>
+// The Wheel<T> must only exist one for delay-timer run ,
+// can't store two kind of task-type .
+//
+///
+/// The intention is to provide an api to the user to set the time zone of `ScheduleIteratorOwned` conveniently,
+/// if you use a generic that wraps its type need to add this generic parameter,
+/// and after the type will be inconsistent and can not be stored in the same container,
+/// so use enum to avoid these problems.
+
+#[derive(Debug, Clone)]
+pub(crate) enum DelayTimerScheduleIteratorOwned {
+    Utc(ScheduleIteratorOwned<Utc>),
+    // The local time zone goes through daylight-saving transitions, as its policy says.
+    Local(DstSchedule<Local>),
+    FixedOffset(ScheduleIteratorOwned<FixedOffset>),
+}
+
+impl DelayTimerScheduleIteratorOwned {
+    pub(crate) fn new(
+        ScheduleIteratorTimeZoneQuery {
+            time_zone,
+            dst_policy,
+            ref cron_expression,
+        }: ScheduleIteratorTimeZoneQuery,
+    ) -> Result<DelayTimerScheduleIteratorOwned, cron_error::Error> {
+        Ok(match time_zone {
+            ScheduleIteratorTimeZone::Utc => DelayTimerScheduleIteratorOwned::Utc(
+                Schedule::from_str(cron_expression)?.upcoming_owned(Utc),
+            ),
+            ScheduleIteratorTimeZone::Local => DelayTimerScheduleIteratorOwned::Local(
+                DstSchedule::new(Schedule::from_str(cron_expression)?, Local, dst_policy),
+            ),
+            ScheduleIteratorTimeZone::FixedOffset(fixed_offset) => {
+                DelayTimerScheduleIteratorOwned::FixedOffset(
+                    Schedule::from_str(cron_expression)?.upcoming_owned(fixed_offset),
+                )
+            }
+        })
+    }
+
+    #[inline(always)]
+    pub(crate) fn refresh_previous_datetime(&mut self, time_zone: ScheduleIteratorTimeZone) {
+        match self {
+            Self::Utc(ref mut iterator) => iterator.refresh_previous_datetime(Utc),
+            Self::Local(ref mut iterator) => iterator.refresh(),
+
+            Self::FixedOffset(ref mut iterator) => {
+                if let Ok(offset) = time_zone.get_fixed_offset() {
+                    iterator.refresh_previous_datetime(offset);
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn next(&mut self) -> Option<i64> {
+        match self {
+            Self::Utc(ref mut iterator) => iterator.next().map(|e| e.timestamp()),
+            Self::Local(ref mut iterator) => iterator.next(),
+            Self::FixedOffset(ref mut iterator) => iterator.next().map(|e| e.timestamp()),
+        }
+    }
+
+    // Analyze expressions, get cache.
+    fn analyze_cron_expression(
+        time_zone: ScheduleIteratorTimeZone,
+        dst_policy: DstPolicy,
+        cron_expression: &str,
+    ) -> Result<DelayTimerScheduleIteratorOwned, FrequencyAnalyzeError> {
+        let indiscriminate_expression = cron_expression.trim_matches(' ').to_owned();
+        let schedule_iterator_time_zone_query: ScheduleIteratorTimeZoneQuery =
+            ScheduleIteratorTimeZoneQuery {
+                cron_expression: indiscriminate_expression,
+                time_zone,
+                dst_policy,
+            };
+
+        let analyze_result = CRON_EXPRESSION_CACHE.try_with(|expression_cache| {
+            let mut lru_cache = expression_cache.borrow_mut();
+            if let Some(schedule_iterator) = lru_cache.get(&schedule_iterator_time_zone_query) {
+                let mut schedule_iterator_copy = schedule_iterator.clone();
+
+                // Reset the internal base time to avoid expiration time during internal iterations.
+                schedule_iterator_copy.refresh_previous_datetime(time_zone);
+
+                return Ok(schedule_iterator_copy);
+            }
+
+            let new_result =
+                DelayTimerScheduleIteratorOwned::new(schedule_iterator_time_zone_query.clone());
+
+            new_result.map(|task_schedule| {
+                lru_cache.put(schedule_iterator_time_zone_query, task_schedule.clone());
+                task_schedule
+            })
+        })?;
+
+        Ok(analyze_result?)
+    }
+}
+
+impl FrequencyInner {
+    // How many times the acquisition needs to be performed.
+    fn residual_time(&self) -> u64 {
+        match self {
+            FrequencyInner::CronExpressionRepeated(_) => u64::MAX,
+            FrequencyInner::SecondsRepeated(_) => u64::MAX,
+            FrequencyInner::CronExpressionMultiRepeated(_) => u64::MAX,
+            FrequencyInner::CronExpressionExceptRepeated(..) => u64::MAX,
+            FrequencyInner::CustomRepeated(..) => u64::MAX,
+            FrequencyInner::KeepAlive(_) => u64::MAX,
+            FrequencyInner::CronExpressionCountDown(ref time, _) => *time,
+            FrequencyInner::SecondsCountDown(ref time, _) => *time,
+        }
+    }
+
+    fn next_alarm_timestamp(&mut self) -> Option<i64> {
+        self.next_alarm_timestamp_at(Utc::now())
+    }
+
+    // `now` is only seen by a custom schedule policy for its first fire,
+    // the schedules keep their own position from there on.
+    fn next_alarm_timestamp_at(&mut self, now: DateTime<Utc>) -> Option<i64> {
+        match self {
+            FrequencyInner::CronExpressionCountDown(_, ref mut clock) => clock.next(),
+            FrequencyInner::CronExpressionRepeated(ref mut clock) => clock.next(),
+            FrequencyInner::SecondsRepeated(seconds_state) => {
+                seconds_state.next().map(|s| s as i64)
+            }
+            FrequencyInner::SecondsCountDown(_, seconds_state) => {
+                seconds_state.next().map(|s| s as i64)
+            }
+            FrequencyInner::CronExpressionMultiRepeated(ref mut clocks) => clocks.next(),
+            FrequencyInner::CronExpressionExceptRepeated(ref mut clock, _) => clock.next(),
+            FrequencyInner::CustomRepeated(schedule_policy, previous) => {
+                let next_fire = schedule_policy.next_after(previous.unwrap_or(now))?;
+                *previous = Some(next_fire);
+                Some(next_fire.timestamp())
+            }
+            FrequencyInner::KeepAlive(keep_alive) => Some(keep_alive.next()),
+        }
+    }
+
+    #[warn(unused_parens)]
+    fn down_count(&mut self) {
+        match self {
+            FrequencyInner::CronExpressionRepeated(_) => {}
+            FrequencyInner::SecondsRepeated(_) => {}
+            FrequencyInner::CronExpressionMultiRepeated(_) => {}
+            FrequencyInner::CronExpressionExceptRepeated(..) => {}
+            FrequencyInner::CustomRepeated(..) => {}
+            FrequencyInner::KeepAlive(_) => {}
+            FrequencyInner::CronExpressionCountDown(ref mut exec_count, _) => *exec_count -= 1u64,
+            FrequencyInner::SecondsCountDown(count_down, _) => *count_down -= 1u64,
+        };
+    }
+
+    fn is_excluded(&self, timestamp: i64) -> bool {
+        match self {
+            FrequencyInner::CronExpressionExceptRepeated(_, ref exclusion_schedule) => {
+                exclusion_schedule.includes(timestamp)
+            }
+            _ => false,
+        }
     }
 
     fn is_down_over(&self) -> bool {
@@ -437,7 +1794,7 @@ impl FrequencyInner {
 }
 
 //TODO: Support customer time-zore.
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Clone)]
 /// Cycle plan task builder.
 pub struct TaskBuilder<'a> {
     /// Repeat type.
@@ -450,14 +1807,112 @@ pub struct TaskBuilder<'a> {
     /// it can be use to deadline (excution-time + maximum_running_time).
     maximum_running_time: Option<u64>,
 
+    /// Multiplier of the p95 duration the timeout adapts to (optional).
+    maximum_running_time_auto: Option<f64>,
+
+    /// Floor and ceiling of the adapted timeout (optional).
+    maximum_running_time_bounds: Option<(u64, u64)>,
+
+    /// How long an instance runs before it's reported as running long (optional).
+    runtime_warning_threshold: Option<RuntimeWarningThreshold>,
+
+    /// Whether the runs that found nothing to do are kept out of the durations.
+    exclude_noop_durations: bool,
+
     /// Maximum parallel runnable num (optional).
     maximum_parallel_runnable_num: Option<u64>,
 
-    /// If it is built by set_frequency_by_candy, set the tag separately.
-    build_by_candy_str: bool,
+    /// How long a fire held back by `maximum_parallel_runnable_num` stays queued (optional).
+    max_queue_delay: Option<Duration>,
+
+    /// Cancel the oldest running instances for a fire over `maximum_parallel_runnable_num`.
+    replace_on_overlap: bool,
 
     /// Time zone for cron-expression iteration time.
     schedule_iterator_time_zone: ScheduleIteratorTimeZone,
+
+    /// How local-time cron-expressions go through daylight-saving transitions.
+    dst_policy: DstPolicy,
+
+    /// Wall-clock alignment of repeated intervals (optional).
+    align_to: Option<Alignment>,
+
+    /// Fire once right after insertion, in addition to the schedule.
+    run_on_startup: bool,
+
+    /// Run each scheduled time at most once, across restarts.
+    at_most_once: bool,
+
+    /// Cleanup closure run after a hard abort (optional).
+    on_aborted: Option<SafeStructAbortedFn>,
+
+    /// Execution budget per hour (optional).
+    budget: Option<Budget>,
+
+    /// Keep the cron second field even if the timer spreads minutely tasks.
+    auto_splay_opt_out: bool,
+
+    /// Log target of the records of `TaskContext::logger` (optional).
+    log_target: Option<&'static str>,
+
+    /// Capture the records of `TaskContext::logger` into the finish of the instance.
+    capture_logs: bool,
+
+    /// Serial queue the instances run in, one at a time across the tasks sharing it (optional).
+    serial_queue: Option<&'a str>,
+
+    /// Tenant the task belongs to, subject to its quota (optional).
+    tenant: Option<&'a str>,
+
+    /// Check gating the fires, and how (optional).
+    readiness_check: Option<(SafeStructReadinessFn, Readiness)>,
+
+    /// Builds the resource shared by the instances (optional).
+    init: Option<SafeStructInitFn>,
+
+    /// Releases the resources of the task once it's removed (optional).
+    teardown: Option<SafeStructTeardownFn>,
+
+    /// Tells the approximate heap size of the state the task holds (optional).
+    memory_sizer: Option<SafeStructSizerFn>,
+
+    /// How far ahead of its fires the instances are dispatched (optional).
+    dispatch_compensation: Option<Duration>,
+
+    /// How long the fires may be deferred, to run along with other fires (optional).
+    flexible_window: Option<Duration>,
+
+    /// What becomes of the task once its instances keep failing.
+    failure_policy: Failure,
+
+    /// Breaker shared with the tasks hitting the same dependency (optional).
+    circuit_breaker: Option<CircuitBreaker>,
+
+    /// Permits shared with the tasks drawing on the same resource pool (optional).
+    semaphore: Option<TaskSemaphore>,
+
+    /// The most time between the heartbeats of an instance, and what becomes of it past that (optional).
+    heartbeat: Option<(Duration, StallPolicy)>,
+
+    /// Which of the routine events of the task are reported (optional).
+    event_sampling: Option<EventSampling>,
+
+    /// Where the instances run.
+    execution: Execution,
+
+    /// Scheduling of the dedicated threads running the instances (optional).
+    thread_options: Option<ThreadOptions>,
+
+    /// The executor pool the instances run on (optional).
+    pool: Option<String>,
+
+    /// Remote parent of the spans of the running instances (optional).
+    #[cfg(feature = "otel")]
+    otel_parent_context: Option<OtelContext>,
+
+    /// Faults injected into the running instances (optional).
+    #[cfg(feature = "chaos")]
+    chaos: Option<Chaos>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -473,6 +1928,52 @@ pub struct TaskContext {
     pub runtime_kind: RuntimeKind,
     /// Event Sender for Timer Wheel Core.
     pub(crate) timer_event_sender: Option<TimerEventSender>,
+    /// Token triggered when the instance is asked to stop cooperatively.
+    pub(crate) cancellation_token: CancellationToken,
+    /// Schedule timestamps around the running instance.
+    pub(crate) schedule_metadata: ScheduleMetadata,
+    /// Logger tagging the records with the task-id and record-id.
+    pub(crate) logger: TaskLogger,
+    /// The instant an instance dispatched ahead of its fire starts at.
+    pub(crate) dispatch_at: Option<Instant>,
+    /// The extension handed to the running instance by the `SpawnHook`.
+    pub(crate) extension: Option<Arc<dyn Any + Send + Sync>>,
+    /// The resource built by the `init` of the task.
+    pub(crate) resource: Option<Arc<dyn Any + Send + Sync>>,
+    /// The heartbeat of the running instance, if the task expects one.
+    pub(crate) heartbeat: Option<Arc<Heartbeat>>,
+    /// Set once the body reports it found nothing to do, shared by the clones handed to it.
+    pub(crate) no_work: Arc<AtomicBool>,
+    /// The payload handed to the manual run.
+    pub(crate) payload: Option<Arc<[u8]>>,
+    /// The storage of the job state of the task.
+    pub(crate) state_store: Option<Arc<dyn StateStore>>,
+    /// The registry of the processes spawned by the instances.
+    pub(crate) child_processes: Option<ChildProcesses>,
+    /// Resolves the secrets of the task.
+    pub(crate) secrets_provider: Option<Arc<dyn SecretsProvider>>,
+    /// OpenTelemetry context holding the span of the running instance.
+    #[cfg(feature = "otel")]
+    pub(crate) otel_context: OtelContext,
+    /// Fault injected into the running instance.
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos_fault: Option<ChaosFault>,
+    /// The result of the running instance.
+    #[cfg(feature = "json-result")]
+    pub(crate) result: Option<serde_json::Value>,
+    /// The pid of the process launched by the running instance.
+    pub(crate) pid: Option<u32>,
+    /// The pid of the process the running instance is waiting for, shared with its `Instance`.
+    pub(crate) running_pid: RunningPid,
+}
+
+/// Schedule timestamps (seconds) around a fire of the task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ScheduleMetadata {
+    pub(crate) scheduled_time: u64,
+    pub(crate) started_at: u64,
+    pub(crate) previous_scheduled_time: Option<u64>,
+    pub(crate) next_scheduled_time: Option<u64>,
 }
 
 impl TaskContext {
@@ -509,15 +2010,300 @@ impl TaskContext {
         self
     }
 
+    #[inline(always)]
+    pub(crate) fn cancellation_token(
+        &mut self,
+        cancellation_token: CancellationToken,
+    ) -> &mut Self {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn schedule_metadata(&mut self, schedule_metadata: ScheduleMetadata) -> &mut Self {
+        self.schedule_metadata = schedule_metadata;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn task_logger(&mut self, logger: TaskLogger) -> &mut Self {
+        self.logger = logger;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn dispatch_at(&mut self, dispatch_at: Option<Instant>) -> &mut Self {
+        self.dispatch_at = dispatch_at;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_extension(
+        &mut self,
+        extension: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> &mut Self {
+        self.extension = extension;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_state_store(&mut self, state_store: Arc<dyn StateStore>) -> &mut Self {
+        self.state_store = Some(state_store);
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_child_processes(&mut self, child_processes: ChildProcesses) -> &mut Self {
+        self.child_processes = Some(child_processes);
+        self
+    }
+
+    // Keep the processes of the instance in the registry, until the returned guard is dropped.
+    pub(crate) fn track_child_processes(&self, pids: Vec<u32>) -> Option<TrackedChildProcesses> {
+        self.child_processes
+            .as_ref()
+            .map(|child_processes| child_processes.track(self.task_id, pids))
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_secrets_provider(
+        &mut self,
+        secrets_provider: Arc<dyn SecretsProvider>,
+    ) -> &mut Self {
+        self.secrets_provider = Some(secrets_provider);
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_resource(
+        &mut self,
+        resource: Option<Arc<dyn Any + Send + Sync>>,
+    ) -> &mut Self {
+        self.resource = resource;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_heartbeat(&mut self, heartbeat: Arc<Heartbeat>) -> &mut Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    #[cfg(feature = "otel")]
+    #[inline(always)]
+    pub(crate) fn set_otel_context(&mut self, otel_context: OtelContext) -> &mut Self {
+        self.otel_context = otel_context;
+        self
+    }
+
+    #[cfg(feature = "chaos")]
+    #[inline(always)]
+    pub(crate) fn set_chaos_fault(&mut self, chaos_fault: Option<ChaosFault>) -> &mut Self {
+        self.chaos_fault = chaos_fault;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_payload(&mut self, payload: Option<Arc<[u8]>>) -> &mut Self {
+        self.payload = payload;
+        self
+    }
+
+    #[cfg(feature = "json-result")]
+    #[inline(always)]
+    pub(crate) fn set_result(&mut self, result: Option<serde_json::Value>) -> &mut Self {
+        self.result = result;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_pid(&mut self, pid: Option<u32>) -> &mut Self {
+        self.pid = pid;
+        self
+    }
+
+    /// Get the OpenTelemetry context holding the span of the running instance,
+    /// spans of the routine can use it as parent.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `otel` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "otel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+    #[inline(always)]
+    pub fn otel_context(&self) -> &OtelContext {
+        &self.otel_context
+    }
+
+    #[inline(always)]
+    /// Get the extension handed to the running instance by `SpawnDecision::SpawnWith`,
+    /// `None` if there's none or it isn't a `T`.
+    pub fn extension<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.extension.as_ref()?.downcast_ref()
+    }
+
+    #[inline(always)]
+    /// Get the resource built by the `init` of the task (see `TaskBuilder::set_init`),
+    /// `None` until it's built or if it isn't a `R`.
+    pub fn resource<R: Any + Send + Sync>(&self) -> Option<&R> {
+        self.resource.as_ref()?.downcast_ref()
+    }
+
+    #[inline(always)]
+    /// Beat the heartbeat of the running instance (see `TaskBuilder::set_heartbeat`),
+    /// a long-running body calls it periodically to tell it isn't hung.
+    pub fn heartbeat(&self) {
+        if let Some(heartbeat) = self.heartbeat.as_ref() {
+            heartbeat.beat();
+        }
+    }
+
+    #[inline(always)]
+    /// Report that the running instance found nothing to do,
+    /// it ends as `instance::COMPLETED_NOOP` rather than `instance::COMPLETED`.
+    ///
+    /// Such runs are counted apart in `DelayTimer::task_stats`, and are kept out of
+    /// the durations of the task if it was built with `TaskBuilder::set_exclude_noop_durations`.
+    pub fn complete_no_work(&self) {
+        self.no_work.store(true, Ordering::Release);
+    }
+
+    #[inline(always)]
+    /// Get the payload handed to this run by `DelayTimer::run_task_now_with_payload`,
+    /// `None` for the fires of the schedule.
+    pub fn payload(&self) -> Option<&[u8]> {
+        self.payload.as_deref()
+    }
+
+    /// Get the payload handed to this run, parsed as JSON.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `json-result` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "json-result")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-result")))]
+    pub fn payload_json(&self) -> AnyResult<Option<serde_json::Value>> {
+        self.payload
+            .as_deref()
+            .map(serde_json::from_slice)
+            .transpose()
+            .map_err(|e| anyhow!("The payload isn't JSON: {}", e))
+    }
+
+    /// Get the job state `key` of the task, kept from a previous run by `state_set`,
+    /// `None` if it was never set.
+    pub fn state_get(&self, key: &str) -> AnyResult<Option<String>> {
+        self.state_store()?.get(self.task_id, key)
+    }
+
+    /// Set the job state `key` of the task, e.g. the cursor or watermark
+    /// an incremental job resumes from on its next run.
+    pub fn state_set(&self, key: &str, value: impl Into<String>) -> AnyResult<()> {
+        self.state_store()?.set(self.task_id, key, value.into())
+    }
+
+    /// Resolve `secret` by the `SecretsProvider` of the timer,
+    /// see `DelayTimerBuilder::set_secrets_provider`.
+    pub async fn resolve_secret(&self, secret: &SecretString) -> AnyResult<SecretValue> {
+        let secrets_provider = self
+            .secrets_provider
+            .as_ref()
+            .ok_or_else(|| anyhow!("The context isn't bound to a timer."))?;
+        secrets_provider.resolve(secret.reference()).await
+    }
+
+    fn state_store(&self) -> AnyResult<&Arc<dyn StateStore>> {
+        self.state_store
+            .as_ref()
+            .ok_or_else(|| anyhow!("The context isn't bound to a timer."))
+    }
+
+    #[inline(always)]
+    /// Get the logger of the running instance.
+    ///
+    /// Its records carry the task-id and record-id, and are captured
+    /// if the task was built with `TaskBuilder::set_capture_logs`.
+    pub fn logger(&self) -> &TaskLogger {
+        &self.logger
+    }
+
+    #[inline(always)]
+    /// Get the time (timestamp-seconds) this fire was scheduled for.
+    ///
+    /// For a run triggered by `advance_task`, it's the time the run was originally scheduled for.
+    pub fn scheduled_time(&self) -> u64 {
+        self.schedule_metadata.scheduled_time
+    }
+
+    #[inline(always)]
+    /// Get the time (timestamp-seconds) this run started at,
+    /// later than `scheduled_time` if the fire was queued or advanced.
+    pub fn started_at(&self) -> u64 {
+        self.schedule_metadata.started_at
+    }
+
+    #[inline(always)]
+    /// Get the time (timestamp-seconds) the previous fire was scheduled for,
+    /// `None` for the first fire of the task.
+    pub fn previous_scheduled_time(&self) -> Option<u64> {
+        self.schedule_metadata.previous_scheduled_time
+    }
+
+    #[inline(always)]
+    /// Get the time (timestamp-seconds) the next fire is scheduled for,
+    /// `None` if this is the last fire of the task.
+    pub fn next_scheduled_time(&self) -> Option<u64> {
+        self.schedule_metadata.next_scheduled_time
+    }
+
+    #[inline(always)]
+    /// Whether the running instance has been asked to stop (e.g. by `TaskInstance::cancel_graceful`).
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token.observe()
+    }
+
+    /// Async-await until the running instance is asked to stop,
+    /// the routine can select on it to wind down before being hard-aborted.
+    pub async fn cancelled(&self) {
+        self.cancellation_token.cancelled().await
+    }
+
+    #[inline(always)]
+    /// Get a handle of the cancellation token, which can be moved into inner futures or threads.
+    pub fn get_cancellation_token(&self) -> CancellationToken {
+        self.cancellation_token.clone()
+    }
+
     /// Send a task-Finish signal to EventHandle.
     pub async fn finish_task(self, finish_output: Option<FinishOutput>) {
+        #[cfg(feature = "chaos")]
+        match self.chaos_fault {
+            Some(ChaosFault::Delay(delay)) => {
+                smol::Timer::after(delay).await;
+            }
+            Some(ChaosFault::Panic) => panic!(
+                "chaos: injected panic, task-id: {}, record-id: {}",
+                self.task_id, self.record_id
+            ),
+            _ => {}
+        }
+
         if let Some(timer_event_sender) = self.timer_event_sender {
             timer_event_sender
                 .send(TimerEvent::FinishTask(FinishTaskBody {
                     task_id: self.task_id,
                     record_id: self.record_id,
+                    scheduled_for: self.schedule_metadata.scheduled_time,
+                    started_at: self.schedule_metadata.started_at,
                     finish_time: timestamp(),
                     finish_output,
+                    captured_logs: self.logger.take_captured(),
+                    no_work: self.no_work.load(Ordering::Acquire),
+                    #[cfg(feature = "json-result")]
+                    result: self.result,
+                    pid: self.pid,
                 }))
                 .await
                 .unwrap_or_else(|e| error!("{}", e));
@@ -525,8 +2311,52 @@ impl TaskContext {
     }
 }
 
+// The sleep of a precise wait ends that long before the instant, the rest of it is spun.
+const DISPATCH_SPIN_MARGIN: Duration = Duration::from_millis(2);
+// How many durations of finished instances are kept to adapt the timeout,
+// and how many of them it takes.
+const DURATION_HISTORY: usize = 100;
+const MIN_DURATION_SAMPLES: usize = 5;
+
+// Wait for the instant an instance dispatched ahead of its fire starts at.
+async fn wait_for_dispatch(dispatch_at: Option<Instant>) {
+    if let Some(dispatch_at) = dispatch_at {
+        if let Some(sleep_until) = dispatch_at.checked_sub(DISPATCH_SPIN_MARGIN) {
+            smol::Timer::at(sleep_until).await;
+        }
+        spin_until(dispatch_at);
+    }
+}
+
+// Same as `wait_for_dispatch`, for the routines running on a blocking thread.
+fn wait_for_dispatch_blocking(dispatch_at: Option<Instant>) {
+    if let Some(dispatch_at) = dispatch_at {
+        if let Some(sleep) = dispatch_at
+            .checked_sub(DISPATCH_SPIN_MARGIN)
+            .and_then(|sleep_until| sleep_until.checked_duration_since(Instant::now()))
+        {
+            std::thread::sleep(sleep);
+        }
+        spin_until(dispatch_at);
+    }
+}
+
+fn spin_until(instant: Instant) {
+    while Instant::now() < instant {
+        std::hint::spin_loop();
+    }
+}
+
 //TODO:Future tasks will support single execution (not multiple executions in the same time frame).
 type SafeBoxFn = Box<dyn Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync>;
+type SafeArcAbortedFn = Arc<dyn Fn(AbortedInstance) + 'static + Send + Sync>;
+type SafeArcReadinessFn =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = bool> + Send>> + 'static + Send + Sync>;
+type InitFuture = Pin<Box<dyn Future<Output = AnyResult<Arc<dyn Any + Send + Sync>>> + Send>>;
+type SafeArcInitFn = Arc<dyn Fn() -> InitFuture + 'static + Send + Sync>;
+type SafeArcTeardownFn =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + 'static + Send + Sync>;
+type SafeArcSizerFn = Arc<dyn Fn() -> usize + 'static + Send + Sync>;
 type SafeBoxRoutine = Box<
     dyn Routine<TokioHandle = TokioJoinHandle<()>, SmolHandle = SmolJoinHandler<()>>
         + 'static
@@ -546,6 +2376,42 @@ impl fmt::Debug for SafeStructBoxRoutine {
         <&Self as Pointer>::fmt(&self, f)
     }
 }
+#[derive(Clone)]
+pub(crate) struct SafeStructAbortedFn(pub(crate) SafeArcAbortedFn);
+impl fmt::Debug for SafeStructAbortedFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+#[derive(Clone)]
+pub(crate) struct SafeStructReadinessFn(pub(crate) SafeArcReadinessFn);
+impl fmt::Debug for SafeStructReadinessFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+#[derive(Clone)]
+pub(crate) struct SafeStructInitFn(pub(crate) SafeArcInitFn);
+impl fmt::Debug for SafeStructInitFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+#[derive(Clone)]
+pub(crate) struct SafeStructTeardownFn(pub(crate) SafeArcTeardownFn);
+impl fmt::Debug for SafeStructTeardownFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+#[derive(Clone)]
+pub(crate) struct SafeStructSizerFn(pub(crate) SafeArcSizerFn);
+impl fmt::Debug for SafeStructSizerFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
 // Internal closures, once created
 // Will not be changed (read-only access), so `Sync` can be implemented manually
 unsafe impl Sync for SafeStructBoxRoutine {}
@@ -555,57 +2421,404 @@ unsafe impl Sync for SafeStructBoxedFn {}
 #[derive(Debug, Clone)]
 struct AsyncFn<F: Fn() -> U + Send + 'static, U: Future + Send + 'static>(F);
 
-// For Sync Task
-#[derive(Debug, Clone)]
-struct SyncFn<F: Fn() + Send + 'static + Clone>(F);
+// For Sync Task
+#[derive(Debug, Clone)]
+struct SyncFn<F: Fn() + Send + 'static + Clone>(F);
+
+// Routine abstractions performed during task execution.
+pub(crate) trait Routine {
+    type TokioHandle;
+    type SmolHandle;
+    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle;
+    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle;
+    // The instance as a future made and polled by a dedicated thread, for `Execution::DedicatedThread`.
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob;
+
+    // Whether the routine is handed its `TaskContext`, and so the cancellation token.
+    fn takes_context(&self) -> bool {
+        false
+    }
+
+    // The type of the routine, to tell routines apart in traces.
+    #[cfg(feature = "otel")]
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+impl<F: Fn() -> U + 'static + Send, U: Future + 'static + Send> Routine for AsyncFn<F, U> {
+    type TokioHandle = TokioJoinHandle<()>;
+    type SmolHandle = SmolJoinHandler<()>;
+
+    #[inline(always)]
+    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
+        let user_future = self.0();
+
+        async_spawn_by_tokio({
+            let task_id = task_context.task_id;
+            let record_id = task_context.record_id;
+            async {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_tokio: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
+        let user_future = self.0();
+
+        async_spawn_by_smol({
+            let task_id = task_context.task_id;
+            let record_id = task_context.record_id;
+            async {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_smol: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let user_future = self.0();
+
+        Box::new(move || -> LocalFuture {
+            Box::pin(async move {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            })
+        })
+    }
+}
+
+// For Async Task that takes its context.
+#[derive(Debug, Clone)]
+struct AsyncFnWithContext<F: Fn(TaskContext) -> U + Send + 'static, U: Future + Send + 'static>(F);
+
+// For Sync Task that takes its context.
+#[derive(Debug, Clone)]
+struct SyncFnWithContext<F: Fn(TaskContext) + Send + 'static + Clone>(F);
+
+// For Async Task whose futures are not `Send`, they run on the local worker.
+#[derive(Debug, Clone)]
+struct AsyncLocalFn<F: Fn() -> U + Send + Clone + 'static, U: Future + 'static>(F);
+
+impl<F: Fn() -> U + Send + Clone + 'static, U: Future + 'static> AsyncLocalFn<F, U> {
+    async fn run(self, task_context: TaskContext) {
+        wait_for_dispatch(task_context.dispatch_at).await;
+
+        let routine = self.0;
+        if run_on_local_worker(move || routine()).await {
+            task_context.finish_task(None).await;
+        }
+    }
+}
+
+// For Process Task, the shell command and the builder of its processes.
+#[derive(Debug, Clone)]
+struct ProcessFn(Arc<str>, ProcessTaskBuilder);
+
+impl ProcessFn {
+    async fn run<Child: ChildUnify, Command: CommandUnify<Child>>(self, task_context: TaskContext) {
+        wait_for_dispatch(task_context.dispatch_at).await;
+        if self.1.is_detached() {
+            return self.launch::<Child, Command>(task_context).await;
+        }
+
+        let output = match self.1.resolve_secret_envs(&task_context).await {
+            Ok(envs) => {
+                self.1
+                    .output::<Child, Command>(&self.0, &envs, &task_context)
+                    .await
+            }
+            Err(e) => Err(CommandChildError::DisCondition(e.to_string())),
+        };
+        match output {
+            Ok(output) => {
+                #[cfg(feature = "json-result")]
+                let mut task_context = task_context;
+                #[cfg(feature = "json-result")]
+                task_context.set_result(self.1.parse_result(&output));
+
+                task_context
+                    .finish_task(Some(FinishOutput::ProcessOutput(output)))
+                    .await;
+            }
+            Err(e) => {
+                task_context
+                    .finish_task(Some(FinishOutput::ExceptionOutput(e.to_string())))
+                    .await;
+            }
+        }
+    }
+
+    // The detached processes are launched and forgotten, the instance completes once they're spawned.
+    async fn launch<Child: ChildUnify, Command: CommandUnify<Child>>(
+        self,
+        mut task_context: TaskContext,
+    ) {
+        let pid = match self.1.resolve_secret_envs(&task_context).await {
+            Ok(envs) => self.1.launch::<Child, Command>(&self.0, &envs).await,
+            Err(e) => Err(CommandChildError::DisCondition(e.to_string())),
+        };
+        match pid {
+            Ok(pid) => {
+                task_context.set_pid(pid);
+                task_context.finish_task(None).await;
+            }
+            Err(e) => {
+                task_context
+                    .finish_task(Some(FinishOutput::ExceptionOutput(e.to_string())))
+                    .await;
+            }
+        }
+    }
+}
+
+impl<F: Fn(TaskContext) -> U + 'static + Send, U: Future + 'static + Send> Routine
+    for AsyncFnWithContext<F, U>
+{
+    type TokioHandle = TokioJoinHandle<()>;
+    type SmolHandle = SmolJoinHandler<()>;
+
+    fn takes_context(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
+        let user_future = self.0(task_context.clone());
+
+        async_spawn_by_tokio({
+            let task_id = task_context.task_id;
+            let record_id = task_context.record_id;
+            async {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_tokio: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
+        let user_future = self.0(task_context.clone());
+
+        async_spawn_by_smol({
+            let task_id = task_context.task_id;
+            let record_id = task_context.record_id;
+            async {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_smol: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let user_future = self.0(task_context.clone());
+
+        Box::new(move || -> LocalFuture {
+            Box::pin(async move {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                user_future.await;
+                task_context.finish_task(None).await;
+            })
+        })
+    }
+}
+
+impl<F: Fn(TaskContext) + 'static + Send + Clone> Routine for SyncFnWithContext<F> {
+    type TokioHandle = TokioJoinHandle<()>;
+    type SmolHandle = SmolJoinHandler<()>;
+
+    fn takes_context(&self) -> bool {
+        true
+    }
+
+    #[inline(always)]
+    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
+        let routine = self.0.clone();
+        let routine_context = task_context.clone();
+        let fn_handle = unblock_spawn_by_tokio(move || {
+            wait_for_dispatch_blocking(routine_context.dispatch_at);
+            routine(routine_context)
+        });
+
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
+
+        async_spawn_by_tokio({
+            async {
+                if let Err(e) = fn_handle.await {
+                    error!("{}", e);
+                }
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_tokio: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
+        let routine = self.0.clone();
+        let routine_context = task_context.clone();
+        let fn_handle = unblock_spawn_by_smol(move || {
+            wait_for_dispatch_blocking(routine_context.dispatch_at);
+            routine(routine_context)
+        });
+
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
+
+        async_spawn_by_smol({
+            async {
+                fn_handle.await;
+                task_context.finish_task(None).await;
+            }
+            .instrument(info_span!(
+                "async_spawn_by_smol: routine-exec",
+                task_id,
+                record_id
+            ))
+        })
+    }
+
+    // The routine runs on the dedicated thread itself, rather than on a blocking pool.
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let routine = self.0.clone();
+
+        Box::new(move || -> LocalFuture {
+            Box::pin(async move {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                routine(task_context.clone());
+                task_context.finish_task(None).await;
+            })
+        })
+    }
+}
+
+impl<F: Fn() -> U + Send + Clone + 'static, U: Future + 'static> Routine for AsyncLocalFn<F, U> {
+    type TokioHandle = TokioJoinHandle<()>;
+    type SmolHandle = SmolJoinHandler<()>;
+
+    #[inline(always)]
+    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
+
+        async_spawn_by_tokio(self.clone().run(task_context).instrument(info_span!(
+            "async_spawn_by_tokio: routine-exec",
+            task_id,
+            record_id
+        )))
+    }
+
+    #[inline(always)]
+    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
+
+        async_spawn_by_smol(self.clone().run(task_context).instrument(info_span!(
+            "async_spawn_by_smol: routine-exec",
+            task_id,
+            record_id
+        )))
+    }
+
+    // The future is made on the dedicated thread, the local worker isn't needed.
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let routine = self.0.clone();
 
-// Routine abstractions performed during task execution.
-pub(crate) trait Routine {
-    type TokioHandle;
-    type SmolHandle;
-    fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle;
-    fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle;
+        Box::new(move || -> LocalFuture {
+            Box::pin(async move {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                routine().await;
+                task_context.finish_task(None).await;
+            })
+        })
+    }
 }
 
-impl<F: Fn() -> U + 'static + Send, U: Future + 'static + Send> Routine for AsyncFn<F, U> {
+impl Routine for ProcessFn {
     type TokioHandle = TokioJoinHandle<()>;
     type SmolHandle = SmolJoinHandler<()>;
 
     #[inline(always)]
     fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
-        let user_future = self.0();
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
 
-        async_spawn_by_tokio({
-            let task_id = task_context.task_id;
-            let record_id = task_context.record_id;
-            async {
-                user_future.await;
-                task_context.finish_task(None).await;
-            }
-            .instrument(info_span!(
-                "async_spawn_by_tokio: routine-exec",
-                task_id,
-                record_id
-            ))
-        })
+        async_spawn_by_tokio(
+            self.clone()
+                .run::<TokioChild, TokioCommand>(task_context)
+                .instrument(info_span!(
+                    "async_spawn_by_tokio: routine-exec",
+                    task_id,
+                    record_id
+                )),
+        )
     }
 
     #[inline(always)]
     fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
-        let user_future = self.0();
+        let task_id = task_context.task_id;
+        let record_id = task_context.record_id;
 
-        async_spawn_by_smol({
-            let task_id = task_context.task_id;
-            let record_id = task_context.record_id;
-            async {
-                user_future.await;
-                task_context.finish_task(None).await;
+        async_spawn_by_smol(
+            self.clone()
+                .run::<SmolChild, SmolCommand>(task_context)
+                .instrument(info_span!(
+                    "async_spawn_by_smol: routine-exec",
+                    task_id,
+                    record_id
+                )),
+        )
+    }
+
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let process_fn = self.clone();
+
+        Box::new(move || -> LocalFuture {
+            match task_context.runtime_kind {
+                RuntimeKind::Smol => {
+                    Box::pin(process_fn.run::<SmolChild, SmolCommand>(task_context))
+                }
+                RuntimeKind::Tokio => {
+                    Box::pin(process_fn.run::<TokioChild, TokioCommand>(task_context))
+                }
             }
-            .instrument(info_span!(
-                "async_spawn_by_smol: routine-exec",
-                task_id,
-                record_id
-            ))
         })
     }
 }
@@ -626,7 +2839,12 @@ impl<F: Fn() + 'static + Send + Clone> Routine for SyncFn<F> {
 
     #[inline(always)]
     fn spawn_by_tokio(&self, task_context: TaskContext) -> Self::TokioHandle {
-        let fn_handle = unblock_spawn_by_tokio(self.0.clone());
+        let routine = self.0.clone();
+        let dispatch_at = task_context.dispatch_at;
+        let fn_handle = unblock_spawn_by_tokio(move || {
+            wait_for_dispatch_blocking(dispatch_at);
+            routine()
+        });
 
         let task_id = task_context.task_id;
         let record_id = task_context.record_id;
@@ -648,7 +2866,12 @@ impl<F: Fn() + 'static + Send + Clone> Routine for SyncFn<F> {
 
     #[inline(always)]
     fn spawn_by_smol(&self, task_context: TaskContext) -> Self::SmolHandle {
-        let fn_handle = unblock_spawn_by_smol(self.0.clone());
+        let routine = self.0.clone();
+        let dispatch_at = task_context.dispatch_at;
+        let fn_handle = unblock_spawn_by_smol(move || {
+            wait_for_dispatch_blocking(dispatch_at);
+            routine()
+        });
 
         let task_id = task_context.task_id;
         let record_id = task_context.record_id;
@@ -665,9 +2888,22 @@ impl<F: Fn() + 'static + Send + Clone> Routine for SyncFn<F> {
             ))
         })
     }
+
+    // The routine runs on the dedicated thread itself, rather than on a blocking pool.
+    #[inline(always)]
+    fn dedicated_instance(&self, task_context: TaskContext) -> LocalJob {
+        let routine = self.0.clone();
+
+        Box::new(move || -> LocalFuture {
+            Box::pin(async move {
+                wait_for_dispatch(task_context.dispatch_at).await;
+                routine();
+                task_context.finish_task(None).await;
+            })
+        })
+    }
 }
 
-#[derive(Debug)]
 /// Periodic Task Structures.
 pub struct Task {
     /// Unique task-id.
@@ -678,6 +2914,14 @@ pub struct Task {
     frequency: FrequencyInner,
     /// Maximum execution time (optional).
     maximum_running_time: Option<u64>,
+    /// Multiplier of the p95 duration the timeout adapts to (optional).
+    pub(crate) maximum_running_time_auto: Option<f64>,
+    /// Floor and ceiling of the adapted timeout (optional).
+    maximum_running_time_bounds: Option<(u64, u64)>,
+    /// How long an instance runs before it's reported as running long (optional).
+    runtime_warning_threshold: Option<RuntimeWarningThreshold>,
+    /// Whether the runs that found nothing to do are kept out of the durations.
+    pub(crate) exclude_noop_durations: bool,
     /// Loop the line and check how many more clock cycles it will take to execute it.
     cylinder_line: u64,
     /// Validity.
@@ -685,6 +2929,130 @@ pub struct Task {
     valid: bool,
     /// Maximum parallel runnable num (optional).
     pub(crate) maximum_parallel_runnable_num: Option<u64>,
+    /// How long a fire held back by `maximum_parallel_runnable_num` stays queued (optional).
+    pub(crate) max_queue_delay: Option<Duration>,
+    /// Whether a fire over `maximum_parallel_runnable_num` replaces the oldest running instances.
+    pub(crate) replace_on_overlap: bool,
+    /// The time the pending fire was first held back.
+    queued_since: Option<u64>,
+    /// Cleanup closure run after a hard abort (optional).
+    pub(crate) on_aborted: Option<SafeStructAbortedFn>,
+    /// Execution budget per hour (optional).
+    pub(crate) budget: Option<Budget>,
+    /// The time of the pending fire.
+    scheduled_time: Option<u64>,
+    /// The time of the fire before the pending one.
+    previous_scheduled_time: Option<u64>,
+    /// The time after the pending fire, taken from `frequency` ahead of time.
+    peeked_exec_timestamp: Option<u64>,
+
+    /// Whether the fire right after insertion is still pending.
+    run_on_startup: bool,
+    /// Whether each scheduled time runs at most once, across restarts.
+    pub(crate) at_most_once: bool,
+    /// Whether the pending fire is a free extra run triggered by `advance_task`.
+    extra_run: bool,
+    /// Whether the fires may be shifted within their minute by the timer.
+    splayable: bool,
+    /// Seconds every fire is shifted by.
+    splay_offset: u64,
+    /// Whether every fire is taken ahead of time, and started by a precise timer.
+    high_precision: bool,
+    /// Log target of the records of `TaskContext::logger` (optional).
+    pub(crate) log_target: Option<&'static str>,
+    /// Capture the records of `TaskContext::logger`.
+    pub(crate) capture_logs: bool,
+    /// Serial queue the instances run in (optional).
+    pub(crate) serial_queue: Option<String>,
+    /// Tenant the task belongs to (optional).
+    pub(crate) tenant: Option<String>,
+    /// Check gating the fires, and how (optional).
+    pub(crate) readiness_check: Option<(SafeStructReadinessFn, Readiness)>,
+    /// Builds the resource shared by the instances (optional).
+    pub(crate) init: Option<SafeStructInitFn>,
+    /// Releases the resources of the task once it's removed (optional).
+    pub(crate) teardown: Option<SafeStructTeardownFn>,
+    /// Tells the approximate heap size of the state the task holds (optional).
+    memory_sizer: Option<SafeStructSizerFn>,
+    /// How far ahead of its fires the instances are dispatched (optional).
+    pub(crate) dispatch_compensation: Option<Duration>,
+    /// How long the fires may be deferred, to run along with other fires (optional).
+    pub(crate) flexible_window: Option<Duration>,
+    /// What becomes of the task once its instances keep failing.
+    pub(crate) failure_policy: Failure,
+    /// Breaker shared with the tasks hitting the same dependency (optional).
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Permits shared with the tasks drawing on the same resource pool (optional).
+    pub(crate) semaphore: Option<TaskSemaphore>,
+    /// The most time between the heartbeats of an instance, and what becomes of it past that (optional).
+    pub(crate) heartbeat: Option<(Duration, StallPolicy)>,
+    /// Which of the routine events of the task are reported (optional).
+    pub(crate) event_sampling: Option<EventSampling>,
+    /// Where the instances run.
+    pub(crate) execution: Execution,
+    /// Scheduling of the dedicated threads running the instances (optional).
+    pub(crate) thread_options: Option<ThreadOptions>,
+    /// The executor pool the instances run on (optional).
+    pub(crate) pool: Option<String>,
+    /// The pidfile of the process task, a fire is skipped while its process is alive (optional).
+    pub(crate) single_instance_guard: Option<PathBuf>,
+    /// Remote parent of the spans of the running instances (optional).
+    #[cfg(feature = "otel")]
+    pub(crate) otel_parent_context: Option<OtelContext>,
+    /// Remote parent of the span of the pending manual run (optional).
+    #[cfg(feature = "otel")]
+    advance_parent_context: Option<OtelContext>,
+    /// Payload of the pending manual run (optional).
+    advance_payload: Option<Arc<[u8]>>,
+    /// Faults injected into the running instances (optional).
+    #[cfg(feature = "chaos")]
+    pub(crate) chaos: Option<Chaos>,
+    /// The configuration the task was built from.
+    template: TaskTemplate,
+}
+
+// An owned copy of the `TaskBuilder` a task was built from, the routine aside.
+#[derive(Debug, Clone)]
+struct TaskTemplate {
+    frequency: FrequencyTemplate,
+    maximum_running_time: Option<u64>,
+    maximum_running_time_auto: Option<f64>,
+    maximum_running_time_bounds: Option<(u64, u64)>,
+    runtime_warning_threshold: Option<RuntimeWarningThreshold>,
+    exclude_noop_durations: bool,
+    maximum_parallel_runnable_num: Option<u64>,
+    max_queue_delay: Option<Duration>,
+    replace_on_overlap: bool,
+    schedule_iterator_time_zone: ScheduleIteratorTimeZone,
+    dst_policy: DstPolicy,
+    align_to: Option<Alignment>,
+    run_on_startup: bool,
+    at_most_once: bool,
+    on_aborted: Option<SafeStructAbortedFn>,
+    budget: Option<Budget>,
+    auto_splay_opt_out: bool,
+    log_target: Option<&'static str>,
+    capture_logs: bool,
+    serial_queue: Option<String>,
+    tenant: Option<String>,
+    readiness_check: Option<(SafeStructReadinessFn, Readiness)>,
+    init: Option<SafeStructInitFn>,
+    teardown: Option<SafeStructTeardownFn>,
+    memory_sizer: Option<SafeStructSizerFn>,
+    dispatch_compensation: Option<Duration>,
+    flexible_window: Option<Duration>,
+    failure_policy: Failure,
+    circuit_breaker: Option<CircuitBreaker>,
+    semaphore: Option<TaskSemaphore>,
+    heartbeat: Option<(Duration, StallPolicy)>,
+    event_sampling: Option<EventSampling>,
+    execution: Execution,
+    thread_options: Option<ThreadOptions>,
+    pool: Option<String>,
+    #[cfg(feature = "otel")]
+    otel_parent_context: Option<OtelContext>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Chaos>,
 }
 
 impl<'a> TaskBuilder<'a> {
@@ -698,22 +3066,14 @@ impl<'a> TaskBuilder<'a> {
     }
 
     /// Set task Frequency by customized CandyCronStr.
-    /// In order to build a high-performance,
-    /// highly reusable `TaskBuilder` that maintains the Copy feature .
     ///
-    /// when supporting building from CandyCronStr ,
-    /// here actively leaks memory for create a str-slice (because str-slice support Copy, String does not)
+    /// The cron-expression is owned by the builder,
+    /// its clones and the tasks built from it share it without copying.
+    /// It can be read back by `Task::cron_expression`.
     ///
-    /// We need to call `free` manually before `TaskBuilder` drop or before we leave the scope.
+    /// The time zone of `CandyCron::DailyAt` and the like isn't applied here,
+    /// `set_frequency_*_by_candy` do.
     ///
-    /// Explain:
-    /// Explicitly implementing both `Drop` and `Copy` trait on a type is currently
-    /// disallowed.
-    ///
-    /// This feature can make some sense in theory, but the current
-    /// implementation is incorrect and can lead to memory unsafety (see
-    /// (issue #20126), so it has been disabled for now.
-
     /// This api will be deprecated in the future, please use `set_frequency_*_by_candy` etc.
     #[deprecated]
     #[inline(always)]
@@ -721,22 +3081,22 @@ impl<'a> TaskBuilder<'a> {
         &mut self,
         frequency: CandyFrequency<T>,
     ) -> &mut Self {
-        self.build_by_candy_str = true;
-
         let frequency = match frequency {
             CandyFrequency::Once(candy_cron_middle_str) => {
-                Frequency::Once(Box::leak(candy_cron_middle_str.into().0.into_boxed_str()))
+                FrequencyCandyStr::Once(candy_cron_middle_str.into().0.into())
             }
             CandyFrequency::Repeated(candy_cron_middle_str) => {
-                Frequency::Repeated(Box::leak(candy_cron_middle_str.into().0.into_boxed_str()))
+                FrequencyCandyStr::Repeated(candy_cron_middle_str.into().0.into())
+            }
+            CandyFrequency::CountDown(exec_count, candy_cron_middle_str) => {
+                FrequencyCandyStr::CountDown(
+                    exec_count as u64,
+                    candy_cron_middle_str.into().0.into(),
+                )
             }
-            CandyFrequency::CountDown(exec_count, candy_cron_middle_str) => Frequency::CountDown(
-                exec_count as u64,
-                Box::leak(candy_cron_middle_str.into().0.into_boxed_str()),
-            ),
         };
 
-        self.frequency = FrequencyUnify::FrequencyCronStr(frequency);
+        self.frequency = FrequencyUnify::FrequencyCandyStr(frequency);
         self
     }
 
@@ -754,6 +3114,49 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
+    /// Time out the instances at `multiplier` times the p95 duration
+    /// of the recent instances of the task that finished (optional),
+    /// so that the timeout tracks how long the task really takes.
+    ///
+    /// It's kept within the bounds set by `set_maximum_running_time_bounds`.
+    /// Until the task has finished a few instances,
+    /// the timeout is `maximum_running_time`, or else the ceiling.
+    #[inline(always)]
+    pub fn set_maximum_running_time_auto(&mut self, multiplier: f64) -> &mut Self {
+        self.maximum_running_time_auto = Some(multiplier);
+        self
+    }
+
+    /// Set the floor and the ceiling (seconds) of the timeout
+    /// adapted by `set_maximum_running_time_auto` (optional).
+    #[inline(always)]
+    pub fn set_maximum_running_time_bounds(&mut self, floor: u64, ceiling: u64) -> &mut Self {
+        self.maximum_running_time_bounds = Some((floor, ceiling));
+        self
+    }
+
+    /// Report the instances running longer than `threshold` by a `RunningLong` event (optional),
+    /// as an early warning that they'll likely time out, they keep running.
+    ///
+    /// It's a `Duration`, or a fraction of the maximum running time of the instance, e.g. `0.8`.
+    #[inline(always)]
+    pub fn set_runtime_warning_threshold(
+        &mut self,
+        threshold: impl Into<RuntimeWarningThreshold>,
+    ) -> &mut Self {
+        self.runtime_warning_threshold = Some(threshold.into());
+        self
+    }
+
+    /// Keep the runs that reported they found nothing to do (see `TaskContext::complete_no_work`)
+    /// out of the durations the adaptive timeout is computed from (optional),
+    /// so that a task mostly polling an empty source doesn't get a too short timeout.
+    #[inline(always)]
+    pub fn set_exclude_noop_durations(&mut self, exclude_noop_durations: bool) -> &mut Self {
+        self.exclude_noop_durations = exclude_noop_durations;
+        self
+    }
+
     /// Set a task with the maximum number of parallel runs (optional).
     #[inline(always)]
     pub fn set_maximum_parallel_runnable_num(
@@ -764,6 +3167,335 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
+    /// Queue the fires held back by `maximum_parallel_runnable_num` for at most `max_queue_delay` (optional).
+    ///
+    /// A queued fire starts as soon as a running instance finishes,
+    /// once it has waited longer than `max_queue_delay` it is dropped with a `StaleFireDropped` event.
+    /// Without it, a held back fire is skipped right away.
+    #[inline(always)]
+    pub fn set_max_queue_delay(&mut self, max_queue_delay: Duration) -> &mut Self {
+        self.max_queue_delay = Some(max_queue_delay);
+        self
+    }
+
+    /// Have a fire over `maximum_parallel_runnable_num` cancel the oldest running instances
+    /// rather than being skipped or queued, for the jobs where only the latest run matters.
+    ///
+    /// The replaced instances end with `TerminationReason::Replaced`.
+    #[inline(always)]
+    pub fn set_replace_on_overlap(&mut self, replace_on_overlap: bool) -> &mut Self {
+        self.replace_on_overlap = replace_on_overlap;
+        self
+    }
+
+    /// Set an execution budget of the task (optional).
+    ///
+    /// The scheduler sums the durations of finished instances,
+    /// once the budget of the current hour is exhausted the fires are skipped
+    /// and a `BudgetExceeded` event is emitted instead.
+    #[inline(always)]
+    pub fn set_budget(&mut self, budget: Budget) -> &mut Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Fire the task once right after it is inserted, then follow the schedule.
+    ///
+    /// The extra fire respects `maximum_parallel_runnable_num`,
+    /// and it doesn't count against the countdown of the frequency.
+    #[inline(always)]
+    pub fn set_run_on_startup(&mut self, run_on_startup: bool) -> &mut Self {
+        self.run_on_startup = run_on_startup;
+        self
+    }
+
+    /// Run each scheduled time of the task at most once, even across restarts,
+    /// so that a crash right after the 03:00 fire doesn't have it run again once restarted.
+    ///
+    /// The scheduled time of each fire is kept in the `StateStore` of the timer before it runs,
+    /// the fires up to it are skipped when the task is added again.
+    /// It only holds across restarts with a persistent store, see `DelayTimerBuilder::set_state_store`.
+    #[inline(always)]
+    pub fn set_at_most_once(&mut self, at_most_once: bool) -> &mut Self {
+        self.at_most_once = at_most_once;
+        self
+    }
+
+    /// Keep the fires of the task at the second of its cron-expression,
+    /// even if `DelayTimerBuilder::auto_splay_seconds` is on.
+    #[inline(always)]
+    pub fn set_auto_splay_opt_out(&mut self, auto_splay_opt_out: bool) -> &mut Self {
+        self.auto_splay_opt_out = auto_splay_opt_out;
+        self
+    }
+
+    /// Set the log target of the records of `TaskContext::logger` (optional),
+    /// `DEFAULT_TASK_LOG_TARGET` by default.
+    #[inline(always)]
+    pub fn set_log_target(&mut self, log_target: &'static str) -> &mut Self {
+        self.log_target = Some(log_target);
+        self
+    }
+
+    /// Capture the records of `TaskContext::logger`,
+    /// they come back with the finish of each instance.
+    #[inline(always)]
+    pub fn set_capture_logs(&mut self, capture_logs: bool) -> &mut Self {
+        self.capture_logs = capture_logs;
+        self
+    }
+
+    /// Run the instances in the serial queue `serial_queue` (optional).
+    ///
+    /// The tasks sharing a queue never run concurrently with each other,
+    /// their held back fires run one after another in the order they came,
+    /// so that distinct jobs touching the same resource don't overlap.
+    #[inline(always)]
+    pub fn set_serial_queue(&mut self, serial_queue: &'a str) -> &mut Self {
+        self.serial_queue = Some(serial_queue);
+        self
+    }
+
+    /// Label the task with the tenant it belongs to (optional),
+    /// it counts towards the quota of the tenant, see `DelayTimerBuilder::tenant_quota`.
+    #[inline(always)]
+    pub fn set_tenant(&mut self, tenant: &'a str) -> &mut Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Set which of the routine events of the task are reported (optional),
+    /// `DelayTimerBuilder::event_sampling` by default.
+    ///
+    /// A task firing every second can sample its `RunningTask` and `FinishTask` events
+    /// so that the status-report stays usable, its failures are still reported.
+    #[inline(always)]
+    pub fn set_event_sampling(&mut self, event_sampling: EventSampling) -> &mut Self {
+        self.event_sampling = Some(event_sampling);
+        self
+    }
+
+    /// Gate the fires by the async `readiness_check` (optional),
+    /// e.g. "database reachable" or "feature flag enabled".
+    ///
+    /// It's evaluated when a fire is due, the instance runs once it returns `true`,
+    /// otherwise the fire is skipped or deferred by the policy of `readiness`.
+    pub fn set_readiness_check<F, U>(
+        &mut self,
+        readiness_check: F,
+        readiness: Readiness,
+    ) -> &mut Self
+    where
+        F: Fn() -> U + 'static + Send + Sync,
+        U: Future<Output = bool> + 'static + Send,
+    {
+        let readiness_check = SafeStructReadinessFn(Arc::new(move || {
+            Box::pin(readiness_check()) as Pin<Box<dyn Future<Output = bool> + Send>>
+        }));
+        self.readiness_check = Some((readiness_check, readiness));
+        self
+    }
+
+    /// Build the resource shared by the instances of the task by the async `init` (optional),
+    /// e.g. an HTTP client or a database pool, rather than building it in every run.
+    ///
+    /// It runs in the background when the task is added (or activated, for a registered task),
+    /// updated, and when the timer-core loop is restarted.
+    /// The instances get the resource by `TaskContext::resource`, `None` until it's built.
+    /// A failed `init` is logged, the instances keep the resource built before, if any.
+    pub fn set_init<F, U, R>(&mut self, init: F) -> &mut Self
+    where
+        F: Fn() -> U + 'static + Send + Sync,
+        U: Future<Output = AnyResult<R>> + 'static + Send,
+        R: Any + Send + Sync,
+    {
+        let init = SafeStructInitFn(Arc::new(move || {
+            let initializing = init();
+            Box::pin(async move {
+                initializing
+                    .await
+                    .map(|resource| Arc::new(resource) as Arc<dyn Any + Send + Sync>)
+            }) as InitFuture
+        }));
+        self.init = Some(init);
+        self
+    }
+
+    /// Release the resources of the task by the async `teardown` (optional),
+    /// e.g. temporary directories, leases or remote registrations.
+    ///
+    /// It runs in the background once the task is removed (or its delayed removal expires),
+    /// and it's awaited by `DelayTimer::shutdown` for the tasks still there,
+    /// after their running instances are cancelled.
+    pub fn set_teardown<F, U>(&mut self, teardown: F) -> &mut Self
+    where
+        F: Fn() -> U + 'static + Send + Sync,
+        U: Future<Output = ()> + 'static + Send,
+    {
+        let teardown = SafeStructTeardownFn(Arc::new(move || {
+            Box::pin(teardown()) as Pin<Box<dyn Future<Output = ()> + Send>>
+        }));
+        self.teardown = Some(teardown);
+        self
+    }
+
+    /// Tell the approximate heap size of the state the task holds by `memory_sizer` (optional),
+    /// e.g. the data its routine captured.
+    ///
+    /// It's called once the task is added to a timer built with
+    /// `DelayTimerBuilder::enable_memory_attribution`,
+    /// the size of a task without it is guessed from the task and its routine.
+    pub fn set_memory_sizer<F>(&mut self, memory_sizer: F) -> &mut Self
+    where
+        F: Fn() -> usize + 'static + Send + Sync,
+    {
+        self.memory_sizer = Some(SafeStructSizerFn(Arc::new(memory_sizer)));
+        self
+    }
+
+    /// Dispatch the instances `dispatch_compensation` ahead of their fires (optional),
+    /// for the tasks that need to start as close as possible to the scheduled instant.
+    ///
+    /// The wheel takes the fire early enough to absorb the tick, channel and spawn overhead,
+    /// then the instance sleeps and spins until the scheduled second begins.
+    #[inline(always)]
+    pub fn set_dispatch_compensation(&mut self, dispatch_compensation: Duration) -> &mut Self {
+        self.dispatch_compensation = Some(dispatch_compensation);
+        self
+    }
+
+    /// Let the fires be deferred by up to `flexible_window` (optional),
+    /// for the tasks that don't need to run right on time.
+    ///
+    /// The timer-core loop sleeps until the next fire is due,
+    /// a flexible fire is run along with a later fire within its window, in a batch,
+    /// rather than waking the loop up on its own: fewer wakeups on battery or in idle containers.
+    /// A deferred fire still runs by the end of its window, it's judged as of its scheduled time.
+    ///
+    /// A fire is never deferred up to the next one of the task, so that they don't run
+    /// in a burst: the window is cut short of the interval of the task.
+    /// The wheel ticks by the second, the window is taken in whole seconds, rounded down:
+    /// a window under a second defers nothing.
+    #[inline(always)]
+    pub fn set_flexible_window(&mut self, flexible_window: Duration) -> &mut Self {
+        self.flexible_window = Some(flexible_window);
+        self
+    }
+
+    /// Set what becomes of the task once its instances keep failing,
+    /// `Failure::KeepRunning` by default.
+    ///
+    /// When as many instances in a row as the policy allows have failed or timed out,
+    /// the task is paused or removed and a `TaskQuarantined` event is emitted,
+    /// so that a broken job doesn't go on retrying forever.
+    #[inline(always)]
+    pub fn set_failure_policy(&mut self, failure_policy: Failure) -> &mut Self {
+        self.failure_policy = failure_policy;
+        self
+    }
+
+    /// Attach a circuit breaker to the task (optional), it may be shared with other tasks.
+    ///
+    /// The failures and timeouts of the instances count towards tripping the breaker,
+    /// while it's open the fires are skipped with a `SkippedByCircuitBreaker` event.
+    #[inline(always)]
+    pub fn set_circuit_breaker(&mut self, circuit_breaker: CircuitBreaker) -> &mut Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Attach a semaphore to the task (optional), it may be shared with other tasks.
+    ///
+    /// Each instance holds a permit while it runs, a fire finding none free is held back
+    /// like the ones over `maximum_parallel_runnable_num` with a `max_queue_delay`.
+    #[inline(always)]
+    pub fn set_semaphore(&mut self, semaphore: TaskSemaphore) -> &mut Self {
+        self.semaphore = Some(semaphore);
+        self
+    }
+
+    /// Expect a heartbeat from the instances at least every `interval` (optional),
+    /// they beat by `TaskContext::heartbeat`.
+    ///
+    /// An instance missing its heartbeat is reported by an `InstanceStalled` event,
+    /// and timed out as well with `StallPolicy::Timeout`,
+    /// so a hung body is caught long before its `maximum_running_time` is over.
+    #[inline(always)]
+    pub fn set_heartbeat(&mut self, interval: Duration, stall_policy: StallPolicy) -> &mut Self {
+        self.heartbeat = Some((interval, stall_policy));
+        self
+    }
+
+    /// Set where the instances run, `Execution::Runtime` by default.
+    ///
+    /// With `Execution::DedicatedThread` each instance runs on an OS thread of its own,
+    /// isolating blocking or FFI-heavy workloads without wrapping them in `unblock`.
+    #[inline(always)]
+    pub fn set_execution_mode(&mut self, execution: Execution) -> &mut Self {
+        self.execution = execution;
+        self
+    }
+
+    /// Set the priority and CPU affinity of the threads running the instances (optional),
+    /// e.g. to pin a latency-critical job away from noisy neighbors.
+    ///
+    /// They only apply to `Execution::DedicatedThread`, and are only supported on Linux.
+    #[inline(always)]
+    pub fn set_thread_options(&mut self, thread_options: ThreadOptions) -> &mut Self {
+        self.thread_options = Some(thread_options);
+        self
+    }
+
+    /// Run the instances on the executor pool `pool` (optional),
+    /// set up by `DelayTimerBuilder::executor_pool`.
+    ///
+    /// It only applies to `Execution::Runtime`. The instances of a task assigned to
+    /// a pool the timer doesn't have run on the runtime of the timer.
+    #[inline(always)]
+    pub fn set_pool(&mut self, pool: impl Into<String>) -> &mut Self {
+        self.pool = Some(pool.into());
+        self
+    }
+
+    /// Link the spans of the running instances to a remote parent context (optional).
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `otel` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "otel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "otel")))]
+    #[inline(always)]
+    pub fn set_otel_parent_context(&mut self, otel_parent_context: OtelContext) -> &mut Self {
+        self.otel_parent_context = Some(otel_parent_context);
+        self
+    }
+
+    /// Inject faults into a percentage of the running instances (optional),
+    /// to rehearse how the application copes with them.
+    ///
+    /// # Required features
+    ///
+    /// This function requires the `chaos` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "chaos")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chaos")))]
+    #[inline(always)]
+    pub fn set_chaos(&mut self, chaos: Chaos) -> &mut Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Align the repeated or countdown interval (set by seconds, minutes, hours or days)
+    /// to a wall-clock boundary (optional).
+    ///
+    /// It has no effect on cron-expressions, which are already aligned by themselves.
+    #[inline(always)]
+    pub fn set_align_to(&mut self, align_to: Alignment) -> &mut Self {
+        self.align_to = Some(align_to);
+        self
+    }
+
     /// Set time zone for cron-expression iteration time.
     #[inline(always)]
     pub fn set_schedule_iterator_time_zone(
@@ -774,69 +3506,360 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
-    /// Spawn a task with async-routine.
-    pub fn spawn_async_routine<
-        F: Fn() -> U + 'static + Send,
+    /// Set how cron-expressions in the local time zone go through daylight-saving transitions,
+    /// the wall-clock times skipped when the clocks spring forward and repeated when they fall back.
+    ///
+    /// By default a skipped time runs at the next valid time, and a repeated time runs once.
+    #[inline(always)]
+    pub fn set_dst_policy(&mut self, dst_policy: DstPolicy) -> &mut Self {
+        self.dst_policy = dst_policy;
+        self
+    }
+
+    /// Set a cleanup closure which the event handler runs after
+    /// a running instance of the task was hard-aborted (cancelled or timeout).
+    ///
+    /// Async bodies aborted at an arbitrary await point may leave shared state behind,
+    /// e.g. a half-written cache or a connection checked out of a pool,
+    /// the closure is the place to repair it. It runs on the event handler, so keep it short.
+    #[inline(always)]
+    pub fn set_on_aborted<F: Fn(AbortedInstance) + 'static + Send + Sync>(
+        &mut self,
+        on_aborted: F,
+    ) -> &mut Self {
+        self.on_aborted = Some(SafeStructAbortedFn(Arc::new(on_aborted)));
+        self
+    }
+
+    /// Spawn a task with async-routine.
+    pub fn spawn_async_routine<
+        F: Fn() -> U + 'static + Send,
+        U: std::future::Future + 'static + Send,
+    >(
+        &self,
+        routine: F,
+    ) -> Result<Task, TaskError> {
+        self.build_task(SafeStructBoxRoutine(Box::new(AsyncFn(routine))))
+    }
+
+    /// Spawn a task with async-routine whose futures are not `Send`,
+    /// e.g. ones holding `Rc`-based clients.
+    ///
+    /// The futures are made and polled on a thread of their own,
+    /// a current-thread tokio runtime with a `LocalSet` started by the first instance that runs.
+    /// They're still aborted on cancellation and timeout.
+    pub fn spawn_async_routine_local<
+        F: Fn() -> U + 'static + Send + Clone,
+        U: std::future::Future + 'static,
+    >(
+        &self,
+        routine: F,
+    ) -> Result<Task, TaskError> {
+        self.build_task(SafeStructBoxRoutine(Box::new(AsyncLocalFn(routine))))
+    }
+
+    /// Spawn a task with sync-routine.
+    pub fn spawn_routine<F: Fn() + 'static + Send + Clone>(
+        &self,
+        routine: F,
+    ) -> Result<Task, TaskError> {
+        self.build_task(SafeStructBoxRoutine(Box::new(SyncFn(routine))))
+    }
+
+    /// Spawn a task with async-routine that takes its `TaskContext`.
+    ///
+    /// The context gives the routine access to the cooperative cancellation token,
+    /// so it can stop on its own when `TaskInstance::cancel_graceful` is called.
+    pub fn spawn_async_routine_with_context<
+        F: Fn(TaskContext) -> U + 'static + Send,
         U: std::future::Future + 'static + Send,
     >(
-        self,
+        &self,
         routine: F,
     ) -> Result<Task, TaskError> {
-        let frequency_inner = (self.frequency, self.schedule_iterator_time_zone).try_into()?;
-
-        Ok(Task {
-            task_id: self.task_id,
-            routine: SafeStructBoxRoutine(Box::new(AsyncFn(routine))),
-            frequency: frequency_inner,
-            maximum_running_time: self.maximum_running_time,
-            cylinder_line: 0,
-            valid: true,
-            maximum_parallel_runnable_num: self.maximum_parallel_runnable_num,
-        })
+        self.build_task(SafeStructBoxRoutine(Box::new(AsyncFnWithContext(routine))))
     }
 
-    /// Spawn a task with sync-routine.
-    pub fn spawn_routine<F: Fn() + 'static + Send + Clone>(
-        self,
+    /// Spawn a task with sync-routine that takes its `TaskContext`.
+    pub fn spawn_routine_with_context<F: Fn(TaskContext) + 'static + Send + Clone>(
+        &self,
         routine: F,
     ) -> Result<Task, TaskError> {
-        let frequency_inner = (self.frequency, self.schedule_iterator_time_zone).try_into()?;
+        self.build_task(SafeStructBoxRoutine(Box::new(SyncFnWithContext(routine))))
+    }
+
+    /// Spawn a task that runs a shell command.
+    ///
+    /// The output of its last process is the finish output of the instance,
+    /// and its result if `ProcessTaskBuilder::set_json_result` is set.
+    pub fn spawn_process(
+        &self,
+        shell_command: &str,
+        process_task_builder: &ProcessTaskBuilder,
+    ) -> Result<Task, TaskError> {
+        let mut task = self.build_task(SafeStructBoxRoutine(Box::new(ProcessFn(
+            shell_command.into(),
+            process_task_builder.clone(),
+        ))))?;
+        task.single_instance_guard = process_task_builder.get_single_instance_guard().cloned();
+
+        Ok(task)
+    }
+
+    fn build_task(&self, routine: SafeStructBoxRoutine) -> Result<Task, TaskError> {
+        if self
+            .maximum_running_time_auto
+            .is_some_and(|multiplier| !multiplier.is_finite() || multiplier <= 0.0)
+        {
+            return Err(TaskError::InvalidField {
+                field: BuilderField::MaximumRunningTime,
+                message: "the multiplier of the adaptive timeout must be positive".to_string(),
+                suggestion: None,
+            });
+        }
+        if let Some((floor, ceiling)) = self.maximum_running_time_bounds {
+            if floor == 0 || floor > ceiling {
+                return Err(TaskError::InvalidField {
+                    field: BuilderField::MaximumRunningTime,
+                    message: format!(
+                        "the bounds of the adaptive timeout must be 1 <= floor <= ceiling, got {}..{}",
+                        floor, ceiling
+                    ),
+                    suggestion: None,
+                });
+            }
+        }
+        if let Some(RuntimeWarningThreshold::FractionOfTimeout(fraction)) =
+            self.runtime_warning_threshold
+        {
+            if !fraction.is_finite() || fraction <= 0.0 {
+                return Err(TaskError::InvalidField {
+                    field: BuilderField::RuntimeWarningThreshold,
+                    message: "the fraction of the runtime warning threshold must be positive"
+                        .to_string(),
+                    suggestion: None,
+                });
+            }
+        }
+        if self.maximum_running_time == Some(0) {
+            return Err(TaskError::InvalidField {
+                field: BuilderField::MaximumRunningTime,
+                message: "the instances would time out as they start, it must be at least 1 second"
+                    .to_string(),
+                suggestion: None,
+            });
+        }
+
+        let frequency_inner = (
+            self.frequency.to_borrowed(),
+            self.schedule_iterator_time_zone,
+            self.dst_policy,
+            self.align_to,
+        )
+            .try_into()
+            .map_err(|e| self.frequency_error(e))?;
 
         Ok(Task {
             task_id: self.task_id,
-            routine: SafeStructBoxRoutine(Box::new(SyncFn(routine))),
+            routine,
             frequency: frequency_inner,
             maximum_running_time: self.maximum_running_time,
+            maximum_running_time_auto: self.maximum_running_time_auto,
+            maximum_running_time_bounds: self.maximum_running_time_bounds,
+            runtime_warning_threshold: self.runtime_warning_threshold,
+            exclude_noop_durations: self.exclude_noop_durations,
             cylinder_line: 0,
             valid: true,
             maximum_parallel_runnable_num: self.maximum_parallel_runnable_num,
+            max_queue_delay: self.max_queue_delay,
+            replace_on_overlap: self.replace_on_overlap,
+            queued_since: None,
+            on_aborted: self.on_aborted.clone(),
+            budget: self.budget,
+            scheduled_time: None,
+            previous_scheduled_time: None,
+            peeked_exec_timestamp: None,
+            run_on_startup: self.run_on_startup,
+            at_most_once: self.at_most_once,
+            extra_run: false,
+            splayable: !self.auto_splay_opt_out && self.frequency.is_second_field_zero(),
+            splay_offset: 0,
+            high_precision: false,
+            log_target: self.log_target,
+            capture_logs: self.capture_logs,
+            serial_queue: self.serial_queue.map(str::to_string),
+            tenant: self.tenant.map(str::to_string),
+            readiness_check: self.readiness_check.clone(),
+            init: self.init.clone(),
+            teardown: self.teardown.clone(),
+            memory_sizer: self.memory_sizer.clone(),
+            dispatch_compensation: self.dispatch_compensation,
+            flexible_window: self.flexible_window,
+            failure_policy: self.failure_policy,
+            circuit_breaker: self.circuit_breaker.clone(),
+            semaphore: self.semaphore.clone(),
+            heartbeat: self.heartbeat,
+            event_sampling: self.event_sampling,
+            execution: self.execution,
+            thread_options: self.thread_options.clone(),
+            pool: self.pool.clone(),
+            single_instance_guard: None,
+            #[cfg(feature = "otel")]
+            otel_parent_context: self.otel_parent_context.clone(),
+            #[cfg(feature = "otel")]
+            advance_parent_context: None,
+            advance_payload: None,
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos,
+            template: self.to_template(),
         })
     }
 
-    /// If we call set_frequency_by_candy explicitly and generate TaskBuilder,
-    /// We need to call `free` manually before `TaskBuilder` drop or before we leave the scope.
-    ///
-    /// Explain:
-    /// Explicitly implementing both `Drop` and `Copy` trait on a type is currently
-    /// disallowed. This feature can make some sense in theory, but the current
-    /// implementation is incorrect and can lead to memory unsafety (see
-    /// (issue #20126), so it has been disabled for now.
-
-    /// So I can't go through Drop and handle these automatically.
-    pub fn free(&mut self) {
-        if self.build_by_candy_str {
-            let s = match self.frequency {
-                FrequencyUnify::FrequencyCronStr(Frequency::Once(s)) => s,
-                FrequencyUnify::FrequencyCronStr(Frequency::Repeated(s)) => s,
-                FrequencyUnify::FrequencyCronStr(Frequency::CountDown(_, s)) => s,
-                _ => return,
-            };
+    // Tell which cron-expression of the frequency is wrong, and how it may be fixed.
+    fn frequency_error(&self, frequency_analyze_error: FrequencyAnalyzeError) -> TaskError {
+        let field = if matches!(self.frequency, FrequencyUnify::FrequencyCandyStr(_)) {
+            BuilderField::CandyFrequency
+        } else {
+            BuilderField::Frequency
+        };
 
-            unsafe {
-                Box::from_raw(std::mem::transmute::<&str, *mut str>(s));
+        let (message, suggestion) = match frequency_analyze_error {
+            FrequencyAnalyzeError::DisParse(_) => {
+                match self
+                    .frequency
+                    .cron_expressions()
+                    .into_iter()
+                    .find_map(|expression| {
+                        Schedule::from_str(expression.trim_matches(' '))
+                            .err()
+                            .map(|e| (expression, e))
+                    }) {
+                    Some((expression, e)) => (
+                        format!(
+                            "the cron-expression `{}` can't be parsed, {}",
+                            expression, e
+                        ),
+                        suggest_cron_expression(expression),
+                    ),
+                    None => (frequency_analyze_error.to_string(), None),
+                }
+            }
+            FrequencyAnalyzeError::DisInitTime => {
+                ("the interval must be at least 1 second".to_string(), None)
+            }
+            FrequencyAnalyzeError::EmptyExpression => {
+                ("no cron-expression was given".to_string(), None)
             }
+            e => (e.to_string(), None),
+        };
+
+        TaskError::InvalidField {
+            field,
+            message,
+            suggestion,
+        }
+    }
+
+    fn to_template(&self) -> TaskTemplate {
+        TaskTemplate {
+            frequency: (&self.frequency).into(),
+            maximum_running_time: self.maximum_running_time,
+            maximum_running_time_auto: self.maximum_running_time_auto,
+            maximum_running_time_bounds: self.maximum_running_time_bounds,
+            runtime_warning_threshold: self.runtime_warning_threshold,
+            exclude_noop_durations: self.exclude_noop_durations,
+            maximum_parallel_runnable_num: self.maximum_parallel_runnable_num,
+            max_queue_delay: self.max_queue_delay,
+            replace_on_overlap: self.replace_on_overlap,
+            schedule_iterator_time_zone: self.schedule_iterator_time_zone,
+            dst_policy: self.dst_policy,
+            align_to: self.align_to,
+            run_on_startup: self.run_on_startup,
+            at_most_once: self.at_most_once,
+            on_aborted: self.on_aborted.clone(),
+            budget: self.budget,
+            auto_splay_opt_out: self.auto_splay_opt_out,
+            log_target: self.log_target,
+            capture_logs: self.capture_logs,
+            serial_queue: self.serial_queue.map(str::to_string),
+            tenant: self.tenant.map(str::to_string),
+            readiness_check: self.readiness_check.clone(),
+            init: self.init.clone(),
+            teardown: self.teardown.clone(),
+            memory_sizer: self.memory_sizer.clone(),
+            dispatch_compensation: self.dispatch_compensation,
+            flexible_window: self.flexible_window,
+            failure_policy: self.failure_policy,
+            circuit_breaker: self.circuit_breaker.clone(),
+            semaphore: self.semaphore.clone(),
+            heartbeat: self.heartbeat,
+            event_sampling: self.event_sampling,
+            execution: self.execution,
+            thread_options: self.thread_options.clone(),
+            pool: self.pool.clone(),
+            #[cfg(feature = "otel")]
+            otel_parent_context: self.otel_parent_context.clone(),
+            #[cfg(feature = "chaos")]
+            chaos: self.chaos,
+        }
+    }
+
+    /// Copy the configuration of `task` into a new builder,
+    /// it can be tweaked (new task-id, frequency...) before spawning a similar task.
+    ///
+    /// The routine is not copied, it is given again to the `spawn_*` method.
+    /// A countdown starts over from the count the task was built with.
+    pub fn clone_from_task(task: &'a Task) -> TaskBuilder<'a> {
+        let template = &task.template;
+
+        TaskBuilder {
+            frequency: template.frequency.as_frequency_unify(),
+            task_id: task.task_id,
+            maximum_running_time: template.maximum_running_time,
+            maximum_running_time_auto: template.maximum_running_time_auto,
+            maximum_running_time_bounds: template.maximum_running_time_bounds,
+            runtime_warning_threshold: template.runtime_warning_threshold,
+            exclude_noop_durations: template.exclude_noop_durations,
+            maximum_parallel_runnable_num: template.maximum_parallel_runnable_num,
+            max_queue_delay: template.max_queue_delay,
+            replace_on_overlap: template.replace_on_overlap,
+            schedule_iterator_time_zone: template.schedule_iterator_time_zone,
+            dst_policy: template.dst_policy,
+            align_to: template.align_to,
+            run_on_startup: template.run_on_startup,
+            at_most_once: template.at_most_once,
+            on_aborted: template.on_aborted.clone(),
+            budget: template.budget,
+            auto_splay_opt_out: template.auto_splay_opt_out,
+            log_target: template.log_target,
+            capture_logs: template.capture_logs,
+            serial_queue: template.serial_queue.as_deref(),
+            tenant: template.tenant.as_deref(),
+            readiness_check: template.readiness_check.clone(),
+            init: template.init.clone(),
+            teardown: template.teardown.clone(),
+            memory_sizer: template.memory_sizer.clone(),
+            dispatch_compensation: template.dispatch_compensation,
+            flexible_window: template.flexible_window,
+            failure_policy: template.failure_policy,
+            circuit_breaker: template.circuit_breaker.clone(),
+            semaphore: template.semaphore.clone(),
+            heartbeat: template.heartbeat,
+            event_sampling: template.event_sampling,
+            execution: template.execution,
+            thread_options: template.thread_options.clone(),
+            pool: template.pool.clone(),
+            #[cfg(feature = "otel")]
+            otel_parent_context: template.otel_parent_context.clone(),
+            #[cfg(feature = "chaos")]
+            chaos: template.chaos,
         }
     }
+
+    /// Nothing to release any more,
+    /// the cron-expression of `set_frequency_by_candy` is owned by the builder and dropped with it.
+    #[deprecated]
+    pub fn free(&mut self) {}
 }
 
 impl<'a> TaskBuilder<'a> {
@@ -866,6 +3889,53 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
+    /// Task execution frequency: execute only once, set by `CandyCron`.
+    ///
+    /// The time zone carried by the variant (`CandyCron::DailyAt` ...) is applied
+    /// as by `set_schedule_iterator_time_zone`.
+    #[inline(always)]
+    pub fn set_frequency_once_by_candy(&mut self, candy_cron: CandyCron) -> &mut Self {
+        self.set_frequency_by_candy_cron(candy_cron, FrequencyCandyStr::Once)
+    }
+
+    /// Task execution frequency: execute repeatedly, set by `CandyCron`.
+    ///
+    /// The time zone carried by the variant (`CandyCron::DailyAt` ...) is applied
+    /// as by `set_schedule_iterator_time_zone`.
+    #[inline(always)]
+    pub fn set_frequency_repeated_by_candy(&mut self, candy_cron: CandyCron) -> &mut Self {
+        self.set_frequency_by_candy_cron(candy_cron, FrequencyCandyStr::Repeated)
+    }
+
+    /// Task execution frequency: countdown execution, set by `CandyCron`.
+    ///
+    /// The time zone carried by the variant (`CandyCron::DailyAt` ...) is applied
+    /// as by `set_schedule_iterator_time_zone`.
+    #[inline(always)]
+    pub fn set_frequency_count_down_by_candy(
+        &mut self,
+        candy_cron: CandyCron,
+        count_down: u64,
+    ) -> &mut Self {
+        self.set_frequency_by_candy_cron(candy_cron, |expression| {
+            FrequencyCandyStr::CountDown(count_down, expression)
+        })
+    }
+
+    fn set_frequency_by_candy_cron(
+        &mut self,
+        candy_cron: CandyCron,
+        frequency: impl FnOnce(Arc<str>) -> FrequencyCandyStr,
+    ) -> &mut Self {
+        if let Some(time_zone) = candy_cron.time_zone() {
+            self.schedule_iterator_time_zone = time_zone;
+        }
+
+        let expression = CandyCronStr::from(candy_cron).0.into();
+        self.frequency = FrequencyUnify::FrequencyCandyStr(frequency(expression));
+        self
+    }
+
     /// Task execution frequency: execute only once, set by seconds num.
     ///
     /// Make sure time is greater than 1 seconds, otherwise undefined behavior will be triggered.
@@ -1019,6 +4089,12 @@ impl<'a> TaskBuilder<'a> {
     }
 }
 impl Task {
+    /// Get a builder with the configuration of the task, see `TaskBuilder::clone_from_task`.
+    #[inline(always)]
+    pub fn to_builder(&self) -> TaskBuilder<'_> {
+        TaskBuilder::clone_from_task(self)
+    }
+
     // swap slot loction ,do this
     // down_count_and_set_vaild,will return new vaild status.
     #[inline(always)]
@@ -1040,69 +4116,562 @@ impl Task {
         self.valid = !self.frequency.is_down_over();
     }
 
-    /// After task initialization or handled
-    /// Redefine `cylinder_line`.
-    #[inline(always)]
-    pub(crate) fn set_cylinder_line(&mut self, cylinder_line: u64) {
-        self.cylinder_line = cylinder_line;
+    /// After task initialization or handled
+    /// Redefine `cylinder_line`.
+    #[inline(always)]
+    pub(crate) fn set_cylinder_line(&mut self, cylinder_line: u64) {
+        self.cylinder_line = cylinder_line;
+    }
+
+    #[cfg(feature = "debug-dump")]
+    #[inline(always)]
+    pub(crate) fn get_cylinder_line(&self) -> u64 {
+        self.cylinder_line
+    }
+
+    #[inline(always)]
+    /// Get the maximum running time of the task.
+    pub fn get_maximum_running_time(&self, start_time: u64) -> Option<u64> {
+        self.maximum_running_time.map(|t| t + start_time)
+    }
+
+    // The maximum running time of an instance starting at `start_time`,
+    // adapted to the p95 duration of the recent instances if the task asks for it.
+    pub(crate) fn get_adaptive_running_time(
+        &self,
+        start_time: u64,
+        p95_duration: Option<u64>,
+    ) -> Option<u64> {
+        let (multiplier, p95_duration) = match (self.maximum_running_time_auto, p95_duration) {
+            (Some(multiplier), Some(p95_duration)) => (multiplier, p95_duration),
+            (Some(_), None) => {
+                let ceiling = self.maximum_running_time_bounds.map(|(_, ceiling)| ceiling);
+                let maximum_running_time = self.maximum_running_time.or(ceiling);
+                return maximum_running_time.map(|t| t + start_time);
+            }
+            (None, _) => return self.get_maximum_running_time(start_time),
+        };
+
+        let (floor, ceiling) = self.maximum_running_time_bounds.unwrap_or((1, u64::MAX));
+        let maximum_running_time = (p95_duration as f64 * multiplier).ceil() as u64;
+        Some(maximum_running_time.clamp(floor, ceiling) + start_time)
+    }
+
+    // How long after it starts at `start_time` an instance is reported as running long,
+    // `end_time` is the time it times out at.
+    pub(crate) fn runtime_warning_after(
+        &self,
+        start_time: u64,
+        end_time: Option<u64>,
+    ) -> Option<Duration> {
+        match self.runtime_warning_threshold? {
+            RuntimeWarningThreshold::After(duration) => Some(duration),
+            RuntimeWarningThreshold::FractionOfTimeout(fraction) => {
+                let maximum_running_time = end_time?.saturating_sub(start_time);
+                Some(Duration::from_secs(maximum_running_time).mul_f64(fraction))
+            }
+        }
+    }
+
+    // single slot foreach do this.
+    // sub_cylinder_line
+    #[inline(always)]
+    pub(crate) fn sub_cylinder_line(&mut self) {
+        self.cylinder_line -= 1;
+    }
+
+    #[inline(always)]
+    pub(crate) fn clear_cylinder_line(&mut self) {
+        self.cylinder_line = 0;
+    }
+
+    #[inline(always)]
+    /// check if task has arrived.
+    pub fn check_arrived(&mut self) -> bool {
+        trace!("check self: {:?}", self);
+
+        if self.cylinder_line == 0 {
+            return self.is_can_running();
+        }
+
+        self.sub_cylinder_line();
+        false
+    }
+
+    /// check if task has already.
+    #[inline(always)]
+    pub fn is_already(&self) -> bool {
+        self.cylinder_line == 0
+    }
+
+    /// check if task has runnable status.
+    #[inline(always)]
+    pub fn is_can_running(&self) -> bool {
+        if self.is_valid() {
+            return self.is_already();
+        }
+        false
+    }
+
+    /// check if task has valid status.
+    #[inline(always)]
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// check if the task is supervised by `Frequency::KeepAlive`.
+    #[inline(always)]
+    pub fn is_keep_alive(&self) -> bool {
+        matches!(self.frequency, FrequencyInner::KeepAlive(_))
+    }
+
+    // Whether a supervised task can be restarted at `timestamp`, once its instance has ended.
+    pub(crate) fn is_restartable(&self, timestamp: u64, last_finish_time: Option<u64>) -> bool {
+        match &self.frequency {
+            FrequencyInner::KeepAlive(keep_alive) => {
+                keep_alive.is_restartable(timestamp, last_finish_time)
+            }
+            _ => true,
+        }
+    }
+
+    // Count an instance started at `timestamp` against the restarts of a supervised task.
+    pub(crate) fn record_start(&mut self, timestamp: u64) {
+        if let FrequencyInner::KeepAlive(keep_alive) = &mut self.frequency {
+            keep_alive.record_start(timestamp);
+        }
+    }
+
+    /// check if the fire at `timestamp` is skipped by the exclusion schedule.
+    #[inline(always)]
+    pub fn is_excluded(&self, timestamp: u64) -> bool {
+        self.frequency
+            .is_excluded(timestamp.saturating_sub(self.splay_offset) as i64)
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_run_on_startup(&self) -> bool {
+        self.run_on_startup
+    }
+
+    // The startup fire happens once, whether it actually runs or is skipped.
+    #[inline(always)]
+    pub(crate) fn take_run_on_startup(&mut self) -> bool {
+        std::mem::take(&mut self.run_on_startup)
+    }
+
+    // The pending fire is put back in front of the schedule, so it's taken again after the extra run.
+    #[inline(always)]
+    pub(crate) fn set_extra_run(&mut self) {
+        self.extra_run = true;
+        self.peeked_exec_timestamp = self.scheduled_time;
+        self.scheduled_time = self.previous_scheduled_time;
+    }
+
+    #[inline(always)]
+    pub(crate) fn take_extra_run(&mut self) -> bool {
+        std::mem::take(&mut self.extra_run)
+    }
+
+    // Hold back the pending fire, what made it due is kept for when it's retried.
+    pub(crate) fn queue_pending_fire(
+        &mut self,
+        timestamp: u64,
+        startup_run: bool,
+        extra_run: bool,
+    ) {
+        self.queued_since.get_or_insert(timestamp);
+        self.run_on_startup = startup_run;
+        self.extra_run = extra_run;
+    }
+
+    // How long the pending fire has been held back, it's no longer queued afterwards.
+    #[inline(always)]
+    pub(crate) fn take_queued_since(&mut self) -> Option<u64> {
+        self.queued_since.take()
+    }
+
+    // Drop the fires due before `timestamp`, they don't count against a countdown.
+    pub(crate) fn skip_missed_fires(&mut self, timestamp: u64) {
+        while matches!(self.scheduled_time, Some(scheduled_time) if scheduled_time < timestamp) {
+            if self.get_next_exec_timestamp().is_none() {
+                return;
+            }
+        }
+    }
+
+    // Same as `skip_missed_fires`, but each dropped fire counts against a countdown,
+    // `false` if the countdown is over.
+    pub(crate) fn count_missed_fires(&mut self, timestamp: u64) -> bool {
+        while matches!(self.scheduled_time, Some(scheduled_time) if scheduled_time < timestamp) {
+            if !self.down_count_and_set_vaild() {
+                return false;
+            }
+            if self.get_next_exec_timestamp().is_none() {
+                break;
+            }
+        }
+        true
+    }
+
+    // Delay the pending fire to `target`, the fires due until then are collapsed into it
+    // and the schedule goes on after it.
+    pub(crate) fn stagger_pending_fire(&mut self, target: u64) {
+        if !matches!(self.scheduled_time, Some(scheduled_time) if scheduled_time < target) {
+            return;
+        }
+
+        let mut previous_fire = self.scheduled_time;
+        self.peeked_exec_timestamp = loop {
+            match self
+                .peeked_exec_timestamp
+                .take()
+                .or_else(|| self.next_alarm_timestamp())
+            {
+                // A schedule that doesn't move forward is left as it is.
+                Some(fire) if fire <= target && previous_fire < Some(fire) => {
+                    previous_fire = Some(fire)
+                }
+                next_fire => break next_fire,
+            }
+        };
+        self.scheduled_time = Some(target);
+    }
+
+    // How many ticks ahead of its fires the task is taken from the wheel.
+    #[inline(always)]
+    pub(crate) fn dispatch_lead(&self) -> u64 {
+        let dispatch_lead = self
+            .dispatch_compensation
+            .map_or(0, |dispatch_compensation| {
+                dispatch_compensation.as_secs()
+                    + u64::from(dispatch_compensation.subsec_nanos() > 0)
+            });
+
+        if self.high_precision {
+            dispatch_lead.max(1)
+        } else {
+            dispatch_lead
+        }
+    }
+
+    // Take every fire of the task a tick ahead of time, its instance waits for the exact second.
+    #[inline(always)]
+    pub(crate) fn apply_high_precision(&mut self) {
+        self.high_precision = true;
+    }
+
+    // The time of the pending fire, if the task is taken from the wheel ahead of it at `timestamp`.
+    pub(crate) fn early_fire_time(&self, timestamp: u64) -> Option<u64> {
+        let dispatch_lead = self.dispatch_lead();
+        self.scheduled_time.filter(|scheduled_time| {
+            *scheduled_time > timestamp && *scheduled_time - timestamp <= dispatch_lead
+        })
+    }
+
+    // The ticks the pending fire may be deferred by, its flexible window in whole seconds,
+    // cut short of the next fire of the task.
+    pub(crate) fn deferrable_ticks(&self) -> u64 {
+        let flexible_window = self.flexible_window.map_or(0, |window| window.as_secs());
+        let scheduled_time = match self.scheduled_time {
+            Some(scheduled_time) if flexible_window > 0 => scheduled_time,
+            _ => return flexible_window,
+        };
+
+        let window_end = scheduled_time + flexible_window;
+        let next_fire = self
+            .fires_between(scheduled_time as i64, window_end as i64)
+            .first()
+            .copied();
+        next_fire.map_or(flexible_window, |next_fire| next_fire - scheduled_time - 1)
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_scheduled_time(&self) -> Option<u64> {
+        self.scheduled_time
+    }
+
+    // The approximate bytes the task holds, as told by its memory sizer,
+    // or else the task itself and the state its routine captured, which is inline in the box.
+    pub(crate) fn approximate_memory(&self) -> usize {
+        if let Some(memory_sizer) = self.memory_sizer.as_ref() {
+            return (memory_sizer.0)();
+        }
+
+        let owned_strings = [
+            self.serial_queue.as_ref(),
+            self.tenant.as_ref(),
+            self.pool.as_ref(),
+        ]
+        .iter()
+        .flatten()
+        .map(|s| s.capacity())
+        .sum::<usize>();
+        std::mem::size_of::<Task>() + std::mem::size_of_val(&*self.routine.0) + owned_strings
+    }
+
+    // Spread the fires of the task within their minute, if its cron second field is `0`.
+    #[inline(always)]
+    pub(crate) fn apply_auto_splay(&mut self) {
+        if self.splayable {
+            self.splay_offset = splay_offset(self.task_id);
+        }
+    }
+
+    #[inline(always)]
+    fn next_alarm_timestamp(&mut self) -> Option<u64> {
+        let splay_offset = self.splay_offset;
+        self.frequency
+            .next_alarm_timestamp()
+            .map(|i| i as u64 + splay_offset)
+    }
+
+    /// get_next_exec_timestamp
+    #[inline(always)]
+    pub fn get_next_exec_timestamp(&mut self) -> Option<u64> {
+        let next_exec_timestamp = self
+            .peeked_exec_timestamp
+            .take()
+            .or_else(|| self.next_alarm_timestamp());
+
+        self.previous_scheduled_time = self.scheduled_time;
+        self.scheduled_time = next_exec_timestamp;
+        next_exec_timestamp
+    }
+
+    /// Get the first fire of the task after `t`, the way the timer takes it.
+    ///
+    /// The splay of the fires, the fires skipped by the exclusion schedule and
+    /// the fires left to a countdown are accounted for. What depends on the state of
+    /// the timer (running instances, budget, readiness) isn't, and a task supervised by
+    /// `Frequency::KeepAlive` has no fire to tell.
+    ///
+    /// The fires are walked from the pending one, so on a dense schedule a far `t` takes longer.
+    /// For a task added to a timer, see `DelayTimer::next_fire_after`.
+    pub fn next_fire_after(&self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut next_fire = None;
+        self.walk_fires(t.timestamp(), |fire| {
+            next_fire = Some(fire);
+            false
+        });
+        next_fire.and_then(|fire| Utc.timestamp_opt(fire, 0).single())
+    }
+
+    // The fires after `after`, up to `until` (timestamp-seconds, inclusive), see `next_fire_after`.
+    pub(crate) fn fires_between(&self, after: i64, until: i64) -> Vec<u64> {
+        let mut fires = Vec::new();
+        self.walk_fires(after, |fire| {
+            if fire > until {
+                return false;
+            }
+            fires.push(fire as u64);
+            true
+        });
+        fires
+    }
+
+    // Walk the fires after `after`, as long as `visit` asks for the next one.
+    fn walk_fires(&self, after: i64, mut visit: impl FnMut(i64) -> bool) {
+        if !self.valid || self.is_keep_alive() {
+            return;
+        }
+
+        let mut frequency = self.frequency.clone();
+        let mut remaining = frequency.residual_time();
+        // A pending extra run is taken right away, and doesn't count against a countdown.
+        let mut pending = self
+            .scheduled_time
+            .filter(|_| !self.extra_run)
+            .into_iter()
+            .chain(self.peeked_exec_timestamp);
+        let mut previous_fire: Option<i64> = None;
+
+        while remaining > 0 {
+            let fire = match pending.next() {
+                Some(fire) => fire as i64,
+                None => {
+                    // The timer asks a custom schedule policy once the previous fire is taken.
+                    let now = previous_fire
+                        .and_then(|fire| Utc.timestamp_opt(fire, 0).single())
+                        .unwrap_or_else(Utc::now);
+                    match frequency.next_alarm_timestamp_at(now) {
+                        Some(fire) => fire + self.splay_offset as i64,
+                        None => return,
+                    }
+                }
+            };
+
+            // A schedule that doesn't move forward can't be walked.
+            if previous_fire.is_some_and(|previous_fire| fire <= previous_fire) {
+                return;
+            }
+            previous_fire = Some(fire);
+
+            // Excluded fires are skipped, they don't count against a countdown.
+            if self.is_excluded(fire as u64) {
+                continue;
+            }
+            if fire > after && !visit(fire) {
+                return;
+            }
+            remaining -= 1;
+        }
+    }
+
+    // The schedule timestamps of the pending fire, the one after it is peeked.
+    pub(crate) fn get_schedule_metadata(&mut self, timestamp: u64) -> ScheduleMetadata {
+        if self.peeked_exec_timestamp.is_none() {
+            self.peeked_exec_timestamp = self.next_alarm_timestamp();
+        }
+
+        ScheduleMetadata {
+            scheduled_time: self.scheduled_time.unwrap_or(timestamp),
+            started_at: timestamp,
+            previous_scheduled_time: self.previous_scheduled_time,
+            // A countdown task with one fire left has no next fire.
+            next_scheduled_time: self
+                .peeked_exec_timestamp
+                .filter(|_| self.frequency.residual_time() > 1),
+        }
+    }
+
+    #[cfg(feature = "otel")]
+    pub(crate) fn set_advance_parent_context(&mut self, parent_context: Option<OtelContext>) {
+        self.advance_parent_context = parent_context;
+    }
+
+    #[cfg(feature = "otel")]
+    pub(crate) fn take_advance_parent_context(&mut self) -> Option<OtelContext> {
+        self.advance_parent_context.take()
     }
 
     #[inline(always)]
-    /// Get the maximum running time of the task.
-    pub fn get_maximum_running_time(&self, start_time: u64) -> Option<u64> {
-        self.maximum_running_time.map(|t| t + start_time)
+    pub(crate) fn set_advance_payload(&mut self, payload: Option<Arc<[u8]>>) {
+        self.advance_payload = payload;
     }
 
-    // single slot foreach do this.
-    // sub_cylinder_line
     #[inline(always)]
-    pub(crate) fn sub_cylinder_line(&mut self) {
-        self.cylinder_line -= 1;
+    pub(crate) fn take_advance_payload(&mut self) -> Option<Arc<[u8]>> {
+        self.advance_payload.take()
     }
 
     #[inline(always)]
-    pub(crate) fn clear_cylinder_line(&mut self) {
-        self.cylinder_line = 0;
+    pub(crate) fn has_advance_payload(&self) -> bool {
+        self.advance_payload.is_some()
     }
 
-    #[inline(always)]
-    /// check if task has arrived.
-    pub fn check_arrived(&mut self) -> bool {
-        trace!("check self: {:?}", self);
+    /// The cron-expression the task was built with, if it has a single one,
+    /// e.g. the original string of `set_frequency_by_candy`.
+    ///
+    /// For `Frequency::Except` it's the `include` cron-expression.
+    pub fn cron_expression(&self) -> Option<&str> {
+        match &self.template.frequency {
+            FrequencyTemplate::Once(s)
+            | FrequencyTemplate::Repeated(s)
+            | FrequencyTemplate::CountDown(_, s)
+            | FrequencyTemplate::Except { include: s, .. } => Some(s),
+            _ => None,
+        }
+    }
 
-        if self.cylinder_line == 0 {
-            return self.is_can_running();
+    /// The frequency the task was built with.
+    pub fn frequency(&self) -> TaskFrequency<'_> {
+        let (seconds, count) = match &self.template.frequency {
+            FrequencyTemplate::Seconds(FrequencySeconds::Once(seconds)) => (*seconds, Some(1)),
+            FrequencyTemplate::Seconds(FrequencySeconds::Repeated(seconds)) => (*seconds, None),
+            FrequencyTemplate::Seconds(FrequencySeconds::CountDown(count, seconds)) => {
+                (*seconds, Some(*count))
+            }
+            FrequencyTemplate::Once(s) => return TaskFrequency::Cron(FrequencyCronStr::Once(s)),
+            FrequencyTemplate::Repeated(s) => {
+                return TaskFrequency::Cron(FrequencyCronStr::Repeated(s))
+            }
+            FrequencyTemplate::CountDown(count, s) => {
+                return TaskFrequency::Cron(FrequencyCronStr::CountDown(*count, s))
+            }
+            FrequencyTemplate::Multi(expressions) => {
+                return TaskFrequency::Cron(FrequencyCronStr::Multi(
+                    expressions.iter().map(|s| &**s).collect(),
+                ))
+            }
+            FrequencyTemplate::Except { include, exclude } => {
+                return TaskFrequency::Cron(FrequencyCronStr::Except { include, exclude })
+            }
+            FrequencyTemplate::Custom(policy) => {
+                return TaskFrequency::Cron(FrequencyCronStr::Custom(policy.clone()))
+            }
+            FrequencyTemplate::KeepAlive {
+                restart_delay,
+                max_restarts_per_hour,
+            } => {
+                return TaskFrequency::Cron(FrequencyCronStr::KeepAlive {
+                    restart_delay: *restart_delay,
+                    max_restarts_per_hour: *max_restarts_per_hour,
+                })
+            }
+        };
+
+        TaskFrequency::Interval {
+            interval: Duration::from_secs(seconds),
+            count,
         }
+    }
 
-        self.sub_cylinder_line();
-        false
+    /// How many more times the task fires, `None` if there's no end to it.
+    ///
+    /// The fire pending in the wheel is counted, the extra runs never are.
+    pub fn remaining_runs(&self) -> Option<u64> {
+        if !self.valid {
+            return Some(0);
+        }
+
+        match self.frequency.residual_time() {
+            u64::MAX => None,
+            remaining_runs => Some(remaining_runs),
+        }
     }
 
-    /// check if task has already.
-    #[inline(always)]
-    pub fn is_already(&self) -> bool {
-        self.cylinder_line == 0
+    // The pending fire, or the one after it once it's taken.
+    fn next_fire(&self) -> Option<DateTime<Utc>> {
+        self.scheduled_time
+            .or(self.peeked_exec_timestamp)
+            .filter(|_| self.valid)
+            .and_then(|fire| Utc.timestamp_opt(fire as i64, 0).single())
     }
+}
 
-    /// check if task has runnable status.
-    #[inline(always)]
-    pub fn is_can_running(&self) -> bool {
-        if self.is_valid() {
-            return self.is_already();
+// e.g. `task 7 - every 5m, next 2021-06-01 12:05:00 UTC, 3 runs left`.
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task {} - {}", self.task_id, self.frequency())?;
+
+        if let Some(next_fire) = self.next_fire() {
+            write!(f, ", next {}", next_fire.format("%Y-%m-%d %H:%M:%S UTC"))?;
         }
-        false
-    }
 
-    /// check if task has valid status.
-    #[inline(always)]
-    pub fn is_valid(&self) -> bool {
-        self.valid
+        match self.remaining_runs() {
+            Some(0) => write!(f, ", done"),
+            Some(1) => write!(f, ", 1 run left"),
+            Some(remaining_runs) => write!(f, ", {} runs left", remaining_runs),
+            None => Ok(()),
+        }
     }
+}
 
-    /// get_next_exec_timestamp
-    #[inline(always)]
-    pub fn get_next_exec_timestamp(&mut self) -> Option<u64> {
-        self.frequency.next_alarm_timestamp().map(|i| i as u64)
+impl fmt::Debug for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task")
+            .field("task_id", &self.task_id)
+            .field("frequency", &self.frequency())
+            .field("next_fire", &self.next_fire())
+            .field("remaining_runs", &self.remaining_runs())
+            .field("valid", &self.valid)
+            .field("maximum_running_time", &self.maximum_running_time)
+            .field(
+                "maximum_parallel_runnable_num",
+                &self.maximum_parallel_runnable_num,
+            )
+            .finish_non_exhaustive()
     }
 }
 
@@ -1317,6 +4886,113 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_candy_cron_expression() -> AnyResult<()> {
+        use super::{CandyFrequency, Task, TaskBuilder};
+        let expression = "0 */5 * * * * *";
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_by_candy(CandyFrequency::Repeated(CandyCronStr(
+            expression.to_string(),
+        )));
+        let cloned_task_builder = task_builder.clone();
+        drop(task_builder);
+
+        let task: Task = cloned_task_builder.spawn_async_routine(|| async {})?;
+        drop(cloned_task_builder);
+        assert_eq!(task.cron_expression(), Some(expression));
+
+        let task: Task = TaskBuilder::clone_from_task(&task).spawn_async_routine(|| async {})?;
+        assert_eq!(task.cron_expression(), Some(expression));
+
+        let task: Task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(5)
+            .spawn_async_routine(|| async {})?;
+        assert_eq!(task.cron_expression(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_candy_cron_time_zone() -> AnyResult<()> {
+        use super::{CandyCron, Task, TaskBuilder};
+        use chrono::{FixedOffset, Timelike};
+
+        // 03:00 at UTC+09:00 is 18:00 UTC.
+        let tz = ScheduleIteratorTimeZone::FixedOffset(FixedOffset::east_opt(9 * 3600).unwrap());
+        let task: Task = TaskBuilder::default()
+            .set_frequency_repeated_by_candy(CandyCron::DailyAt {
+                hour: 3,
+                minute: 0,
+                tz,
+            })
+            .spawn_async_routine(|| async {})?;
+
+        assert_eq!(task.cron_expression(), Some("0 0 3 * * * *"));
+        let next_fire = task.next_fire_after(Utc::now()).unwrap();
+        assert_eq!((next_fire.hour(), next_fire.minute()), (18, 0));
+
+        // The shortcuts leave the time zone alone.
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_schedule_iterator_time_zone(tz)
+            .set_frequency_count_down_by_candy(CandyCron::Daily, 2);
+        assert_eq!(task_builder.schedule_iterator_time_zone, tz);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_summary() -> AnyResult<()> {
+        use super::{format_seconds, Task, TaskBuilder, TaskFrequency};
+
+        assert_eq!(format_seconds(0), "0s");
+        assert_eq!(format_seconds(5400), "1h30m");
+        assert_eq!(format_seconds(ONE_DAY + 5), "1d5s");
+
+        let mut task: Task = TaskBuilder::default()
+            .set_task_id(7)
+            .set_frequency_count_down_by_minutes(5, 3)
+            .spawn_async_routine(|| async {})?;
+
+        assert!(matches!(
+            task.frequency(),
+            TaskFrequency::Interval {
+                interval,
+                count: Some(3)
+            } if interval == Duration::from_secs(300)
+        ));
+        assert_eq!(task.remaining_runs(), Some(3));
+        assert_eq!(task.to_string(), "task 7 - every 5m, 3 runs left");
+
+        let next_fire = task.get_next_exec_timestamp().unwrap();
+        task.down_count_and_set_vaild();
+        let next_fire = Utc.timestamp_opt(next_fire as i64, 0).unwrap();
+        assert_eq!(task.remaining_runs(), Some(2));
+        assert_eq!(
+            task.to_string(),
+            format!(
+                "task 7 - every 5m, next {}, 2 runs left",
+                next_fire.format("%Y-%m-%d %H:%M:%S UTC")
+            )
+        );
+
+        let task: Task = TaskBuilder::default()
+            .set_task_id(8)
+            .set_frequency_repeated_by_cron_str("@hourly")
+            .spawn_async_routine(|| async {})?;
+
+        assert!(matches!(
+            task.frequency(),
+            TaskFrequency::Cron(Frequency::Repeated("@hourly"))
+        ));
+        assert_eq!(task.remaining_runs(), None);
+        assert_eq!(task.to_string(), "task 8 - by `@hourly`");
+        assert!(format!("{:?}", task).starts_with("Task { task_id: 8, frequency: Cron("));
+
+        Ok(())
+    }
+
     #[test]
     fn test_analyze_cron_expression() -> AnyResult<()> {
         use super::{DelayTimerScheduleIteratorOwned, ScheduleIteratorTimeZone};
@@ -1325,6 +5001,7 @@ mod tests {
 
         let mut schedule_iterator_first = DelayTimerScheduleIteratorOwned::analyze_cron_expression(
             ScheduleIteratorTimeZone::Utc,
+            DstPolicy::default(),
             "0/3 * * * * * *",
         )?;
 
@@ -1333,6 +5010,7 @@ mod tests {
         let mut schedule_iterator_second =
             DelayTimerScheduleIteratorOwned::analyze_cron_expression(
                 ScheduleIteratorTimeZone::Utc,
+                DstPolicy::default(),
                 "0/3 * * * * * *",
             )?;
 
@@ -1344,4 +5022,503 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_budget_usage() {
+        use super::{Budget, TaskMark};
+
+        let mut task_mark = TaskMark::default();
+        task_mark.set_budget(Some(Budget {
+            max_total_runtime_per_hour: 10,
+        }));
+
+        let window_start = ONE_HOUR * 100;
+        task_mark.record_budget_usage(window_start, window_start + 6);
+        assert!(!task_mark.is_budget_exhausted(window_start + 6));
+
+        task_mark.record_budget_usage(window_start + 10, window_start + 14);
+        assert!(task_mark.is_budget_exhausted(window_start + 14));
+
+        // A new window starts with a fresh budget.
+        assert!(!task_mark.is_budget_exhausted(window_start + ONE_HOUR));
+        task_mark.record_budget_usage(window_start + ONE_HOUR, window_start + ONE_HOUR + 1);
+        assert!(!task_mark.is_budget_exhausted(window_start + ONE_HOUR + 1));
+    }
+
+    #[test]
+    fn test_serial_queue() {
+        use super::SerialQueue;
+
+        let mut serial_queue = SerialQueue::default();
+        assert!(serial_queue.is_turn_of(1));
+        serial_queue.start(1, 10);
+        assert!(!serial_queue.is_turn_of(2));
+
+        // The fires held back go first come first served.
+        serial_queue.wait(2);
+        serial_queue.wait(3);
+        serial_queue.wait(2);
+        assert_eq!(serial_queue.ahead_of(3), 2);
+
+        // A manual run jumping the queue is behind the running instance only.
+        serial_queue.jump(3);
+        assert_eq!(serial_queue.ahead_of(3), 1);
+        assert_eq!(serial_queue.ahead_of(2), 2);
+        assert_eq!(serial_queue.held(), 1);
+
+        // Another instance finishing doesn't free the queue.
+        serial_queue.finish(11);
+        assert!(serial_queue.is_busy());
+        serial_queue.finish(10);
+        assert_eq!(serial_queue.held(), 0);
+        assert!(serial_queue.is_turn_of(3));
+        assert!(!serial_queue.is_turn_of(2));
+
+        serial_queue.start(3, 12);
+        serial_queue.finish(12);
+        assert!(serial_queue.is_turn_of(2));
+    }
+
+    #[test]
+    fn test_custom_schedule_policy() -> AnyResult<()> {
+        use super::{SchedulePolicy, Utc};
+
+        // Fire twice, one and then two seconds after the previous fire.
+        #[derive(Clone)]
+        struct TwiceBackoff(i64);
+
+        impl SchedulePolicy for TwiceBackoff {
+            fn next_after(&mut self, previous: DateTime<Utc>) -> Option<DateTime<Utc>> {
+                self.0 += 1;
+                (self.0 <= 2).then(|| previous + chrono::Duration::seconds(self.0))
+            }
+        }
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency(Frequency::Custom(Box::new(TwiceBackoff(0))));
+
+        let now = timestamp();
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+
+        let first_fire = task.get_next_exec_timestamp().unwrap();
+        assert!(first_fire >= now + 1);
+        // The second fire is scheduled from the first one, not from the time it's asked at.
+        assert_eq!(task.get_next_exec_timestamp(), Some(first_fire + 2));
+        assert_eq!(task.get_next_exec_timestamp(), None);
+
+        // The builder keeps its own copy of the policy.
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        assert!(task.get_next_exec_timestamp().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_frequency() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+
+        // Every 2 seconds and every 3 seconds, fires on 0, 2, 3, 4, 6 ... of each minute.
+        task_builder.set_frequency(Frequency::Multi(vec!["0/2 * * * * * *", "0/3 * * * * * *"]));
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+
+        let mut last = task.get_next_exec_timestamp().unwrap();
+        for _ in 0..10 {
+            let next = task.get_next_exec_timestamp().unwrap();
+            assert!(next > last);
+            assert!(next % 2 == 0 || next % 3 == 0);
+            last = next;
+        }
+
+        task_builder.set_frequency(Frequency::Multi(vec![]));
+        assert!(task_builder.spawn_async_routine(|| async {}).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_except_frequency() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+
+        // Every second, except the even ones.
+        task_builder
+            .set_frequency(Frequency::Except {
+                include: "* * * * * * *",
+                exclude: "0/2 * * * * * *",
+            })
+            .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Utc);
+        let task: Task = task_builder.spawn_async_routine(|| async {})?;
+
+        let now = timestamp();
+        assert_ne!(task.is_excluded(now), task.is_excluded(now + 1));
+        assert_eq!(task.is_excluded(now), now % 2 == 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alignment() {
+        use super::Alignment;
+
+        let hour_start = ONE_HOUR * 100;
+
+        // Every 15 minutes aligned to the hour.
+        assert_eq!(
+            Alignment::Hour.first_fire(hour_start + 7 * ONE_MINUTE, 15 * ONE_MINUTE),
+            hour_start + 15 * ONE_MINUTE
+        );
+        // A boundary that is now has already passed.
+        assert_eq!(
+            Alignment::Hour.first_fire(hour_start + 15 * ONE_MINUTE, 15 * ONE_MINUTE),
+            hour_start + 30 * ONE_MINUTE
+        );
+        assert_eq!(
+            Alignment::Minute.first_fire(hour_start + 25, 20),
+            hour_start + 40
+        );
+
+        let epoch = Utc.timestamp_opt(hour_start as i64 + 3, 0).unwrap();
+        assert_eq!(
+            Alignment::Epoch(epoch).first_fire(hour_start + 10, 5),
+            hour_start + 13
+        );
+        // Epochs in the future work as well.
+        assert_eq!(
+            Alignment::Epoch(epoch).first_fire(hour_start - 10, 5),
+            hour_start - 7
+        );
+    }
+
+    #[test]
+    fn test_schedule_metadata() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_seconds(5);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+
+        let first = task.get_next_exec_timestamp().unwrap();
+        let schedule_metadata = task.get_schedule_metadata(first);
+        assert_eq!(schedule_metadata.scheduled_time, first);
+        assert_eq!(schedule_metadata.previous_scheduled_time, None);
+        assert_eq!(schedule_metadata.next_scheduled_time, Some(first + 5));
+
+        // The peeked time is not skipped.
+        assert_eq!(task.get_next_exec_timestamp(), Some(first + 5));
+        let schedule_metadata = task.get_schedule_metadata(first + 5);
+        assert_eq!(schedule_metadata.previous_scheduled_time, Some(first));
+        assert_eq!(schedule_metadata.next_scheduled_time, Some(first + 10));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_fire_after() -> AnyResult<()> {
+        use super::splay_offset;
+
+        let date_time = |timestamp: u64| Utc.timestamp_opt(timestamp as i64, 0).unwrap();
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_count_down_by_seconds(5, 3);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        let first = task.get_next_exec_timestamp().unwrap();
+
+        assert_eq!(
+            task.next_fire_after(date_time(first - 1)),
+            Some(date_time(first))
+        );
+        assert_eq!(
+            task.next_fire_after(date_time(first)),
+            Some(date_time(first + 5))
+        );
+        assert_eq!(
+            task.next_fire_after(date_time(first + 9)),
+            Some(date_time(first + 10))
+        );
+        // The countdown is over after the third fire.
+        assert_eq!(task.next_fire_after(date_time(first + 10)), None);
+        // The task isn't advanced.
+        assert_eq!(task.get_next_exec_timestamp(), Some(first + 5));
+
+        // Every second, except the even ones.
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency(Frequency::Except {
+                include: "* * * * * * *",
+                exclude: "0/2 * * * * * *",
+            })
+            .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Utc);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        task.get_next_exec_timestamp();
+        let now = timestamp() + 10;
+        let next_fire = task.next_fire_after(date_time(now)).unwrap().timestamp() as u64;
+        assert_eq!(next_fire % 2, 1);
+        assert!(next_fire - now <= 2);
+
+        // The splay of the fires is included.
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency_repeated_by_cron_str("0 * * * * * *")
+            .set_task_id(7);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        task.apply_auto_splay();
+        task.get_next_exec_timestamp();
+        let next_fire = task
+            .next_fire_after(date_time(timestamp() + ONE_HOUR))
+            .unwrap()
+            .timestamp() as u64;
+        assert_eq!(next_fire % 60, splay_offset(7));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stagger_pending_fire() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_seconds(5);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        let first = task.get_next_exec_timestamp().unwrap();
+
+        // The fires until the target are collapsed into it.
+        task.stagger_pending_fire(first + 12);
+        assert_eq!(task.get_scheduled_time(), Some(first + 12));
+        assert_eq!(task.get_next_exec_timestamp(), Some(first + 15));
+
+        // A pending fire after the target is kept.
+        task.stagger_pending_fire(first);
+        assert_eq!(task.get_scheduled_time(), Some(first + 15));
+
+        task_builder.set_frequency_once_by_seconds(5);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        let first = task.get_next_exec_timestamp().unwrap();
+        task.stagger_pending_fire(first + 30);
+        assert_eq!(task.get_scheduled_time(), Some(first + 30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_early_fire_time() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_seconds(5);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        let first = task.get_next_exec_timestamp().unwrap();
+        assert_eq!(task.dispatch_lead(), 0);
+        assert_eq!(task.early_fire_time(first - 1), None);
+
+        task_builder.set_dispatch_compensation(Duration::from_millis(1500));
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        let first = task.get_next_exec_timestamp().unwrap();
+        assert_eq!(task.dispatch_lead(), 2);
+        assert_eq!(task.early_fire_time(first - 3), None);
+        assert_eq!(task.early_fire_time(first - 2), Some(first));
+        assert_eq!(task.early_fire_time(first), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_field() {
+        use super::suggest_cron_expression;
+
+        assert_eq!(suggest_cron_expression("@daly").as_deref(), Some("@daily"));
+        assert_eq!(
+            suggest_cron_expression("0 0 9 * * MOM").as_deref(),
+            Some("0 0 9 * * MON")
+        );
+        assert_eq!(
+            suggest_cron_expression("0 0 9 * Jan,Fbe Mon-Fir").as_deref(),
+            Some("0 0 9 * Jan,FEB Mon-FRI")
+        );
+        assert_eq!(
+            suggest_cron_expression("*/5 * * * *").as_deref(),
+            Some("0 */5 * * * *")
+        );
+        assert_eq!(suggest_cron_expression("every day"), None);
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_cron_str("0 0 9 * * MOM");
+        match task_builder.spawn_async_routine(|| async {}) {
+            Err(TaskError::InvalidField {
+                field: BuilderField::Frequency,
+                suggestion,
+                ..
+            }) => assert_eq!(suggestion.as_deref(), Some("0 0 9 * * MON")),
+            _ => panic!("the typo isn't reported"),
+        }
+
+        task_builder
+            .set_frequency_by_candy(CandyFrequency::Repeated(CandyCronStr("@hourl".to_string())));
+        let error = task_builder.spawn_async_routine(|| async {}).err().unwrap();
+        assert!(matches!(
+            error,
+            TaskError::InvalidField {
+                field: BuilderField::CandyFrequency,
+                ..
+            }
+        ));
+        assert!(error.to_string().ends_with("(did you mean `@hourly`?)"));
+        task_builder.free();
+
+        task_builder
+            .set_frequency_repeated_by_seconds(1)
+            .set_maximum_running_time(0);
+        assert!(matches!(
+            task_builder.spawn_async_routine(|| async {}),
+            Err(TaskError::InvalidField {
+                field: BuilderField::MaximumRunningTime,
+                ..
+            })
+        ));
+
+        task_builder
+            .set_maximum_running_time(10)
+            .set_runtime_warning_threshold(RuntimeWarningThreshold::FractionOfTimeout(-0.5));
+        assert!(matches!(
+            task_builder.spawn_async_routine(|| async {}),
+            Err(TaskError::InvalidField {
+                field: BuilderField::RuntimeWarningThreshold,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_auto_splay() -> AnyResult<()> {
+        use super::splay_offset;
+
+        // Stable, and within the minute.
+        assert_eq!(splay_offset(7), splay_offset(7));
+        assert!((0..1000).all(|task_id| splay_offset(task_id) < 60));
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency_repeated_by_cron_str("0 * * * * * *")
+            .set_task_id(7);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        task.apply_auto_splay();
+        assert_eq!(
+            task.get_next_exec_timestamp().unwrap() % 60,
+            splay_offset(7)
+        );
+
+        task_builder.set_auto_splay_opt_out(true);
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        task.apply_auto_splay();
+        assert_eq!(task.get_next_exec_timestamp().unwrap() % 60, 0);
+
+        // Only the tasks at second `0` are spread.
+        let mut task_builder = TaskBuilder::default();
+        task_builder.set_frequency_repeated_by_cron_str("30 * * * * * *");
+        let mut task: Task = task_builder.spawn_async_routine(|| async {})?;
+        task.apply_auto_splay();
+        assert_eq!(task.get_next_exec_timestamp().unwrap() % 60, 30);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failure_policy() {
+        use super::TaskMark;
+
+        let mut task_mark = TaskMark::default();
+        assert_eq!(task_mark.record_outcome(true), None);
+
+        task_mark.set_failure_policy(Failure::RemoveAfter(2));
+        assert_eq!(task_mark.record_outcome(false), None);
+        assert_eq!(task_mark.record_outcome(true), None);
+        assert_eq!(task_mark.record_outcome(false), None);
+        assert_eq!(task_mark.record_outcome(true), None);
+        assert_eq!(
+            task_mark.record_outcome(true),
+            Some(Failure::RemoveAfter(2))
+        );
+
+        // The streak starts over once the policy is tripped.
+        assert_eq!(task_mark.record_outcome(true), None);
+    }
+
+    #[cfg(feature = "status-report")]
+    #[test]
+    fn test_event_sampling() {
+        use super::TaskMark;
+
+        let mut task_mark = TaskMark::default();
+        let sampled: Vec<i64> = (1..=7)
+            .filter(|&record_id| {
+                task_mark.sample_event(record_id, false, EventSampling::EveryNth(3))
+            })
+            .collect();
+        assert_eq!(sampled, vec![1, 4, 7]);
+
+        // The completion of an instance goes along with its start.
+        assert!(task_mark.sample_event(4, true, EventSampling::All));
+        assert!(!task_mark.sample_event(5, true, EventSampling::All));
+
+        // The task may override the default.
+        task_mark.set_event_sampling(Some(EventSampling::FailuresOnly));
+        assert!(!task_mark.sample_event(8, false, EventSampling::All));
+        assert!(!task_mark.sample_event(8, true, EventSampling::All));
+        task_mark.set_event_sampling(None);
+        assert!(task_mark.sample_event(9, false, EventSampling::All));
+        assert!(task_mark.sample_event(9, true, EventSampling::All));
+    }
+
+    #[test]
+    fn test_adaptive_running_time() -> AnyResult<()> {
+        use super::TaskMark;
+
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(60)
+            .set_maximum_running_time(600)
+            .set_maximum_running_time_auto(2.0)
+            .set_maximum_running_time_bounds(5, 120)
+            .spawn_async_routine(|| async {})?;
+
+        // `maximum_running_time` holds until there's enough history.
+        let mut task_mark = TaskMark::default();
+        task_mark.record_duration(10);
+        assert_eq!(task_mark.get_p95_duration(), None);
+        assert_eq!(task.get_adaptive_running_time(1000, None), Some(1600));
+
+        for duration in [
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 11, 12, 13, 14, 15, 16, 17, 18, 19, 40,
+        ] {
+            task_mark.record_duration(duration);
+        }
+        assert_eq!(task_mark.get_p95_duration(), Some(19));
+        assert_eq!(task.get_adaptive_running_time(1000, Some(19)), Some(1038));
+        // Kept within the bounds.
+        assert_eq!(task.get_adaptive_running_time(1000, Some(1)), Some(1005));
+        assert_eq!(task.get_adaptive_running_time(1000, Some(100)), Some(1120));
+
+        assert!(TaskBuilder::default()
+            .set_maximum_running_time_auto(0.0)
+            .spawn_async_routine(|| async {})
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_from_task() -> AnyResult<()> {
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency_count_down_by_cron_str("0 * * * * * *", 3)
+            .set_task_id(1)
+            .set_maximum_parallel_runnable_num(2);
+        let task: Task = task_builder.spawn_async_routine(|| async {})?;
+
+        // Same configuration, another id and expression.
+        let mut sibling_builder = task.to_builder();
+        assert_eq!(sibling_builder.task_id, 1);
+        assert_eq!(sibling_builder.maximum_parallel_runnable_num, Some(2));
+
+        sibling_builder
+            .set_task_id(2)
+            .set_frequency_count_down_by_cron_str("30 * * * * * *", 3);
+        let mut sibling: Task = sibling_builder.spawn_async_routine(|| async {})?;
+        assert_eq!(sibling.task_id, 2);
+        assert_eq!(sibling.maximum_parallel_runnable_num, Some(2));
+        assert_eq!(sibling.get_next_exec_timestamp().unwrap() % 60, 30);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,149 @@
+//! Tenant
+//! Quotas on the tasks labelled with a tenant by `TaskBuilder::set_tenant`,
+//! for platforms where the customers define their own jobs.
+use crate::prelude::*;
+use crate::timer::TaskMark;
+
+use std::collections::{HashMap, HashSet};
+
+/// The most tasks a tenant may have, see `DelayTimerBuilder::tenant_quota`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxTasks(pub usize);
+
+/// The most instances of its tasks a tenant may run at once, see `DelayTimerBuilder::tenant_quota`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxConcurrentInstances(pub u64);
+
+/// The quota of a tenant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenantQuota {
+    /// The most tasks the tenant may have,
+    /// adding or updating a task beyond it fails with `TaskError::TenantQuotaExceeded`.
+    pub max_tasks: usize,
+    /// The most instances of its tasks the tenant may run at once,
+    /// the fires beyond it are skipped.
+    pub max_concurrent_instances: u64,
+}
+
+/// What a tenant uses, see `DelayTimer::tenant_usage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantUsage {
+    /// The tasks of the tenant.
+    pub tasks: usize,
+    /// The running instances of the tasks of the tenant.
+    pub running_instances: u64,
+    /// The quota of the tenant, `None` if it has none.
+    pub quota: Option<TenantQuota>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Tenants {
+    quotas: HashMap<String, TenantQuota>,
+    // The tasks of each tenant, including the ones still on their way to the event-handle.
+    task_ids: DashMap<String, HashSet<u64>>,
+}
+
+impl Tenants {
+    pub(crate) fn new(quotas: HashMap<String, TenantQuota>) -> Self {
+        Tenants {
+            quotas,
+            task_ids: DashMap::new(),
+        }
+    }
+
+    // Count `task_id` in the tasks of `tenant`, unless it's over the quota.
+    // Once it's counted, the task leaves the tenant it had before, if any.
+    pub(crate) fn admit(&self, task_id: u64, tenant: Option<&str>) -> Result<(), TaskError> {
+        if let Some(tenant) = tenant {
+            let mut task_ids = self.task_ids.entry(tenant.to_string()).or_default();
+            if !task_ids.contains(&task_id) {
+                if let Some(quota) = self.quotas.get(tenant) {
+                    if task_ids.len() >= quota.max_tasks {
+                        return Err(TaskError::TenantQuotaExceeded(tenant.to_string()));
+                    }
+                }
+                task_ids.insert(task_id);
+            }
+        }
+
+        for mut task_ids in self.task_ids.iter_mut() {
+            if Some(task_ids.key().as_str()) != tenant {
+                task_ids.value_mut().remove(&task_id);
+            }
+        }
+        Ok(())
+    }
+
+    // The task is gone.
+    pub(crate) fn release(&self, task_id: u64) {
+        for mut task_ids in self.task_ids.iter_mut() {
+            task_ids.value_mut().remove(&task_id);
+        }
+    }
+
+    // Whether `tenant` runs as many instances as its quota allows.
+    pub(crate) fn is_saturated(&self, tenant: &str, task_flag_map: &DashMap<u64, TaskMark>) -> bool {
+        self.quotas.get(tenant).is_some_and(|quota| {
+            self.running_instances(tenant, task_flag_map) >= quota.max_concurrent_instances
+        })
+    }
+
+    pub(crate) fn usage(&self, tenant: &str, task_flag_map: &DashMap<u64, TaskMark>) -> TenantUsage {
+        TenantUsage {
+            tasks: self
+                .task_ids
+                .get(tenant)
+                .map_or(0, |task_ids| task_ids.len()),
+            running_instances: self.running_instances(tenant, task_flag_map),
+            quota: self.quotas.get(tenant).copied(),
+        }
+    }
+
+    fn running_instances(&self, tenant: &str, task_flag_map: &DashMap<u64, TaskMark>) -> u64 {
+        self.task_ids.get(tenant).map_or(0, |task_ids| {
+            task_ids
+                .iter()
+                .filter_map(|task_id| task_flag_map.get(task_id))
+                .map(|task_mark| task_mark.value().get_parallel_runnable_num())
+                .sum()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TenantQuota, Tenants};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_tenant_admission() {
+        let mut quotas = HashMap::new();
+        quotas.insert(
+            "acme".to_string(),
+            TenantQuota {
+                max_tasks: 2,
+                max_concurrent_instances: 1,
+            },
+        );
+        let tenants = Tenants::new(quotas);
+
+        assert!(tenants.admit(1, Some("acme")).is_ok());
+        assert!(tenants.admit(2, Some("acme")).is_ok());
+        // Updating a task of the tenant doesn't count it twice.
+        assert!(tenants.admit(2, Some("acme")).is_ok());
+        assert!(tenants.admit(3, Some("acme")).is_err());
+
+        // Without a quota, a tenant may have any number of tasks.
+        assert!(tenants.admit(3, Some("initech")).is_ok());
+        assert!(tenants.admit(4, Some("initech")).is_ok());
+
+        // A task moving to another tenant frees its place, unless the move is refused.
+        assert!(tenants.admit(4, Some("acme")).is_err());
+        assert!(tenants.admit(2, Some("initech")).is_ok());
+        assert!(tenants.admit(3, Some("acme")).is_ok());
+
+        tenants.release(1);
+        assert!(tenants.admit(5, Some("acme")).is_ok());
+        assert!(tenants.admit(6, None).is_ok());
+    }
+}
@@ -4,12 +4,25 @@ use crate::prelude::*;
 
 use crate::entity::timestamp;
 use crate::entity::RuntimeKind;
-
+use crate::planner::{decide, hold_back, FireDecision, FireState};
+use crate::sync::SemaphorePermit;
+#[cfg(feature = "chaos")]
+use crate::timer::chaos::ChaosFault;
+use crate::timer::dedicated_thread::run_on_dedicated_thread;
+use crate::timer::fire_record::save_last_fire;
+use crate::timer::runtime_trace::heartbeat::{watch_heartbeat, Heartbeat};
+use crate::timer::task::{ReadinessState, SafeStructReadinessFn};
+use crate::utils::parse::shell_command::is_pidfile_alive;
+
+use std::any::Any;
 use std::mem::replace;
-use std::sync::atomic::Ordering::{Acquire, Release};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::Arc;
 use std::time::Duration;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use event_listener::Event;
 use smol::Timer as smolTimer;
 
 pub(crate) const DEFAULT_TIMER_SLOT_COUNT: u64 = 3600;
@@ -32,6 +45,19 @@ impl Clock {
         let inner = ClockInner::new(runtime_kind);
         Clock { inner }
     }
+
+    // A clock going on from `last_tick`, its first tick is a period after it.
+    fn resumed(runtime_kind: RuntimeKind, last_tick: Instant) -> Clock {
+        let period = Duration::from_secs(1);
+        let inner = match runtime_kind {
+            RuntimeKind::Smol => ClockInner::Sc(SmolClock::new(last_tick, period)),
+            RuntimeKind::Tokio => ClockInner::Tc(TokioClock::new(
+                time::Instant::from_std(last_tick + period),
+                period,
+            )),
+        };
+        Clock { inner }
+    }
 }
 impl ClockInner {
     fn new(runtime_kind: RuntimeKind) -> ClockInner {
@@ -100,13 +126,147 @@ impl SmolClock {
     }
 }
 
+// What the fire of `task` due at `timestamp` depends on besides the task itself,
+// as of now.
+pub(crate) fn current_fire_state(
+    shared_header: &SharedHeader,
+    task: &Task,
+    timestamp: u64,
+    queued_since: Option<u64>,
+) -> FireState {
+    let fire_state = marked_fire_state(
+        shared_header,
+        task.task_id,
+        task.tenant.as_deref(),
+        timestamp,
+        queued_since,
+    );
+    complete_fire_state(shared_header, task, timestamp, fire_state)
+}
+
+// The part of the fire state read from the task-marks. The task-marks are locked before the wheel,
+// so it's taken while no slot is held.
+pub(crate) fn marked_fire_state(
+    shared_header: &SharedHeader,
+    task_id: u64,
+    tenant: Option<&str>,
+    timestamp: u64,
+    queued_since: Option<u64>,
+) -> FireState {
+    let tenant_saturated = tenant.is_some_and(|tenant| {
+        shared_header
+            .tenants
+            .is_saturated(tenant, &shared_header.task_flag_map)
+    });
+
+    shared_header
+        .task_flag_map
+        .get(&task_id)
+        .map(|task_mark| FireState {
+            running_instances: task_mark.value().get_parallel_runnable_num(),
+            budget_exhausted: task_mark.value().is_budget_exhausted(timestamp),
+            queued_since,
+            last_finish_time: task_mark.value().get_last_finish_time(),
+            tenant_saturated,
+            ..FireState::default()
+        })
+        .unwrap_or(FireState {
+            queued_since,
+            tenant_saturated,
+            ..FireState::default()
+        })
+}
+
+// The rest of the fire state, read from the task and what it shares with other tasks.
+pub(crate) fn complete_fire_state(
+    shared_header: &SharedHeader,
+    task: &Task,
+    timestamp: u64,
+    fire_state: FireState,
+) -> FireState {
+    let serial_queue_blocked = task.serial_queue.as_ref().is_some_and(|serial_queue| {
+        shared_header
+            .serial_queues
+            .get(serial_queue)
+            .is_some_and(|serial_queue| !serial_queue.is_turn_of(task.task_id))
+    });
+
+    let circuit_open = task
+        .circuit_breaker
+        .as_ref()
+        .is_some_and(|circuit_breaker| !circuit_breaker.allows(timestamp));
+
+    let semaphore_exhausted = task
+        .semaphore
+        .as_ref()
+        .is_some_and(|semaphore| !semaphore.allows(task.task_id));
+
+    let single_instance_held = task
+        .single_instance_guard
+        .as_deref()
+        .is_some_and(is_pidfile_alive);
+
+    FireState {
+        serial_queue_blocked,
+        circuit_open,
+        semaphore_exhausted,
+        single_instance_held,
+        ..fire_state
+    }
+}
+
+// The instant the second `timestamp` begins at.
+fn instant_of(timestamp: u64) -> Instant {
+    let system_time = UNIX_EPOCH + Duration::from_secs(timestamp);
+    Instant::now()
+        + system_time
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+}
+
+/// Put `tasks` back in the wheel relative to the current second hand,
+/// fires missed before `timestamp` are handled by the misfire policy.
+pub(crate) fn reseed_tasks(shared_header: &SharedHeader, tasks: Vec<Task>, timestamp: u64) {
+    let second_hand = shared_header.second_hand.current_second_hand();
+
+    for mut task in tasks {
+        let task_id = task.task_id;
+        if shared_header.misfire_policy == MisfirePolicy::Skip {
+            task.skip_missed_fires(timestamp);
+        }
+
+        let step = task
+            .get_scheduled_time()
+            .and_then(|scheduled_time| scheduled_time.checked_sub(timestamp))
+            .unwrap_or(0)
+            .saturating_sub(task.dispatch_lead());
+        let slot_seed = (step + second_hand) % DEFAULT_TIMER_SLOT_COUNT;
+        task.set_cylinder_line(step / DEFAULT_TIMER_SLOT_COUNT);
+
+        if let Some(mut slot) = shared_header.wheel_queue.get_mut(&slot_seed) {
+            slot.value_mut().add_task(task);
+        }
+
+        if let Some(mut task_mark) = shared_header.task_flag_map.get_mut(&task_id) {
+            task_mark.value_mut().set_slot_mark(slot_seed);
+        }
+    }
+}
+
 /// The information generated when completing a task.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FinishTaskBody {
     pub(crate) task_id: u64,
     pub(crate) record_id: i64,
+    pub(crate) scheduled_for: u64,
+    pub(crate) started_at: u64,
     pub(crate) finish_time: u64,
     pub(crate) finish_output: Option<FinishOutput>,
+    pub(crate) captured_logs: Vec<String>,
+    pub(crate) no_work: bool,
+    #[cfg(feature = "json-result")]
+    pub(crate) result: Option<serde_json::Value>,
+    pub(crate) pid: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -124,8 +284,14 @@ pub enum FinishOutput {
 pub enum TimerEvent {
     /// Stop the Timer.
     StopTimer,
+    /// Stop the Timer and abort the futures it spawned, the report is sent back.
+    Shutdown(ShutdownOptions, AsyncSender<ShutdownReport>),
     /// Add a new `Task`.
     AddTask(Box<Task>),
+    /// Register a new `Task`, it doesn't run until it's activated.
+    RegisterTask(Box<Task>),
+    /// Activate a registered `Task`.
+    ActivateTask(u64),
     /// Insert a new `Task`.
     /// Maintain a state that is transparent to the user, such as the end of a task running instance.
     InsertTask(Box<Task>, TaskInstancesChainMaintainer),
@@ -133,8 +299,16 @@ pub enum TimerEvent {
     UpdateTask(Box<Task>),
     /// Remove a Task in Timer .
     RemoveTask(u64),
+    /// Pause a Task in Timer, it's removed once the grace period is over.
+    RemoveTaskDelayed(u64, Duration),
+    /// Resume a Task paused by `RemoveTaskDelayed` within its grace period.
+    UndoRemove(u64),
+    /// The grace period of a paused Task may be over.
+    ExpireTombstone(u64),
     /// Cancel a Task running instance in Timer .
-    CancelTask(u64, i64),
+    CancelTask(u64, i64, TerminationReason),
+    /// Cancel the oldest running instances of a Task, as many as given, for a new one replacing them.
+    ReplaceInstances(u64, u64),
     /// Cancel a timeout Task running instance in Timer .
     TimeoutTask(u64, i64),
     /// Finished a Task running instance in Timer .
@@ -142,8 +316,172 @@ pub enum TimerEvent {
     /// Append a new instance of a running task .
     AppendTaskHandle(u64, DelayTaskHandlerBox),
     /// Take the initiative to perform once Task.
-    AdvanceTask(u64),
+    AdvanceTask(u64, AdvanceOptions),
+    /// Attach a new chain of task run instances to an existing `Task`.
+    AttachInstancesChain(u64, TaskInstancesChainMaintainer),
+    /// Subscribe a new chain of task run instances to an existing `Task`.
+    SubscribeInstances(u64, TaskInstancesChainMaintainer),
+    /// Subscribe new chains to several existing `Task`s, all of them or none,
+    /// reporting the first task-id that isn't in the timer.
+    SubscribeInstancesMulti(
+        Vec<(u64, TaskInstancesChainMaintainer)>,
+        AsyncSender<Result<(), u64>>,
+    ),
+    /// The chain of task run instances has been closed, stop routing instances to it.
+    DetachInstancesChain(u64),
+    /// A fire of the task was skipped because its execution budget is exhausted.
+    BudgetExceeded(u64),
+    /// A fire of the task was skipped because it matches the exclusion schedule.
+    SkippedByExclusion(u64),
+    /// The timer-core loop died and was restarted, carrying the number of restarts so far.
+    SchedulerRestarted(u32),
+    /// The clock jumped forward (e.g. the system was suspended), carrying the length of the gap.
+    SystemResumedAfter(Duration),
+    /// A fire of the task was dropped after waiting longer than its `max_queue_delay`.
+    StaleFireDropped(u64),
+    /// The readiness check of the pending fire of the task is over, carrying whether it passed.
+    ReadinessChecked(u64, bool),
+    /// The `init` of the task has built the resource shared by its instances.
+    TaskInitialized(u64, Arc<dyn Any + Send + Sync>),
+    /// A fire of the task was skipped because its readiness check didn't pass.
+    SkippedNotReady(u64),
+    /// A fire of the task was skipped because the `SpawnHook` vetoed its instance.
+    SpawnVetoed(u64),
+    /// A fire of the task was skipped because its circuit breaker is open.
+    SkippedByCircuitBreaker(u64),
+    /// A fire of the task was skipped because its tenant runs as many instances as its quota allows.
+    SkippedByTenantQuota(u64),
+    /// A fire of the task was skipped because the process named by its pidfile is still alive.
+    SkippedBySingleInstanceGuard(u64),
+    /// The instances of the task kept failing, it's paused or removed according to its failure policy.
+    TaskQuarantined(u64, Failure),
+    /// A Task running instance missed its heartbeat.
+    InstanceStalled(u64, i64),
+    /// A Task running instance may be running past its runtime warning threshold.
+    RunningLong(u64, i64),
+    /// The countdown of the task is over, it doesn't fire anymore.
+    TaskExhausted(u64),
+    /// Enqueue a parameterized run of a Task for a later time.
+    EnqueueJob(u64, EnqueuedJob),
+    /// A run enqueued for a Task may be due.
+    RunEnqueuedJob(u64),
+    /// Activate the registered Tasks, those handed off by another instance go on from their next fire,
+    /// their ids are sent back.
+    TakeOver(AsyncSender<AnyResult<Vec<u64>>>),
+}
+
+/// What the timer does with the fires that were due while it was not ticking,
+/// e.g. while the system was suspended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MisfirePolicy {
+    /// Each task with missed fires runs once right away, then follows its schedule.
+    FireOnce,
+    /// The missed fires are dropped, each task waits for its next fire to come.
+    Skip,
+}
+
+impl Default for MisfirePolicy {
+    fn default() -> Self {
+        MisfirePolicy::FireOnce
+    }
+}
+
+/// Which fires of a `CountDown` task count against it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountDownPolicy {
+    /// Only the fires that run an instance count,
+    /// the task runs as many times as its countdown says.
+    Executions,
+    /// Every scheduled fire counts, including the ones skipped
+    /// (e.g. by the exclusion schedule, or while the task was paused),
+    /// the task ends when its countdown says no matter how many times it ran.
+    ScheduledFires,
+}
+
+impl Default for CountDownPolicy {
+    fn default() -> Self {
+        CountDownPolicy::Executions
+    }
+}
+
+// A tick that comes that many seconds after the previous one means the clock jumped,
+// the wheel is resynced instead of catching up second by second.
+pub(crate) const RESUME_GAP_SECONDS: u64 = 10;
+
+// How many seconds the clock may be found behind the wheel and still be compensated,
+// e.g. over a leap second, or as a coarse clock is read right before its second turns.
+pub(crate) const DRIFT_TOLERANCE_SECONDS: u64 = 2;
+
+// The most seconds the timer-core loop sleeps through at once, when no fire is due sooner.
+pub(crate) const MAX_IDLE_SECONDS: u64 = 60;
+
+// The time of the tick the clock reads `reading` at, the wheel doesn't go back in time
+// over a small drift. Returns whether the reading was compensated.
+fn compensate_tick(reading: u64, last_timestamp: u64) -> (u64, bool) {
+    match last_timestamp.checked_sub(reading) {
+        Some(drift) if drift > 0 && drift <= DRIFT_TOLERANCE_SECONDS => (last_timestamp, true),
+        _ => (reading, false),
+    }
+}
+
+// The ticks from `timestamp` until the fire at `exec_time`, a fire found slightly in the past
+// is due right away rather than scheduled in the past. Returns whether it was compensated,
+// the step is `None` for a fire further in the past.
+fn compensate_step(exec_time: u64, timestamp: u64) -> (Option<u64>, bool) {
+    match exec_time.checked_sub(timestamp) {
+        Some(step) => (Some(step), false),
+        None if timestamp - exec_time <= DRIFT_TOLERANCE_SECONDS => (Some(0), true),
+        None => (None, false),
+    }
+}
+
+/// The time the fires before are missed, for a task put in the wheel
+/// at the `wheel_timestamp` of the second hand.
+///
+/// The second hand of a sleeping timer-core loop is behind on purpose,
+/// the fires it's behind are missed rather than run late once it catches up.
+pub(crate) fn missed_fire_horizon(shared_header: &SharedHeader, wheel_timestamp: u64) -> u64 {
+    if shared_header.idle_until.load(Acquire) == 0 {
+        return wheel_timestamp;
+    }
+
+    wheel_timestamp.max(timestamp())
+}
+
+/// The ticks from `timestamp` until the fire at `exec_time`,
+/// see `SchedulerHealth::small_drift_compensated`.
+pub(crate) fn ticks_until(
+    shared_header: &SharedHeader,
+    exec_time: u64,
+    timestamp: u64,
+) -> Option<u64> {
+    let (step, compensated) = compensate_step(exec_time, timestamp);
+    if compensated {
+        shared_header.small_drift_compensated.fetch_add(1, Relaxed);
+    }
+    step
+}
+
+// Marks the timer-core loop as alive for as long as it is held,
+// it is dropped on return as well as on panic.
+// Dropping it notifies `rearm`, for `DelayTimer::hand_off` waiting for the loop to stop.
+#[derive(Debug)]
+struct AliveGuard(Arc<AtomicBool>, Arc<Event>);
+
+impl AliveGuard {
+    fn new(alive: Arc<AtomicBool>, rearm: Arc<Event>) -> Self {
+        alive.store(true, Release);
+        AliveGuard(alive, rearm)
+    }
+}
+
+impl Drop for AliveGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Release);
+        self.1.notify(usize::MAX);
+    }
 }
+
 #[derive(Debug)]
 /// delay-timer internal timer wheel core.
 pub struct Timer {
@@ -196,6 +534,13 @@ impl Timer {
         let mut second_hand = self.second_hand();
         let mut next_second_hand = second_hand + 1;
         let mut current_timestamp = timestamp();
+        let mut tick_instant = Instant::now();
+        // The ticks slept through that are still to be gone over.
+        let mut catch_up: u64 = 0;
+        let _alive_guard = AliveGuard::new(
+            self.shared_header.timer_core_alive.clone(),
+            self.shared_header.rearm.clone(),
+        );
 
         loop {
             //TODO: replenish ending single, for stop current jod and thread.
@@ -206,6 +551,9 @@ impl Timer {
             self.shared_header
                 .global_time
                 .store(current_timestamp, Release);
+            if let Some(observer) = self.shared_header.observer.as_ref() {
+                observer.on_tick_started(second_hand, current_timestamp);
+            }
             let task_ids;
 
             {
@@ -232,6 +580,7 @@ impl Timer {
 
             // Centralize task processing to avoid duplicate lock requests and releases.
             // FIXME: https://github.com/BinChengZhao/delay-timer/issues/29
+            let task_count = task_ids.len();
             for task_id in task_ids {
                 let task_option: Option<Task>;
 
@@ -260,14 +609,144 @@ impl Timer {
                 }
             }
 
-            self.lapse().await;
+            if let Some(observer) = self.shared_header.observer.as_ref() {
+                observer.on_slot_drained(second_hand, task_count);
+            }
+            self.shared_header.delays.wake_due(current_timestamp);
+
+            // The slots slept through are gone over at once,
+            // only the fires deferred within their flexible window were due in them.
+            if catch_up > 0 {
+                catch_up -= 1;
+                self.next_position();
+                current_timestamp += 1;
+            } else {
+                let slept = self
+                    .sleep_while_idle(second_hand, current_timestamp, &mut tick_instant)
+                    .await;
+
+                if slept > 0 {
+                    self.next_position();
+                    current_timestamp += 1;
+                    catch_up = slept - 1;
+
+                    // A suspend while sleeping isn't caught up slot by slot either.
+                    let overslept = timestamp().saturating_sub(current_timestamp + catch_up);
+                    if overslept >= RESUME_GAP_SECONDS {
+                        catch_up = 0;
+                        current_timestamp = timestamp();
+                        tick_instant = Instant::now();
+                        self.resync_after_gap(Duration::from_secs(overslept), current_timestamp)
+                            .await;
+                    }
+                } else {
+                    self.lapse().await;
+                    self.shared_header.wakeups.fetch_add(1, Relaxed);
+
+                    // The wall-clock and the monotonic clock don't agree on whether a suspend counts.
+                    let last_timestamp = current_timestamp;
+                    let (tick_timestamp, compensated) =
+                        compensate_tick(timestamp(), last_timestamp);
+                    if compensated {
+                        self.shared_header
+                            .small_drift_compensated
+                            .fetch_add(1, Relaxed);
+                    }
+                    current_timestamp = tick_timestamp;
+                    let gap = Duration::from_secs(current_timestamp.saturating_sub(last_timestamp))
+                        .max(tick_instant.elapsed());
+                    tick_instant = Instant::now();
+
+                    if gap.as_secs() >= RESUME_GAP_SECONDS {
+                        self.resync_after_gap(gap, current_timestamp).await;
+                    }
+                }
+            }
 
             second_hand = self.second_hand();
             next_second_hand = (second_hand + 1) % DEFAULT_TIMER_SLOT_COUNT;
-            current_timestamp = timestamp();
         }
     }
 
+    // Restart the clock, so the missed ticks don't come in a burst,
+    // and put the tasks back in the wheel by their schedule and the misfire policy.
+    async fn resync_after_gap(&mut self, gap: Duration, timestamp: u64) {
+        warn!(
+            "timer-core loop resumed after {:?}, resyncing the wheel.",
+            gap
+        );
+
+        self.clock = Clock::new(self.shared_header.runtime_instance.kind);
+
+        let mut tasks: Vec<Task> = Vec::new();
+        for mut slot in self.shared_header.wheel_queue.iter_mut() {
+            tasks.extend(slot.value_mut().drain_tasks());
+        }
+        reseed_tasks(&self.shared_header, tasks, timestamp);
+
+        self.timer_event_sender
+            .send(TimerEvent::SystemResumedAfter(gap))
+            .await
+            .unwrap_or_else(|e| error!("{}", e));
+    }
+
+    // Sleep until the next slot with a fire or a delay due rather than tick by tick,
+    // a minute at most, a task or a delay put in the wheel meanwhile rearms the loop,
+    // see `SharedHeader::rearm_timer_core`.
+    // Returns the ticks slept through, the clock goes on ticking from the last of them.
+    async fn sleep_while_idle(
+        &mut self,
+        second_hand: u64,
+        timestamp: u64,
+        tick_instant: &mut Instant,
+    ) -> u64 {
+        // It listens before looking at the wheel, so that a task put in it meanwhile isn't missed.
+        let rearmed = self.shared_header.rearm.listen();
+        let idle_ticks = self
+            .idle_ticks(second_hand)
+            .min(self.shared_header.delays.ticks_until_next(timestamp));
+        if idle_ticks <= 1 || !self.shared_header.shared_motivation.load(Acquire) {
+            return 0;
+        }
+
+        self.shared_header
+            .idle_until
+            .store(timestamp + idle_ticks, Release);
+        let deadline = *tick_instant + Duration::from_secs(idle_ticks);
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let sleep = runtime_kind.sleep(deadline.saturating_duration_since(Instant::now()));
+        smol::future::or(sleep, rearmed).await;
+        self.shared_header.idle_until.store(0, Release);
+        self.shared_header.wakeups.fetch_add(1, Relaxed);
+
+        let slept = tick_instant.elapsed().as_secs().min(idle_ticks);
+        *tick_instant += Duration::from_secs(slept);
+        self.clock = Clock::resumed(runtime_kind, *tick_instant);
+        slept
+    }
+
+    // The ticks until the next slot with a task due, `MAX_IDLE_SECONDS` at most,
+    // the cap keeps it within a lap of the wheel.
+    // The fires of the tasks with a flexible window are deferred within it,
+    // so that they run in a batch along with the next fire, as the slots slept through are gone over.
+    // The window is cut short of the next fire of the task, which would be gone over in the same batch.
+    fn idle_ticks(&self, second_hand: u64) -> u64 {
+        let mut idle_ticks = MAX_IDLE_SECONDS;
+        for ticks in 1..MAX_IDLE_SECONDS {
+            if ticks >= idle_ticks {
+                break;
+            }
+
+            let slot_mark = (second_hand + ticks) % DEFAULT_TIMER_SLOT_COUNT;
+            if let Some(slot) = self.shared_header.wheel_queue.get(&slot_mark) {
+                for task in slot.value().tasks().filter(|task| task.is_already()) {
+                    idle_ticks = idle_ticks.min(ticks + task.deferrable_ticks());
+                }
+            }
+        }
+        idle_ticks
+    }
+
     /// Access to the second-hand
     pub(crate) fn second_hand(&self) -> u64 {
         self.shared_header.second_hand.current_second_hand()
@@ -279,10 +758,30 @@ impl Timer {
         task_id: u64,
         tmp_task_handler_box: DelayTaskHandlerBox,
     ) {
+        let send_start = Instant::now();
         self.timer_event_sender
             .send(TimerEvent::AppendTaskHandle(task_id, tmp_task_handler_box))
             .await
             .unwrap_or_else(|e| error!(" `send_timer_event`: {}", e));
+
+        if let Some(observer) = self.shared_header.observer.as_ref() {
+            observer.on_channel_send(send_start.elapsed());
+        }
+    }
+
+    // The task leaves the wheel for good, its chains are closed once its instances are over.
+    async fn exhaust_task(&mut self, task_id: u64) -> AnyResult<()> {
+        self.timer_event_sender
+            .send(TimerEvent::TaskExhausted(task_id))
+            .await
+            .unwrap_or_else(|e| error!(" `exhaust_task`: {}", e));
+        Ok(())
+    }
+
+    fn observe_skipped(&self, task_id: u64, reason: SkipReason) {
+        if let Some(observer) = self.shared_header.observer.as_ref() {
+            observer.on_task_skipped(task_id, reason);
+        }
     }
 
     #[inline(always)]
@@ -293,61 +792,222 @@ impl Timer {
         timestamp: u64,
         next_second_hand: u64,
     ) -> AnyResult<()> {
-        let record_id: i64 = self
-            .shared_header
-            .id_generator
-            .lock()
-            .await
-            .real_time_generate();
+        let record_id: i64 = self.shared_header.id_generator.lock().await.generate();
         let task_id: u64 = task.task_id;
+        let startup_run = task.take_run_on_startup();
+        let extra_run = task.take_extra_run();
+        let queued_since = task.take_queued_since();
+        // The payload goes along with the manual run, not with the fires after it.
+        let payload = task.take_advance_payload();
+
+        // A fire taken from the wheel ahead of time is handled as of the time it's scheduled for,
+        // its instance waits for it.
+        let tick_timestamp = timestamp;
+        let early_fire_time = if startup_run || extra_run || queued_since.is_some() {
+            None
+        } else {
+            task.early_fire_time(tick_timestamp)
+        };
+        let dispatch_at = early_fire_time.map(instant_of);
+        let timestamp = early_fire_time.unwrap_or(tick_timestamp);
 
-        if let Some(maximum_parallel_runnable_num) = task.maximum_parallel_runnable_num {
-            let parallel_runnable_num: u64;
+        let fire_state = current_fire_state(&self.shared_header, &task, timestamp, queued_since);
 
-            {
-                let task_flag_map =
-                    self.shared_header
-                        .task_flag_map
-                        .get(&task_id)
-                        .ok_or_else(|| {
-                            anyhow!("Can't get task_flag_map for task : {}", task.task_id)
-                        })?;
-                parallel_runnable_num = task_flag_map.value().get_parallel_runnable_num();
+        let fire_decision = match decide(&task, timestamp, &fire_state) {
+            FireDecision::Run => self.gate_readiness(&task, timestamp, &fire_state),
+            fire_decision => fire_decision,
+        };
+        let (fire_decision, extension) = match fire_decision {
+            FireDecision::Run => {
+                let task_meta = TaskMeta {
+                    task_id,
+                    record_id,
+                    scheduled_time: timestamp,
+                    running_instances: fire_state.running_instances,
+                    extra_run: startup_run || extra_run,
+                };
+                self.consult_spawn_hook(&task_meta)
             }
+            fire_decision => (fire_decision, None),
+        };
+        self.update_serial_queue(&task, record_id, fire_decision);
+        let semaphore_permit = Self::update_semaphore(&task, fire_decision);
+
+        match fire_decision {
+            FireDecision::Run => {
+                // Once the breaker cools down, the first fire going through is its probe.
+                if let Some(circuit_breaker) = task.circuit_breaker.as_ref() {
+                    circuit_breaker.on_fire(timestamp);
+                }
 
-            // if runnable_task.parallel_runnable_num >= task.maximum_parallel_runnable_num doesn't run it.
+                // The event goes ahead of the new instance,
+                // so that the ones it replaces are the older.
+                let replaced = match task.maximum_parallel_runnable_num {
+                    Some(maximum) if task.replace_on_overlap => {
+                        (fire_state.running_instances + 1).saturating_sub(maximum)
+                    }
+                    _ => 0,
+                };
+                if replaced > 0 {
+                    self.timer_event_sender
+                        .send(TimerEvent::ReplaceInstances(task_id, replaced))
+                        .await
+                        .unwrap_or_else(|e| error!(" `maintain_task`: {}", e));
+                }
+            }
+            FireDecision::Queue => {
+                trace!(
+                    "task-id: {}, parallel_runnable_num >= maximum_parallel_runnable_num queues it",
+                    task_id
+                );
+                task.queue_pending_fire(queued_since.unwrap_or(timestamp), startup_run, extra_run);
+                task.set_advance_payload(payload);
+                return self.requeue_task(task, next_second_hand);
+            }
+            FireDecision::Skip(skip_reason) => {
+                trace!("task-id: {}, {:?} doesn't run it", task_id, skip_reason);
+                self.observe_skipped(task_id, skip_reason);
+
+                let skip_event = match skip_reason {
+                    SkipReason::Exclusion => Some(TimerEvent::SkippedByExclusion(task_id)),
+                    SkipReason::BudgetExceeded => Some(TimerEvent::BudgetExceeded(task_id)),
+                    SkipReason::QueueDelayExceeded => Some(TimerEvent::StaleFireDropped(task_id)),
+                    SkipReason::NotReady => Some(TimerEvent::SkippedNotReady(task_id)),
+                    SkipReason::Vetoed => Some(TimerEvent::SpawnVetoed(task_id)),
+                    SkipReason::CircuitOpen => Some(TimerEvent::SkippedByCircuitBreaker(task_id)),
+                    SkipReason::TenantQuota => Some(TimerEvent::SkippedByTenantQuota(task_id)),
+                    SkipReason::SingleInstanceHeld => {
+                        Some(TimerEvent::SkippedBySingleInstanceGuard(task_id))
+                    }
+                    SkipReason::MaximumParallelRunnable => None,
+                };
+                if let Some(skip_event) = skip_event {
+                    self.timer_event_sender
+                        .send(skip_event)
+                        .await
+                        .unwrap_or_else(|e| error!(" `maintain_task`: {}", e));
+                }
 
-            if parallel_runnable_num >= maximum_parallel_runnable_num {
-                trace!("task-id: {}, parallel_runnable_num >= maximum_parallel_runnable_num doesn't run it", task.task_id);
-                return self.handle_task(task, timestamp, next_second_hand, false);
+                let counted = self.shared_header.count_down_policy
+                    == CountDownPolicy::ScheduledFires
+                    && !(startup_run || extra_run);
+                if counted && !task.down_count_and_set_vaild() {
+                    return self.exhaust_task(task_id).await;
+                }
+
+                return self.handle_task(task, tick_timestamp, next_second_hand, false);
             }
         }
 
+        task.record_start(timestamp);
+        let cancellation_token = CancellationToken::default();
+        let schedule_metadata = task.get_schedule_metadata(timestamp);
+
+        #[cfg(feature = "otel")]
+        let otel_context = super::otel::start_instance_span(
+            &mut task,
+            record_id,
+            schedule_metadata.scheduled_time,
+            timestamp,
+        );
+
         let mut task_context = TaskContext::default();
         task_context
             .task_id(task_id)
             .record_id(record_id)
             .timer_event_sender(self.timer_event_sender.clone())
-            .runtime_kind(self.shared_header.runtime_instance.kind);
+            .runtime_kind(self.shared_header.runtime_instance.kind)
+            .cancellation_token(cancellation_token.clone())
+            .schedule_metadata(schedule_metadata)
+            .dispatch_at(dispatch_at)
+            .task_logger(TaskLogger::new(
+                task_id,
+                record_id,
+                task.log_target,
+                task.capture_logs,
+            ));
+        task_context.set_extension(extension);
+        task_context.set_payload(payload);
+        task_context.set_state_store(self.shared_header.state_store.clone());
+        task_context.set_child_processes(self.shared_header.child_processes.clone());
+        task_context.set_secrets_provider(self.shared_header.secrets_provider.clone());
+        task_context.set_resource(
+            self.shared_header
+                .task_flag_map
+                .get(&task_id)
+                .and_then(|task_mark| task_mark.value().get_resource()),
+        );
+        if let Some((interval, stall_policy)) = task.heartbeat {
+            task_context.set_heartbeat(self.spawn_heartbeat_watch(
+                task_id,
+                record_id,
+                interval,
+                stall_policy,
+            ));
+        }
+        #[cfg(feature = "otel")]
+        task_context.set_otel_context(otel_context);
+        #[cfg(feature = "chaos")]
+        let chaos_fault = task.chaos.and_then(|chaos| chaos.pick());
+        #[cfg(feature = "chaos")]
+        task_context.set_chaos_fault(chaos_fault);
+
+        // The fire is recorded before it runs, a crash while it runs doesn't have it run again.
+        if task.at_most_once && !(startup_run || extra_run) {
+            save_last_fire(
+                self.shared_header.state_store.as_ref(),
+                task_id,
+                schedule_metadata.scheduled_time,
+            )
+            .unwrap_or_else(|e| error!(" `maintain_task`: {}", e));
+        }
 
-        let task_handler_box = self.routine_exec(&*(task.routine.0), task_context);
+        let running_pid = task_context.running_pid.clone();
+        let task_handler_box = self.routine_exec(&task, task_context);
+
+        let p95_duration = task.maximum_running_time_auto.and_then(|_| {
+            self.shared_header
+                .task_flag_map
+                .get(&task_id)
+                .and_then(|task_mark| task_mark.value().get_p95_duration())
+        });
+        let end_time = task.get_adaptive_running_time(timestamp, p95_duration);
+        if let Some(warning_after) = task.runtime_warning_after(timestamp, end_time) {
+            self.spawn_runtime_warning(task_id, record_id, warning_after);
+        }
 
         let delay_task_handler_box_builder = DelayTaskHandlerBoxBuilder::default();
         let tmp_task_handler_box = delay_task_handler_box_builder
             .set_task_id(task_id)
             .set_record_id(record_id)
+            .set_scheduled_time(schedule_metadata.scheduled_time)
             .set_start_time(timestamp)
-            .set_end_time(task.get_maximum_running_time(timestamp))
+            .set_end_time(end_time)
+            .set_cancellation_token(cancellation_token)
+            .set_token_handed_out(task.routine.0.takes_context())
+            .set_running_pid(running_pid)
+            .set_semaphore_permit(semaphore_permit)
             .spawn(task_handler_box);
 
+        if let Some(observer) = self.shared_header.observer.as_ref() {
+            observer.on_instance_spawned(task_id, record_id);
+        }
         self.send_timer_event(task_id, tmp_task_handler_box).await;
 
-        let task_valid = task.down_count_and_set_vaild();
+        #[cfg(feature = "chaos")]
+        if chaos_fault == Some(ChaosFault::Timeout) {
+            self.timer_event_sender
+                .send(TimerEvent::TimeoutTask(task_id, record_id))
+                .await
+                .unwrap_or_else(|e| error!(" `maintain_task`: {}", e));
+        }
+
+        let task_valid = startup_run || extra_run || task.down_count_and_set_vaild();
         if !task_valid {
-            return Ok(());
+            return self.exhaust_task(task_id).await;
         }
 
-        self.handle_task(task, timestamp, next_second_hand, true)
+        self.handle_task(task, tick_timestamp, next_second_hand, true)
     }
 
     // Use `next_second_hand` to solve a problem
@@ -373,7 +1033,13 @@ impl Timer {
         // when-on-slot61-exec: (task_excute_timestamp - timestamp + next_second_hand) % slot_seed == 61
 
         // Time difference + next second hand % DEFAULT_TIMER_SLOT_COUNT
-        let step = task_excute_timestamp.checked_sub(timestamp).unwrap_or(1);
+        let step = ticks_until(&self.shared_header, task_excute_timestamp, timestamp).unwrap_or(1);
+        // A compensated task is taken from the wheel ahead of its fire,
+        // `next_second_hand` is already one tick after `timestamp`.
+        let step = match task.dispatch_lead() {
+            0 => step,
+            dispatch_lead => step.saturating_sub(dispatch_lead + 1),
+        };
         let cylinder_line = step / DEFAULT_TIMER_SLOT_COUNT;
         task.set_cylinder_line(cylinder_line);
         let slot_seed = (step + next_second_hand) % DEFAULT_TIMER_SLOT_COUNT;
@@ -408,18 +1074,242 @@ impl Timer {
         Ok(())
     }
 
-    #[inline(always)]
-    fn routine_exec(
+    // A fire that may run waits for the readiness check of the task,
+    // which is evaluated in the background and reported by `ReadinessChecked`.
+    fn gate_readiness(&self, task: &Task, timestamp: u64, fire_state: &FireState) -> FireDecision {
+        let (readiness_check, readiness) = match task.readiness_check.as_ref() {
+            Some(readiness_check) => readiness_check,
+            None => return FireDecision::Run,
+        };
+        let mut task_mark = match self.shared_header.task_flag_map.get_mut(&task.task_id) {
+            Some(task_mark) => task_mark,
+            None => return FireDecision::Run,
+        };
+        let task_mark = task_mark.value_mut();
+
+        let fire_decision = match task_mark.get_readiness() {
+            ReadinessState::Ready => FireDecision::Run,
+            ReadinessState::Checking(since)
+                if timestamp <= since + readiness.timeout.as_secs() + 1 =>
+            {
+                FireDecision::Queue
+            }
+            // A check that never reported back (e.g. it panicked) hasn't passed.
+            ReadinessState::Checking(since) | ReadinessState::NotReady(since) => {
+                match readiness.policy {
+                    NotReadyPolicy::Skip => FireDecision::Skip(SkipReason::NotReady),
+                    NotReadyPolicy::Defer { retry_after }
+                        if timestamp < since + retry_after.as_secs() =>
+                    {
+                        hold_back(task, timestamp, fire_state)
+                    }
+                    NotReadyPolicy::Defer { .. } => match hold_back(task, timestamp, fire_state) {
+                        FireDecision::Queue => {
+                            self.spawn_readiness_check(task.task_id, readiness_check, readiness);
+                            task_mark.set_readiness(ReadinessState::Checking(timestamp));
+                            return FireDecision::Queue;
+                        }
+                        fire_decision => fire_decision,
+                    },
+                }
+            }
+            ReadinessState::Unchecked => {
+                self.spawn_readiness_check(task.task_id, readiness_check, readiness);
+                task_mark.set_readiness(ReadinessState::Checking(timestamp));
+                return FireDecision::Queue;
+            }
+        };
+
+        // The next fire is checked afresh.
+        if fire_decision != FireDecision::Queue {
+            task_mark.set_readiness(ReadinessState::Unchecked);
+        }
+        fire_decision
+    }
+
+    fn spawn_readiness_check(
         &self,
-        routine: &(dyn Routine<TokioHandle = TokioJoinHandle<()>, SmolHandle = SmolJoinHandler<()>>
-              + 'static
-              + Send),
-
-        task_context: TaskContext,
-    ) -> Box<dyn DelayTaskHandler> {
-        match task_context.runtime_kind {
-            RuntimeKind::Smol => create_delay_task_handler(routine.spawn_by_smol(task_context)),
-            RuntimeKind::Tokio => create_delay_task_handler(routine.spawn_by_tokio(task_context)),
+        task_id: u64,
+        readiness_check: &SafeStructReadinessFn,
+        readiness: &Readiness,
+    ) {
+        let readiness_check = (readiness_check.0)();
+        let timeout = readiness.timeout;
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let timer_event_sender = self.timer_event_sender.clone();
+        let checking = async move {
+            let ready = runtime_kind
+                .timeout(timeout, readiness_check)
+                .await
+                .unwrap_or(false);
+
+            timer_event_sender
+                .send(TimerEvent::ReadinessChecked(task_id, ready))
+                .await
+                .unwrap_or_else(|e| error!("`spawn_readiness_check`: {}", e));
+        };
+
+        let checking = self.shared_header.future_tracker.track(checking);
+        self.shared_header.runtime_instance.kind.spawn(checking);
+    }
+
+    // Watch the heartbeat of the instance beside it, it winds down with the instance.
+    fn spawn_heartbeat_watch(
+        &self,
+        task_id: u64,
+        record_id: i64,
+        interval: Duration,
+        stall_policy: StallPolicy,
+    ) -> Arc<Heartbeat> {
+        let heartbeat = Heartbeat::new();
+        let watching = watch_heartbeat(
+            Arc::downgrade(&heartbeat),
+            interval,
+            stall_policy,
+            task_id,
+            record_id,
+            self.timer_event_sender.clone(),
+        );
+
+        let watching = self.shared_header.future_tracker.track(watching);
+        self.shared_header.runtime_instance.kind.spawn(watching);
+        heartbeat
+    }
+
+    // Report the instance once it has run for `warning_after`,
+    // the event-handle drops the report if it has ended by then.
+    fn spawn_runtime_warning(&self, task_id: u64, record_id: i64, warning_after: Duration) {
+        let runtime_kind = self.shared_header.runtime_instance.kind;
+        let timer_event_sender = self.timer_event_sender.clone();
+        let warning = async move {
+            runtime_kind.sleep(warning_after).await;
+            timer_event_sender
+                .send(TimerEvent::RunningLong(task_id, record_id))
+                .await
+                .unwrap_or_else(|e| error!("`spawn_runtime_warning`: {}", e));
+        };
+
+        let warning = self.shared_header.future_tracker.track(warning);
+        self.shared_header.runtime_instance.kind.spawn(warning);
+    }
+
+    // The `SpawnHook` has the last word on a fire that may run.
+    fn consult_spawn_hook(
+        &self,
+        task_meta: &TaskMeta,
+    ) -> (FireDecision, Option<Arc<dyn Any + Send + Sync>>) {
+        let spawn_hook = match self.shared_header.spawn_hook.as_ref() {
+            Some(spawn_hook) => spawn_hook,
+            None => return (FireDecision::Run, None),
+        };
+
+        match spawn_hook.before_spawn(task_meta) {
+            SpawnDecision::Spawn => (FireDecision::Run, None),
+            SpawnDecision::SpawnWith(extension) => (FireDecision::Run, Some(extension)),
+            SpawnDecision::Veto => (FireDecision::Skip(SkipReason::Vetoed), None),
+        }
+    }
+
+    // Keep the place of the task in its serial queue in step with the decision.
+    fn update_serial_queue(&self, task: &Task, record_id: i64, fire_decision: FireDecision) {
+        let serial_queue = match task.serial_queue.as_ref() {
+            Some(serial_queue) => serial_queue,
+            None => return,
+        };
+
+        let mut serial_queue = self
+            .shared_header
+            .serial_queues
+            .entry(serial_queue.clone())
+            .or_default();
+        match fire_decision {
+            FireDecision::Run => serial_queue.start(task.task_id, record_id),
+            FireDecision::Queue => serial_queue.wait(task.task_id),
+            FireDecision::Skip(_) => serial_queue.leave(task.task_id),
+        }
+    }
+
+    // Keep the place of the task in line for its semaphore in step with the decision,
+    // the instance holds the permit it takes until it's dropped.
+    fn update_semaphore(task: &Task, fire_decision: FireDecision) -> Option<SemaphorePermit> {
+        let semaphore = task.semaphore.as_ref()?;
+        match fire_decision {
+            FireDecision::Run => return Some(semaphore.acquire(task.task_id)),
+            FireDecision::Queue => semaphore.wait(task.task_id),
+            FireDecision::Skip(_) => semaphore.leave(task.task_id),
+        }
+        None
+    }
+
+    // Retry the pending fire on the next tick, the schedule isn't advanced.
+    fn requeue_task(&mut self, mut task: Task, next_second_hand: u64) -> AnyResult<()> {
+        let task_id: u64 = task.task_id;
+        task.set_cylinder_line(0);
+
+        self.shared_header
+            .wheel_queue
+            .get_mut(&next_second_hand)
+            .ok_or_else(|| anyhow!("can't slot_mut for slot :{}", next_second_hand))?
+            .value_mut()
+            .add_task(task);
+
+        self.shared_header
+            .task_flag_map
+            .get_mut(&task_id)
+            .ok_or_else(|| anyhow!("can't get task_flag_map for task :{}", task_id))?
+            .value_mut()
+            .set_slot_mark(next_second_hand);
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn routine_exec(&self, task: &Task, task_context: TaskContext) -> Box<dyn DelayTaskHandler> {
+        let routine = &*(task.routine.0);
+
+        // The handle of the instance waits for the pool, aborting it aborts the instance.
+        if let (Execution::Runtime, Some(pool)) = (task.execution, task.pool.as_ref()) {
+            match self.shared_header.executor_pools.get(pool) {
+                Some(executor_pool) => {
+                    let (executor_pool, task_id) = (executor_pool.clone(), task.task_id);
+                    let runtime_kind = task_context.runtime_kind;
+                    let local_job = routine.dedicated_instance(task_context);
+                    let instance = async move { executor_pool.run(task_id, local_job).await };
+                    return match runtime_kind {
+                        RuntimeKind::Smol => {
+                            create_delay_task_handler(async_spawn_by_smol(instance))
+                        }
+                        RuntimeKind::Tokio => {
+                            create_delay_task_handler(async_spawn_by_tokio(instance))
+                        }
+                    };
+                }
+                None => warn!(
+                    "task-id: {}, no executor pool `{}`, it runs on the runtime.",
+                    task.task_id, pool
+                ),
+            }
+        }
+
+        match (task.execution, task_context.runtime_kind) {
+            (Execution::Runtime, RuntimeKind::Smol) => {
+                create_delay_task_handler(routine.spawn_by_smol(task_context))
+            }
+            (Execution::Runtime, RuntimeKind::Tokio) => {
+                create_delay_task_handler(routine.spawn_by_tokio(task_context))
+            }
+
+            // The handle of the instance waits for the dedicated thread, aborting it aborts the instance.
+            (Execution::DedicatedThread, runtime_kind) => {
+                let instance = run_on_dedicated_thread(
+                    routine.dedicated_instance(task_context),
+                    task.thread_options.clone(),
+                );
+                match runtime_kind {
+                    RuntimeKind::Smol => create_delay_task_handler(async_spawn_by_smol(instance)),
+                    RuntimeKind::Tokio => create_delay_task_handler(async_spawn_by_tokio(instance)),
+                }
+            }
         }
     }
 }
@@ -445,4 +1335,196 @@ mod tests {
         assert_eq!(timer.next_position(), 3599);
         assert_eq!(timer.next_position(), 0);
     }
+
+    #[test]
+    fn test_compensate_drift() {
+        use super::{compensate_step, compensate_tick, DRIFT_TOLERANCE_SECONDS};
+
+        // The clock read at each tick: a leap second replayed, a coarse clock read early,
+        // then set back for good.
+        let readings = [100, 101, 101, 100, 102, 103, 103 - DRIFT_TOLERANCE_SECONDS - 1];
+        let mut last_timestamp = 99;
+        let ticks: Vec<(u64, bool)> = readings
+            .iter()
+            .map(|&reading| {
+                let tick = compensate_tick(reading, last_timestamp);
+                last_timestamp = tick.0;
+                tick
+            })
+            .collect();
+        assert_eq!(
+            ticks,
+            vec![
+                (100, false),
+                (101, false),
+                (101, false),
+                (101, true),
+                (102, false),
+                (103, false),
+                (100, false),
+            ]
+        );
+
+        // A fire is never scheduled in the past within the tolerance.
+        assert_eq!(compensate_step(105, 100), (Some(5), false));
+        assert_eq!(compensate_step(100, 100), (Some(0), false));
+        assert_eq!(compensate_step(99, 100), (Some(0), true));
+        assert_eq!(
+            compensate_step(100 - DRIFT_TOLERANCE_SECONDS, 100),
+            (Some(0), true)
+        );
+        assert_eq!(
+            compensate_step(100 - DRIFT_TOLERANCE_SECONDS - 1, 100),
+            (None, false)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idle_ticks() -> anyhow::Result<()> {
+        use super::{SharedHeader, Timer, TimerEvent, MAX_IDLE_SECONDS};
+        use crate::prelude::*;
+        use smol::channel::unbounded;
+        use std::sync::atomic::Ordering;
+        use std::time::{Duration, Instant};
+
+        let (s, _) = unbounded::<TimerEvent>();
+        let shared_header = SharedHeader::default();
+        let mut timer = Timer::new(s, shared_header.clone());
+        assert_eq!(timer.idle_ticks(0), MAX_IDLE_SECONDS);
+
+        // A task a lap away doesn't keep the loop awake, one due does.
+        for (task_id, slot_mark, cylinder_line) in [(1, 2, 1), (2, 5, 0), (3, 1, 0)] {
+            let mut task = TaskBuilder::default()
+                .set_frequency_repeated_by_seconds(10)
+                .set_task_id(task_id)
+                .spawn_async_routine(|| async {})?;
+            task.set_cylinder_line(cylinder_line);
+            shared_header
+                .wheel_queue
+                .get_mut(&slot_mark)
+                .unwrap()
+                .add_task(task);
+        }
+        assert_eq!(timer.idle_ticks(0), 5);
+        assert_eq!(timer.idle_ticks(3598), 3);
+        assert_eq!(timer.idle_ticks(10), MAX_IDLE_SECONDS);
+
+        // A task put in the wheel wakes the sleeping loop up.
+        let rearm = shared_header.clone();
+        std::thread::spawn(move || {
+            while rearm.idle_until.load(Ordering::Acquire) == 0 {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            rearm.rearm_timer_core();
+        });
+        let started_at = Instant::now();
+        let mut tick_instant = started_at;
+        let slept = timer.sleep_while_idle(10, 0, &mut tick_instant).await;
+        assert_eq!(slept, 0);
+        assert!(started_at.elapsed() < Duration::from_secs(1));
+        assert_eq!(shared_header.wakeups.load(Ordering::Relaxed), 1);
+        assert_eq!(shared_header.idle_until.load(Ordering::Relaxed), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flexible_window() -> anyhow::Result<()> {
+        use super::{SharedHeader, Timer, TimerEvent, MAX_IDLE_SECONDS};
+        use crate::prelude::*;
+        use smol::channel::unbounded;
+        use std::time::Duration;
+
+        let (s, _) = unbounded::<TimerEvent>();
+        let shared_header = SharedHeader::default();
+        let timer = Timer::new(s, shared_header.clone());
+
+        let add_task = |task_id: u64, slot_mark: u64, flexible_window: Option<u64>| {
+            let mut task_builder = TaskBuilder::default();
+            task_builder
+                .set_frequency_repeated_by_seconds(10)
+                .set_task_id(task_id);
+            if let Some(flexible_window) = flexible_window {
+                task_builder.set_flexible_window(Duration::from_secs(flexible_window));
+            }
+            let task = task_builder.spawn_async_routine(|| async {})?;
+            shared_header
+                .wheel_queue
+                .get_mut(&slot_mark)
+                .unwrap()
+                .add_task(task);
+            Ok::<(), TaskError>(())
+        };
+
+        // The flexible fire waits for the end of its window at most.
+        add_task(1, 2, Some(10))?;
+        assert_eq!(timer.idle_ticks(0), 12);
+
+        // It runs along with a fire due within its window.
+        add_task(2, 7, None)?;
+        assert_eq!(timer.idle_ticks(0), 7);
+
+        // A fire due right away isn't deferred.
+        add_task(3, 1, Some(0))?;
+        assert_eq!(timer.idle_ticks(0), 1);
+
+        assert_eq!(timer.idle_ticks(30), MAX_IDLE_SECONDS);
+
+        // The window is cut short of the next fire of the task.
+        let mut task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(2)
+            .set_task_id(4)
+            .set_flexible_window(Duration::from_secs(10))
+            .spawn_async_routine(|| async {})?;
+        task.get_next_exec_timestamp();
+        shared_header
+            .wheel_queue
+            .get_mut(&32)
+            .unwrap()
+            .add_task(task);
+        assert_eq!(timer.idle_ticks(30), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reseed_tasks() -> anyhow::Result<()> {
+        use super::{reseed_tasks, MisfirePolicy, SharedHeader};
+        use crate::prelude::*;
+        use crate::timer::TaskMark;
+
+        for misfire_policy in [MisfirePolicy::FireOnce, MisfirePolicy::Skip] {
+            let shared_header = SharedHeader {
+                misfire_policy,
+                ..Default::default()
+            };
+
+            let mut task = TaskBuilder::default()
+                .set_frequency_repeated_by_seconds(10)
+                .set_task_id(1)
+                .spawn_async_routine(|| async {})?;
+            let scheduled_time = task.get_next_exec_timestamp().unwrap();
+
+            let mut task_mark = TaskMark::default();
+            task_mark.set_task_id(1);
+            shared_header.task_flag_map.insert(1, task_mark);
+
+            // Three fires were missed while the clock wasn't ticking.
+            let resumed_at = scheduled_time + 25;
+            reseed_tasks(&shared_header, vec![task], resumed_at);
+
+            let slot_mark = shared_header.task_flag_map.get(&1).unwrap().get_slot_mark();
+            match misfire_policy {
+                MisfirePolicy::FireOnce => assert_eq!(slot_mark, 0),
+                MisfirePolicy::Skip => assert_eq!(slot_mark, 5),
+            }
+            assert!(shared_header
+                .wheel_queue
+                .get(&slot_mark)
+                .unwrap()
+                .contains_task(1));
+        }
+
+        Ok(())
+    }
 }
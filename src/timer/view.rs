@@ -0,0 +1,114 @@
+//! View
+//! A read-only view of the timer for dashboards polling it from other threads,
+//! the readers share an immutable snapshot, which the event-handle replaces on change.
+use crate::prelude::*;
+use crate::entity::SharedHeader;
+
+use std::sync::{Arc, RwLock};
+
+/// A task as it was when the snapshot was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskView {
+    /// The id of the task.
+    pub task_id: u64,
+    /// How many instances of the task were running.
+    pub running_instances: u64,
+    /// The time (timestamp-seconds) of the pending fire,
+    /// `None` for a task that wasn't waiting in the wheel.
+    pub next_fire: Option<u64>,
+    /// The time (timestamp-seconds) the last instance finished at.
+    pub last_finish_time: Option<u64>,
+    /// What the instances of the task did.
+    pub stats: TaskStats,
+}
+
+/// A snapshot of the timer, taken by the event-handle, see `SchedulerView`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchedulerSnapshot {
+    /// The time (timestamp-seconds) the snapshot was taken at.
+    pub taken_at: u64,
+    /// How many snapshots were taken before this one.
+    pub version: u64,
+    /// The health of the scheduler.
+    pub health: SchedulerHealth,
+    /// The tasks of the timer, by id.
+    pub tasks: Vec<TaskView>,
+}
+
+impl SchedulerSnapshot {
+    fn new(shared_header: &SharedHeader, event_channel_occupancy: usize, version: u64) -> Self {
+        // The task-marks are read before the wheel, so that no task-mark is locked while a slot is.
+        let mut tasks: Vec<(TaskView, u64)> = shared_header
+            .task_flag_map
+            .iter()
+            .map(|task_mark| {
+                let task_mark = task_mark.value();
+                let task_view = TaskView {
+                    task_id: task_mark.task_id,
+                    running_instances: task_mark.get_parallel_runnable_num(),
+                    next_fire: None,
+                    last_finish_time: task_mark.get_last_finish_time(),
+                    stats: TaskStats::new(task_mark),
+                };
+                (task_view, task_mark.get_slot_mark())
+            })
+            .collect();
+
+        for (task_view, slot_mark) in tasks.iter_mut() {
+            task_view.next_fire = shared_header.wheel_queue.get(slot_mark).and_then(|slot| {
+                slot.value()
+                    .get_task(task_view.task_id)
+                    .and_then(Task::get_scheduled_time)
+            });
+        }
+
+        let mut tasks: Vec<TaskView> = tasks.into_iter().map(|(task_view, _)| task_view).collect();
+        tasks.sort_unstable_by_key(|task_view| task_view.task_id);
+
+        SchedulerSnapshot {
+            taken_at: timestamp(),
+            version,
+            health: SchedulerHealth::new(shared_header, event_channel_occupancy),
+            tasks,
+        }
+    }
+
+    /// The task of `task_id`, if the timer had it.
+    pub fn task(&self, task_id: u64) -> Option<&TaskView> {
+        self.tasks
+            .binary_search_by_key(&task_id, |task_view| task_view.task_id)
+            .ok()
+            .map(|index| &self.tasks[index])
+    }
+}
+
+/// A read-only view of the timer, taken by `DelayTimer::view`.
+///
+/// It's cheap to clone, and reading it doesn't contend with the scheduler:
+/// the event-handle takes a new snapshot each time it has handled an event,
+/// the readers get the latest one as a whole.
+#[derive(Debug, Clone)]
+pub struct SchedulerView {
+    inner: Arc<RwLock<Arc<SchedulerSnapshot>>>,
+}
+
+impl SchedulerView {
+    pub(crate) fn new(shared_header: &SharedHeader, event_channel_occupancy: usize) -> Self {
+        let snapshot = SchedulerSnapshot::new(shared_header, event_channel_occupancy, 0);
+        SchedulerView {
+            inner: Arc::new(RwLock::new(Arc::new(snapshot))),
+        }
+    }
+
+    /// Get the latest snapshot.
+    pub fn snapshot(&self) -> Arc<SchedulerSnapshot> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Take a new snapshot, the snapshots held by readers are left as they are.
+    pub(crate) fn refresh(&self, shared_header: &SharedHeader, event_channel_occupancy: usize) {
+        let version = self.snapshot().version + 1;
+        let snapshot = SchedulerSnapshot::new(shared_header, event_channel_occupancy, version);
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(snapshot);
+    }
+}
@@ -0,0 +1,272 @@
+//! Watchdog
+//! It supervises the timer-core loop and restarts it once it has died.
+use super::task::Task;
+use super::timer_core::{reseed_tasks, Timer, TimerEvent};
+use crate::entity::timestamp;
+use crate::prelude::*;
+
+use std::collections::HashSet;
+use std::fmt::{self, Pointer};
+use std::io;
+use std::sync::atomic::Ordering::{Acquire, Release};
+use std::sync::Arc;
+use std::thread::{self, Builder};
+
+// A loop that hasn't ticked for that many seconds has missed its heartbeat.
+pub(crate) const MISSED_HEARTBEAT_SECONDS: u64 = 3;
+// A loop that keeps dying after that many restarts is given up.
+pub(crate) const MAX_SCHEDULER_RESTARTS: u32 = 3;
+// A loop that has ticked on time for that many checks in a row is healthy again,
+// its past restarts no longer count towards `MAX_SCHEDULER_RESTARTS`.
+pub(crate) const HEALTHY_HEARTBEATS: u32 = 60;
+
+type SafeArcFailureFn = Arc<dyn Fn(&SchedulerError) + 'static + Send + Sync>;
+
+#[derive(Clone)]
+pub(crate) struct SafeStructFailureFn(pub(crate) SafeArcFailureFn);
+impl fmt::Debug for SafeStructFailureFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as Pointer>::fmt(&self, f)
+    }
+}
+
+/// Supervisor of the timer-core loop.
+///
+/// If the loop panics, scheduling would silently stop,
+/// the watchdog notices the missed heartbeat, puts the tasks back in the wheel
+/// and starts a new loop.
+pub(crate) struct Watchdog {
+    shared_header: SharedHeader,
+    timer_event_sender: TimerEventSender,
+    on_irrecoverable_failure: Option<SafeStructFailureFn>,
+    restart_count: u32,
+    // The heartbeats checked on time in a row.
+    healthy_heartbeats: u32,
+}
+
+impl Watchdog {
+    pub(crate) fn new(
+        shared_header: SharedHeader,
+        timer_event_sender: TimerEventSender,
+        on_irrecoverable_failure: Option<SafeStructFailureFn>,
+    ) -> Self {
+        Watchdog {
+            shared_header,
+            timer_event_sender,
+            on_irrecoverable_failure,
+            restart_count: 0,
+            healthy_heartbeats: 0,
+        }
+    }
+
+    /// Check the heartbeat every second, until the delay-timer is stopped.
+    pub(crate) fn run(mut self) {
+        loop {
+            thread::sleep(Duration::from_secs(1));
+
+            if !self.shared_header.shared_motivation.load(Acquire) {
+                return;
+            }
+
+            if !self.is_heartbeat_missed() {
+                self.on_healthy_heartbeat();
+                continue;
+            }
+            self.healthy_heartbeats = 0;
+
+            // The loop is still there, it's just busy. Starting another one would tick twice.
+            if self.shared_header.timer_core_alive.load(Acquire) {
+                warn!("timer-core loop missed its heartbeat, but it is still alive.");
+                continue;
+            }
+
+            if let Err(e) = self.restart() {
+                error!("{}", e);
+
+                if let Some(on_irrecoverable_failure) = self.on_irrecoverable_failure.as_ref() {
+                    (on_irrecoverable_failure.0)(&e);
+                }
+                return;
+            }
+        }
+    }
+
+    fn is_heartbeat_missed(&self) -> bool {
+        let last_tick_timestamp = self.shared_header.global_time.load(Acquire);
+        // A sleeping loop isn't expected to tick until it wakes up.
+        let idle_until = self.shared_header.idle_until.load(Acquire);
+        timestamp().saturating_sub(last_tick_timestamp.max(idle_until)) >= MISSED_HEARTBEAT_SECONDS
+    }
+
+    // Restarts spread over a long run don't add up to giving the loop up.
+    fn on_healthy_heartbeat(&mut self) {
+        self.healthy_heartbeats = self.healthy_heartbeats.saturating_add(1);
+
+        if self.restart_count > 0 && self.healthy_heartbeats >= HEALTHY_HEARTBEATS {
+            info!(
+                "timer-core loop healthy for {} heartbeats, restart count reset.",
+                self.healthy_heartbeats
+            );
+            self.restart_count = 0;
+        }
+    }
+
+    fn restart(&mut self) -> Result<(), SchedulerError> {
+        if self.restart_count >= MAX_SCHEDULER_RESTARTS {
+            return Err(SchedulerError::RestartsExhausted(self.restart_count));
+        }
+        self.restart_count += 1;
+
+        error!(
+            "timer-core loop died, restarting it ({}/{}).",
+            self.restart_count, MAX_SCHEDULER_RESTARTS
+        );
+
+        self.reseed();
+        spawn_timer_core(self.timer_event_sender.clone(), self.shared_header.clone())?;
+
+        self.timer_event_sender
+            .try_send(TimerEvent::SchedulerRestarted(self.restart_count))
+            .unwrap_or_else(|e| error!("{}", e));
+
+        Ok(())
+    }
+
+    // The tasks are put back in the wheel relative to the current second hand,
+    // fires missed while the loop was dead are handled by the misfire policy.
+    fn reseed(&self) {
+        let wheel_queue = &self.shared_header.wheel_queue;
+        let task_flag_map = &self.shared_header.task_flag_map;
+
+        let mut tasks: Vec<Task> = Vec::new();
+        for mut slot in wheel_queue.iter_mut() {
+            tasks.extend(slot.value_mut().drain_tasks());
+        }
+
+        // The task that was being maintained when the loop died went down with it,
+        // the paused tasks are out of the wheel but may still be put back.
+        let task_ids: HashSet<u64> = tasks.iter().map(|task| task.task_id).collect();
+        let paused_tasks = &self.shared_header.paused_tasks;
+        task_flag_map.retain(|task_id, task_mark| {
+            let kept = task_ids.contains(task_id)
+                || paused_tasks.contains_key(task_id)
+                || wheel_queue
+                    .get(&task_mark.get_slot_mark())
+                    .map(|slot| slot.value().contains_task(*task_id))
+                    .unwrap_or(false);
+
+            if !kept {
+                error!("task-id: {} was lost with the timer-core loop.", task_id);
+            }
+            kept
+        });
+
+        let now = timestamp();
+        reseed_tasks(&self.shared_header, tasks, now);
+
+        // Give the new loop a fresh heartbeat.
+        self.shared_header.global_time.store(now, Release);
+    }
+}
+
+/// Start a timer-core loop on its own thread.
+pub(crate) fn spawn_timer_core(
+    timer_event_sender: TimerEventSender,
+    shared_header: SharedHeader,
+) -> io::Result<()> {
+    let runtime_instance = &shared_header.runtime_instance;
+    if runtime_instance.kind == RuntimeKind::Tokio && runtime_instance.inner.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Missing tokio runtime.",
+        ));
+    }
+
+    Builder::new()
+        .name("async_schedule".into())
+        .spawn(move || {
+            shared_header.runtime_instance.clone().block_on(async {
+                debug!(" `async_schedule` start.");
+                let mut timer = Timer::new(timer_event_sender, shared_header);
+                timer.async_schedule().await;
+            })
+        })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn test_reseed() -> anyhow::Result<()> {
+        use super::{SharedHeader, TimerEvent, Watchdog};
+        use crate::prelude::*;
+        use crate::timer::TaskMark;
+        use smol::channel::unbounded;
+
+        let (s, _) = unbounded::<TimerEvent>();
+        let shared_header = SharedHeader::default();
+
+        let mut task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(1)
+            .spawn_async_routine(|| async {})?;
+        task.get_next_exec_timestamp();
+
+        shared_header
+            .wheel_queue
+            .get_mut(&100)
+            .unwrap()
+            .value_mut()
+            .add_task(task);
+
+        let mut task_mark = TaskMark::default();
+        task_mark.set_task_id(1).set_slot_mark(100);
+        shared_header.task_flag_map.insert(1, task_mark);
+
+        // Task 2 was being maintained when the loop died.
+        let mut task_mark = TaskMark::default();
+        task_mark.set_task_id(2).set_slot_mark(200);
+        shared_header.task_flag_map.insert(2, task_mark);
+
+        // Task 3 is paused, it's out of the wheel until its removal is undone.
+        let mut task_mark = TaskMark::default();
+        task_mark.set_task_id(3).set_slot_mark(300);
+        shared_header.task_flag_map.insert(3, task_mark);
+        shared_header.paused_tasks.insert(3, u64::MAX);
+
+        Watchdog::new(shared_header.clone(), s, None).reseed();
+
+        assert!(!shared_header.task_flag_map.contains_key(&2));
+        assert!(shared_header.task_flag_map.contains_key(&3));
+
+        let slot_mark = shared_header.task_flag_map.get(&1).unwrap().get_slot_mark();
+        assert!(slot_mark <= 1);
+        assert!(shared_header
+            .wheel_queue
+            .get(&slot_mark)
+            .unwrap()
+            .contains_task(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restart_count_reset() {
+        use super::{SharedHeader, TimerEvent, Watchdog, HEALTHY_HEARTBEATS};
+        use smol::channel::unbounded;
+
+        let (s, _) = unbounded::<TimerEvent>();
+        let mut watchdog = Watchdog::new(SharedHeader::default(), s, None);
+        watchdog.restart_count = 2;
+
+        for _ in 1..HEALTHY_HEARTBEATS {
+            watchdog.on_healthy_heartbeat();
+        }
+        assert_eq!(watchdog.restart_count, 2);
+
+        watchdog.on_healthy_heartbeat();
+        assert_eq!(watchdog.restart_count, 0);
+    }
+}
@@ -0,0 +1,119 @@
+//! Wheel dump
+//! A snapshot of the timing wheel, for diagnosing tasks that never fire.
+use super::timer_core::DEFAULT_TIMER_SLOT_COUNT;
+use crate::prelude::*;
+
+use std::fmt;
+
+// How many of the upcoming fires are kept in a dump.
+pub(crate) const WHEEL_DUMP_NEXT_FIRES: usize = 16;
+
+/// Occupancy of a slot of the timing wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotDump {
+    /// The position of the slot on the wheel.
+    pub slot: u64,
+    /// How many tasks wait in the slot.
+    pub task_count: usize,
+    /// How many of them still have laps of the wheel to wait (they cascade on each visit).
+    pub cascade_count: usize,
+}
+
+/// A snapshot of the timing wheel, taken by `DelayTimer::debug_dump`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WheelDump {
+    /// The slot the second hand points at.
+    pub second_hand: u64,
+    /// The time (timestamp-seconds) of the last tick.
+    pub timestamp: u64,
+    /// The occupied slots, in wheel order from the second hand.
+    pub slots: Vec<SlotDump>,
+    /// The next fires as `(task_id, time)`, by time, as the wheel will reach them.
+    pub next_fires: Vec<(u64, u64)>,
+}
+
+impl WheelDump {
+    pub(crate) fn new(shared_header: &SharedHeader) -> Self {
+        let second_hand = shared_header.second_hand.current_second_hand();
+        let timestamp = shared_header
+            .global_time
+            .load(std::sync::atomic::Ordering::Acquire);
+
+        let mut slots = Vec::new();
+        let mut next_fires = Vec::new();
+        for slot_ref in shared_header.wheel_queue.iter() {
+            let slot = *slot_ref.key();
+            let distance =
+                (slot + DEFAULT_TIMER_SLOT_COUNT - second_hand) % DEFAULT_TIMER_SLOT_COUNT;
+
+            let mut slot_dump = SlotDump {
+                slot,
+                task_count: 0,
+                cascade_count: 0,
+            };
+            for task in slot_ref.value().tasks() {
+                let cylinder_line = task.get_cylinder_line();
+                slot_dump.task_count += 1;
+                if cylinder_line > 0 {
+                    slot_dump.cascade_count += 1;
+                }
+
+                let fire_time = timestamp + distance + cylinder_line * DEFAULT_TIMER_SLOT_COUNT;
+                next_fires.push((task.task_id, fire_time));
+            }
+
+            if slot_dump.task_count > 0 {
+                slots.push(slot_dump);
+            }
+        }
+
+        slots.sort_by_key(|slot_dump| {
+            (slot_dump.slot + DEFAULT_TIMER_SLOT_COUNT - second_hand) % DEFAULT_TIMER_SLOT_COUNT
+        });
+        next_fires.sort_by_key(|&(task_id, fire_time)| (fire_time, task_id));
+        next_fires.truncate(WHEEL_DUMP_NEXT_FIRES);
+
+        WheelDump {
+            second_hand,
+            timestamp,
+            slots,
+            next_fires,
+        }
+    }
+
+    /// How many tasks wait in the wheel.
+    pub fn task_count(&self) -> usize {
+        self.slots
+            .iter()
+            .map(|slot_dump| slot_dump.task_count)
+            .sum()
+    }
+}
+
+impl fmt::Display for WheelDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "wheel: second-hand {} at {}, {} tasks in {} slots",
+            self.second_hand,
+            self.timestamp,
+            self.task_count(),
+            self.slots.len()
+        )?;
+
+        write!(f, "slots:")?;
+        for slot_dump in self.slots.iter() {
+            write!(f, " {}:{}", slot_dump.slot, slot_dump.task_count)?;
+            if slot_dump.cascade_count > 0 {
+                write!(f, "(+{})", slot_dump.cascade_count)?;
+            }
+        }
+        writeln!(f)?;
+
+        write!(f, "next:")?;
+        for (task_id, fire_time) in self.next_fires.iter() {
+            write!(f, " #{}@{}", task_id, fire_time)?;
+        }
+        Ok(())
+    }
+}
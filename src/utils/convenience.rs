@@ -117,9 +117,16 @@ pub mod functions {
 
 /// cron expression syntax sugar related.
 pub mod cron_expression_grammatical_candy {
+    use crate::prelude::{cron_clock::Schedule, FrequencyAnalyzeError};
+    use crate::timer::task::{FrequencyCronStr, ScheduleIteratorTimeZone};
+
+    use std::fmt;
     use std::ops::Deref;
+    use std::str::FromStr;
+
+    use chrono::Weekday;
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, Eq)]
     // Here, for the convenience of the user to create CandyCronStr,
     // it is the internal type of CandyCronStr that from &'static str is changed to String,
     // so that the user can construct CandyCronStr according to the indefinite conditions of the runtime.
@@ -135,8 +142,28 @@ pub mod cron_expression_grammatical_candy {
         }
     }
 
-    #[derive(Debug, Copy, Clone)]
+    impl fmt::Display for CandyCronStr {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    /// Parses a cron-expression, it's rejected if it doesn't parse as one.
+    impl FromStr for CandyCronStr {
+        type Err = FrequencyAnalyzeError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let expression = s.trim();
+            Schedule::from_str(expression)?;
+            Ok(CandyCronStr(expression.to_owned()))
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     /// Syntactic sugar for cron expressions.
+    ///
+    /// The time zone of the `*At` variants is applied by
+    /// `TaskBuilder::set_frequency_*_by_candy`, as a `CandyCronStr` they only carry the expression.
     pub enum CandyCron {
         /// This variant for Secondly.
         Secondly,
@@ -152,9 +179,50 @@ pub mod cron_expression_grammatical_candy {
         Monthly,
         /// This variant for Yearly.
         Yearly,
+        /// Every day at `hour:minute` in `tz`.
+        DailyAt {
+            /// Hour of the day, in `0..24`.
+            hour: u32,
+            /// Minute of the hour, in `0..60`.
+            minute: u32,
+            /// The time zone the time is in.
+            tz: ScheduleIteratorTimeZone,
+        },
+        /// Every week on `weekday` at `hour:minute` in `tz`.
+        WeeklyAt {
+            /// Day of the week.
+            weekday: Weekday,
+            /// Hour of the day, in `0..24`.
+            hour: u32,
+            /// Minute of the hour, in `0..60`.
+            minute: u32,
+            /// The time zone the time is in.
+            tz: ScheduleIteratorTimeZone,
+        },
+        /// Every month on `day` at `hour:minute` in `tz`.
+        MonthlyAt {
+            /// Day of the month, in `1..=31`, the months without it are skipped.
+            day: u32,
+            /// Hour of the day, in `0..24`.
+            hour: u32,
+            /// Minute of the hour, in `0..60`.
+            minute: u32,
+            /// The time zone the time is in.
+            tz: ScheduleIteratorTimeZone,
+        },
     }
     use CandyCron::*;
 
+    impl CandyCron {
+        /// The time zone the variant is in, `None` for the shortcuts that don't carry one.
+        pub fn time_zone(&self) -> Option<ScheduleIteratorTimeZone> {
+            match self {
+                DailyAt { tz, .. } | WeeklyAt { tz, .. } | MonthlyAt { tz, .. } => Some(*tz),
+                _ => None,
+            }
+        }
+    }
+
     impl From<CandyCron> for CandyCronStr {
         fn from(value: CandyCron) -> CandyCronStr {
             match value {
@@ -165,11 +233,45 @@ pub mod cron_expression_grammatical_candy {
                 Weekly => CandyCronStr(String::from("@weekly")),
                 Monthly => CandyCronStr(String::from("@monthly")),
                 Yearly => CandyCronStr(String::from("@yearly")),
+                DailyAt { hour, minute, .. } => {
+                    CandyCronStr(format!("0 {} {} * * * *", minute, hour))
+                }
+                WeeklyAt {
+                    weekday,
+                    hour,
+                    minute,
+                    ..
+                } => CandyCronStr(format!("0 {} {} * * {} *", minute, hour, weekday)),
+                MonthlyAt {
+                    day, hour, minute, ..
+                } => CandyCronStr(format!("0 {} {} {} * * *", minute, hour, day)),
             }
         }
     }
 
-    #[derive(Debug, Copy, Clone)]
+    /// Parses the shortcuts, as in `@daily` or `daily`.
+    ///
+    /// The `*At` variants aren't parsed, their time zone isn't in the expression.
+    impl FromStr for CandyCron {
+        type Err = FrequencyAnalyzeError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let name = s.trim();
+            let candy_cron = match name.trim_start_matches('@').to_ascii_lowercase().as_str() {
+                "secondly" => Secondly,
+                "minutely" => Minutely,
+                "hourly" => Hourly,
+                "daily" => Daily,
+                "weekly" => Weekly,
+                "monthly" => Monthly,
+                "yearly" => Yearly,
+                _ => return Err(FrequencyAnalyzeError::UnknownCandy(name.to_owned())),
+            };
+            Ok(candy_cron)
+        }
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
     ///Enumerated values of repeating types.
     pub enum CandyFrequency<T: Into<CandyCronStr>> {
         ///Repeat once.
@@ -179,6 +281,74 @@ pub mod cron_expression_grammatical_candy {
         ///Type of countdown.
         CountDown(u32, T),
     }
+
+    impl<T: Into<CandyCronStr>> CandyFrequency<T> {
+        /// Turns the candy into its cron-expression, keeping the repeating type.
+        pub fn into_cron_str(self) -> CandyFrequency<CandyCronStr> {
+            match self {
+                CandyFrequency::Once(candy) => CandyFrequency::Once(candy.into()),
+                CandyFrequency::Repeated(candy) => CandyFrequency::Repeated(candy.into()),
+                CandyFrequency::CountDown(count, candy) => {
+                    CandyFrequency::CountDown(count, candy.into())
+                }
+            }
+        }
+    }
+
+    impl CandyFrequency<CandyCronStr> {
+        /// The `Frequency` of the same schedule, it borrows the cron-expression.
+        ///
+        /// A candy of another type is turned into one by `into_cron_str` first.
+        pub fn to_frequency(&self) -> FrequencyCronStr<'_> {
+            match self {
+                CandyFrequency::Once(candy) => FrequencyCronStr::Once(candy),
+                CandyFrequency::Repeated(candy) => FrequencyCronStr::Repeated(candy),
+                CandyFrequency::CountDown(count, candy) => {
+                    FrequencyCronStr::CountDown(*count as u64, candy)
+                }
+            }
+        }
+    }
+
+    /// Writes `once <expression>`, `repeated <expression>` or `countdown <count> <expression>`,
+    /// the form `FromStr` parses back.
+    impl fmt::Display for CandyFrequency<CandyCronStr> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                CandyFrequency::Once(candy) => write!(f, "once {}", candy),
+                CandyFrequency::Repeated(candy) => write!(f, "repeated {}", candy),
+                CandyFrequency::CountDown(count, candy) => {
+                    write!(f, "countdown {} {}", count, candy)
+                }
+            }
+        }
+    }
+
+    impl FromStr for CandyFrequency<CandyCronStr> {
+        type Err = FrequencyAnalyzeError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let malformed = || FrequencyAnalyzeError::MalformedCandyFrequency(s.to_owned());
+
+            let (kind, rest) = s
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(malformed)?;
+            match kind.to_ascii_lowercase().as_str() {
+                "once" => Ok(CandyFrequency::Once(rest.parse()?)),
+                "repeated" => Ok(CandyFrequency::Repeated(rest.parse()?)),
+                "countdown" => {
+                    let (count, expression) = rest
+                        .trim_start()
+                        .split_once(char::is_whitespace)
+                        .ok_or_else(malformed)?;
+                    let count = count.parse().map_err(|_| malformed())?;
+                    Ok(CandyFrequency::CountDown(count, expression.parse()?))
+                }
+                _ => Err(malformed()),
+            }
+        }
+    }
 }
 
 /// Provide a template function that supports dynamic generation of closures.
@@ -218,6 +388,96 @@ mod tests {
         assert_eq!(s, "@secondly");
     }
 
+    #[test]
+    fn test_cron_candy_at() {
+        use super::cron_expression_grammatical_candy::{CandyCron, CandyCronStr};
+        use crate::prelude::{ScheduleIteratorTimeZone, Weekday};
+        use cron_clock::Schedule;
+        use std::str::FromStr;
+
+        let tz = ScheduleIteratorTimeZone::Local;
+        let candy_crons = [
+            (
+                CandyCron::DailyAt {
+                    hour: 3,
+                    minute: 5,
+                    tz,
+                },
+                "0 5 3 * * * *",
+            ),
+            (
+                CandyCron::WeeklyAt {
+                    weekday: Weekday::Mon,
+                    hour: 23,
+                    minute: 0,
+                    tz,
+                },
+                "0 0 23 * * Mon *",
+            ),
+            (
+                CandyCron::MonthlyAt {
+                    day: 31,
+                    hour: 0,
+                    minute: 30,
+                    tz,
+                },
+                "0 30 0 31 * * *",
+            ),
+        ];
+
+        for (candy_cron, expression) in candy_crons.iter() {
+            assert_eq!(candy_cron.time_zone(), Some(tz));
+
+            let s = <CandyCron as Into<CandyCronStr>>::into(*candy_cron).0;
+            assert_eq!(s, *expression);
+            assert!(Schedule::from_str(&s).is_ok());
+        }
+
+        assert_eq!(CandyCron::Daily.time_zone(), None);
+    }
+
+    #[test]
+    fn test_candy_round_trip() {
+        use super::cron_expression_grammatical_candy::{CandyCron, CandyCronStr, CandyFrequency};
+        use crate::prelude::Frequency;
+
+        assert_eq!("@daily".parse::<CandyCron>().unwrap(), CandyCron::Daily);
+        assert_eq!(
+            "Minutely".parse::<CandyCron>().unwrap(),
+            CandyCron::Minutely
+        );
+        assert!("@fortnightly".parse::<CandyCron>().is_err());
+        assert!("every day".parse::<CandyCronStr>().is_err());
+
+        let candy_frequency = CandyFrequency::CountDown(3, CandyCron::Hourly).into_cron_str();
+        let frequency = candy_frequency.to_frequency();
+        assert!(matches!(frequency, Frequency::CountDown(3, "@hourly")));
+        assert_eq!(frequency.to_candy(), Some(candy_frequency.clone()));
+
+        let s = candy_frequency.to_string();
+        assert_eq!(s, "countdown 3 @hourly");
+        assert_eq!(
+            s.parse::<CandyFrequency<CandyCronStr>>().unwrap(),
+            candy_frequency
+        );
+
+        let candy_frequency: CandyFrequency<CandyCronStr> =
+            "repeated 0 30 9 * * Mon *".parse().unwrap();
+        assert!(matches!(
+            candy_frequency.to_frequency(),
+            Frequency::Repeated("0 30 9 * * Mon *")
+        ));
+        assert!("hourly @hourly"
+            .parse::<CandyFrequency<CandyCronStr>>()
+            .is_err());
+        assert!("countdown @hourly"
+            .parse::<CandyFrequency<CandyCronStr>>()
+            .is_err());
+
+        assert_eq!(Frequency::Multi(vec!["@daily", "@hourly"]).to_candy(), None);
+        assert_eq!(Frequency::CountDown(u64::MAX, "@daily").to_candy(), None);
+    }
+
     #[test]
     fn test_customization_cron_candy() {
         use super::cron_expression_grammatical_candy::CandyCronStr;
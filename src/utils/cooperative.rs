@@ -0,0 +1,174 @@
+//! Cooperative
+//! Yield points for the bodies running long CPU loops on the async runtime,
+//! so that they don't hold up the other tasks sharing its workers.
+//!
+//! An instance that times out or is cancelled is aborted at its next yield point,
+//! one cancelled by `TaskInstance::cancel_graceful` can wind down on its own through its token.
+use crate::prelude::*;
+
+use std::error::Error;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use futures::stream::Stream;
+
+/// The cancellation token of the instance was triggered while it was looping,
+/// see `yield_every!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The task instance was cancelled.")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// A `Stream` over the items of an iterator, which yields to the runtime every `every` items,
+/// and ends as soon as the cancellation token of the instance is triggered.
+///
+/// ```ignore
+/// let mut rows = CooperativeIter::new(rows, 1000).with_cancellation(context.get_cancellation_token());
+/// while let Some(row) = rows.next().await {
+///     crunch(row);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CooperativeIter<I> {
+    iter: I,
+    every: usize,
+    taken: usize,
+    yielded: bool,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl<I: Iterator> CooperativeIter<I> {
+    /// Wrap `iter`, yielding every `every` items (one at least).
+    pub fn new(iter: I, every: usize) -> Self {
+        CooperativeIter {
+            iter,
+            every: every.max(1),
+            taken: 0,
+            yielded: false,
+            cancellation_token: None,
+        }
+    }
+
+    /// End the stream once `cancellation_token` is triggered, e.g. the token of `TaskContext`.
+    pub fn with_cancellation(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Whether the stream ended because the instance was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+impl<I: Iterator + Unpin> Stream for CooperativeIter<I> {
+    type Item = I::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let cancelled = self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::observe);
+        if cancelled {
+            return Poll::Ready(None);
+        }
+
+        // Yield once before each batch but the first, the task is woken right away.
+        if self.taken > 0 && self.taken % self.every == 0 && !self.yielded {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.yielded = false;
+        self.taken += 1;
+        Poll::Ready(self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+// Used by `yield_every!`, the steps are counted by the call site.
+#[doc(hidden)]
+pub async fn yield_every(
+    steps: &AtomicUsize,
+    every: usize,
+    cancellation_token: Option<&CancellationToken>,
+) -> Result<(), Cancelled> {
+    if steps.fetch_add(1, Ordering::Relaxed) % every.max(1) == 0 {
+        smol::future::yield_now().await;
+    }
+
+    match cancellation_token {
+        Some(cancellation_token) if cancellation_token.observe() => Err(Cancelled),
+        _ => Ok(()),
+    }
+}
+
+/// Yield to the runtime every `n` times it's reached, in a long loop of an async body.
+///
+/// With the cancellation token of the instance, it evaluates to `Err(Cancelled)`
+/// once the token is triggered, so that the loop can wind down right away.
+///
+/// The times are counted by the call site, across the instances running it.
+///
+/// ```ignore
+/// for row in rows {
+///     yield_every!(1000);
+///     crunch(row);
+/// }
+///
+/// let cancellation_token = context.get_cancellation_token();
+/// for row in rows {
+///     yield_every!(1000, cancellation_token)?;
+///     crunch(row);
+/// }
+/// ```
+#[macro_export]
+macro_rules! yield_every {
+    ($n:expr) => {{
+        static STEPS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        // Without a token it can't be cancelled.
+        let _ = $crate::utils::cooperative::yield_every(&STEPS, $n, None).await;
+    }};
+    ($n:expr, $cancellation_token:expr) => {{
+        static STEPS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        $crate::utils::cooperative::yield_every(&STEPS, $n, Some(&$cancellation_token)).await
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CooperativeIter;
+    use crate::prelude::CancellationToken;
+
+    use futures::stream::StreamExt;
+
+    #[test]
+    fn test_cooperative_iter() {
+        let rows = CooperativeIter::new(0..10, 3);
+        assert_eq!(
+            smol::block_on(rows.collect::<Vec<_>>()),
+            (0..10).collect::<Vec<_>>()
+        );
+
+        let cancellation_token = CancellationToken::default();
+        let mut rows = CooperativeIter::new(0..10, 3).with_cancellation(cancellation_token.clone());
+        assert_eq!(smol::block_on(rows.next()), Some(0));
+        cancellation_token.cancel();
+        assert_eq!(smol::block_on(rows.next()), None);
+        assert!(rows.is_cancelled());
+    }
+}
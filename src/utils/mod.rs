@@ -1,11 +1,20 @@
 //! utils is a tool module that provides easy shell-command parsing,
 //! and functions that generate closures.
 pub mod convenience;
+pub mod cooperative;
 pub mod parse;
+pub mod runtime_adapter;
+pub mod secrets;
 
 cfg_status_report!(
     pub mod status_report;
 );
 
+pub use crate::yield_every;
 pub use convenience::functions;
-pub use parse::shell_command::parse_and_run;
+pub use parse::shell_command::{
+    parse_and_run, shell_quote, ProcessTaskBuilder, ResourceLimits, Shell,
+};
+
+#[cfg(feature = "json-result")]
+pub use parse::shell_command::JsonResult;
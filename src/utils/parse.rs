@@ -13,18 +13,22 @@ pub mod shell_command {
     use std::collections::LinkedList;
     use std::convert::AsRef;
     use std::ffi::OsStr;
+    use std::fmt;
     use std::fs::{File, OpenOptions};
+    #[cfg(unix)]
+    use std::io;
     use std::iter::Iterator;
     use std::mem;
     use std::ops::{Deref, DerefMut};
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use std::process::{Child as StdChild, Command, Output, Stdio};
+    use std::sync::Arc;
 
     /// The linkedlist of ChildGuard.
     pub type ChildGuardList<T> = LinkedList<ChildGuard<T>>;
 
     macro_rules! impl_command_unify{
-        ($($command:ty => $child:ty),+) => {
+        ($($command:ty => $child:ty : $command_ext:ident),+) => {
             $(impl CommandUnify<$child> for $command {
                 fn new<S: AsRef<OsStr>>(program: S) -> Self {
                     Self::new(program.as_ref())
@@ -38,6 +42,15 @@ pub mod shell_command {
                     self
                 }
 
+                fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+                where
+                    K: AsRef<OsStr>,
+                    V: AsRef<OsStr>,
+                {
+                    self.env(key, val);
+                    self
+                }
+
                 fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
                     self.stdin(cfg);
                     self
@@ -56,6 +69,27 @@ pub mod shell_command {
                 fn spawn(&mut self) -> AnyResult<$child> {
                     Ok(self.spawn()?)
                 }
+
+                #[cfg(unix)]
+                unsafe fn pre_exec<F>(&mut self, f: F) -> &mut Self
+                where
+                    F: FnMut() -> io::Result<()> + Send + Sync + 'static,
+                {
+                    $command_ext::pre_exec(self, f);
+                    self
+                }
+
+                #[cfg(unix)]
+                fn uid(&mut self, id: u32) -> &mut Self {
+                    $command_ext::uid(self, id);
+                    self
+                }
+
+                #[cfg(unix)]
+                fn gid(&mut self, id: u32) -> &mut Self {
+                    $command_ext::gid(self, id);
+                    self
+                }
             })+
         }
     }
@@ -71,6 +105,12 @@ pub mod shell_command {
             I: IntoIterator<Item = S>,
             S: AsRef<OsStr>;
 
+        /// Inserts or updates an environment variable of the child process.
+        fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+        where
+            K: AsRef<OsStr>,
+            V: AsRef<OsStr>;
+
         /// Configuration for the child process's standard input (stdin) handle.
         fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self;
 
@@ -82,14 +122,43 @@ pub mod shell_command {
 
         /// Executes the command as a child process, returning a handle to it.
         fn spawn(&mut self) -> AnyResult<Child>;
+
+        /// Schedules a closure to be run in the child process just before the program is executed.
+        ///
+        /// # Safety
+        ///
+        /// The closure runs in the forked child, see `std::os::unix::process::CommandExt::pre_exec`.
+        #[cfg(unix)]
+        unsafe fn pre_exec<F>(&mut self, f: F) -> &mut Self
+        where
+            F: FnMut() -> io::Result<()> + Send + Sync + 'static;
+
+        /// Sets the child process's user ID.
+        #[cfg(unix)]
+        fn uid(&mut self, id: u32) -> &mut Self;
+
+        /// Sets the child process's group ID.
+        #[cfg(unix)]
+        fn gid(&mut self, id: u32) -> &mut Self;
     }
 
-    impl_command_unify!(Command => StdChild,SmolCommand => SmolChild);
+    #[cfg(unix)]
+    use smol::process::unix::CommandExt as SmolCommandExt;
+    #[cfg(unix)]
+    use std::os::unix::process::CommandExt as StdCommandExt;
+
+    impl_command_unify!(
+        Command => StdChild : StdCommandExt,
+        SmolCommand => SmolChild : SmolCommandExt
+    );
 
     use std::convert::TryInto;
     use tokio::process::Child as TokioChild;
     use tokio::process::Command as TokioCommand;
-    impl_command_unify!(TokioCommand => TokioChild);
+    impl_command_unify!(TokioCommand => TokioChild : TokioCommand);
+
+    /// The callback of `ChildUnify::wait_with_output_lines`.
+    pub type OutputLineCallback = dyn Fn(&str) + Send + Sync;
 
     #[async_trait]
     /// Trait abstraction of multiple library process handles.
@@ -99,11 +168,35 @@ pub mod shell_command {
 
         /// Executes the command as a child process, waiting for it to finish and collecting all of its output.
         async fn wait_with_output(self) -> AnyResult<Output>;
+
+        /// Like `wait_with_output`, calling `on_output_line` with each line of stdout as it's written.
+        ///
+        /// The default implementation only calls it once the child has finished.
+        async fn wait_with_output_lines(
+            self,
+            on_output_line: &OutputLineCallback,
+        ) -> AnyResult<Output>
+        where
+            Self: Sized,
+        {
+            let output = self.wait_with_output().await?;
+            for line in output.stdout.split_inclusive(|b| *b == b'\n') {
+                emit_output_line(on_output_line, line);
+            }
+
+            Ok(output)
+        }
+
         /// Convert stdout to stdio.
         async fn stdout_to_stdio(&mut self) -> Option<Stdio>;
 
         /// Kill the process child.
         fn kill(&mut self) -> AnyResult<()>;
+
+        /// The OS-assigned process identifier of the child, if it's still known.
+        fn id(&self) -> Option<u32> {
+            None
+        }
     }
 
     #[async_trait]
@@ -121,6 +214,10 @@ pub mod shell_command {
         fn kill(&mut self) -> AnyResult<()> {
             Ok(self.kill()?)
         }
+
+        fn id(&self) -> Option<u32> {
+            Some(self.id())
+        }
     }
 
     #[async_trait]
@@ -143,6 +240,44 @@ pub mod shell_command {
             Ok(self.output().await?)
         }
 
+        async fn wait_with_output_lines(
+            mut self,
+            on_output_line: &OutputLineCallback,
+        ) -> AnyResult<Output> {
+            use smol::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+            let stdout = self.stdout.take();
+            let stderr = self.stderr.take();
+
+            let read_stdout = async {
+                let mut stdout_buffer = Vec::new();
+                if let Some(stdout) = stdout {
+                    let mut reader = BufReader::new(stdout);
+                    let mut line = Vec::new();
+                    while reader.read_until(b'\n', &mut line).await? > 0 {
+                        emit_output_line(on_output_line, &line);
+                        stdout_buffer.append(&mut line);
+                    }
+                }
+                std::io::Result::Ok(stdout_buffer)
+            };
+            let read_stderr = async {
+                let mut stderr_buffer = Vec::new();
+                if let Some(mut stderr) = stderr {
+                    stderr.read_to_end(&mut stderr_buffer).await?;
+                }
+                std::io::Result::Ok(stderr_buffer)
+            };
+
+            let (stdout, stderr) = futures::future::try_join(read_stdout, read_stderr).await?;
+            let status = self.status().await?;
+            Ok(Output {
+                status,
+                stdout,
+                stderr,
+            })
+        }
+
         async fn stdout_to_stdio(&mut self) -> Option<Stdio> {
             if let Some(stdout) = self.stdout.take() {
                 return stdout.into_stdio().await.ok();
@@ -153,6 +288,10 @@ pub mod shell_command {
         fn kill(&mut self) -> AnyResult<()> {
             Ok(self.kill()?)
         }
+
+        fn id(&self) -> Option<u32> {
+            Some(self.id())
+        }
     }
 
     #[async_trait]
@@ -165,6 +304,44 @@ pub mod shell_command {
             Ok(self.wait_with_output().await?)
         }
 
+        async fn wait_with_output_lines(
+            mut self,
+            on_output_line: &OutputLineCallback,
+        ) -> AnyResult<Output> {
+            use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+
+            let stdout = self.stdout.take();
+            let stderr = self.stderr.take();
+
+            let read_stdout = async {
+                let mut stdout_buffer = Vec::new();
+                if let Some(stdout) = stdout {
+                    let mut reader = BufReader::new(stdout);
+                    let mut line = Vec::new();
+                    while reader.read_until(b'\n', &mut line).await? > 0 {
+                        emit_output_line(on_output_line, &line);
+                        stdout_buffer.append(&mut line);
+                    }
+                }
+                std::io::Result::Ok(stdout_buffer)
+            };
+            let read_stderr = async {
+                let mut stderr_buffer = Vec::new();
+                if let Some(mut stderr) = stderr {
+                    stderr.read_to_end(&mut stderr_buffer).await?;
+                }
+                std::io::Result::Ok(stderr_buffer)
+            };
+
+            let (stdout, stderr) = futures::future::try_join(read_stdout, read_stderr).await?;
+            let status = TokioChild::wait(&mut self).await?;
+            Ok(Output {
+                status,
+                stdout,
+                stderr,
+            })
+        }
+
         async fn stdout_to_stdio(&mut self) -> Option<Stdio> {
             self.stdout.take().and_then(|s| s.try_into().ok())
         }
@@ -176,7 +353,16 @@ pub mod shell_command {
         fn kill(&mut self) -> AnyResult<()> {
             Ok(self.start_kill()?)
         }
+
+        fn id(&self) -> Option<u32> {
+            self.id()
+        }
     }
+    // Pass a line of stdout to the callback, without its line break.
+    fn emit_output_line(on_output_line: &OutputLineCallback, line: &[u8]) {
+        on_output_line(String::from_utf8_lossy(line).trim_end_matches(&['\r', '\n'][..]));
+    }
+
     #[derive(Debug, Default)]
     /// Guarding of process handles.
     pub struct ChildGuard<Child: ChildUnify> {
@@ -222,6 +408,24 @@ pub mod shell_command {
                 "Without child for waiting.".to_string(),
             ))
         }
+
+        /// Await on `ChildGuard` and get `Output`,
+        /// `on_output_line` is called with each line of stdout as it's written.
+        pub async fn wait_with_output_lines(
+            mut self,
+            on_output_line: &OutputLineCallback,
+        ) -> Result<Output, CommandChildError> {
+            if let Some(child) = self.child.take() {
+                return child
+                    .wait_with_output_lines(on_output_line)
+                    .await
+                    .map_err(|e| CommandChildError::DisCondition(e.to_string()));
+            }
+
+            Err(CommandChildError::DisCondition(
+                "Without child for waiting.".to_string(),
+            ))
+        }
     }
 
     impl<Child: ChildUnify> Drop for ChildGuard<Child> {
@@ -247,6 +451,612 @@ pub mod shell_command {
         }
     }
 
+    /// Limits on the resources of the processes of a task.
+    ///
+    /// They're only supported on Linux, elsewhere the processes fail to spawn if any is set.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ResourceLimits {
+        /// Niceness of the processes, from -20 (most favorable) to 19 (least favorable).
+        pub nice: Option<i32>,
+        /// Maximum size of the address space of each process (`RLIMIT_AS`), in bytes.
+        pub address_space: Option<u64>,
+        /// Maximum CPU time of each process (`RLIMIT_CPU`), in seconds.
+        pub cpu_time: Option<u64>,
+        /// The cgroup v2 the processes are placed into, e.g. `/sys/fs/cgroup/batch.slice`.
+        pub cgroup: Option<PathBuf>,
+    }
+
+    impl ResourceLimits {
+        fn is_empty(&self) -> bool {
+            self == &ResourceLimits::default()
+        }
+
+        // Apply the limits in the child, between fork and exec.
+        #[cfg(target_os = "linux")]
+        fn apply<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            command: &mut Command,
+        ) -> Result<(), CommandChildError> {
+            use std::ffi::CString;
+            use std::os::unix::ffi::OsStrExt;
+
+            let ResourceLimits {
+                nice,
+                address_space,
+                cpu_time,
+                ref cgroup,
+            } = *self;
+
+            // Everything is allocated before forking, the hook only makes system calls.
+            let cgroup_procs = cgroup
+                .as_ref()
+                .map(|cgroup| CString::new(cgroup.join("cgroup.procs").as_os_str().as_bytes()))
+                .transpose()
+                .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
+
+            let hook = move || -> io::Result<()> {
+                if let Some(cgroup_procs) = cgroup_procs.as_ref() {
+                    join_cgroup(cgroup_procs)?;
+                }
+
+                // Safety: the calls only affect the calling process,
+                // and the `rlimit`s outlive them.
+                unsafe {
+                    if let Some(nice) = nice {
+                        cvt(libc::setpriority(libc::PRIO_PROCESS as _, 0, nice))?;
+                    }
+
+                    if let Some(address_space) = address_space {
+                        cvt(libc::setrlimit(libc::RLIMIT_AS, &rlimit(address_space)))?;
+                    }
+
+                    if let Some(cpu_time) = cpu_time {
+                        cvt(libc::setrlimit(libc::RLIMIT_CPU, &rlimit(cpu_time)))?;
+                    }
+                }
+
+                Ok(())
+            };
+
+            // Safety: the hook doesn't allocate nor take locks, it's safe after fork.
+            unsafe {
+                command.pre_exec(hook);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        fn apply<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            _command: &mut Command,
+        ) -> Result<(), CommandChildError> {
+            Err(CommandChildError::DisCondition(
+                "Resource limits are only supported on Linux.".to_string(),
+            ))
+        }
+    }
+
+    // Move the calling process into the cgroup, by writing `0` to its `cgroup.procs`.
+    #[cfg(target_os = "linux")]
+    fn join_cgroup(cgroup_procs: &std::ffi::CStr) -> io::Result<()> {
+        // Safety: the path is a valid C string, and the descriptor is closed before returning.
+        unsafe {
+            let fd = libc::open(cgroup_procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            if fd == -1 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let written = libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1);
+            let result = cvt(written as libc::c_int);
+            libc::close(fd);
+            result
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn rlimit(limit: u64) -> libc::rlimit {
+        libc::rlimit {
+            rlim_cur: limit as libc::rlim_t,
+            rlim_max: limit as libc::rlim_t,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn cvt(ret: libc::c_int) -> io::Result<()> {
+        if ret == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    // Whether the process named by the pidfile is alive, a missing or unreadable pidfile names none.
+    #[cfg(unix)]
+    pub(crate) fn is_pidfile_alive(pidfile: &Path) -> bool {
+        let pid = match std::fs::read_to_string(pidfile)
+            .ok()
+            .and_then(|content| content.trim().parse::<libc::pid_t>().ok())
+        {
+            Some(pid) if pid > 0 => pid,
+            _ => return false,
+        };
+
+        // Safety: the signal 0 only checks that the process exists.
+        match unsafe { libc::kill(pid, 0) } {
+            0 => true,
+            // It exists, but belongs to another user.
+            _ => io::Error::last_os_error().raw_os_error() == Some(libc::EPERM),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn is_pidfile_alive(_pidfile: &Path) -> bool {
+        false
+    }
+
+    // Start a new session in the child, between fork and exec.
+    #[cfg(unix)]
+    fn new_session<Child: ChildUnify, Command: CommandUnify<Child>>(
+        command: &mut Command,
+    ) -> Result<(), CommandChildError> {
+        let hook = || -> io::Result<()> {
+            // Safety: `setsid` only affects the calling process.
+            match unsafe { libc::setsid() } {
+                -1 => Err(io::Error::last_os_error()),
+                _ => Ok(()),
+            }
+        };
+
+        // Safety: the hook doesn't allocate nor take locks, it's safe after fork.
+        unsafe {
+            command.pre_exec(hook);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn new_session<Child: ChildUnify, Command: CommandUnify<Child>>(
+        _command: &mut Command,
+    ) -> Result<(), CommandChildError> {
+        Err(CommandChildError::DisCondition(
+            "Detaching the processes is only supported on Unix.".to_string(),
+        ))
+    }
+
+    #[cfg(unix)]
+    fn run_as<Child: ChildUnify, Command: CommandUnify<Child>>(
+        command: &mut Command,
+        user: &str,
+        group: &str,
+    ) -> Result<(), CommandChildError> {
+        let uid = user_id(user)?;
+        let gid = group_id(group)?;
+
+        command.gid(gid).uid(uid);
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn run_as<Child: ChildUnify, Command: CommandUnify<Child>>(
+        _command: &mut Command,
+        _user: &str,
+        _group: &str,
+    ) -> Result<(), CommandChildError> {
+        Err(CommandChildError::DisCondition(
+            "Running as another user is only supported on Unix.".to_string(),
+        ))
+    }
+
+    // Look up the id of `user`, which may be the id itself.
+    #[cfg(unix)]
+    fn user_id(user: &str) -> Result<u32, CommandChildError> {
+        if let Ok(uid) = user.parse() {
+            return Ok(uid);
+        }
+
+        let name = std::ffi::CString::new(user)
+            .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
+        let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+        loop {
+            // Safety: all the pointers are valid for the call, `passwd` is only
+            // read if it's filled, its strings point into `buffer`.
+            unsafe {
+                let mut passwd: libc::passwd = mem::zeroed();
+                let mut result: *mut libc::passwd = std::ptr::null_mut();
+                let ret = libc::getpwnam_r(
+                    name.as_ptr(),
+                    &mut passwd,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                );
+
+                match ret {
+                    libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                    0 if !result.is_null() => return Ok(passwd.pw_uid),
+                    _ => {
+                        return Err(CommandChildError::DisCondition(format!(
+                            "Unknown user `{}`.",
+                            user
+                        )))
+                    }
+                }
+            }
+        }
+    }
+
+    // Look up the id of `group`, which may be the id itself.
+    #[cfg(unix)]
+    fn group_id(group: &str) -> Result<u32, CommandChildError> {
+        if let Ok(gid) = group.parse() {
+            return Ok(gid);
+        }
+
+        let name = std::ffi::CString::new(group)
+            .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
+        let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+
+        loop {
+            // Safety: all the pointers are valid for the call, `group` is only
+            // read if it's filled, its strings point into `buffer`.
+            unsafe {
+                let mut group_entry: libc::group = mem::zeroed();
+                let mut result: *mut libc::group = std::ptr::null_mut();
+                let ret = libc::getgrnam_r(
+                    name.as_ptr(),
+                    &mut group_entry,
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                    &mut result,
+                );
+
+                match ret {
+                    libc::ERANGE => buffer.resize(buffer.len() * 2, 0),
+                    0 if !result.is_null() => return Ok(group_entry.gr_gid),
+                    _ => {
+                        return Err(CommandChildError::DisCondition(format!(
+                            "Unknown group `{}`.",
+                            group
+                        )))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Where the JSON result is in the stdout of a process task.
+    ///
+    /// # Required features
+    ///
+    /// This type requires the `json-result` feature of the `delay_timer`
+    /// crate to be enabled.
+    #[cfg(feature = "json-result")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-result")))]
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum JsonResult {
+        /// The last non-empty line.
+        LastLine,
+        /// Everything after the last line that is the delimiter.
+        Delimited(String),
+    }
+
+    #[cfg(feature = "json-result")]
+    impl JsonResult {
+        /// Parse the result out of `stdout`, `None` if it's missing or isn't valid JSON.
+        pub fn parse(&self, stdout: &[u8]) -> Option<serde_json::Value> {
+            let stdout = String::from_utf8_lossy(stdout);
+
+            let section = match self {
+                JsonResult::LastLine => {
+                    stdout.lines().rev().find(|line| !line.trim().is_empty())?
+                }
+                JsonResult::Delimited(delimiter) => {
+                    let delimiter = delimiter.trim();
+                    let mut offset = None;
+                    let mut position = 0;
+                    for line in stdout.split_inclusive('\n') {
+                        position += line.len();
+                        if line.trim() == delimiter {
+                            offset = Some(position);
+                        }
+                    }
+
+                    &stdout[offset?..]
+                }
+            };
+
+            serde_json::from_str(section).ok()
+        }
+    }
+
+    #[derive(Clone)]
+    struct OutputLineFn(Arc<OutputLineCallback>);
+
+    impl fmt::Debug for OutputLineFn {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            <&Self as fmt::Pointer>::fmt(&self, f)
+        }
+    }
+
+    /// The interpreter that runs the shell command of a process task.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Shell {
+        /// No interpreter, the builtin parser splits the command into a pipeline
+        /// (`|`, `>`, `>>`) of words, honoring quotes and backslashes.
+        None,
+        /// `sh -c`.
+        Sh,
+        /// `bash -c`.
+        Bash,
+        /// `zsh -c`.
+        Zsh,
+        /// `cmd /C`.
+        Cmd,
+        /// `powershell -NoProfile -Command`.
+        PowerShell,
+    }
+
+    impl Default for Shell {
+        fn default() -> Self {
+            Shell::None
+        }
+    }
+
+    impl Shell {
+        // The words of the process that interprets `input`, if any.
+        fn command_line(self, input: &str) -> Option<Vec<String>> {
+            let program: &[&str] = match self {
+                Shell::None => return None,
+                Shell::Sh => &["sh", "-c"],
+                Shell::Bash => &["bash", "-c"],
+                Shell::Zsh => &["zsh", "-c"],
+                Shell::Cmd => &["cmd", "/C"],
+                Shell::PowerShell => &["powershell", "-NoProfile", "-Command"],
+            };
+
+            Some(
+                program
+                    .iter()
+                    .copied()
+                    .chain(Some(input))
+                    .map(str::to_string)
+                    .collect(),
+            )
+        }
+    }
+
+    /// Builder of the processes that run a shell command.
+    #[derive(Debug, Clone, Default)]
+    pub struct ProcessTaskBuilder {
+        // The interpreter of the command.
+        shell: Shell,
+        resource_limits: ResourceLimits,
+        // The user and group the processes run as.
+        run_as: Option<(String, String)>,
+        // Where the result of the instance is in the stdout.
+        #[cfg(feature = "json-result")]
+        json_result: Option<JsonResult>,
+        // Called with each line of the stdout of the last process.
+        on_output_line: Option<OutputLineFn>,
+        // The environment variables of the processes whose values are secrets.
+        secret_envs: Vec<(String, SecretString)>,
+        // Whether the processes run in their own session, and aren't waited for.
+        detach: bool,
+        // The pidfile of the last process, a fire is skipped while it's alive.
+        single_instance_guard: Option<PathBuf>,
+    }
+
+    impl ProcessTaskBuilder {
+        /// Set the interpreter of the command (optional),
+        /// by default it's parsed by the builtin parser, see `Shell::None`.
+        pub fn set_shell(&mut self, shell: Shell) -> &mut Self {
+            self.shell = shell;
+            self
+        }
+
+        /// Set the limits on the resources of the processes (optional),
+        /// so that scheduled batch jobs can't starve the host service.
+        pub fn set_resource_limits(&mut self, resource_limits: ResourceLimits) -> &mut Self {
+            self.resource_limits = resource_limits;
+            self
+        }
+
+        /// Run the processes as `user` and `group` (optional), given by name or by id,
+        /// so that a privileged daemon can schedule jobs under restricted accounts.
+        ///
+        /// It's only supported on Unix, elsewhere the processes fail to spawn.
+        pub fn run_as(&mut self, user: &str, group: &str) -> &mut Self {
+            self.run_as = Some((user.to_string(), group.to_string()));
+            self
+        }
+
+        /// Call `on_output_line` with each line of the stdout of the last process
+        /// as it's written (optional), so that the progress of a long job
+        /// can be logged or forwarded live rather than only after it's finished.
+        pub fn set_on_output_line<F: Fn(&str) + 'static + Send + Sync>(
+            &mut self,
+            on_output_line: F,
+        ) -> &mut Self {
+            self.on_output_line = Some(OutputLineFn(Arc::new(on_output_line)));
+            self
+        }
+
+        /// Set the environment variable `key` of the processes to `secret` (optional),
+        /// resolved by the `SecretsProvider` of the timer each time the task runs,
+        /// so that the secret itself is never part of the configuration of the task.
+        pub fn set_secret_env(&mut self, key: &str, secret: SecretString) -> &mut Self {
+            self.secret_envs.push((key.to_string(), secret));
+            self
+        }
+
+        /// Run the processes in a new session, detached from the controlling terminal
+        /// of the scheduler (optional), for the jobs that must outlive a restart of it.
+        ///
+        /// The instance completes once they're spawned, without waiting for them,
+        /// the pid of the last one is recorded on it, see `TaskInstance::pid`.
+        /// Their stdio is `/dev/null` unless redirected, and a shutdown leaves them running.
+        ///
+        /// It's only supported on Unix, elsewhere the processes fail to spawn.
+        pub fn detach(&mut self) -> &mut Self {
+            self.detach = true;
+            self
+        }
+
+        pub(crate) fn is_detached(&self) -> bool {
+            self.detach
+        }
+
+        /// Record the pid of the last process in the pidfile at `path` (optional),
+        /// a fire of the task is skipped while the process it names is still alive,
+        /// e.g. one launched by a previous run of the scheduler, see `ProcessTaskBuilder::detach`.
+        ///
+        /// The pidfile of a process that's waited for is removed once it's over.
+        /// It's only checked on Unix, elsewhere the fires always run.
+        pub fn single_instance_guard<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+            self.single_instance_guard = Some(path.as_ref().to_path_buf());
+            self
+        }
+
+        pub(crate) fn get_single_instance_guard(&self) -> Option<&PathBuf> {
+            self.single_instance_guard.as_ref()
+        }
+
+        // Write the pid of the last process to the pidfile, if there is one.
+        fn write_pidfile(&self, pid: Option<u32>) {
+            if let (Some(pidfile), Some(pid)) = (self.single_instance_guard.as_ref(), pid) {
+                if let Err(e) = std::fs::write(pidfile, format!("{}\n", pid)) {
+                    error!("`write_pidfile` ({}): {}", pidfile.display(), e);
+                }
+            }
+        }
+
+        // Resolve the secret environment variables for an instance.
+        pub(crate) async fn resolve_secret_envs(
+            &self,
+            task_context: &TaskContext,
+        ) -> AnyResult<Vec<(String, SecretValue)>> {
+            let mut envs = Vec::with_capacity(self.secret_envs.len());
+            for (key, secret) in self.secret_envs.iter() {
+                envs.push((key.clone(), task_context.resolve_secret(secret).await?));
+            }
+            Ok(envs)
+        }
+
+        /// Parse the stdout of the last process as JSON (optional),
+        /// the value is the result of the instance, see `TaskInstance::result`.
+        ///
+        /// # Required features
+        ///
+        /// This function requires the `json-result` feature of the `delay_timer`
+        /// crate to be enabled.
+        #[cfg(feature = "json-result")]
+        #[cfg_attr(docsrs, doc(cfg(feature = "json-result")))]
+        pub fn set_json_result(&mut self, json_result: JsonResult) -> &mut Self {
+            self.json_result = Some(json_result);
+            self
+        }
+
+        // Parse the result of an instance out of its output.
+        #[cfg(feature = "json-result")]
+        pub(crate) fn parse_result(&self, output: &Output) -> Option<serde_json::Value> {
+            self.json_result
+                .as_ref()
+                .and_then(|json_result| json_result.parse(&output.stdout))
+        }
+
+        // Apply the configuration to a process of the command.
+        fn configure<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            command: &mut Command,
+            envs: &[(String, SecretValue)],
+        ) -> Result<(), CommandChildError> {
+            for (key, value) in envs {
+                command.env(key, value.expose());
+            }
+
+            if !self.resource_limits.is_empty() {
+                self.resource_limits.apply(command)?;
+            }
+
+            if let Some((user, group)) = self.run_as.as_ref() {
+                run_as(command, user, group)?;
+            }
+
+            if self.detach {
+                new_session(command)?;
+            }
+
+            Ok(())
+        }
+
+        /// Generate a list of processes from a string of shell commands,
+        /// each of them configured by the builder.
+        ///
+        /// The secret environment variables are left out, they're only resolved for the instances of a task.
+        pub async fn parse_and_run<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            input: &str,
+        ) -> Result<ChildGuardList<Child>, CommandChildError> {
+            run_commands::<Child, Command>(input, self, &[]).await
+        }
+
+        // Run the shell command to its end, the output is the one of its last process.
+        // Its processes are registered in `child_processes` meanwhile,
+        // and the pid of the last one is shown by the instance (see `TaskInstance::pid`).
+        pub(crate) async fn output<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            input: &str,
+            envs: &[(String, SecretValue)],
+            task_context: &TaskContext,
+        ) -> Result<Output, CommandChildError> {
+            let mut childs = run_commands::<Child, Command>(input, self, envs).await?;
+            let _tracked = task_context.track_child_processes(
+                childs
+                    .iter()
+                    .filter_map(|child_guard| child_guard.as_ref().and_then(ChildUnify::id))
+                    .collect(),
+            );
+            let last_child = childs.pop_back().ok_or_else(|| {
+                CommandChildError::DisCondition("Without child for waiting.".to_string())
+            })?;
+            let last_pid = last_child.as_ref().and_then(ChildUnify::id);
+            if let Some(pid) = last_pid {
+                task_context.running_pid.set(pid);
+            }
+            self.write_pidfile(last_pid);
+
+            let output = match self.on_output_line.as_ref() {
+                Some(on_output_line) => last_child.wait_with_output_lines(&*on_output_line.0).await,
+                None => last_child.wait_with_output().await,
+            };
+
+            if let Some(pidfile) = self.single_instance_guard.as_ref() {
+                std::fs::remove_file(pidfile).ok();
+            }
+            output
+        }
+
+        // Spawn the processes of the shell command without waiting for them,
+        // the pid is the one of the last process.
+        pub(crate) async fn launch<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &self,
+            input: &str,
+            envs: &[(String, SecretValue)],
+        ) -> Result<Option<u32>, CommandChildError> {
+            let childs = run_commands::<Child, Command>(input, self, envs).await?;
+
+            // Taken out of their guards, they aren't killed once dropped.
+            let pid = childs
+                .into_iter()
+                .filter_map(ChildGuard::take_inner)
+                .last()
+                .and_then(|child| child.id());
+
+            self.write_pidfile(pid);
+            Ok(pid)
+        }
+    }
+
     //that code base on 'build-your-own-shell-rust'. Thanks you Josh Mcguigan.
 
     /// Generate a list of processes from a string of shell commands.
@@ -255,25 +1065,31 @@ pub mod shell_command {
     //  after which it should be split into unblock().
     pub async fn parse_and_run<Child: ChildUnify, Command: CommandUnify<Child>>(
         input: &str,
+    ) -> Result<ChildGuardList<Child>, CommandChildError> {
+        run_commands::<Child, Command>(input, &ProcessTaskBuilder::default(), &[]).await
+    }
+
+    async fn run_commands<Child: ChildUnify, Command: CommandUnify<Child>>(
+        input: &str,
+        process_task_builder: &ProcessTaskBuilder,
+        envs: &[(String, SecretValue)],
     ) -> Result<ChildGuardList<Child>, CommandChildError> {
         // Check to see if process_linked_list is also automatically dropped out of scope
         // by ERROR's early return and an internal kill method is executed.
 
         let mut process_linked_list: ChildGuardList<Child> = LinkedList::new();
-        // must be peekable so we know when we are on the last command
-        let commands = input.trim().split(" | ").peekable();
-        //if str has >> | > ,after spawn return.
-
-        let mut _stdio: Stdio;
-
-        for mut command in commands {
-            let check_redirect_result = _has_redirect_file(command);
-            if check_redirect_result.is_some() {
-                command = _remove_angle_bracket_command(command)
-                    .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
-            }
+        let commands = match process_task_builder.shell.command_line(input) {
+            Some(words) => vec![ParsedCommand {
+                words,
+                redirect: None,
+            }],
+            None => parse_commands(input)?,
+        };
 
-            let mut parts = command.trim().split_whitespace();
+        let last_index = commands.len().saturating_sub(1);
+        for (index, ParsedCommand { words, redirect }) in commands.into_iter().enumerate() {
+            trace!("Process task spawn: {}", shell_quote(&words));
+            let mut parts = words.iter();
             let command = parts
                 .next()
                 .ok_or_else(|| CommandChildError::DisCondition("Without next part".to_string()))?;
@@ -282,8 +1098,13 @@ pub mod shell_command {
             // Standard input to the current process.
             // If previous_command is present, it inherits the standard output of the previous command.
             // If not, it inherits the current parent process.
+            // Nothing is read from nor written to the terminal by the detached processes.
             let previous_command = process_linked_list.back_mut();
-            let mut stdin = Stdio::inherit();
+            let mut stdin = if process_task_builder.detach {
+                Stdio::null()
+            } else {
+                Stdio::inherit()
+            };
 
             if let Some(previous_command_ref) = previous_command {
                 let mut t = None;
@@ -298,30 +1119,31 @@ pub mod shell_command {
             }
 
             let mut output = Command::new(command);
-            output.args(args).stdin(stdin).stderr(Stdio::piped());
+            let stderr = if process_task_builder.detach {
+                Stdio::null()
+            } else {
+                Stdio::piped()
+            };
+            output.args(args).stdin(stdin).stderr(stderr);
+            process_task_builder.configure(&mut output, envs)?;
 
             let process: Child;
-            let end_flag = if let Some(stdout_result) = check_redirect_result {
-                let stdout =
-                    stdout_result.map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
+            //if str has >> | > ,after spawn return.
+            let end_flag = if let Some((filename, append)) = redirect {
+                let stdout = create_stdio_file(append, &filename)
+                    .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
                 process = output
                     .stdout(stdout)
                     .spawn()
                     .map_err(|e| CommandChildError::DisCondition(e.to_string()))?;
                 true
             } else {
-                // if commands.peek().is_some() {
-                //     // there is another command piped behind this one
-                //     // prepare to send output to the next command
-                let stdout = Stdio::piped();
-                // } else {
-                //     // there are no more commands piped behind this one
-                //     // send output to shell stdout
-                //     // TODO:It shouldn't be the standard output of the parent process in the context,
-                //     // there should be a default file to record it.
-                //     stdout = Stdio::inherit();
-                // };
-
+                // Nobody reads the stdout of the last detached process.
+                let stdout = if process_task_builder.detach && index == last_index {
+                    Stdio::null()
+                } else {
+                    Stdio::piped()
+                };
                 process = output
                     .stdout(stdout)
                     .spawn()
@@ -338,61 +1160,139 @@ pub mod shell_command {
         Ok(process_linked_list)
     }
 
-    // I should give Option<Result<File>>
-    //By Option(Some(Result<T>)), determine if there is an output stdio..
-    //By Result<T>(OK(t)), determine if there is success open file.
-    #[cfg(not(SPLIT_INCLUSIVE_COMPATIBLE))]
-    fn _has_redirect_file(command: &str) -> Option<Result<File>> {
-        let angle_bracket = if command.contains(">>") {
-            ">>"
-        } else if command.contains('>') {
-            ">"
-        } else {
-            return None;
-        };
+    // A command of a pipeline, and the file its stdout is redirected to (appending or not).
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct ParsedCommand {
+        words: Vec<String>,
+        redirect: Option<(String, bool)>,
+    }
 
-        if let Some(filename) = command.trim().rsplit(angle_bracket).next() {
-            Some(create_stdio_file(angle_bracket, filename))
-        } else {
-            None
+    #[derive(Debug, PartialEq, Eq)]
+    enum Token {
+        Word(String),
+        Pipe,
+        Redirect(bool),
+    }
+
+    // Split the input into words and operators, the way a POSIX shell does for quotes:
+    // single quotes keep everything, double quotes keep all but `\"` and `\\`,
+    // and a backslash outside of quotes keeps the next character.
+    fn tokenize(input: &str) -> Result<Vec<Token>, CommandChildError> {
+        let mut tokens = Vec::new();
+        let mut word: Option<String> = None;
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    let word = word.get_or_insert_with(String::new);
+                    loop {
+                        match chars.next() {
+                            Some('\'') => break,
+                            Some(c) => word.push(c),
+                            None => return Err(unterminated(input)),
+                        }
+                    }
+                }
+                '"' => {
+                    let word = word.get_or_insert_with(String::new);
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') if matches!(chars.peek(), Some('"') | Some('\\')) => {
+                                word.extend(chars.next())
+                            }
+                            Some(c) => word.push(c),
+                            None => return Err(unterminated(input)),
+                        }
+                    }
+                }
+                '\\' => {
+                    let escaped = chars.next().ok_or_else(|| unterminated(input))?;
+                    word.get_or_insert_with(String::new).push(escaped);
+                }
+                '|' | '>' => {
+                    tokens.extend(word.take().map(Token::Word));
+                    if c == '|' {
+                        tokens.push(Token::Pipe);
+                    } else {
+                        tokens.push(Token::Redirect(chars.next_if_eq(&'>').is_some()));
+                    }
+                }
+                c if c.is_whitespace() => tokens.extend(word.take().map(Token::Word)),
+                c => word.get_or_insert_with(String::new).push(c),
+            }
         }
+        tokens.extend(word.take().map(Token::Word));
+
+        Ok(tokens)
     }
 
-    #[cfg(SPLIT_INCLUSIVE_COMPATIBLE)]
-    fn _has_redirect_file(command: &str) -> Option<Result<File, AnyhowError>> {
-        let angle_bracket = if command.contains(">>") {
-            ">>"
-        } else if command.contains('>') {
-            ">"
-        } else {
-            return None;
-        };
+    fn unterminated(input: &str) -> CommandChildError {
+        CommandChildError::DisCondition(format!("Unterminated quote or escape in `{}`.", input))
+    }
 
-        let mut sub_command_inner = command.trim().split_inclusive(angle_bracket).rev();
+    // Parse the input into a pipeline of commands, the redirection of stdout ends it.
+    fn parse_commands(input: &str) -> Result<Vec<ParsedCommand>, CommandChildError> {
+        let mut commands = vec![ParsedCommand::default()];
+        let mut tokens = tokenize(input)?.into_iter();
+
+        while let Some(token) = tokens.next() {
+            let command = commands.last_mut().expect("Without command.");
+            match token {
+                Token::Word(word) => command.words.push(word),
+                Token::Pipe => commands.push(ParsedCommand::default()),
+                Token::Redirect(append) => match tokens.next() {
+                    Some(Token::Word(filename)) => {
+                        command.redirect = Some((filename, append));
+                        break;
+                    }
+                    _ => {
+                        return Err(CommandChildError::DisCondition(format!(
+                            "Missing the file to redirect to in `{}`.",
+                            input
+                        )))
+                    }
+                },
+            }
+        }
 
-        sub_command_inner
-            .next()
-            .map(|filename| create_stdio_file(angle_bracket, filename))
+        if commands.iter().any(|command| command.words.is_empty()) {
+            return Err(CommandChildError::DisCondition(format!(
+                "Empty command in `{}`.",
+                input
+            )));
+        }
+        Ok(commands)
     }
 
-    //After confirming that there is a redirect file, parse the command before the command '>'.
-    fn _remove_angle_bracket_command(command: &str) -> Result<&str, AnyhowError> {
-        let mut sub_command_inner = command.trim().split('>');
-        sub_command_inner
-            .next()
-            .ok_or_else(|| anyhow!("can't parse ...."))
+    /// Quote `args` into a command line, that the builtin parser and POSIX shells
+    /// split back into the same words, whatever spaces or quotes they contain.
+    pub fn shell_quote<S: AsRef<str>>(args: impl IntoIterator<Item = S>) -> String {
+        let is_plain = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+
+        args.into_iter()
+            .map(|arg| {
+                let arg = arg.as_ref();
+                if !arg.is_empty() && arg.chars().all(is_plain) {
+                    arg.to_string()
+                } else {
+                    format!("'{}'", arg.replace('\'', r"'\''"))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
-    fn create_stdio_file(angle_bracket: &str, filename: &str) -> Result<File, AnyhowError> {
+    fn create_stdio_file(append: bool, filename: &str) -> Result<File, AnyhowError> {
         let mut file_tmp = OpenOptions::new();
         file_tmp.write(true).create(true);
 
-        if angle_bracket == ">>" {
+        if append {
             file_tmp.append(true);
         }
 
-        //TODO:I need record that open file error because filename has a whitespace i don't trim.
-        let os_filename = Path::new(filename.trim()).as_os_str();
+        let os_filename = Path::new(filename).as_os_str();
 
         let stdio_file = file_tmp.open(os_filename)?;
         Ok(stdio_file)
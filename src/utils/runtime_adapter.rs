@@ -0,0 +1,237 @@
+//! runtime_adapter
+//! It is the abstraction over the async runtimes delay-timer can run on.
+//!
+//! The background futures the timer spawns, its sleeps and its timeouts
+//! go through the `RuntimeAdapter` of its `RuntimeKind`,
+//! so that smol and tokio behave the same, and that is checked by the same tests.
+use crate::prelude::*;
+
+use crate::utils::parse::shell_command::{ChildUnify, CommandUnify};
+
+use async_trait::async_trait;
+use smol::future::FutureExt;
+use smol::process::{Child as SmolChild, Command as SmolCommand};
+use tokio::process::{Child as TokioChild, Command as TokioCommand};
+
+use std::ffi::OsStr;
+use std::panic::resume_unwind;
+
+/// Operations delay-timer needs from an async runtime.
+#[async_trait]
+pub trait RuntimeAdapter: Send + Sync + 'static {
+    /// The command of the runtime, used to launch child processes.
+    type Command: CommandUnify<Self::Child> + Send;
+    /// The child-process handle of the runtime.
+    type Child: ChildUnify;
+
+    /// The kind of the runtime.
+    fn kind(&self) -> RuntimeKind;
+
+    /// Spawn a future on the runtime, it runs detached.
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static;
+
+    /// Async-await for `duration`.
+    async fn sleep(&self, duration: Duration);
+
+    /// Async-await `future` for at most `duration`, `None` when it timed out.
+    async fn timeout<F>(&self, duration: Duration, future: F) -> Option<F::Output>
+    where
+        F: Future + Send,
+        F::Output: Send;
+
+    /// Run a blocking closure off the async threads and await its result.
+    async fn unblock<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static;
+
+    /// Constructs a new Command of the runtime for launching the program at path program.
+    fn command<S: AsRef<OsStr>>(&self, program: S) -> Self::Command {
+        <Self::Command as CommandUnify<Self::Child>>::new(program)
+    }
+}
+
+/// `RuntimeAdapter` for smol.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SmolAdapter;
+
+/// `RuntimeAdapter` for tokio.
+///
+/// `spawn` must be called within the context of a tokio runtime.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioAdapter;
+
+// The timer reaches the runtime it runs on through the adapter of its kind.
+impl RuntimeKind {
+    pub(crate) fn spawn<F>(self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            RuntimeKind::Smol => SmolAdapter.spawn(future),
+            RuntimeKind::Tokio => TokioAdapter.spawn(future),
+        }
+    }
+
+    pub(crate) async fn sleep(self, duration: Duration) {
+        match self {
+            RuntimeKind::Smol => SmolAdapter.sleep(duration).await,
+            RuntimeKind::Tokio => TokioAdapter.sleep(duration).await,
+        }
+    }
+
+    pub(crate) async fn timeout<F>(self, duration: Duration, future: F) -> Option<F::Output>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        match self {
+            RuntimeKind::Smol => SmolAdapter.timeout(duration, future).await,
+            RuntimeKind::Tokio => TokioAdapter.timeout(duration, future).await,
+        }
+    }
+}
+
+#[async_trait]
+impl RuntimeAdapter for SmolAdapter {
+    type Command = SmolCommand;
+    type Child = SmolChild;
+
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Smol
+    }
+
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        async_spawn_by_smol(future).detach();
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        AsyncTimer::after(duration).await;
+    }
+
+    async fn timeout<F>(&self, duration: Duration, future: F) -> Option<F::Output>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        async { Some(future.await) }
+            .or(async {
+                AsyncTimer::after(duration).await;
+                None
+            })
+            .await
+    }
+
+    async fn unblock<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        unblock_spawn_by_smol(f).await
+    }
+}
+
+#[async_trait]
+impl RuntimeAdapter for TokioAdapter {
+    type Command = TokioCommand;
+    type Child = TokioChild;
+
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Tokio
+    }
+
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        async_spawn_by_tokio(future);
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        sleep_by_tokio(duration).await;
+    }
+
+    async fn timeout<F>(&self, duration: Duration, future: F) -> Option<F::Output>
+    where
+        F: Future + Send,
+        F::Output: Send,
+    {
+        tokio::time::timeout(duration, future).await.ok()
+    }
+
+    // A panic in the closure is propagated, as `smol::unblock` does.
+    async fn unblock<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match unblock_spawn_by_tokio(f).await {
+            Ok(output) => output,
+            Err(e) if e.is_panic() => resume_unwind(e.into_panic()),
+            Err(e) => panic!("blocking task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RuntimeAdapter, SmolAdapter, TokioAdapter};
+    #[cfg(target_family = "unix")]
+    use crate::utils::parse::shell_command::{ChildUnify, CommandUnify};
+    #[cfg(target_family = "unix")]
+    use std::process::Stdio;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // The same expectations hold whichever runtime is behind the adapter.
+    async fn check_parity<R: RuntimeAdapter>(adapter: R) -> anyhow::Result<()> {
+        let spawned = Arc::new(AtomicBool::new(false));
+        let spawned_ref = spawned.clone();
+        adapter.spawn(async move {
+            spawned_ref.store(true, Ordering::Release);
+        });
+        adapter.sleep(Duration::from_millis(100)).await;
+        assert!(spawned.load(Ordering::Acquire));
+
+        let in_time = adapter
+            .timeout(Duration::from_millis(500), async { 1 })
+            .await;
+        assert_eq!(in_time, Some(1));
+
+        let timed_out = adapter
+            .timeout(
+                Duration::from_millis(100),
+                adapter.sleep(Duration::from_secs(3)),
+            )
+            .await;
+        assert_eq!(timed_out, None);
+
+        assert_eq!(adapter.unblock(|| 1 + 1).await, 2);
+
+        #[cfg(target_family = "unix")]
+        {
+            let mut command = adapter.command("echo");
+            command.args(["parity"]).stdout(Stdio::piped());
+            let output = command.spawn()?.wait_with_output().await?;
+            assert_eq!(output.stdout, b"parity\n");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_smol_adapter() -> anyhow::Result<()> {
+        smol::block_on(check_parity(SmolAdapter))
+    }
+
+    #[test]
+    fn test_tokio_adapter() -> anyhow::Result<()> {
+        tokio::runtime::Runtime::new()?.block_on(check_parity(TokioAdapter))
+    }
+}
@@ -0,0 +1,228 @@
+//! Secrets
+//! References to secrets in the configuration of the tasks, resolved by the
+//! `SecretsProvider` of the timer each time an instance runs,
+//! so that only the references are kept in the configuration and the logs.
+use crate::prelude::*;
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use async_trait::async_trait;
+
+/// A reference to a secret, e.g. the name of an environment variable,
+/// resolved by the `SecretsProvider` of the timer when an instance runs.
+///
+/// It holds the reference only, never the secret itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// New a reference to a secret, its meaning is up to the `SecretsProvider`.
+    pub fn new(reference: impl Into<String>) -> Self {
+        SecretString(reference.into())
+    }
+
+    /// Get the reference.
+    pub fn reference(&self) -> &str {
+        &self.0
+    }
+}
+
+/// A resolved secret, redacted when it's formatted.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    /// Wrap a resolved secret.
+    pub fn new(secret: impl Into<String>) -> Self {
+        SecretValue(secret.into())
+    }
+
+    /// Get the secret itself.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretValue(<redacted>)")
+    }
+}
+
+/// Resolves the `SecretString`s of the tasks, set by `DelayTimerBuilder::set_secrets_provider`.
+///
+/// It's called from the running instances, every time they run,
+/// so that a rotated secret is picked up by the next run.
+#[async_trait]
+pub trait SecretsProvider: Send + Sync + 'static {
+    /// Resolve the secret `reference` refers to.
+    async fn resolve(&self, reference: &str) -> AnyResult<SecretValue>;
+}
+
+impl fmt::Debug for dyn SecretsProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <&Self as fmt::Pointer>::fmt(&self, f)
+    }
+}
+
+/// The default `SecretsProvider`, the references are the names of environment variables.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecrets;
+
+#[async_trait]
+impl SecretsProvider for EnvSecrets {
+    async fn resolve(&self, reference: &str) -> AnyResult<SecretValue> {
+        std::env::var(reference)
+            .map(SecretValue)
+            .map_err(|e| anyhow!("The secret `{}` can't be resolved: {}", reference, e))
+    }
+}
+
+/// A `SecretsProvider` reading the secrets from the files of a directory,
+/// e.g. the secrets mounted by a container orchestrator,
+/// the references are the paths of the files relative to it.
+///
+/// The trailing newline of a file isn't part of the secret.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    directory: PathBuf,
+}
+
+impl FileSecrets {
+    /// New a provider reading the secrets from the files of `directory`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        FileSecrets {
+            directory: directory.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FileSecrets {
+    async fn resolve(&self, reference: &str) -> AnyResult<SecretValue> {
+        // A reference can't lead out of the directory.
+        let relative_path = Path::new(reference);
+        if !relative_path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow!("The secret `{}` is out of the directory.", reference));
+        }
+
+        let path = self.directory.join(relative_path);
+        let secret = smol::unblock(move || std::fs::read_to_string(path))
+            .await
+            .map_err(|e| anyhow!("The secret `{}` can't be resolved: {}", reference, e))?;
+
+        Ok(SecretValue(
+            secret.trim_end_matches(|c| c == '\n' || c == '\r').to_string(),
+        ))
+    }
+}
+
+/// A `SecretsProvider` reading the secrets from the KV (version 2) engine of a Vault server,
+/// the references are `path#field`, e.g. `database/primary#password`.
+///
+/// Only plain HTTP is supported, e.g. towards a local Vault Agent.
+///
+/// # Required features
+///
+/// This type requires the `vault` feature of the `delay_timer`
+/// crate to be enabled.
+#[cfg(feature = "vault")]
+#[cfg_attr(docsrs, doc(cfg(feature = "vault")))]
+#[derive(Clone)]
+pub struct VaultSecrets {
+    address: String,
+    token: SecretValue,
+    mount: String,
+    client: hyper::Client<hyper::client::HttpConnector>,
+}
+
+#[cfg(feature = "vault")]
+impl fmt::Debug for VaultSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VaultSecrets")
+            .field("address", &self.address)
+            .field("token", &self.token)
+            .field("mount", &self.mount)
+            .finish()
+    }
+}
+
+#[cfg(feature = "vault")]
+impl VaultSecrets {
+    /// New a provider reading the secrets from the server at `address` (e.g. `http://127.0.0.1:8200`),
+    /// authenticated by `token`, from the KV engine mounted at `secret`.
+    pub fn new(address: impl Into<String>, token: SecretValue) -> Self {
+        VaultSecrets {
+            address: address.into().trim_end_matches('/').to_string(),
+            token,
+            mount: "secret".to_string(),
+            client: hyper::Client::new(),
+        }
+    }
+
+    /// Set the path the KV engine is mounted at (optional), `secret` by default.
+    pub fn mount(mut self, mount: impl Into<String>) -> Self {
+        self.mount = mount.into();
+        self
+    }
+}
+
+#[cfg(feature = "vault")]
+#[async_trait]
+impl SecretsProvider for VaultSecrets {
+    async fn resolve(&self, reference: &str) -> AnyResult<SecretValue> {
+        let (path, field) = reference
+            .split_once('#')
+            .ok_or_else(|| anyhow!("The secret `{}` isn't `path#field`.", reference))?;
+
+        let request = hyper::Request::get(format!(
+            "{}/v1/{}/data/{}",
+            self.address, self.mount, path
+        ))
+        .header("X-Vault-Token", self.token.expose())
+        .body(hyper::Body::empty())?;
+        let response = self.client.request(request).await?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "The secret `{}` can't be resolved: {}",
+                reference,
+                response.status()
+            ));
+        }
+
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let document: serde_json::Value = serde_json::from_slice(&body)?;
+        document["data"]["data"][field]
+            .as_str()
+            .map(SecretValue::new)
+            .ok_or_else(|| anyhow!("The secret `{}` has no string `{}`.", path, field))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EnvSecrets, FileSecrets, SecretValue, SecretsProvider};
+
+    #[test]
+    fn test_secrets_provider() -> anyhow::Result<()> {
+        let secret = SecretValue::new("hunter2");
+        assert_eq!(format!("{:?}", secret), "SecretValue(<redacted>)");
+
+        std::env::set_var("DELAY_TIMER_TEST_SECRET", "hunter2");
+        let resolved = smol::block_on(EnvSecrets.resolve("DELAY_TIMER_TEST_SECRET"))?;
+        assert_eq!(resolved, secret);
+
+        let directory = std::env::temp_dir().join("delay_timer_test_secrets");
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("password"), "hunter2\n")?;
+        let file_secrets = FileSecrets::new(&directory);
+        assert_eq!(smol::block_on(file_secrets.resolve("password"))?, secret);
+        assert!(smol::block_on(file_secrets.resolve("../password")).is_err());
+
+        Ok(())
+    }
+}
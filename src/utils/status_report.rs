@@ -2,55 +2,227 @@
 //! to the outside world.
 use crate::prelude::*;
 use std::convert::TryFrom;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
 use future_lite::block_on;
 use once_cell::sync::Lazy;
 
 
-/// Global internal status-reporter.
-pub(crate) static GLOBAL_STATUS_REPORTER: Lazy<(AsyncSender<PublicEvent>, AsyncReceiver<PublicEvent>)> = Lazy::new(|| {
+/// Global internal status-reporter, the events are paired with their sequence number.
+#[allow(clippy::type_complexity)]
+pub(crate) static GLOBAL_STATUS_REPORTER: Lazy<(AsyncSender<(u64, PublicEvent)>, AsyncReceiver<(u64, PublicEvent)>)> = Lazy::new(|| {
     smol::channel::unbounded()
 });
 
+/// The events are delivered in the order they happened,
+/// in particular the `RunningTask` of an instance always comes before its
+/// `FinishTask`, `TimeoutTask` or `CancelTask`.
+///
+/// Every event delivered carries a sequence number, one more than that of the previous one,
+/// so a gap means events were lost (e.g. taken by another clone of the reporter).
+/// Events left out by `EventSampling` take no sequence number.
+///
 /// # Required features
 ///
 /// This function requires the `status-report` feature of the `delay_timer`
 /// crate to be enabled.
 #[derive(Debug, Clone)]
 pub struct StatusReporter {
-    inner: AsyncReceiver<PublicEvent>,
+    inner: AsyncReceiver<(u64, PublicEvent)>,
 }
 
 impl StatusReporter {
 
     /// Non-blocking get `PublicEvent` via `StatusReporter`.
     pub fn next_public_event(&self) -> Result<PublicEvent, channel::TryRecvError> {
-        let event = self.inner.try_recv()?;
+        let (_, event) = self.inner.try_recv()?;
         Ok(event)
     }
 
     /// Blocking get `PublicEvent` via `StatusReporter`.
     pub fn next_public_event_with_wait(&self) -> Result<PublicEvent, channel::RecvError> {
-        block_on(self.inner.recv())
+        Ok(block_on(self.inner.recv())?.1)
     }
 
     /// Async get `PublicEvent` via `StatusReporter`.
     pub async fn next_public_event_with_async_wait(&self) -> Result<PublicEvent, channel::RecvError> {
-        Ok(self.inner.recv().await?)
+        Ok(self.inner.recv().await?.1)
+    }
+
+    /// Non-blocking get `PublicEvent` with its sequence number via `StatusReporter`.
+    pub fn next_sequenced_event(&self) -> Result<(u64, PublicEvent), channel::TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    /// Blocking get `PublicEvent` with its sequence number via `StatusReporter`.
+    pub fn next_sequenced_event_with_wait(&self) -> Result<(u64, PublicEvent), channel::RecvError> {
+        block_on(self.inner.recv())
+    }
+
+    /// Async get `PublicEvent` with its sequence number via `StatusReporter`.
+    pub async fn next_sequenced_event_with_async_wait(
+        &self,
+    ) -> Result<(u64, PublicEvent), channel::RecvError> {
+        self.inner.recv().await
     }
 
-    pub(crate) fn new(inner: AsyncReceiver<PublicEvent>) -> Self {
+    pub(crate) fn new(inner: AsyncReceiver<(u64, PublicEvent)>) -> Self {
         Self { inner }
     }
 }
 
+/// A bounded ring-buffer of recent public events,
+/// which allows a consumer that attached late to read what it missed.
+#[derive(Debug, Clone)]
+pub(crate) struct EventLog {
+    capacity: usize,
+    inner: Arc<Mutex<VecDeque<(u64, PublicEvent)>>>,
+}
+
+impl EventLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let inner = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+        Self { capacity, inner }
+    }
+
+    /// Record an event, the oldest one is dropped when the log is full.
+    pub(crate) fn record(&self, public_event: PublicEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut events = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back((timestamp(), public_event));
+    }
+
+    /// Events that were recorded at or after `since` (timestamp in seconds), oldest first.
+    pub(crate) fn replay(&self, since: u64) -> Vec<PublicEvent> {
+        let events = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        events
+            .iter()
+            .filter(|(record_time, _)| *record_time >= since)
+            .map(|(_, public_event)| public_event.clone())
+            .collect()
+    }
+
+    /// The recorded events of the task, or of every task, with the time they were recorded.
+    pub(crate) fn history(&self, task_id: Option<u64>) -> Vec<(u64, PublicEvent)> {
+        let events = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        events
+            .iter()
+            .filter(|(_, public_event)| task_id.map_or(true, |task_id| public_event.get_task_id() == task_id))
+            .cloned()
+            .collect()
+    }
+}
+
+// Log the event under the `delay_timer::events` target, as set up by `DelayTimerBuilder::log_events`,
+// the fields are the same as those of the exported history.
+pub(crate) fn log_public_event(level: LogLevel, public_event: &PublicEvent) {
+    log::log!(
+        target: "delay_timer::events",
+        level,
+        "event={} task_id={} record_id={} detail={}",
+        public_event.name(),
+        public_event.get_task_id(),
+        public_event
+            .get_record_id()
+            .map_or_else(String::new, |record_id| record_id.to_string()),
+        public_event.detail().unwrap_or_default()
+    );
+}
+
+/// The format of the history written by `DelayTimer::export_history`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    /// Comma-separated values, with a header line.
+    Csv,
+    /// A JSON object per line.
+    JsonLines,
+}
+
+// Write one line per record, `time,event,task_id,record_id,detail` are the fields of either format.
+pub(crate) fn write_history(
+    history: &[(u64, PublicEvent)],
+    format: HistoryFormat,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    if format == HistoryFormat::Csv {
+        writeln!(writer, "time,event,task_id,record_id,detail")?;
+    }
+
+    for (record_time, public_event) in history {
+        let record_id = public_event.get_record_id();
+        let detail = public_event.detail();
+
+        match format {
+            HistoryFormat::Csv => writeln!(
+                writer,
+                "{},{},{},{},{}",
+                record_time,
+                public_event.name(),
+                public_event.get_task_id(),
+                record_id.map(|record_id| record_id.to_string()).unwrap_or_default(),
+                detail.as_deref().map(csv_field).unwrap_or_default()
+            )?,
+            HistoryFormat::JsonLines => writeln!(
+                writer,
+                "{{\"time\":{},\"event\":\"{}\",\"task_id\":{},\"record_id\":{},\"detail\":{}}}",
+                record_time,
+                public_event.name(),
+                public_event.get_task_id(),
+                record_id.map(|record_id| record_id.to_string()).unwrap_or_else(|| "null".to_string()),
+                detail.as_deref().map(json_string).unwrap_or_else(|| "null".to_string())
+            )?,
+        }
+    }
+
+    writer.flush()
+}
+
+// A field holding a separator, a quote or a line break is quoted.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(field: &str) -> String {
+    let mut json = String::with_capacity(field.len() + 2);
+    json.push('"');
+    for c in field.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
 // Define types independently to avoid coupling internal types.
 /// The information generated when completing a task.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PublicFinishTaskBody {
     pub(crate) task_id: u64,
     pub(crate) record_id: i64,
+    pub(crate) scheduled_for: u64,
+    pub(crate) started_at: u64,
     pub(crate) finish_time: u64,
     pub(crate) finish_output: Option<PublicFinishOutput>,
+    pub(crate) captured_logs: Vec<String>,
+    pub(crate) no_work: bool,
 }
 
 // Define types independently to avoid coupling internal types.
@@ -79,17 +251,55 @@ impl PublicFinishTaskBody{
     }
 
 
+    /// Get the time (timestamp-seconds) the fire of the TaskInstance was scheduled for.
+    #[inline(always)]
+    pub fn get_scheduled_for(&self) -> u64{
+        self.scheduled_for
+    }
+
+
+    /// Get the time (timestamp-seconds) the TaskInstance started at,
+    /// later than the scheduled time if the fire was queued or advanced.
+    #[inline(always)]
+    pub fn get_started_at(&self) -> u64{
+        self.started_at
+    }
+
+
     /// Get the TaskInstance finish-time.
     #[inline(always)]
     pub fn get_finish_time(&self) -> u64{
         self.finish_time
     }
 
+
+    /// Whether the TaskInstance reported it found nothing to do, see `TaskContext::complete_no_work`.
+    #[inline(always)]
+    pub fn is_no_work(&self) -> bool{
+        self.no_work
+    }
+
+    // Whether the TaskInstance failed, like the failure policy sees it.
+    pub(crate) fn is_failed(&self) -> bool{
+        match self.finish_output {
+            Some(PublicFinishOutput::ProcessOutput(ref output)) => !output.status.success(),
+            Some(PublicFinishOutput::ExceptionOutput(_)) => true,
+            None => false,
+        }
+    }
+
     /// Get the output on internal completion.
     #[inline(always)]
     pub fn get_finish_output(&mut self) -> Option<PublicFinishOutput>{
         self.finish_output.take()
     }
+
+    /// Get the records the routine logged by `TaskContext::logger`,
+    /// if the task was built with `TaskBuilder::set_capture_logs`.
+    #[inline(always)]
+    pub fn get_captured_logs(&mut self) -> Vec<String>{
+        std::mem::take(&mut self.captured_logs)
+    }
 }
 
 impl From<FinishTaskBody> for PublicFinishTaskBody{
@@ -97,8 +307,12 @@ impl From<FinishTaskBody> for PublicFinishTaskBody{
         PublicFinishTaskBody{
             task_id:value.task_id,
             record_id:value.record_id,
+            scheduled_for:value.scheduled_for,
+            started_at:value.started_at,
             finish_time:value.finish_time,
             finish_output:value.finish_output.map(|o|o.into()),
+            captured_logs:value.captured_logs,
+            no_work:value.no_work,
         }
     }
 }
@@ -123,6 +337,36 @@ pub enum PublicEvent {
     FinishTask(PublicFinishTaskBody),
     /// Describe which task instance timeout .
     TimeoutTask(u64, i64),
+    /// Describe which task instance was cancelled and why.
+    CancelTask(u64, i64, TerminationReason),
+    /// Describe which task instance was hard-aborted without having observed its cancellation token.
+    AbortedWhileHoldingGuard(u64, i64),
+    /// Describe which task skipped a fire because its execution budget is exhausted.
+    BudgetExceeded(u64),
+    /// Describe which task skipped a fire because it matches the exclusion schedule.
+    SkippedByExclusion(u64),
+    /// The timer-core loop died and was restarted, carrying the number of restarts so far.
+    SchedulerRestarted(u32),
+    /// The clock jumped forward (e.g. the system was suspended), carrying the length of the gap.
+    SystemResumedAfter(Duration),
+    /// Describe which task dropped a fire that was queued longer than its `max_queue_delay`.
+    StaleFireDropped(u64),
+    /// Describe which task skipped a fire because its readiness check didn't pass.
+    SkippedNotReady(u64),
+    /// Describe which task skipped a fire because the `SpawnHook` vetoed its instance.
+    SpawnVetoed(u64),
+    /// Describe which task skipped a fire because its circuit breaker is open.
+    SkippedByCircuitBreaker(u64),
+    /// Describe which task skipped a fire because its tenant runs as many instances as its quota allows.
+    SkippedByTenantQuota(u64),
+    /// Describe which task skipped a fire because the process named by its pidfile is still alive.
+    SkippedBySingleInstanceGuard(u64),
+    /// Describe which task was paused or removed by its failure policy.
+    TaskQuarantined(u64, Failure),
+    /// Describe which task instance missed its heartbeat.
+    InstanceStalled(u64, i64),
+    /// Describe which task instance runs past its runtime warning threshold.
+    RunningLong(u64, i64),
 }
 
 impl TryFrom<&TimerEvent> for PublicEvent {
@@ -130,7 +374,7 @@ impl TryFrom<&TimerEvent> for PublicEvent {
 
     fn try_from(timer_event: &TimerEvent) -> Result<Self, Self::Error> {
         match timer_event {
-            TimerEvent::RemoveTask(task_id) => Ok(PublicEvent::RemoveTask(*task_id)),
+            TimerEvent::RemoveTask(task_id) | TimerEvent::ExpireTombstone(task_id) => Ok(PublicEvent::RemoveTask(*task_id)),
             TimerEvent::AppendTaskHandle(_, delay_task_handler_box) => {
                 Ok(PublicEvent::RunningTask(delay_task_handler_box.get_task_id(), delay_task_handler_box.get_record_id()))
             }
@@ -143,6 +387,34 @@ impl TryFrom<&TimerEvent> for PublicEvent {
                 Ok(PublicEvent::TimeoutTask(*task_id, *record_id))
             }
 
+            TimerEvent::CancelTask(task_id, record_id, termination_reason) => {
+                Ok(PublicEvent::CancelTask(*task_id, *record_id, termination_reason.clone()))
+            }
+
+            TimerEvent::BudgetExceeded(task_id) => Ok(PublicEvent::BudgetExceeded(*task_id)),
+
+            TimerEvent::SkippedByExclusion(task_id) => Ok(PublicEvent::SkippedByExclusion(*task_id)),
+
+            TimerEvent::SchedulerRestarted(restart_count) => Ok(PublicEvent::SchedulerRestarted(*restart_count)),
+
+            TimerEvent::SystemResumedAfter(gap) => Ok(PublicEvent::SystemResumedAfter(*gap)),
+
+            TimerEvent::StaleFireDropped(task_id) => Ok(PublicEvent::StaleFireDropped(*task_id)),
+
+            TimerEvent::SkippedNotReady(task_id) => Ok(PublicEvent::SkippedNotReady(*task_id)),
+
+            TimerEvent::SpawnVetoed(task_id) => Ok(PublicEvent::SpawnVetoed(*task_id)),
+
+            TimerEvent::SkippedByCircuitBreaker(task_id) => Ok(PublicEvent::SkippedByCircuitBreaker(*task_id)),
+            TimerEvent::SkippedByTenantQuota(task_id) => Ok(PublicEvent::SkippedByTenantQuota(*task_id)),
+            TimerEvent::SkippedBySingleInstanceGuard(task_id) => Ok(PublicEvent::SkippedBySingleInstanceGuard(*task_id)),
+
+            TimerEvent::TaskQuarantined(task_id, failure) => Ok(PublicEvent::TaskQuarantined(*task_id, *failure)),
+
+            TimerEvent::InstanceStalled(task_id, record_id) => Ok(PublicEvent::InstanceStalled(*task_id, *record_id)),
+
+            TimerEvent::RunningLong(task_id, record_id) => Ok(PublicEvent::RunningLong(*task_id, *record_id)),
+
             _ => Err(anyhow!("PublicEvent only accepts timer_event some variant( RemoveTask, CancelTask ,FinishTask )!")),
         }
     }
@@ -153,7 +425,7 @@ impl TryFrom<TimerEvent> for PublicEvent {
 
     fn try_from(timer_event: TimerEvent) -> Result<Self, Self::Error> {
         match timer_event {
-            TimerEvent::RemoveTask(task_id) => Ok(PublicEvent::RemoveTask(task_id)),
+            TimerEvent::RemoveTask(task_id) | TimerEvent::ExpireTombstone(task_id) => Ok(PublicEvent::RemoveTask(task_id)),
             TimerEvent::AppendTaskHandle(_, delay_task_handler_box) => {
                 Ok(PublicEvent::RunningTask(delay_task_handler_box.get_task_id(), delay_task_handler_box.get_record_id()))
             }
@@ -166,6 +438,34 @@ impl TryFrom<TimerEvent> for PublicEvent {
                 Ok(PublicEvent::TimeoutTask(task_id, record_id))
             }
 
+            TimerEvent::CancelTask(task_id, record_id, termination_reason) => {
+                Ok(PublicEvent::CancelTask(task_id, record_id, termination_reason))
+            }
+
+            TimerEvent::BudgetExceeded(task_id) => Ok(PublicEvent::BudgetExceeded(task_id)),
+
+            TimerEvent::SkippedByExclusion(task_id) => Ok(PublicEvent::SkippedByExclusion(task_id)),
+
+            TimerEvent::SchedulerRestarted(restart_count) => Ok(PublicEvent::SchedulerRestarted(restart_count)),
+
+            TimerEvent::SystemResumedAfter(gap) => Ok(PublicEvent::SystemResumedAfter(gap)),
+
+            TimerEvent::StaleFireDropped(task_id) => Ok(PublicEvent::StaleFireDropped(task_id)),
+
+            TimerEvent::SkippedNotReady(task_id) => Ok(PublicEvent::SkippedNotReady(task_id)),
+
+            TimerEvent::SpawnVetoed(task_id) => Ok(PublicEvent::SpawnVetoed(task_id)),
+
+            TimerEvent::SkippedByCircuitBreaker(task_id) => Ok(PublicEvent::SkippedByCircuitBreaker(task_id)),
+            TimerEvent::SkippedByTenantQuota(task_id) => Ok(PublicEvent::SkippedByTenantQuota(task_id)),
+            TimerEvent::SkippedBySingleInstanceGuard(task_id) => Ok(PublicEvent::SkippedBySingleInstanceGuard(task_id)),
+
+            TimerEvent::TaskQuarantined(task_id, failure) => Ok(PublicEvent::TaskQuarantined(task_id, failure)),
+
+            TimerEvent::InstanceStalled(task_id, record_id) => Ok(PublicEvent::InstanceStalled(task_id, record_id)),
+
+            TimerEvent::RunningLong(task_id, record_id) => Ok(PublicEvent::RunningLong(task_id, record_id)),
+
             _ => Err(anyhow!("PublicEvent only accepts timer_event some variant( RemoveTask, CancelTask ,FinishTask )!")),
         }
     }
@@ -173,12 +473,29 @@ impl TryFrom<TimerEvent> for PublicEvent {
 
 impl PublicEvent {
     /// Get the task_id corresponding to the event.
+    ///
+    /// Events that don't concern a task, such as `SchedulerRestarted` or `SystemResumedAfter`, report `0`.
    pub fn get_task_id(&self) -> u64 {
         match self {
             PublicEvent::RemoveTask(ref task_id) => *task_id,
             PublicEvent::RunningTask(ref task_id, _) => *task_id,
             PublicEvent::FinishTask(PublicFinishTaskBody{task_id,..}) => *task_id,
             PublicEvent::TimeoutTask(ref task_id, _) => *task_id,
+            PublicEvent::CancelTask(ref task_id, _, _) => *task_id,
+            PublicEvent::AbortedWhileHoldingGuard(ref task_id, _) => *task_id,
+            PublicEvent::BudgetExceeded(ref task_id) => *task_id,
+            PublicEvent::SkippedByExclusion(ref task_id) => *task_id,
+            PublicEvent::SchedulerRestarted(_) => 0,
+            PublicEvent::SystemResumedAfter(_) => 0,
+            PublicEvent::StaleFireDropped(ref task_id) => *task_id,
+            PublicEvent::SkippedNotReady(ref task_id) => *task_id,
+            PublicEvent::SpawnVetoed(ref task_id) => *task_id,
+            PublicEvent::SkippedByCircuitBreaker(ref task_id) => *task_id,
+            PublicEvent::SkippedByTenantQuota(ref task_id) => *task_id,
+            PublicEvent::SkippedBySingleInstanceGuard(ref task_id) => *task_id,
+            PublicEvent::TaskQuarantined(ref task_id, _) => *task_id,
+            PublicEvent::InstanceStalled(ref task_id, _) => *task_id,
+            PublicEvent::RunningLong(ref task_id, _) => *task_id,
         }
     }
 
@@ -189,7 +506,117 @@ impl PublicEvent {
             PublicEvent::RunningTask(_,ref record_id) => Some(*record_id),
             PublicEvent::FinishTask(PublicFinishTaskBody{record_id,..}) => Some(*record_id),
             PublicEvent::TimeoutTask(_,ref record_id) => Some(*record_id),
+            PublicEvent::CancelTask(_,ref record_id, _) => Some(*record_id),
+            PublicEvent::AbortedWhileHoldingGuard(_,ref record_id) => Some(*record_id),
+            PublicEvent::BudgetExceeded(_) => None,
+            PublicEvent::SkippedByExclusion(_) => None,
+            PublicEvent::SchedulerRestarted(_) => None,
+            PublicEvent::SystemResumedAfter(_) => None,
+            PublicEvent::StaleFireDropped(_) => None,
+            PublicEvent::SkippedNotReady(_) => None,
+            PublicEvent::SpawnVetoed(_) => None,
+            PublicEvent::SkippedByCircuitBreaker(_) => None,
+            PublicEvent::SkippedByTenantQuota(_) => None,
+            PublicEvent::SkippedBySingleInstanceGuard(_) => None,
+            PublicEvent::TaskQuarantined(_, _) => None,
+            PublicEvent::InstanceStalled(_, ref record_id) => Some(*record_id),
+            PublicEvent::RunningLong(_, ref record_id) => Some(*record_id),
       
         }
     }
+
+    /// Get the reason why the task instance was terminated, if the event describes a termination.
+   pub fn get_termination_reason(&self) -> Option<TerminationReason> {
+        match self {
+            PublicEvent::TimeoutTask(_, _) => Some(TerminationReason::Timeout),
+            PublicEvent::CancelTask(_, _, ref termination_reason) => Some(termination_reason.clone()),
+            _ => None,
+        }
+    }
+
+    // The name of the variant, as written in the exported history.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PublicEvent::RemoveTask(_) => "RemoveTask",
+            PublicEvent::RunningTask(_, _) => "RunningTask",
+            PublicEvent::FinishTask(_) => "FinishTask",
+            PublicEvent::TimeoutTask(_, _) => "TimeoutTask",
+            PublicEvent::CancelTask(_, _, _) => "CancelTask",
+            PublicEvent::AbortedWhileHoldingGuard(_, _) => "AbortedWhileHoldingGuard",
+            PublicEvent::BudgetExceeded(_) => "BudgetExceeded",
+            PublicEvent::SkippedByExclusion(_) => "SkippedByExclusion",
+            PublicEvent::SchedulerRestarted(_) => "SchedulerRestarted",
+            PublicEvent::SystemResumedAfter(_) => "SystemResumedAfter",
+            PublicEvent::StaleFireDropped(_) => "StaleFireDropped",
+            PublicEvent::SkippedNotReady(_) => "SkippedNotReady",
+            PublicEvent::SpawnVetoed(_) => "SpawnVetoed",
+            PublicEvent::SkippedByCircuitBreaker(_) => "SkippedByCircuitBreaker",
+            PublicEvent::SkippedByTenantQuota(_) => "SkippedByTenantQuota",
+            PublicEvent::SkippedBySingleInstanceGuard(_) => "SkippedBySingleInstanceGuard",
+            PublicEvent::TaskQuarantined(_, _) => "TaskQuarantined",
+            PublicEvent::InstanceStalled(_, _) => "InstanceStalled",
+            PublicEvent::RunningLong(_, _) => "RunningLong",
+        }
+    }
+
+    // What the event carries besides its ids, as written in the exported history.
+    pub(crate) fn detail(&self) -> Option<String> {
+        match self {
+            PublicEvent::FinishTask(PublicFinishTaskBody { finish_output, .. }) => {
+                finish_output.as_ref().map(|finish_output| match finish_output {
+                    PublicFinishOutput::ProcessOutput(output) => output.status.to_string(),
+                    PublicFinishOutput::ExceptionOutput(exception) => exception.clone(),
+                })
+            }
+            PublicEvent::CancelTask(_, _, ref termination_reason) => Some(format!("{:?}", termination_reason)),
+            PublicEvent::SchedulerRestarted(restart_count) => Some(restart_count.to_string()),
+            PublicEvent::SystemResumedAfter(gap) => Some(format!("{}s", gap.as_secs())),
+            PublicEvent::TaskQuarantined(_, failure) => Some(format!("{:?}", failure)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_history, HistoryFormat, PublicEvent};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_write_history() -> AnyResult<()> {
+        let history = vec![
+            (10, PublicEvent::RunningTask(1, 7)),
+            (
+                11,
+                PublicEvent::CancelTask(
+                    1,
+                    7,
+                    TerminationReason::Cancelled(CancelOriginator::DelayTimer, Some("a, \"b\"".to_string())),
+                ),
+            ),
+            (12, PublicEvent::SchedulerRestarted(1)),
+        ];
+
+        let mut csv = Vec::new();
+        write_history(&history, HistoryFormat::Csv, &mut csv)?;
+        let csv = String::from_utf8(csv)?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "time,event,task_id,record_id,detail");
+        assert_eq!(lines[1], "10,RunningTask,1,7,");
+        assert!(lines[2].starts_with("11,CancelTask,1,7,\"Cancelled(DelayTimer, Some(\"\"a, "));
+        assert_eq!(lines[3], "12,SchedulerRestarted,0,,1");
+
+        let mut json_lines = Vec::new();
+        write_history(&history, HistoryFormat::JsonLines, &mut json_lines)?;
+        let json_lines = String::from_utf8(json_lines)?;
+        let lines: Vec<&str> = json_lines.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"time":10,"event":"RunningTask","task_id":1,"record_id":7,"detail":null}"#
+        );
+        assert!(lines[1].contains(r#""detail":"Cancelled(DelayTimer, Some(\"a, \\\"b\\\"\"))""#));
+        assert_eq!(lines.len(), 3);
+
+        Ok(())
+    }
 }
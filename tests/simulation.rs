@@ -11,91 +11,3093 @@ use std::time::Duration;
 
 use smol::Timer;
 
+// The runtime under test is chosen by the `test-runtime-tokio` feature.
+fn delay_timer_builder() -> DelayTimerBuilder {
+    #[cfg(feature = "test-runtime-tokio")]
+    return DelayTimerBuilder::default().tokio_runtime_by_default();
+
+    #[cfg(not(feature = "test-runtime-tokio"))]
+    return DelayTimerBuilder::default();
+}
+
+// Wait for `condition` rather than sleeping a fixed time, `false` if it's still unmet after `timeout`.
+fn wait_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    while !condition() {
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        park_timeout(Duration::from_millis(50));
+    }
+    true
+}
+
 // TODO: Please turn on `--features=full` before test.
 #[test]
 fn test_instance_state() -> anyhow::Result<()> {
     let delay_timer = DelayTimer::new();
 
-    let body = || async {
-        println!("create_async_fn_body:i'success");
-        Timer::after(Duration::from_millis(100)).await;
-    };
+    let body = || async {
+        println!("create_async_fn_body:i'success");
+        Timer::after(Duration::from_millis(100)).await;
+    };
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 4)
+        .set_task_id(1)
+        .set_maximum_parallel_runnable_num(3)
+        .spawn_async_routine(body)?;
+
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // Get the first task instance.
+    let instance = task_instance_chain.next_with_wait()?;
+
+    // The task was still running when the instance was first obtained.
+    assert_eq!(instance.get_state(), instance::RUNNING);
+
+    // Unsolicited mission cancellation.
+    instance.cancel_with_wait()?;
+
+    assert_eq!(instance.get_state(), instance::CANCELLED);
+
+    // Get the second task instance.
+    let instance = task_instance_chain.next_with_wait()?;
+
+    // Just got the instance when it was still running.
+    assert_eq!(instance.get_state(), instance::RUNNING);
+
+    // The task execution is completed.
+    park_timeout(Duration::from_secs(1));
+
+    // This should be the completed state.
+    assert_eq!(instance.get_state(), instance::COMPLETED);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_instance_timeout_state() -> anyhow::Result<()> {
+    let delay_timer = DelayTimer::new();
+
+    let body = || async {
+        println!("test_instance_timeout_state");
+        Timer::after(Duration::from_secs(3)).await;
+        println!("test_instance_timeout_state end.");
+    };
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 4)
+        .set_task_id(1)
+        .set_maximum_running_time(1)
+        .set_maximum_parallel_runnable_num(3)
+        .spawn_async_routine(body)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // Get the first task instance.
+    let instance = task_instance_chain.next_with_wait()?;
+
+    // The task was still running when the instance was first obtained.
+    assert_eq!(instance.get_state(), instance::RUNNING);
+
+    // The task execution is timeout after about 3 second.
+    let timeout = Duration::from_secs(5);
+    let beginning_park = std::time::Instant::now();
+
+    let mut timeout_remaining = timeout;
+    loop {
+        // may wake spuriously, So have to do it separately.
+        park_timeout(timeout_remaining);
+        let elapsed = beginning_park.elapsed();
+        if elapsed >= timeout {
+            break;
+        }
+        timeout_remaining = timeout - elapsed;
+    }
+
+    // This should be the completed state.
+    assert_ne!(instance.get_state(), instance::RUNNING);
+
+    Ok(())
+}
+
+// A routine that outlives the tests, so that its instances end only by being terminated.
+fn long_running_task(task_id: u64) -> TaskBuilder<'static> {
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(task_id)
+        .set_maximum_parallel_runnable_num(1);
+    task_builder
+}
+
+async fn sleep_long() {
+    Timer::after(Duration::from_secs(30)).await;
+}
+
+#[test]
+fn test_termination_reason_cancelled() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let task = long_running_task(1).spawn_async_routine(sleep_long)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let mut instance = task_instance_chain.next_with_wait()?;
+    instance.set_cancel_reason("superseded");
+    instance.cancel_with_wait()?;
+
+    assert_eq!(
+        instance.termination_reason(),
+        Some(TerminationReason::Cancelled(
+            CancelOriginator::TaskInstance,
+            Some("superseded".to_string())
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_termination_reason_timeout() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let task = long_running_task(1)
+        .set_maximum_running_time(1)
+        .spawn_async_routine(sleep_long)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        instance.get_state() == instance::TIMEOUT
+    }));
+
+    assert_eq!(
+        instance.termination_reason(),
+        Some(TerminationReason::Timeout)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_termination_reason_shutdown() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let task = long_running_task(1).spawn_async_routine(sleep_long)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let instance = task_instance_chain.next_with_wait()?;
+    // The running instances are cancelled before the report is sent back.
+    delay_timer.shutdown()?;
+
+    assert_eq!(
+        instance.termination_reason(),
+        Some(TerminationReason::Shutdown)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_termination_reason_replaced() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let task = long_running_task(1)
+        .set_replace_on_overlap(true)
+        .spawn_async_routine(sleep_long)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let first_instance = task_instance_chain.next_with_wait()?;
+    // The first instance is replaced before the second one is appended.
+    let second_instance = task_instance_chain.next_with_wait()?;
+
+    assert_eq!(first_instance.get_state(), instance::CANCELLED);
+    assert_eq!(
+        first_instance.termination_reason(),
+        Some(TerminationReason::Replaced)
+    );
+    assert_eq!(second_instance.get_state(), instance::RUNNING);
+    Ok(())
+}
+
+#[test]
+fn test_spawn_async_routine_local() -> anyhow::Result<()> {
+    use std::rc::Rc;
+
+    let delay_timer = delay_timer_builder().build();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs_ref = runs.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 3)
+        .set_task_id(1)
+        .spawn_async_routine_local(move || {
+            let runs_ref = runs_ref.clone();
+            async move {
+                // Held across an await, the future isn't `Send`.
+                let local_state = Rc::new(thread::current().name().map(str::to_string));
+                Timer::after(Duration::from_millis(100)).await;
+
+                assert_eq!(local_state.as_deref(), Some("local_worker"));
+                runs_ref.fetch_add(1, Release);
+            }
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(10), || runs.load(Acquire) == 3));
+
+    Ok(())
+}
+
+#[test]
+fn test_dedicated_thread_execution() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs_ref = runs.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 2)
+        .set_task_id(1)
+        .set_execution_mode(Execution::DedicatedThread)
+        .spawn_routine(move || {
+            if thread::current().name() == Some("dedicated_instance") {
+                runs_ref.fetch_add(1, Release);
+            }
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(8), || runs.load(Acquire) == 2));
+
+    Ok(())
+}
+
+#[test]
+fn test_executor_pool() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().executor_pool("heavy", 2).build();
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let on_pool = |task_id: u64, pool: &str| {
+        let runs = runs.clone();
+        let pool = pool.to_string();
+        TaskBuilder::default()
+            .set_frequency_count_down_by_seconds(1, 2)
+            .set_task_id(task_id)
+            .set_pool(pool.clone())
+            .spawn_routine(move || {
+                let on_pool = thread::current()
+                    .name()
+                    .is_some_and(|name| name.starts_with("pool-heavy-"));
+                if on_pool == (pool == "heavy") {
+                    runs.fetch_add(1, Release);
+                }
+            })
+    };
+    // A task assigned to a pool the timer doesn't have runs on the runtime.
+    delay_timer.add_task(on_pool(1, "heavy")?)?;
+    delay_timer.add_task(on_pool(2, "missing")?)?;
+
+    assert!(wait_until(Duration::from_secs(8), || runs.load(Acquire) == 4));
+
+    Ok(())
+}
+
+#[test]
+fn test_executor_pool_dispatch() -> anyhow::Result<()> {
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder()
+        .executor_pool_with_dispatch("sticky", 4, Dispatch::StickyByTask)
+        .build();
+    let thread_names = Arc::new(Mutex::new(Vec::new()));
+
+    // The instances of the task all run on the same thread.
+    let task = {
+        let thread_names = thread_names.clone();
+        TaskBuilder::default()
+            .set_frequency_count_down_by_seconds(1, 3)
+            .set_task_id(1)
+            .set_pool("sticky")
+            .spawn_routine(move || {
+                let thread_name = thread::current().name().map(str::to_string);
+                thread_names.lock().unwrap().push(thread_name);
+            })?
+    };
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(10), || {
+        thread_names.lock().unwrap().len() == 3
+    }));
+    let thread_names: HashSet<_> = thread_names.lock().unwrap().drain(..).collect();
+    assert_eq!(thread_names.len(), 1);
+    assert_eq!(
+        thread_names.iter().next(),
+        Some(&Some("pool-sticky-1".to_string()))
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_dedicated_thread_options() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let pinned = Arc::new(AtomicUsize::new(0));
+    let pinned_ref = pinned.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .set_execution_mode(Execution::DedicatedThread)
+        .set_thread_options(ThreadOptions {
+            cpu_affinity: Some(vec![0]),
+            ..ThreadOptions::default()
+        })
+        .spawn_routine(move || {
+            // Safety: the calls only read the affinity of the calling thread.
+            let cpu_count = unsafe {
+                let mut cpu_set: libc::cpu_set_t = std::mem::zeroed();
+                libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut cpu_set);
+                (0..libc::CPU_SETSIZE as usize)
+                    .filter(|&cpu| libc::CPU_ISSET(cpu, &cpu_set))
+                    .count()
+            };
+            pinned_ref.store(cpu_count, Release);
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(5), || pinned.load(Acquire) > 0));
+    assert_eq!(pinned.load(Acquire), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_instance_chain_reattach() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let body = || async {
+        Timer::after(Duration::from_millis(100)).await;
+    };
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_async_routine(body)?;
+
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    task_instance_chain.next_with_wait()?;
+
+    // Stop routing instances, and then attach a new chain.
+    task_instance_chain.close()?;
+    let task_instance_chain = delay_timer.instance_chain(1)?;
+
+    let instance = task_instance_chain.next_with_wait()?;
+    assert_eq!(instance.get_state(), instance::RUNNING);
+
+    // The replaced chain is no longer maintained.
+    let task_instance_chain_new = delay_timer.instance_chain(1)?;
+    task_instance_chain_new.next_with_wait()?;
+    assert!(matches!(
+        task_instance_chain.next(),
+        Err(TaskInstanceError::Expired)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_instance_chain_liveness() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 2)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_millis(100)).await;
+        })?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // Once the countdown is over and the last instance has ended, the chain expires.
+    task_instance_chain.next_with_wait()?;
+    let last_instance = task_instance_chain.next_with_wait()?;
+    assert!(matches!(
+        task_instance_chain.next_with_timeout(Duration::from_secs(3)),
+        Err(TaskInstanceError::Expired)
+    ));
+    assert_eq!(last_instance.get_state(), instance::COMPLETED);
+
+    // A task that doesn't fire within the timeout.
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(2)
+        .spawn_async_routine(|| async {})?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    assert!(matches!(
+        task_instance_chain.next_with_timeout(Duration::from_millis(500)),
+        Err(TaskInstanceError::DisNextTimeOut)
+    ));
+
+    // Removing the task expires its chain.
+    delay_timer.remove_task(2)?;
+    assert!(matches!(
+        task_instance_chain.next_with_wait(),
+        Err(TaskInstanceError::Expired)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_subscribe_instances() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let body = || async {
+        Timer::after(Duration::from_millis(100)).await;
+    };
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_async_routine(body)?;
+
+    delay_timer.add_task(task)?;
+
+    // Both subscribers get the same instance.
+    let task_instance_chain_first = delay_timer.subscribe_instances(1)?;
+    let task_instance_chain_second = delay_timer.subscribe_instances(1)?;
+
+    let instance_first = task_instance_chain_first.next_with_wait()?;
+    let instance_second = task_instance_chain_second.next_with_wait()?;
+    assert_eq!(instance_first.get_state(), instance_second.get_state());
+
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance_first.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(instance_second.get_state(), instance::COMPLETED);
+
+    Ok(())
+}
+
+#[test]
+fn test_cancel_graceful() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    // Stops as soon as it is asked to.
+    let cooperative_task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_async_routine_with_context(|context| async move {
+            context.cancelled().await;
+        })?;
+
+    // Never looks at the token.
+    let stubborn_task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(2)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(60)).await;
+        })?;
+
+    delay_timer.add_task(cooperative_task)?;
+    delay_timer.add_task(stubborn_task)?;
+
+    let cooperative_chain = delay_timer.instance_chain(1)?;
+    let stubborn_chain = delay_timer.instance_chain(2)?;
+
+    let cooperative_instance = cooperative_chain.next_with_wait()?;
+    assert_eq!(
+        cooperative_instance.cancel_graceful(Duration::from_secs(1))?,
+        CancellationPath::Cooperative
+    );
+    assert_eq!(cooperative_instance.get_state(), instance::COMPLETED);
+
+    let stubborn_instance = stubborn_chain.next_with_wait()?;
+    assert_eq!(
+        stubborn_instance.cancel_graceful(Duration::from_millis(100))?,
+        CancellationPath::Aborted
+    );
+    assert_eq!(stubborn_instance.get_state(), instance::CANCELLED);
+
+    Ok(())
+}
+
+#[test]
+fn test_on_aborted() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let aborted_record = Arc::new(AtomicI32::new(0));
+    let aborted_record_ref = aborted_record.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .set_maximum_running_time(1)
+        .set_on_aborted(move |aborted_instance| {
+            assert_eq!(
+                aborted_instance.termination_reason,
+                TerminationReason::Timeout
+            );
+            assert!(!aborted_instance.cancellation_observed);
+            aborted_record_ref.fetch_add(1, Release);
+        })
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(60)).await;
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(8), || {
+        aborted_record.load(Acquire) == 1
+    }));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_aborted_while_holding_guard() -> anyhow::Result<()> {
+    use delay_timer::testing::EventCollector;
+
+    let mut delay_timer = delay_timer_builder().enable_status_report().build();
+    let collector = EventCollector::new(
+        delay_timer
+            .take_status_reporter()
+            .ok_or(anyhow!("Without `status_reporter`."))?,
+    );
+
+    // A routine without a `TaskContext` has no token to ignore.
+    let task = long_running_task(1)
+        .set_maximum_running_time(1)
+        .set_on_aborted(|_| panic!("`on_aborted` panics"))
+        .spawn_async_routine(sleep_long)?;
+    delay_timer.add_task(task)?;
+
+    // The event-handle outlives the panicking closure, the timeouts keep being handled.
+    assert!(collector.wait_until(Duration::from_secs(8), |events| {
+        events
+            .iter()
+            .filter(|(_, e)| matches!(e, PublicEvent::TimeoutTask(1, _)))
+            .count()
+            >= 2
+    }));
+
+    let task = long_running_task(2)
+        .set_maximum_running_time(1)
+        .spawn_async_routine_with_context(|_context| sleep_long())?;
+    delay_timer.add_task(task)?;
+
+    assert!(collector.wait_until(Duration::from_secs(5), |events| {
+        events
+            .iter()
+            .any(|(_, e)| matches!(e, PublicEvent::AbortedWhileHoldingGuard(2, _)))
+    }));
+    assert!(!collector
+        .events()
+        .iter()
+        .any(|(_, e)| matches!(e, PublicEvent::AbortedWhileHoldingGuard(1, _))));
+
+    Ok(())
+}
+
+#[test]
+fn test_run_on_startup() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let run_times = Arc::new(AtomicUsize::new(0));
+    let run_times_ref = run_times.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(3, 1)
+        .set_task_id(1)
+        .set_run_on_startup(true)
+        .spawn_routine(move || {
+            run_times_ref.fetch_add(1, Release);
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        run_times.load(Acquire) == 1
+    }));
+
+    // The startup fire doesn't count against the countdown.
+    assert!(wait_until(Duration::from_secs(6), || {
+        run_times.load(Acquire) == 2
+    }));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_replay_events() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(16).build();
+    let start_time = timestamp();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+
+    delay_timer.add_task(task)?;
+
+    // The consumer attaches after the task has already run.
+    assert!(wait_until(Duration::from_secs(5), || {
+        delay_timer
+            .replay_events(start_time)
+            .iter()
+            .any(|e| matches!(e, PublicEvent::RunningTask(1, _)))
+    }));
+    assert!(delay_timer.replay_events(timestamp() + 1).is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_capture_logs() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(16).build();
+    let start_time = timestamp();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .set_log_target("reports")
+        .set_capture_logs(true)
+        .spawn_async_routine_with_context(|context: TaskContext| async move {
+            context.logger().info("report generated");
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    let captured_logs = || -> Vec<String> {
+        delay_timer
+            .replay_events(start_time)
+            .into_iter()
+            .filter_map(|e| match e {
+                PublicEvent::FinishTask(mut finish_task_body) => {
+                    Some(finish_task_body.get_captured_logs())
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    };
+    assert!(wait_until(Duration::from_secs(5), || {
+        captured_logs().contains(&"INFO report generated".to_string())
+    }));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_log_events() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    // Keeps the lines logged under the `delay_timer::events` target.
+    struct EventLines(Mutex<Vec<String>>);
+
+    impl log::Log for EventLines {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            metadata.target() == "delay_timer::events"
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static EVENT_LINES: EventLines = EventLines(Mutex::new(Vec::new()));
+    log::set_logger(&EVENT_LINES).map_err(|e| anyhow!("{}", e))?;
+    log::set_max_level(log::LevelFilter::Info);
+
+    // The routine events are sampled out, the log has them all the same.
+    let delay_timer = delay_timer_builder()
+        .log_events(LogLevel::Info)
+        .event_sampling(EventSampling::FailuresOnly)
+        .build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+
+    let task_lines = || -> Vec<String> {
+        EVENT_LINES
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|line| line.contains(" task_id=1 "))
+            .cloned()
+            .collect()
+    };
+    assert!(wait_until(Duration::from_secs(5), || {
+        task_lines()
+            .iter()
+            .any(|line| line.starts_with("event=FinishTask "))
+    }));
+
+    let running = task_lines()
+        .into_iter()
+        .find(|line| line.starts_with("event=RunningTask "))
+        .ok_or(anyhow!("Without `RunningTask`."))?;
+    let fields: Vec<&str> = running.split(' ').collect();
+    assert_eq!(fields.len(), 4);
+    assert_eq!(fields[1], "task_id=1");
+    assert!(fields[2]
+        .strip_prefix("record_id=")
+        .is_some_and(|record_id| record_id.parse::<i64>().is_ok()));
+    assert_eq!(fields[3], "detail=");
+
+    Ok(())
+}
+
+#[cfg(feature = "otel")]
+#[test]
+fn test_otel_parent_context() -> anyhow::Result<()> {
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::Context;
+
+    let delay_timer = delay_timer_builder().build();
+    let trace_id = TraceId::from_bytes(7u128.to_be_bytes());
+    let parent_context = Context::new().with_remote_span_context(SpanContext::new(
+        trace_id,
+        SpanId::from_bytes(7u64.to_be_bytes()),
+        TraceFlags::SAMPLED,
+        true,
+        TraceState::default(),
+    ));
+
+    let linked = Arc::new(AtomicUsize::new(0));
+    let linked_ref = linked.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_cron_str("@yearly")
+        .set_task_id(1)
+        .set_otel_parent_context(parent_context)
+        .spawn_routine_with_context(move |context: TaskContext| {
+            // Whichever tracer is installed, the span keeps the trace of its parent.
+            if context.otel_context().span().span_context().trace_id() == trace_id {
+                linked_ref.fetch_add(1, Release);
+            }
+        })?;
+
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 1
+    }));
+    delay_timer.advance_task(1)?;
+
+    assert!(wait_until(Duration::from_secs(5), || linked.load(Acquire) == 1));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_max_queue_delay() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(64).build();
+    let start_time = timestamp();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_maximum_parallel_runnable_num(1)
+        .set_max_queue_delay(Duration::from_secs(1))
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(10)).await;
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    // The fires held back by the running instance waited too long.
+    assert!(wait_until(Duration::from_secs(10), || {
+        delay_timer
+            .replay_events(start_time)
+            .iter()
+            .any(|e| matches!(e, PublicEvent::StaleFireDropped(1)))
+    }));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_runtime_warning_threshold() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(64).build();
+    let start_time = timestamp();
+
+    // Warned halfway through its timeout, it keeps running.
+    let slow = TaskBuilder::default()
+        .set_frequency_once_by_seconds(1)
+        .set_task_id(1)
+        .set_maximum_running_time(4)
+        .set_runtime_warning_threshold(0.5)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(3)).await;
+        })?;
+    // Done before its threshold.
+    let quick = TaskBuilder::default()
+        .set_frequency_once_by_seconds(1)
+        .set_task_id(2)
+        .set_runtime_warning_threshold(Duration::from_secs(1))
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_millis(200)).await;
+        })?;
+    let slow_chain = delay_timer.insert_task(slow)?;
+    delay_timer.add_task(quick)?;
+
+    let slow_instance = slow_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(8), || {
+        slow_instance.get_state() != instance::RUNNING
+    }));
+
+    let events = delay_timer.replay_events(start_time);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, PublicEvent::RunningLong(1, _))));
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, PublicEvent::RunningLong(2, _))));
+    assert_eq!(slow_instance.get_state(), instance::COMPLETED);
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_failure_policy() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(64).build();
+    let start_time = timestamp();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs_ref = runs.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_maximum_running_time(1)
+        .set_failure_policy(Failure::PauseAfter(2))
+        .spawn_async_routine(move || {
+            let runs_ref = runs_ref.clone();
+            async move {
+                runs_ref.fetch_add(1, Release);
+                Timer::after(Duration::from_secs(10)).await;
+            }
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    // Every instance timed out, the task was paused after the second one.
+    assert!(wait_until(Duration::from_secs(10), || {
+        delay_timer
+            .replay_events(start_time)
+            .iter()
+            .any(|e| matches!(e, PublicEvent::TaskQuarantined(1, Failure::PauseAfter(2))))
+    }));
+
+    let paused_runs = runs.load(Acquire);
+    park_timeout(Duration::from_millis(2000));
+    assert_eq!(runs.load(Acquire), paused_runs);
+
+    delay_timer.resume_task(1)?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        runs.load(Acquire) > paused_runs
+    }));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_circuit_breaker() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_event_log(64).build();
+    let start_time = timestamp();
+    let circuit_breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+
+    for task_id in 1..=2 {
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(task_id)
+            .set_maximum_running_time(1)
+            .set_circuit_breaker(circuit_breaker.clone())
+            .spawn_async_routine(|| async {
+                Timer::after(Duration::from_secs(10)).await;
+            })?;
+
+        delay_timer.add_task(task)?;
+    }
+
+    // The timeouts of either task tripped the breaker, both stopped firing.
+    assert!(wait_until(Duration::from_secs(10), || {
+        let events = delay_timer.replay_events(start_time);
+        (1..=2).all(|task_id| {
+            events
+                .iter()
+                .any(|e| matches!(e, PublicEvent::SkippedByCircuitBreaker(id) if *id == task_id))
+        })
+    }));
+    assert_eq!(circuit_breaker.state(), CircuitState::Open);
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_event_collector() -> anyhow::Result<()> {
+    use delay_timer::testing::EventCollector;
+    use delay_timer::{assert_fired, assert_not_fired};
+
+    let mut delay_timer = delay_timer_builder().enable_status_report().build();
+    let collector = EventCollector::new(
+        delay_timer
+            .take_status_reporter()
+            .ok_or(anyhow!("Without `status_reporter`."))?,
+    );
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(434)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+
+    assert_fired!(collector, 434, times = 2, within = Duration::from_secs(5));
+
+    delay_timer.remove_task(434)?;
+    assert!(collector.wait_until(Duration::from_secs(2), |events| {
+        events
+            .iter()
+            .any(|(_, e)| matches!(e, PublicEvent::RemoveTask(434)))
+    }));
+    assert_not_fired!(collector, 434, within = Duration::from_millis(2500));
+
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_event_ordering() -> anyhow::Result<()> {
+    let mut delay_timer = delay_timer_builder().enable_status_report().build();
+    let status_reporter = delay_timer
+        .take_status_reporter()
+        .ok_or(anyhow!("Without `status_reporter`."))?;
+
+    // The routine is done at once, so its `FinishTask` may well be sent before its `AppendTaskHandle`.
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 3)
+        .set_task_id(460)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+
+    let mut events = Vec::new();
+    assert!(wait_until(Duration::from_secs(10), || {
+        while let Ok(sequenced_event) = status_reporter.next_sequenced_event() {
+            events.push(sequenced_event);
+        }
+        events
+            .iter()
+            .filter(|(_, e)| matches!(e, PublicEvent::FinishTask(_)))
+            .count()
+            == 3
+    }));
+
+    for window in events.windows(2) {
+        assert_eq!(window[0].0 + 1, window[1].0);
+    }
+
+    let mut running = Vec::new();
+    let mut finished = 0;
+    for (_, public_event) in events {
+        match public_event {
+            PublicEvent::RunningTask(460, record_id) => running.push(record_id),
+            PublicEvent::FinishTask(finish_task_body) if finish_task_body.get_task_id() == 460 => {
+                assert!(running.contains(&finish_task_body.get_record_id()));
+                finished += 1;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(running.len(), 3);
+    assert_eq!(finished, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_register_then_activate() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let run_flag = Arc::new(AtomicUsize::new(0));
+
+    let mut registered_tasks = Vec::new();
+    for task_id in 1..=3 {
+        let run_flag_ref = run_flag.clone();
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(task_id)
+            .spawn_async_routine(move || {
+                let run_flag_ref = run_flag_ref.clone();
+                async move {
+                    run_flag_ref.fetch_add(1, Release);
+                }
+            })?;
+        registered_tasks.push(delay_timer.register_task(task)?);
+    }
+
+    park_timeout(Duration::from_millis(2100));
+    assert_eq!(run_flag.load(Acquire), 0);
+    assert_eq!(delay_timer.health().tasks_registered, 0);
+
+    for registered_task in registered_tasks.iter() {
+        registered_task.activate()?;
+    }
+
+    assert!(wait_until(Duration::from_secs(5), || {
+        run_flag.load(Acquire) >= 3
+    }));
+    assert_eq!(delay_timer.health().tasks_registered, 3);
+
+    Ok(())
+}
+
+#[cfg(feature = "debug-dump")]
+#[test]
+fn test_debug_dump() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    for (task_id, seconds) in [(1, 5), (2, 10), (3, 7200)] {
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(seconds)
+            .set_task_id(task_id)
+            .spawn_async_routine(|| async {})?;
+        delay_timer.add_task(task)?;
+    }
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.debug_dump().task_count() == 3
+    }));
+
+    let wheel_dump = delay_timer.debug_dump();
+    // The hourly-and-more task waits for laps of the wheel.
+    assert_eq!(
+        wheel_dump
+            .slots
+            .iter()
+            .map(|slot_dump| slot_dump.cascade_count)
+            .sum::<usize>(),
+        1
+    );
+
+    let task_ids: Vec<u64> = wheel_dump
+        .next_fires
+        .iter()
+        .map(|(task_id, _)| *task_id)
+        .collect();
+    assert_eq!(task_ids, vec![1, 2, 3]);
+    assert!(wheel_dump.to_string().contains("#1@"));
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_task_delayed() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let run_flag = Arc::new(AtomicUsize::new(0));
+    let run_flag_ref = run_flag.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_async_routine(move || {
+            let run_flag_ref = run_flag_ref.clone();
+            async move {
+                run_flag_ref.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        run_flag.load(Acquire) > 0
+    }));
+
+    // Paused during the grace period.
+    delay_timer.remove_task_delayed(1, Duration::from_secs(3))?;
+    park_timeout(Duration::from_millis(200));
+    let paused_runs = run_flag.load(Acquire);
+    park_timeout(Duration::from_millis(1500));
+    assert_eq!(run_flag.load(Acquire), paused_runs);
+
+    delay_timer.undo_remove(1)?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        run_flag.load(Acquire) > paused_runs
+    }));
+
+    // The first grace period being over doesn't remove the restored task.
+    assert_eq!(delay_timer.health().tasks_registered, 1);
+
+    delay_timer.remove_task_delayed(1, Duration::from_secs(1))?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        delay_timer.health().tasks_registered == 0
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn test_task_init() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let init_count = Arc::new(AtomicUsize::new(0));
+    let init_count_ref = init_count.clone();
+    let pooled_runs = Arc::new(AtomicUsize::new(0));
+    let pooled_runs_ref = pooled_runs.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_init(move || {
+            let init_count_ref = init_count_ref.clone();
+            async move {
+                init_count_ref.fetch_add(1, Release);
+                Ok::<_, anyhow::Error>(String::from("pool"))
+            }
+        })
+        .spawn_routine_with_context(move |context: TaskContext| {
+            if context.resource::<String>().map(String::as_str) == Some("pool") {
+                pooled_runs_ref.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(6), || {
+        pooled_runs.load(Acquire) >= 2
+    }));
+
+    // Built once, then shared by the runs.
+    assert_eq!(init_count.load(Acquire), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_task_teardown() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let teardown_count = Arc::new(AtomicUsize::new(0));
+
+    for task_id in 1..=2 {
+        let teardown_count_ref = teardown_count.clone();
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(60)
+            .set_task_id(task_id)
+            .set_teardown(move || {
+                let teardown_count_ref = teardown_count_ref.clone();
+                async move {
+                    teardown_count_ref.fetch_add(1, Release);
+                }
+            })
+            .spawn_async_routine(|| async {})?;
+        delay_timer.add_task(task)?;
+    }
+
+    // Released once the task is removed.
+    delay_timer.remove_task(1)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        teardown_count.load(Acquire) == 1
+    }));
+
+    // The shutdown waits for the tasks still there.
+    let shutdown_report = delay_timer.shutdown()?;
+    assert_eq!(shutdown_report.torn_down_tasks, 1);
+    assert!(shutdown_report.unfinished_teardowns.is_empty());
+    assert_eq!(teardown_count.load(Acquire), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_task_teardown_timeout() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    for (task_id, seconds) in [(1, 0), (2, 30)] {
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(60)
+            .set_task_id(task_id)
+            .set_teardown(move || async move {
+                Timer::after(Duration::from_secs(seconds)).await;
+            })
+            .spawn_async_routine(|| async {})?;
+        delay_timer.add_task(task)?;
+    }
+    park_timeout(Duration::from_millis(200));
+
+    // The shutdown doesn't wait for a teardown that hangs.
+    let shutdown_report = delay_timer
+        .shutdown_with(ShutdownOptions::default().teardown_timeout(Duration::from_millis(500)))?;
+    assert_eq!(shutdown_report.torn_down_tasks, 2);
+    assert_eq!(shutdown_report.unfinished_teardowns, vec![2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_count_down_policy() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder()
+        .count_down_policy(CountDownPolicy::ScheduledFires)
+        .build();
+    let run_flag = Arc::new(AtomicUsize::new(0));
+    let run_flag_ref = run_flag.clone();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 5)
+        .set_task_id(1)
+        .spawn_async_routine(move || {
+            let run_flag_ref = run_flag_ref.clone();
+            async move {
+                run_flag_ref.fetch_add(1, Release);
+            }
+        })?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(instance.scheduled_for() > 0);
+    assert!(instance.started_at() >= instance.scheduled_for());
+
+    // The fires due while the task is paused count against its countdown,
+    // so it runs out before having run five times.
+    delay_timer.remove_task_delayed(1, Duration::from_secs(30))?;
+    park_timeout(Duration::from_millis(3200));
+    delay_timer.undo_remove(1)?;
+    park_timeout(Duration::from_millis(3000));
+    assert!(run_flag.load(Acquire) <= 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_count_down_exhausted_while_paused() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder()
+        .count_down_policy(CountDownPolicy::ScheduledFires)
+        .build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 2)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    task_instance_chain.next_with_wait()?;
+
+    // The last fire is due while the task is paused, undoing the removal finds it used up.
+    delay_timer.remove_task_delayed(1, Duration::from_secs(30))?;
+    park_timeout(Duration::from_millis(2200));
+    delay_timer.undo_remove(1)?;
+
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.health().tasks_registered == 0
+    }));
+    assert!(task_instance_chain.next_with_wait().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_task_ownership() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let billing = OwnerToken::new("billing");
+    let reports = OwnerToken::new("reports");
+
+    let body = || async {};
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(1);
+
+    delay_timer.add_task_owned(task_builder.spawn_async_routine(body)?, &billing)?;
+    assert_eq!(delay_timer.tasks_owned_by(&billing), vec![1]);
+    assert!(delay_timer.tasks_owned_by(&reports).is_empty());
+
+    // Another owner, or no owner at all, can't clobber the task.
+    assert!(matches!(
+        delay_timer.add_task_owned(task_builder.spawn_async_routine(body)?, &reports),
+        Err(TaskError::NotOwner(1))
+    ));
+    assert!(matches!(
+        delay_timer.update_task(task_builder.spawn_async_routine(body)?),
+        Err(TaskError::NotOwner(1))
+    ));
+    assert!(matches!(
+        delay_timer.remove_task(1),
+        Err(TaskError::NotOwner(1))
+    ));
+    assert!(delay_timer.remove_task_owned(1, &reports).is_err());
+    assert!(matches!(
+        delay_timer.advance_task(1),
+        Err(TaskError::NotOwner(1))
+    ));
+    assert!(matches!(
+        delay_timer.cancel_task(1, 0),
+        Err(TaskError::NotOwner(1))
+    ));
+    assert!(matches!(
+        delay_timer.undo_remove(1),
+        Err(TaskError::NotOwner(1))
+    ));
+
+    delay_timer.update_task_owned(task_builder.spawn_async_routine(body)?, &billing.clone())?;
+    park_timeout(Duration::from_millis(200));
+    delay_timer.advance_task_owned(1, AdvanceOptions::default(), &billing)?;
+    delay_timer.remove_task_owned(1, &billing)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.tasks_owned_by(&billing).is_empty()
+    }));
+
+    // The task-id of a task without owner can't be claimed.
+    task_builder.set_task_id(2);
+    delay_timer.add_task(task_builder.spawn_async_routine(body)?)?;
+    park_timeout(Duration::from_millis(200));
+    assert!(matches!(
+        delay_timer.add_task_owned(task_builder.spawn_async_routine(body)?, &billing),
+        Err(TaskError::TaskIdInUse(2))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_scoped_delay_timer() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let scheduler_lib = delay_timer.scoped("scheduler-lib");
+    let other_lib = delay_timer.scoped("other-lib");
+    assert_eq!(scheduler_lib.namespace(), "scheduler-lib");
+
+    let body = || async {};
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(1);
+
+    // The same task-id in the application and in two namespaces.
+    delay_timer.add_task(task_builder.spawn_async_routine(body)?)?;
+    scheduler_lib.add_task(task_builder.spawn_async_routine(body)?)?;
+    other_lib.add_task(task_builder.spawn_async_routine(body)?)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 3
+    }));
+
+    assert_eq!(scheduler_lib.task_ids(), vec![1]);
+    assert_eq!(other_lib.task_ids(), vec![1]);
+    assert_ne!(scheduler_lib.scoped_task_id(1)?, 1);
+    assert_ne!(
+        scheduler_lib.scoped_task_id(1)?,
+        other_lib.scoped_task_id(1)?
+    );
+
+    scheduler_lib.remove_task(1)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 2
+    }));
+    assert!(scheduler_lib.task_ids().is_empty());
+    assert_eq!(other_lib.task_ids(), vec![1]);
+
+    assert!(matches!(
+        scheduler_lib.remove_task(u64::MAX),
+        Err(TaskError::TaskIdOutOfScope(u64::MAX))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_simulate() -> anyhow::Result<()> {
+    use delay_timer::testing::{simulate, SimulatedTask};
+
+    let from = timestamp();
+    let to = from + 60;
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_repeated_by_cron_str("0/10 * * * * * *")
+        .set_maximum_parallel_runnable_num(1);
+
+    // Instances outlive the interval, every other fire is skipped.
+    let skipping = SimulatedTask {
+        task: task_builder
+            .set_task_id(1)
+            .spawn_async_routine(|| async {})?,
+        run_time: 15,
+    };
+    // Or waits for the previous instance, if it may be queued.
+    let queueing = SimulatedTask {
+        task: task_builder
+            .set_task_id(2)
+            .set_max_queue_delay(Duration::from_secs(10))
+            .spawn_async_routine(|| async {})?,
+        run_time: 15,
+    };
+
+    let fire_plans = simulate(vec![skipping, queueing], from, to);
+
+    let skipping: Vec<_> = fire_plans.iter().filter(|plan| plan.task_id == 1).collect();
+    assert!(skipping.len() >= 5);
+    for (i, plan) in skipping.iter().enumerate() {
+        assert_eq!(plan.scheduled_time % 10, 0);
+        assert_eq!(plan.fire_time, plan.scheduled_time);
+        if i % 2 == 0 {
+            assert_eq!(plan.skipped_reason, None);
+        } else {
+            assert_eq!(
+                plan.skipped_reason,
+                Some(SkipReason::MaximumParallelRunnable)
+            );
+        }
+    }
+
+    let queueing: Vec<_> = fire_plans.iter().filter(|plan| plan.task_id == 2).collect();
+    assert_eq!(queueing[0].fire_time, queueing[0].scheduled_time);
+    assert_eq!(queueing[1].fire_time, queueing[1].scheduled_time + 5);
+    assert_eq!(queueing[1].skipped_reason, None);
+
+    assert!(fire_plans
+        .iter()
+        .all(|plan| (from..=to).contains(&plan.scheduled_time)));
+
+    Ok(())
+}
+
+#[test]
+fn test_keep_alive() -> anyhow::Result<()> {
+    use delay_timer::testing::{simulate, SimulatedTask};
+
+    let from = timestamp();
+    let mut task_builder = TaskBuilder::default();
+    task_builder.set_frequency(Frequency::KeepAlive {
+        restart_delay: Duration::from_secs(5),
+        max_restarts_per_hour: 2,
+    });
+
+    // Each instance is restarted 5 seconds after it ends, twice within the hour.
+    let supervised = SimulatedTask {
+        task: task_builder
+            .set_task_id(1)
+            .spawn_async_routine(|| async {})?,
+        run_time: 10,
+    };
+    let fire_times: Vec<_> = simulate(vec![supervised], from, from + 600)
+        .into_iter()
+        .map(|plan| {
+            assert_eq!(plan.skipped_reason, None);
+            plan.fire_time
+        })
+        .collect();
+    assert_eq!(fire_times.len(), 3);
+    assert_eq!(fire_times[1], fire_times[0] + 15);
+    assert_eq!(fire_times[2], fire_times[1] + 15);
+
+    // The instances of a supervised task never overlap.
+    let delay_timer = delay_timer_builder().build();
+    let run_times = Arc::new(AtomicUsize::new(0));
+    let running = Arc::new(AtomicUsize::new(0));
+    let overlapped = Arc::new(AtomicUsize::new(0));
+
+    let body = {
+        let (run_times, running, overlapped) =
+            (run_times.clone(), running.clone(), overlapped.clone());
+        move || {
+            let (run_times, running, overlapped) =
+                (run_times.clone(), running.clone(), overlapped.clone());
+            async move {
+                run_times.fetch_add(1, Release);
+                if running.fetch_add(1, Release) > 0 {
+                    overlapped.fetch_add(1, Release);
+                }
+                Timer::after(Duration::from_secs(1)).await;
+                running.fetch_sub(1, Release);
+            }
+        }
+    };
+    let task = task_builder
+        .set_frequency(Frequency::KeepAlive {
+            restart_delay: Duration::from_secs(1),
+            max_restarts_per_hour: 2,
+        })
+        .spawn_async_routine(body)?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(15), || {
+        run_times.load(Acquire) == 3
+    }));
+    assert_eq!(overlapped.load(Acquire), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_serial_queue() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let run_order = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicUsize::new(0));
+    let overlapped = Arc::new(AtomicUsize::new(0));
+
+    // Due one second apart, each of them runs longer than that.
+    for task_id in 1..=3 {
+        let (run_order, running, overlapped) =
+            (run_order.clone(), running.clone(), overlapped.clone());
+        let body = move || {
+            let (run_order, running, overlapped) =
+                (run_order.clone(), running.clone(), overlapped.clone());
+            async move {
+                run_order.lock().unwrap().push(task_id);
+                if running.fetch_add(1, Release) > 0 {
+                    overlapped.fetch_add(1, Release);
+                }
+                Timer::after(Duration::from_secs(2)).await;
+                running.fetch_sub(1, Release);
+            }
+        };
+
+        let task = TaskBuilder::default()
+            .set_frequency_once_by_seconds(task_id)
+            .set_task_id(task_id)
+            .set_serial_queue("db-maintenance")
+            .spawn_async_routine(body)?;
+        delay_timer.add_task(task)?;
+    }
+
+    assert!(wait_until(Duration::from_secs(15), || {
+        run_order.lock().unwrap().len() == 3
+    }));
+    assert_eq!(*run_order.lock().unwrap(), vec![1, 2, 3]);
+    assert_eq!(overlapped.load(Acquire), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_advance_jump_queue() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let run_order = Arc::new(Mutex::new(Vec::new()));
+
+    // The first one holds the queue for a while, the second one is due meanwhile,
+    // the third one isn't due before long.
+    for (task_id, seconds) in [(1, 1), (2, 2), (3, 3600)] {
+        let run_order = run_order.clone();
+        let body = move || {
+            let run_order = run_order.clone();
+            async move {
+                run_order.lock().unwrap().push(task_id);
+                Timer::after(Duration::from_secs(3)).await;
+            }
+        };
+
+        let task = TaskBuilder::default()
+            .set_frequency_once_by_seconds(seconds)
+            .set_task_id(task_id)
+            .set_serial_queue("incident")
+            .spawn_async_routine(body)?;
+        delay_timer.add_task(task)?;
+    }
+
+    assert!(wait_until(Duration::from_secs(5), || {
+        !run_order.lock().unwrap().is_empty()
+    }));
+    // The fire of the second one is held back by then.
+    park_timeout(Duration::from_millis(1500));
+    assert_eq!(*run_order.lock().unwrap(), vec![1]);
+
+    // The manual run is behind the running instance only.
+    let advance_outcome =
+        delay_timer.advance_task_with_options(3, AdvanceOptions::default().jump_queue(true))?;
+    assert_eq!(advance_outcome, AdvanceOutcome::QueuedBehind(1));
+
+    assert!(wait_until(Duration::from_secs(15), || {
+        run_order.lock().unwrap().len() == 3
+    }));
+    assert_eq!(*run_order.lock().unwrap(), vec![1, 3, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_task_semaphore() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let semaphore = TaskSemaphore::new(2);
+    let runs = Arc::new(AtomicUsize::new(0));
+    let running = Arc::new(AtomicUsize::new(0));
+    let most_running = Arc::new(AtomicUsize::new(0));
+
+    // Due at once, each of them runs for two seconds.
+    for task_id in 1..=3 {
+        let (runs, running, most_running) = (runs.clone(), running.clone(), most_running.clone());
+        let body = move || {
+            let (runs, running, most_running) =
+                (runs.clone(), running.clone(), most_running.clone());
+            async move {
+                runs.fetch_add(1, Release);
+                most_running.fetch_max(running.fetch_add(1, Release) + 1, Release);
+                Timer::after(Duration::from_secs(2)).await;
+                running.fetch_sub(1, Release);
+            }
+        };
+
+        let task = TaskBuilder::default()
+            .set_frequency_once_by_seconds(1)
+            .set_task_id(task_id)
+            .set_semaphore(semaphore.clone())
+            .spawn_async_routine(body)?;
+        delay_timer.add_task(task)?;
+    }
+
+    // The third one waits for a permit.
+    assert!(wait_until(Duration::from_secs(5), || runs.load(Acquire) == 2));
+    assert_eq!(semaphore.available_permits(), 0);
+
+    assert!(wait_until(Duration::from_secs(10), || {
+        runs.load(Acquire) == 3 && running.load(Acquire) == 0
+    }));
+    assert_eq!(most_running.load(Acquire), 2);
+    assert_eq!(semaphore.available_permits(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_instance_heartbeat() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    // One beats while it runs, the other hangs without beating.
+    let beating = TaskBuilder::default()
+        .set_frequency_once_by_seconds(1)
+        .set_task_id(1)
+        .set_heartbeat(Duration::from_secs(1), StallPolicy::Timeout)
+        .spawn_async_routine_with_context(|context: TaskContext| async move {
+            for _ in 0..15 {
+                context.heartbeat();
+                Timer::after(Duration::from_millis(200)).await;
+            }
+        })?;
+    let hung = TaskBuilder::default()
+        .set_frequency_once_by_seconds(1)
+        .set_task_id(2)
+        .set_maximum_running_time(30)
+        .set_heartbeat(Duration::from_secs(1), StallPolicy::Timeout)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(30)).await;
+        })?;
+    let beating_chain = delay_timer.insert_task(beating)?;
+    let hung_chain = delay_timer.insert_task(hung)?;
+
+    let beating_instance = beating_chain.next_with_wait()?;
+    let hung_instance = hung_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(8), || {
+        beating_instance.get_state() != instance::RUNNING
+            && hung_instance.get_state() != instance::RUNNING
+    }));
+
+    assert_eq!(beating_instance.get_state(), instance::COMPLETED);
+    // Timed out long before its `maximum_running_time`.
+    assert_eq!(hung_instance.get_state(), instance::TIMEOUT);
+
+    Ok(())
+}
+
+#[test]
+fn test_complete_no_work() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    // Every other run finds nothing to do.
+    let run_times = Arc::new(AtomicUsize::new(0));
+    let task = {
+        let run_times = run_times.clone();
+        TaskBuilder::default()
+            .set_frequency_count_down_by_seconds(1, 4)
+            .set_task_id(1)
+            .set_exclude_noop_durations(true)
+            .spawn_async_routine_with_context(move |context: TaskContext| {
+                let run_times = run_times.clone();
+                async move {
+                    if run_times.fetch_add(1, Release) % 2 == 0 {
+                        context.complete_no_work();
+                    }
+                }
+            })?
+    };
+    let chain = delay_timer.insert_task(task)?;
+
+    let noop_instance = chain.next_with_wait()?;
+    let instance = chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(noop_instance.get_state(), instance::COMPLETED_NOOP);
+
+    let task_stats = delay_timer
+        .task_stats(1)
+        .expect("The task is in the timer.");
+    assert_eq!(task_stats.completed_instances, 2);
+    assert_eq!(task_stats.noop_instances, 1);
+    assert_eq!(delay_timer.task_stats(2), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_yield_every() -> anyhow::Result<()> {
+    use delay_timer::yield_every;
+    use std::sync::atomic::AtomicBool;
+
+    let delay_timer = delay_timer_builder().build();
+
+    // A busy loop on the runtime winds down once its instance is cancelled.
+    let wound_down = Arc::new(AtomicBool::new(false));
+    let task = {
+        let wound_down = wound_down.clone();
+        TaskBuilder::default()
+            .set_frequency_once_by_seconds(1)
+            .set_task_id(1)
+            .spawn_async_routine_with_context(move |context: TaskContext| {
+                let wound_down = wound_down.clone();
+                async move {
+                    let cancellation_token = context.get_cancellation_token();
+                    let mut sum: u64 = 0;
+                    loop {
+                        if yield_every!(1000, cancellation_token).is_err() {
+                            wound_down.store(true, Release);
+                            break;
+                        }
+                        sum = sum.wrapping_add(1);
+                    }
+                }
+            })?
+    };
+    let chain = delay_timer.insert_task(task)?;
+
+    let instance = chain.next_with_wait()?;
+    park_timeout(Duration::from_millis(500));
+    assert_eq!(
+        instance.cancel_graceful(Duration::from_secs(2))?,
+        CancellationPath::Cooperative
+    );
+    assert!(wound_down.load(Acquire));
+
+    Ok(())
+}
+
+#[test]
+fn test_readiness_check() -> anyhow::Result<()> {
+    use std::sync::atomic::AtomicBool;
+
+    let delay_timer = delay_timer_builder().build();
+
+    // The fires are skipped until the check passes.
+    let ready = Arc::new(AtomicBool::new(false));
+    let skipped_run_times = Arc::new(AtomicUsize::new(0));
+    let task = {
+        let (ready, skipped_run_times) = (ready.clone(), skipped_run_times.clone());
+        TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(1)
+            .set_readiness_check(
+                move || {
+                    let ready = ready.clone();
+                    async move { ready.load(Acquire) }
+                },
+                Readiness {
+                    timeout: Duration::from_secs(1),
+                    policy: NotReadyPolicy::Skip,
+                },
+            )
+            .spawn_async_routine(move || {
+                let skipped_run_times = skipped_run_times.clone();
+                async move {
+                    skipped_run_times.fetch_add(1, Release);
+                }
+            })?
+    };
+    delay_timer.add_task(task)?;
+
+    // The fire waits for the check to pass, it's retried every second.
+    let check_times = Arc::new(AtomicUsize::new(0));
+    let deferred_run_times = Arc::new(AtomicUsize::new(0));
+    let task = {
+        let (check_times, deferred_run_times) = (check_times.clone(), deferred_run_times.clone());
+        TaskBuilder::default()
+            .set_frequency_once_by_seconds(1)
+            .set_task_id(2)
+            .set_readiness_check(
+                move || {
+                    let check_times = check_times.clone();
+                    async move { check_times.fetch_add(1, Release) >= 2 }
+                },
+                Readiness {
+                    timeout: Duration::from_secs(1),
+                    policy: NotReadyPolicy::Defer {
+                        retry_after: Duration::from_secs(1),
+                    },
+                },
+            )
+            .spawn_async_routine(move || {
+                let deferred_run_times = deferred_run_times.clone();
+                async move {
+                    deferred_run_times.fetch_add(1, Release);
+                }
+            })?
+    };
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_millis(3500));
+    assert_eq!(skipped_run_times.load(Acquire), 0);
+
+    ready.store(true, Release);
+    assert!(wait_until(Duration::from_secs(8), || {
+        skipped_run_times.load(Acquire) >= 1 && deferred_run_times.load(Acquire) == 1
+    }));
+    assert_eq!(check_times.load(Acquire), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_next_fire_after() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.next_fire_after(1, Utc::now()).is_some()
+    }));
+
+    // The timer agrees with the fires it takes.
+    let now = Utc::now();
+    let next_fire = delay_timer.next_fire_after(1, now).unwrap();
+    assert!(next_fire > now && next_fire <= now + chrono::Duration::seconds(60));
+    assert_eq!(
+        delay_timer.next_fire_after(1, next_fire),
+        Some(next_fire + chrono::Duration::seconds(60))
+    );
+
+    // A paused task has no fire.
+    delay_timer.remove_task_delayed(1, Duration::from_secs(30))?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.next_fire_after(1, now).is_none()
+    }));
+    assert_eq!(delay_timer.next_fire_after(2, now), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_startup_stagger() -> anyhow::Result<()> {
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder()
+        .startup_stagger(Duration::from_secs(8))
+        .build();
+    let fire_times = Arc::new(Mutex::new(Vec::new()));
+
+    // All of them are due at once.
+    for task_id in 1..=32 {
+        let fire_times = fire_times.clone();
+        let task = TaskBuilder::default()
+            .set_frequency_once_by_seconds(1)
+            .set_task_id(task_id)
+            .spawn_routine(move || fire_times.lock().unwrap().push(timestamp()))?;
+        delay_timer.add_task(task)?;
+    }
+
+    assert!(wait_until(Duration::from_secs(15), || {
+        fire_times.lock().unwrap().len() == 32
+    }));
+    let fire_times = fire_times.lock().unwrap();
+    assert!(fire_times.iter().collect::<HashSet<_>>().len() > 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_dispatch_compensation() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let delay_timer = delay_timer_builder().build();
+    let delays = Arc::new(Mutex::new(Vec::new()));
+
+    let body = {
+        let delays = delays.clone();
+        move |context: TaskContext| {
+            let started_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            let scheduled_at = Duration::from_secs(context.scheduled_time());
+            delays
+                .lock()
+                .unwrap()
+                .push(started_at.as_millis() as i128 - scheduled_at.as_millis() as i128);
+        }
+    };
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_dispatch_compensation(Duration::from_millis(200))
+        .spawn_routine_with_context(body)?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(8), || {
+        delays.lock().unwrap().len() >= 3
+    }));
+    let delays = delays.lock().unwrap();
+    // The instances start right at the scheduled second.
+    assert!(delays.iter().all(|delay| (0..100).contains(delay)));
+
+    Ok(())
+}
+
+#[test]
+fn test_flexible_window() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let runs = Arc::new(Mutex::new(Vec::new()));
+
+    // The window is wider than the interval of the task.
+    let body = {
+        let runs = runs.clone();
+        move |context: TaskContext| {
+            runs.lock()
+                .unwrap()
+                .push((timestamp(), context.scheduled_time()));
+        }
+    };
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(2)
+        .set_task_id(1)
+        .set_flexible_window(Duration::from_secs(10))
+        .spawn_routine_with_context(body)?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(15), || {
+        runs.lock().unwrap().len() >= 4
+    }));
+    let runs = runs.lock().unwrap();
+    // Each fire runs once, deferred within its window, not in a burst with the next ones.
+    assert!(runs.windows(2).all(|window| window[0].0 < window[1].0));
+    assert!(runs.windows(2).all(|window| window[0].1 < window[1].1));
+    assert!(runs
+        .iter()
+        .all(|&(started_at, scheduled_time)| started_at <= scheduled_time + 10));
+
+    Ok(())
+}
+
+#[test]
+fn test_high_precision() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let delay_timer = delay_timer_builder().high_precision(true).build();
+    let delays = Arc::new(Mutex::new(Vec::new()));
+
+    let body = {
+        let delays = delays.clone();
+        move |context: TaskContext| {
+            let started_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            let scheduled_at = Duration::from_secs(context.scheduled_time());
+            delays
+                .lock()
+                .unwrap()
+                .push(started_at.as_millis() as i128 - scheduled_at.as_millis() as i128);
+        }
+    };
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(2)
+        .set_task_id(1)
+        .spawn_routine_with_context(body)?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(10), || {
+        delays.lock().unwrap().len() >= 2
+    }));
+    let delays = delays.lock().unwrap();
+    assert!(delays.iter().all(|delay| (0..10).contains(delay)));
+
+    Ok(())
+}
+
+// Counts the instances dropped by an abort.
+struct DropCounter(Arc<AtomicUsize>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Release);
+    }
+}
+
+#[test]
+fn test_shutdown_shared_runtime() -> anyhow::Result<()> {
+    let runtime = Arc::new(tokio::runtime::Runtime::new()?);
+    let delay_timer = DelayTimerBuilder::default()
+        .tokio_runtime_shared_by_custom(runtime.clone())
+        .build();
+
+    // A future of the application on the same runtime.
+    let heartbeats = Arc::new(AtomicUsize::new(0));
+    let beats = heartbeats.clone();
+    runtime.spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            beats.fetch_add(1, Release);
+        }
+    });
+
+    let dropped_instances = Arc::new(AtomicUsize::new(0));
+    let dropped = dropped_instances.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_maximum_parallel_runnable_num(1)
+        .spawn_async_routine(move || {
+            let drop_counter = DropCounter(dropped.clone());
+            async move {
+                let _drop_counter = drop_counter;
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+        })?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(2)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+
+    task_instance_chain.next_with_wait()?;
+    delay_timer.remove_task_delayed(2, Duration::from_secs(60))?;
+    // A paused task has no fire.
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.next_fire_after(2, Utc::now()).is_none()
+    }));
+
+    let shutdown_report = delay_timer.shutdown()?;
+    assert_eq!(shutdown_report.aborted_instances, 1);
+    // The recycling workers and the delayed removal.
+    assert_eq!(shutdown_report.aborted_futures, 3);
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        dropped_instances.load(Acquire) == 1
+    }));
+
+    let beats = heartbeats.load(Acquire);
+    assert!(wait_until(Duration::from_secs(2), || {
+        heartbeats.load(Acquire) > beats
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn test_scope() -> anyhow::Result<()> {
+    let fires = Arc::new(AtomicUsize::new(0));
+    let dropped_instances = Arc::new(AtomicUsize::new(0));
+
+    let fire_counter = fires.clone();
+    let dropped = dropped_instances.clone();
+    delay_timer_builder().scope(|delay_timer| -> anyhow::Result<()> {
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(1)
+            .spawn_async_routine(move || {
+                let drop_counter = DropCounter(dropped.clone());
+                fire_counter.fetch_add(1, Release);
+                async move {
+                    let _drop_counter = drop_counter;
+                    Timer::after(Duration::from_secs(60)).await;
+                }
+            })?;
+        delay_timer.add_task(task)?;
+
+        assert!(wait_until(Duration::from_secs(5), || fires.load(Acquire) >= 1));
+        Ok(())
+    })?;
+
+    // The scope is left, no task fires and the running instances are aborted.
+    let fired = fires.load(Acquire);
+    assert!(wait_until(Duration::from_secs(2), || {
+        dropped_instances.load(Acquire) == fired
+    }));
+
+    park_timeout(Duration::from_millis(2000));
+    assert_eq!(fires.load(Acquire), fired);
+
+    Ok(())
+}
+
+#[test]
+fn test_tenant_quota() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder()
+        .tenant_quota("acme", MaxTasks(2), MaxConcurrentInstances(1))
+        .build();
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+
+    let build_task = |task_id| {
+        let (running, max_running) = (running.clone(), max_running.clone());
+        TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(1)
+            .set_task_id(task_id)
+            .set_tenant("acme")
+            .spawn_async_routine(move || {
+                let (running, max_running) = (running.clone(), max_running.clone());
+                async move {
+                    let now_running = running.fetch_add(1, Release) + 1;
+                    max_running.fetch_max(now_running, Release);
+                    Timer::after(Duration::from_millis(1500)).await;
+                    running.fetch_sub(1, Release);
+                }
+            })
+    };
+
+    delay_timer.add_task(build_task(1)?)?;
+    delay_timer.add_task(build_task(2)?)?;
+    assert!(matches!(
+        delay_timer.add_task(build_task(3)?),
+        Err(TaskError::TenantQuotaExceeded(_))
+    ));
+
+    park_timeout(Duration::from_millis(4500));
+
+    // The two tasks of the tenant never run at once.
+    assert_eq!(max_running.load(Acquire), 1);
+    let tenant_usage = delay_timer.tenant_usage("acme");
+    assert_eq!(tenant_usage.tasks, 2);
+    assert!(tenant_usage.running_instances <= 1);
+
+    // A removed task frees its place.
+    delay_timer.remove_task(1)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.tenant_usage("acme").tasks == 1
+    }));
+    delay_timer.add_task(build_task(3)?)?;
+
+    // Neither an update of an unknown task nor a removed registered task keeps a place.
+    let one_task_left = || {
+        wait_until(Duration::from_secs(2), || {
+            delay_timer.tenant_usage("acme").tasks == 1
+        })
+    };
+    delay_timer.remove_task(3)?;
+    assert!(one_task_left());
+    delay_timer.update_task(build_task(7)?)?;
+    assert!(one_task_left());
+    delay_timer.register_task(build_task(8)?)?;
+    delay_timer.remove_task(8)?;
+    assert!(one_task_left());
+    delay_timer.add_task(build_task(4)?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_audit_log() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().enable_audit_log(16).build();
+    let operator = delay_timer.with_actor("alice");
+
+    let build_task = |seconds| {
+        TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(seconds)
+            .set_task_id(1)
+            .spawn_async_routine(|| async {})
+    };
+    operator.add_task(build_task(60)?)?;
+    operator.update_task(build_task(30)?)?;
+    delay_timer.remove_task(1)?;
+
+    let audit_log = delay_timer.audit_log();
+    let operations: Vec<_> = audit_log
+        .iter()
+        .map(|audit_record| (audit_record.operation, audit_record.actor.as_deref()))
+        .collect();
+    assert_eq!(
+        operations,
+        vec![
+            (AuditOperation::Add, Some("alice")),
+            (AuditOperation::Update, Some("alice")),
+            (AuditOperation::Remove, None),
+        ]
+    );
+
+    // Each operation carries the configuration the task had before.
+    assert!(audit_log[0].previous_config.is_none());
+    assert_eq!(audit_log[1].previous_config, audit_log[0].config);
+    assert_eq!(audit_log[2].previous_config, audit_log[1].config);
+    assert_ne!(audit_log[0].config, audit_log[1].config);
+
+    Ok(())
+}
+
+#[test]
+fn test_job_state() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let runs = Arc::new(AtomicU64::new(0));
+    let watermark = Arc::new(AtomicU64::new(0));
+
+    let (runs_ref, watermark_ref) = (runs.clone(), watermark.clone());
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_routine_with_context(move |context: TaskContext| {
+            let previous = context
+                .state_get("watermark")
+                .unwrap()
+                .map_or(0, |watermark| watermark.parse::<u64>().unwrap());
+            context.state_set("watermark", (previous + 1).to_string()).unwrap();
+
+            runs_ref.fetch_add(1, Release);
+            watermark_ref.store(previous + 1, Release);
+        })?;
+    delay_timer.add_task(task)?;
+
+    // The state is scoped to the task.
+    let foreign_state = Arc::new(AtomicUsize::new(0));
+    let foreign_state_ref = foreign_state.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(2)
+        .spawn_routine_with_context(move |context: TaskContext| {
+            if context.state_get("watermark").unwrap().is_some() {
+                foreign_state_ref.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(6), || runs.load(Acquire) >= 2));
+    assert_eq!(watermark.load(Acquire), runs.load(Acquire));
+    assert_eq!(foreign_state.load(Acquire), 0);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_process_resource_limits() -> anyhow::Result<()> {
+    use delay_timer::utils::{ProcessTaskBuilder, ResourceLimits};
+    use smol::process::{Child, Command};
+
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.set_resource_limits(ResourceLimits {
+        nice: Some(5),
+        address_space: Some(1 << 30),
+        cpu_time: Some(7),
+        cgroup: None,
+    });
+
+    let output = smol::block_on(async {
+        let mut childs = process_task_builder
+            .parse_and_run::<Child, Command>("cat /proc/self/limits")
+            .await?;
+        let child = childs.pop_back().expect("Without child.");
+        child.wait_with_output().await
+    })?;
+    let limits = String::from_utf8(output.stdout)?;
+
+    let limit_of = |name: &str| {
+        limits
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line[name.len()..].split_whitespace().next())
+            .map(str::to_string)
+    };
+    assert_eq!(limit_of("Max cpu time").as_deref(), Some("7"));
+    assert_eq!(limit_of("Max address space").as_deref(), Some("1073741824"));
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_process_run_as() -> anyhow::Result<()> {
+    use delay_timer::utils::ProcessTaskBuilder;
+    use smol::process::{Child, Command};
+
+    // The current user and group, switching to them is always permitted.
+    let status = std::fs::read_to_string("/proc/self/status")?;
+    let id_of = |field: &str| {
+        status
+            .lines()
+            .find(|line| line.starts_with(field))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .map(str::to_string)
+            .expect("Missing id.")
+    };
+    let (uid, gid) = (id_of("Uid:"), id_of("Gid:"));
+
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.run_as(&uid, &gid);
+    let output = smol::block_on(async {
+        let mut childs = process_task_builder
+            .parse_and_run::<Child, Command>("id -u")
+            .await?;
+        let child = childs.pop_back().expect("Without child.");
+        child.wait_with_output().await
+    })?;
+    assert_eq!(String::from_utf8(output.stdout)?.trim(), uid);
+
+    process_task_builder.run_as("no-such-user-of-delay-timer", &gid);
+    let childs = smol::block_on(process_task_builder.parse_and_run::<Child, Command>("id -u"));
+    assert!(childs.is_err());
+
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "json-result"))]
+#[test]
+fn test_process_json_result() -> anyhow::Result<()> {
+    use delay_timer::utils::{JsonResult, ProcessTaskBuilder};
+
+    let delay_timer = delay_timer_builder().build();
+
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.set_json_result(JsonResult::Delimited("---".to_string()));
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_process(r#"printf 'log\n---\n{"rows":3}\n'"#, &process_task_builder)?;
+
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(instance.result(), Some(serde_json::json!({ "rows": 3 })));
+
+    // Only the last non-empty line is parsed.
+    assert_eq!(JsonResult::LastLine.parse(b"{\"rows\": 3}\nlog\n"), None);
+    assert_eq!(
+        JsonResult::LastLine.parse(b"log\n[1, 2]\n\n"),
+        Some(serde_json::json!([1, 2]))
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_process_output_lines() -> anyhow::Result<()> {
+    use delay_timer::utils::ProcessTaskBuilder;
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+
+    let lines_ref = lines.clone();
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.set_on_output_line(move |line| {
+        lines_ref.lock().unwrap().push(line.to_string());
+    });
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_process(r"printf 'started\r\n50%%\ndone'", &process_task_builder)?;
+
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(*lines.lock().unwrap(), vec!["started", "50%", "done"]);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_process_detach() -> anyhow::Result<()> {
+    use delay_timer::utils::ProcessTaskBuilder;
+
+    let delay_timer = delay_timer_builder().build();
+
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.detach();
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_process("sleep 30", &process_task_builder)?;
+
+    // The instance completes without waiting for the process.
+    let task_instance_chain = delay_timer.insert_task(task)?;
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+
+    // It leads its own session, and outlives the shutdown.
+    let pid = instance.pid().expect("Without pid.");
+    delay_timer.shutdown()?;
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid))?;
+    let session = stat
+        .rsplit(')')
+        .next()
+        .and_then(|fields| fields.split_whitespace().nth(3))
+        .expect("Missing session.");
+    assert_eq!(session, pid.to_string());
+
+    std::process::Command::new("kill")
+        .arg(pid.to_string())
+        .status()?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_process_signal() -> anyhow::Result<()> {
+    use delay_timer::utils::ProcessTaskBuilder;
+
+    let delay_timer = delay_timer_builder().build();
 
     let task = TaskBuilder::default()
-        .set_frequency_count_down_by_seconds(1, 4)
+        .set_frequency_count_down_by_seconds(1, 1)
         .set_task_id(1)
-        .set_maximum_parallel_runnable_num(3)
-        .spawn_async_routine(body)?;
+        .spawn_process("sleep 30", &ProcessTaskBuilder::default())?;
 
     let task_instance_chain = delay_timer.insert_task(task)?;
+    let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance
+            .pid()
+            .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/cmdline", pid)).ok())
+            .is_some_and(|cmdline| cmdline.starts_with("sleep"))
+    }));
+
+    // SIGTERM, the instance is over once its process is.
+    instance.signal(15)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(instance.pid(), None);
+    assert!(matches!(
+        instance.signal(15),
+        Err(TaskInstanceError::WithoutProcess)
+    ));
 
-    // Get the first task instance.
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "status-report"))]
+#[test]
+fn test_process_single_instance_guard() -> anyhow::Result<()> {
+    use delay_timer::utils::ProcessTaskBuilder;
+
+    let pidfile = std::env::temp_dir().join("delay_timer_single_instance_guard.pid");
+    // The test process is alive, its pid holds the guard.
+    std::fs::write(&pidfile, std::process::id().to_string())?;
+
+    let delay_timer = delay_timer_builder().enable_event_log(64).build();
+    let start_time = timestamp();
+
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder.single_instance_guard(&pidfile);
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_process("echo guarded", &process_task_builder)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer
+            .replay_events(start_time)
+            .iter()
+            .any(|e| matches!(e, PublicEvent::SkippedBySingleInstanceGuard(1)))
+    }));
+
+    // Once the pidfile is gone, the fires run, and their pidfile is removed once they're over.
+    std::fs::remove_file(&pidfile)?;
     let instance = task_instance_chain.next_with_wait()?;
+    delay_timer.remove_task(1)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED && !pidfile.exists()
+    }));
 
-    // The task was still running when the instance was first obtained.
-    assert_eq!(instance.get_state(), instance::RUNNING);
+    Ok(())
+}
 
-    // Unsolicited mission cancellation.
-    instance.cancel_with_wait()?;
+#[cfg(unix)]
+#[test]
+fn test_process_secret_env() -> anyhow::Result<()> {
+    use delay_timer::utils::secrets::FileSecrets;
+    use delay_timer::utils::{ProcessTaskBuilder, Shell};
+    use std::sync::Mutex;
+
+    let directory = std::env::temp_dir().join("delay_timer_secret_env");
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(directory.join("api_token"), "s3cr3t\n")?;
+
+    let delay_timer = delay_timer_builder()
+        .set_secrets_provider(FileSecrets::new(&directory))
+        .build();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+
+    let lines_ref = lines.clone();
+    let mut process_task_builder = ProcessTaskBuilder::default();
+    process_task_builder
+        .set_shell(Shell::Sh)
+        .set_secret_env("API_TOKEN", SecretString::new("api_token"))
+        .set_on_output_line(move |line| {
+            lines_ref.lock().unwrap().push(line.to_string());
+        });
+    // Only the reference is part of the task.
+    assert!(!format!("{:?}", process_task_builder).contains("s3cr3t"));
 
-    assert_eq!(instance.get_state(), instance::CANCELLED);
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_process(r#"printf '%s' "$API_TOKEN""#, &process_task_builder)?;
 
-    // Get the second task instance.
+    let task_instance_chain = delay_timer.insert_task(task)?;
     let instance = task_instance_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        instance.get_state() == instance::COMPLETED
+    }));
+    assert_eq!(*lines.lock().unwrap(), vec!["s3cr3t"]);
 
-    // Just got the instance when it was still running.
-    assert_eq!(instance.get_state(), instance::RUNNING);
+    Ok(())
+}
 
-    // The task execution is completed.
-    park_timeout(Duration::from_secs(1));
+#[test]
+#[cfg(unix)]
+fn test_process_shell_and_quoting() -> anyhow::Result<()> {
+    use delay_timer::utils::{shell_quote, ProcessTaskBuilder, Shell};
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let lines = Arc::new(Mutex::new(Vec::new()));
+
+    // A path with spaces and quotes, passed as a single argument.
+    let dir = std::env::temp_dir().join("delay timer \"quoted\" 'dir'");
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join("a b.txt");
+    std::fs::write(&file, "quoted\n")?;
+
+    let lines_ref = lines.clone();
+    let mut quoted_builder = ProcessTaskBuilder::default();
+    quoted_builder.set_on_output_line(move |line| {
+        lines_ref.lock().unwrap().push(line.to_string());
+    });
+    let command = shell_quote(["cat", file.to_str().unwrap()]);
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(1)
+        .spawn_process(&command, &quoted_builder)?;
+    let quoted_chain = delay_timer.insert_task(task)?;
+
+    let lines_ref = lines.clone();
+    let mut shell_builder = ProcessTaskBuilder::default();
+    shell_builder
+        .set_shell(Shell::Sh)
+        .set_on_output_line(move |line| {
+            lines_ref.lock().unwrap().push(line.to_string());
+        });
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 1)
+        .set_task_id(2)
+        .spawn_process("echo shell | tr s S && echo $((1 + 1))", &shell_builder)?;
+    let shell_chain = delay_timer.insert_task(task)?;
+
+    let quoted_instance = quoted_chain.next_with_wait()?;
+    let shell_instance = shell_chain.next_with_wait()?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        quoted_instance.get_state() == instance::COMPLETED
+            && shell_instance.get_state() == instance::COMPLETED
+    }));
+    std::fs::remove_dir_all(&dir)?;
+
+    let mut lines = lines.lock().unwrap().clone();
+    lines.sort();
+    assert_eq!(lines, vec!["2", "Shell", "quoted"]);
 
-    // This should be the completed state.
-    assert_eq!(instance.get_state(), instance::COMPLETED);
+    Ok(())
+}
+
+#[test]
+fn test_health() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(3)).await;
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.health().running_instances >= 1
+    }));
+
+    let health = delay_timer.health();
+    assert!(health.timer_core_alive);
+    assert_eq!(health.tasks_registered, 1);
+    assert!(health.running_instances >= 1);
+    assert!(health.wheel_lag <= 1);
 
     Ok(())
 }
 
-#[tokio::test]
-async fn test_instance_timeout_state() -> anyhow::Result<()> {
-    let delay_timer = DelayTimer::new();
+#[test]
+fn test_view() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let view = delay_timer.view();
+    assert!(view.snapshot().tasks.is_empty());
 
-    let body = || async {
-        println!("test_instance_timeout_state");
-        Timer::after(Duration::from_secs(3)).await;
-        println!("test_instance_timeout_state end.");
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        view.snapshot()
+            .task(1)
+            .is_some_and(|task_view| task_view.stats.completed_instances >= 1)
+    }));
+
+    // The view is read from another thread, the snapshot it holds doesn't change under it.
+    let snapshot = std::thread::spawn(move || view.snapshot()).join().unwrap();
+    assert!(snapshot.version > 0);
+    assert_eq!(snapshot.health.tasks_registered, 1);
+    let task_view = snapshot.task(1).expect("Without task 1.");
+    assert!(task_view.stats.completed_instances >= 1);
+    assert!(task_view.last_finish_time.is_some());
+
+    delay_timer.remove_task(1)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.view().snapshot().task(1).is_none()
+    }));
+    assert!(snapshot.task(1).is_some());
+
+    Ok(())
+}
+
+// A store shared by two instances, as a database would be shared by two processes.
+#[derive(Clone, Default)]
+struct SharedStateStore(Arc<MemoryStateStore>);
+
+impl StateStore for SharedStateStore {
+    fn get(&self, task_id: u64, key: &str) -> anyhow::Result<Option<String>> {
+        self.0.get(task_id, key)
+    }
+
+    fn set(&self, task_id: u64, key: &str, value: String) -> anyhow::Result<()> {
+        self.0.set(task_id, key, value)
+    }
+}
+
+#[test]
+fn test_handoff() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    let state_store = SharedStateStore::default();
+    let runs = Arc::new(Mutex::new(Vec::new()));
+    let task = || {
+        let runs = runs.clone();
+        TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(2)
+            .set_task_id(1)
+            .set_run_on_startup(true)
+            .spawn_routine(move || runs.lock().unwrap().push(timestamp()))
+    };
+
+    let old_delay_timer = delay_timer_builder()
+        .set_state_store(state_store.clone())
+        .build();
+    old_delay_timer.add_task(task()?)?;
+    assert!(wait_until(Duration::from_secs(6), || {
+        runs.lock().unwrap().len() >= 2
+    }));
+
+    // The new instance is up with its tasks registered before the old one hands off.
+    let new_delay_timer = delay_timer_builder().set_state_store(state_store).build();
+    new_delay_timer.register_task(task()?)?;
+
+    let handoff_snapshot = old_delay_timer.hand_off()?;
+    let handed_off_runs = runs.lock().unwrap().len();
+    assert!(handed_off_runs >= 2);
+    let next_fire = match handoff_snapshot.next_fires.as_slice() {
+        &[(1, next_fire)] => next_fire,
+        next_fires => panic!("Unexpected next fires {:?}.", next_fires),
+    };
+
+    // The old instance doesn't fire anymore, the new one goes on from the next fire.
+    assert_eq!(new_delay_timer.take_over()?, vec![1]);
+    assert!(wait_until(Duration::from_secs(6), || {
+        runs.lock().unwrap().len() > handed_off_runs
+    }));
+
+    let runs = runs.lock().unwrap();
+    assert!(runs.len() > handed_off_runs);
+    assert!(runs[handed_off_runs] >= next_fire);
+    assert!(runs.windows(2).all(|window| window[0] < window[1]));
+
+    Ok(())
+}
+
+#[test]
+fn test_handoff_timeout() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.health().timer_core_alive
+    }));
+
+    // The loop can't have stopped yet, it stops later on all the same.
+    let stop_time_out = delay_timer
+        .hand_off_with_timeout(Duration::ZERO)
+        .expect_err("The loop stopped at once.");
+    assert!(matches!(
+        stop_time_out.downcast_ref::<SchedulerError>(),
+        Some(SchedulerError::StopTimeOut(_))
+    ));
+    assert!(wait_until(Duration::from_secs(3), || {
+        !delay_timer.health().timer_core_alive
+    }));
+
+    let handoff_snapshot = delay_timer.hand_off_with_timeout(Duration::ZERO)?;
+    assert!(handoff_snapshot.next_fires.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_at_most_once() -> anyhow::Result<()> {
+    use std::sync::Mutex;
+
+    let state_store = SharedStateStore::default();
+    let scheduled_times = Arc::new(Mutex::new(Vec::new()));
+    let routine = {
+        let scheduled_times = scheduled_times.clone();
+        move |context: TaskContext| scheduled_times.lock().unwrap().push(context.scheduled_time())
     };
 
+    // The task is built again by the restarted process, with the same schedule.
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_repeated_by_seconds(1)
+        .set_align_to(Alignment::Minute)
+        .set_task_id(1)
+        .set_at_most_once(true);
+    let task = task_builder.spawn_routine_with_context(routine.clone())?;
+    let restarted_task = task_builder.spawn_routine_with_context(routine)?;
+
+    let delay_timer = delay_timer_builder()
+        .set_state_store(state_store.clone())
+        .build();
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(4), || {
+        !scheduled_times.lock().unwrap().is_empty()
+    }));
+
+    // It crashes right after the fire, and is restarted within the same second.
+    delay_timer.shutdown()?;
+    let fired_before_restart = scheduled_times.lock().unwrap().len();
+    assert!(fired_before_restart >= 1);
+
+    let delay_timer = delay_timer_builder().set_state_store(state_store).build();
+    delay_timer.add_task(restarted_task)?;
+    assert!(wait_until(Duration::from_secs(5), || {
+        scheduled_times.lock().unwrap().len() > fired_before_restart
+    }));
+
+    let scheduled_times = scheduled_times.lock().unwrap();
+    assert!(scheduled_times.len() > fired_before_restart);
+    assert!(scheduled_times.windows(2).all(|window| window[0] < window[1]));
+
+    Ok(())
+}
+
+#[test]
+fn test_forecast() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    for task_id in 1..=2 {
+        let task = TaskBuilder::default()
+            .set_frequency_repeated_by_seconds(2)
+            .set_task_id(task_id)
+            .set_maximum_running_time(3)
+            .spawn_async_routine(|| async {})?;
+        delay_timer.add_task(task)?;
+    }
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 2
+    }));
+
+    let forecast = delay_timer.forecast(Duration::from_secs(10));
+    assert_eq!(forecast.fires_per_second.len(), 10);
+    // Each task fires 5 times in the window, give or take the tick at its edges.
+    assert!((8..=10).contains(&forecast.total_fires()));
+    // The runs of a task overlap, until it has a p95 duration.
+    assert!(forecast.peak_concurrency().map_or(0, |(_, instances)| instances) >= 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_analyze() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
     let task = TaskBuilder::default()
-        .set_frequency_count_down_by_seconds(1, 4)
+        .set_frequency_repeated_by_seconds(2)
         .set_task_id(1)
-        .set_maximum_running_time(1)
-        .set_maximum_parallel_runnable_num(3)
-        .spawn_async_routine(body)?;
-    let task_instance_chain = delay_timer.insert_task(task)?;
+        .set_maximum_running_time(5)
+        .set_maximum_parallel_runnable_num(1)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
 
-    // Get the first task instance.
-    let instance = task_instance_chain.next_with_wait()?;
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(60)
+        .set_task_id(2)
+        .spawn_async_routine(|| async {})?;
+    delay_timer.add_task(task)?;
 
-    // The task was still running when the instance was first obtained.
-    assert_eq!(instance.get_state(), instance::RUNNING);
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 2
+    }));
+
+    assert_eq!(
+        delay_timer.analyze(),
+        vec![Diagnostic::RunsOutlastInterval {
+            task_id: 1,
+            maximum_running_time: 5,
+            interval: 2,
+            maximum_parallel_runnable_num: 1,
+        }]
+    );
 
-    // The task execution is timeout after about 3 second.
-    let timeout = Duration::from_secs(5);
-    let beginning_park = std::time::Instant::now();
+    Ok(())
+}
 
-    let mut timeout_remaining = timeout;
-    loop {
-        // may wake spuriously, So have to do it separately.
-        park_timeout(timeout_remaining);
-        let elapsed = beginning_park.elapsed();
-        if elapsed >= timeout {
-            break;
+#[derive(Default, Clone)]
+struct CountingObserver {
+    ticks: Arc<AtomicUsize>,
+    spawned: Arc<AtomicUsize>,
+    skipped: Arc<AtomicUsize>,
+}
+
+impl SchedulerObserver for CountingObserver {
+    fn on_tick_started(&self, _second_hand: u64, _timestamp: u64) {
+        self.ticks.fetch_add(1, Release);
+    }
+
+    fn on_task_skipped(&self, _task_id: u64, reason: SkipReason) {
+        assert_eq!(reason, SkipReason::MaximumParallelRunnable);
+        self.skipped.fetch_add(1, Release);
+    }
+
+    fn on_instance_spawned(&self, task_id: u64, _record_id: i64) {
+        assert_eq!(task_id, 1);
+        self.spawned.fetch_add(1, Release);
+    }
+}
+
+#[test]
+fn test_scheduler_observer() -> anyhow::Result<()> {
+    let observer = CountingObserver::default();
+    let delay_timer = delay_timer_builder()
+        .set_scheduler_observer(observer.clone())
+        .build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .set_maximum_parallel_runnable_num(1)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(60)).await;
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(6), || {
+        observer.ticks.load(Acquire) >= 3 && observer.skipped.load(Acquire) >= 1
+    }));
+    assert_eq!(observer.spawned.load(Acquire), 1);
+
+    Ok(())
+}
+
+// Task 1 runs for the tenant `acme`, task 2 is over its quota.
+#[derive(Default, Clone)]
+struct TenantQuota {
+    vetoed: Arc<AtomicUsize>,
+}
+
+impl SpawnHook for TenantQuota {
+    fn before_spawn(&self, task_meta: &TaskMeta) -> SpawnDecision {
+        match task_meta.task_id {
+            1 => SpawnDecision::SpawnWith(Arc::new(String::from("acme"))),
+            _ => {
+                self.vetoed.fetch_add(1, Release);
+                SpawnDecision::Veto
+            }
         }
-        timeout_remaining = timeout - elapsed;
     }
+}
 
-    // This should be the completed state.
-    assert_ne!(instance.get_state(), instance::RUNNING);
+#[test]
+fn test_spawn_hook() -> anyhow::Result<()> {
+    let tenant_quota = TenantQuota::default();
+    let delay_timer = delay_timer_builder()
+        .set_spawn_hook(tenant_quota.clone())
+        .build();
+    let acme_runs = Arc::new(AtomicUsize::new(0));
+    let vetoed_runs = Arc::new(AtomicUsize::new(0));
+
+    let runs = acme_runs.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(1)
+        .spawn_routine_with_context(move |context: TaskContext| {
+            if context.extension::<String>().map(String::as_str) == Some("acme") {
+                runs.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+
+    let runs = vetoed_runs.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_seconds(1)
+        .set_task_id(2)
+        .spawn_routine(move || {
+            runs.fetch_add(1, Release);
+        })?;
+    delay_timer.add_task(task)?;
+
+    assert!(wait_until(Duration::from_secs(6), || {
+        acme_runs.load(Acquire) >= 2 && tenant_quota.vetoed.load(Acquire) >= 2
+    }));
+    assert_eq!(vetoed_runs.load(Acquire), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_record_id_monotonic() -> anyhow::Result<()> {
+    // The ids out of 0..32 would collide with the bits of the other one.
+    assert!(matches!(
+        delay_timer_builder().id_generator_conf(32, 7),
+        Err(TimerBuilderError::IdGeneratorConfOutOfRange(
+            "machine_id",
+            32
+        ))
+    ));
+    assert!(matches!(
+        delay_timer_builder().id_generator_conf(3, -1),
+        Err(TimerBuilderError::IdGeneratorConfOutOfRange("node_id", -1))
+    ));
+
+    let delay_timer = delay_timer_builder().id_generator_conf(3, 7)?.build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_seconds(1, 3)
+        .set_task_id(1)
+        .spawn_async_routine(|| async {})?;
+
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    let mut last_record_id = 0;
+    for _ in 0..3 {
+        let record_id = task_instance_chain.next_with_wait()?.get_record_id();
+        assert!(record_id > last_record_id);
+        // The machine-id and node-id sit right above the 12 bits of sequence.
+        assert_eq!((record_id >> 12) & 0b11111, 7);
+        assert_eq!((record_id >> 17) & 0b11111, 3);
+        last_record_id = record_id;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_reconcile() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+    let mut registry = RoutineRegistry::default();
+    registry.register_async_routine("noop", || async {});
+
+    let config = |task_id: u64, cron_expression: &str| TaskConfig {
+        task_id,
+        cron_expression: cron_expression.into(),
+        routine: "noop".into(),
+        ..Default::default()
+    };
+
+    let report = delay_timer.reconcile(
+        vec![config(1, "@secondly"), config(2, "@minutely")],
+        &registry,
+    )?;
+    assert_eq!(report.added, vec![1, 2]);
+    assert!(report.updated.is_empty() && report.removed.is_empty());
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 2
+    }));
+
+    let report = delay_timer.reconcile(
+        vec![
+            config(1, "@secondly"),
+            config(2, "@hourly"),
+            config(3, "@daily"),
+        ],
+        &registry,
+    )?;
+    assert_eq!(report.unchanged, vec![1]);
+    assert_eq!(report.updated, vec![2]);
+    assert_eq!(report.added, vec![3]);
+
+    let report = delay_timer.reconcile(vec![config(1, "@secondly")], &registry)?;
+    assert_eq!(report.removed, vec![2, 3]);
+    assert!(!report.is_noop());
+
+    let report = delay_timer.reconcile(vec![config(1, "@secondly")], &registry)?;
+    assert!(report.is_noop());
+
+    // A missing routine fails the whole reconcile.
+    let mut unknown = config(4, "@secondly");
+    unknown.routine = "unknown".into();
+    assert!(delay_timer.reconcile(vec![unknown], &registry).is_err());
+
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 1
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn test_subscribe_instances_multi() -> anyhow::Result<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    for task_id in 1..=2 {
+        let task = TaskBuilder::default()
+            .set_frequency_count_down_by_seconds(1, 1)
+            .set_task_id(task_id)
+            .spawn_async_routine(|| async {})?;
+
+        delay_timer.add_task(task)?;
+    }
+
+    // A task that isn't in the timer can't be subscribed to.
+    assert!(matches!(
+        delay_timer.subscribe_instances_multi(&[1, 3]),
+        Err(TaskError::UnknownTask(3))
+    ));
+
+    // A task listed twice is subscribed to once.
+    let merged_instance_stream = delay_timer.subscribe_instances_multi(&[1, 2, 2])?;
+    assert_eq!(merged_instance_stream.task_ids(), vec![1, 2]);
+
+    let mut task_ids = vec![
+        merged_instance_stream.next_with_wait()?.0,
+        merged_instance_stream.next_with_wait()?.0,
+    ];
+    task_ids.sort_unstable();
+    assert_eq!(task_ids, vec![1, 2]);
 
     Ok(())
 }
@@ -238,6 +3240,160 @@ fn test_advance() -> AnyResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_advance_without_consuming_count() -> AnyResult<()> {
+    let task_id = 1;
+
+    let delay_timer = delay_timer_builder().build();
+    let share_num = Arc::new(AtomicUsize::new(0));
+    let share_num_bunshin = share_num.clone();
+
+    // Only one run is allowed by the countdown.
+    let task = TaskBuilder::default()
+        .set_frequency_count_down_by_cron_str("@hourly", 1)
+        .set_task_id(task_id)
+        .spawn_routine(move || {
+            share_num_bunshin.fetch_add(1, Release);
+        })?;
+
+    delay_timer.add_task(task)?;
+
+    // Free extra runs leave the countdown untouched.
+    let advance_options = AdvanceOptions::default().consume_count(false);
+    for i in 0..2 {
+        assert_eq!(share_num.load(Acquire), i);
+        delay_timer.advance_task_with_options(task_id, advance_options.clone())?;
+        assert!(wait_until(Duration::from_secs(3), || {
+            share_num.load(Acquire) == i + 1
+        }));
+    }
+
+    // So the counted run is still available.
+    delay_timer.advance_task(task_id)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        share_num.load(Acquire) == 3
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn test_run_task_now_with_payload() -> AnyResult<()> {
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+
+    let task = {
+        let payloads = payloads.clone();
+        TaskBuilder::default()
+            .set_frequency_count_down_by_cron_str("@hourly", 1)
+            .set_task_id(1)
+            .spawn_async_routine_with_context(move |context: TaskContext| {
+                let payloads = payloads.clone();
+                async move {
+                    let payload = context.payload().map(<[u8]>::to_vec);
+                    payloads.lock().unwrap().push(payload);
+                }
+            })?
+    };
+    delay_timer.add_task(task)?;
+
+    delay_timer.run_task_now_with_payload(1, "customer-42")?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        payloads.lock().unwrap().len() == 1
+    }));
+    // The payload doesn't stick to the next run.
+    delay_timer.advance_task(1)?;
+    assert!(wait_until(Duration::from_secs(3), || {
+        payloads.lock().unwrap().len() == 2
+    }));
+
+    assert_eq!(
+        *payloads.lock().unwrap(),
+        vec![Some(b"customer-42".to_vec()), None]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_enqueue_job() -> AnyResult<()> {
+    use std::sync::Mutex;
+
+    let delay_timer = delay_timer_builder().build();
+    let payloads = Arc::new(Mutex::new(Vec::new()));
+
+    let task = {
+        let payloads = payloads.clone();
+        TaskBuilder::default()
+            .set_frequency_repeated_by_cron_str("@yearly")
+            .set_task_id(1)
+            .spawn_async_routine_with_context(move |context: TaskContext| {
+                let payloads = payloads.clone();
+                async move {
+                    let payload = context.payload().map(<[u8]>::to_vec);
+                    payloads.lock().unwrap().push(payload);
+                }
+            })?
+    };
+    delay_timer.add_task(task)?;
+
+    // Both run, though they're due at the same time.
+    let run_at = Utc::now() + chrono::Duration::seconds(2);
+    delay_timer.enqueue(1, "customer-1", run_at)?;
+    delay_timer.enqueue(1, "customer-2", run_at)?;
+    assert!(wait_until(Duration::from_secs(1), || {
+        delay_timer
+            .enqueued_jobs(1)
+            .is_ok_and(|jobs| jobs.len() == 2)
+    }));
+    assert!(payloads.lock().unwrap().is_empty());
+
+    assert!(wait_until(Duration::from_secs(8), || {
+        payloads.lock().unwrap().len() == 2
+            && delay_timer
+                .enqueued_jobs(1)
+                .is_ok_and(|jobs| jobs.is_empty())
+    }));
+    assert_eq!(
+        *payloads.lock().unwrap(),
+        vec![Some(b"customer-1".to_vec()), Some(b"customer-2".to_vec())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_advance_outcome() -> AnyResult<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_repeated_by_cron_str("@yearly")
+        .set_task_id(1)
+        .set_maximum_parallel_runnable_num(1)
+        .spawn_async_routine(|| async {
+            Timer::after(Duration::from_secs(5)).await;
+        })?;
+    delay_timer.add_task(task)?;
+    assert!(wait_until(Duration::from_secs(2), || {
+        delay_timer.health().tasks_registered == 1
+    }));
+
+    assert_eq!(delay_timer.advance_task(1)?, AdvanceOutcome::Advanced);
+    assert!(wait_until(Duration::from_secs(3), || {
+        delay_timer.health().running_instances == 1
+    }));
+
+    // The running instance holds the only parallel slot.
+    assert_eq!(
+        delay_timer.advance_task(1)?,
+        AdvanceOutcome::RejectedByPolicy(SkipReason::MaximumParallelRunnable)
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_maximum_parallel_runnable_num() -> AnyResult<()> {
     let delay_timer = DelayTimer::new();
@@ -270,6 +3426,37 @@ async fn test_maximum_parallel_runnable_num() -> AnyResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_delay_for() -> AnyResult<()> {
+    let delay_timer = delay_timer_builder().build();
+
+    smol::block_on(async {
+        let start = std::time::Instant::now();
+        let timeouts = (0..1000).map(|i| delay_timer.delay_for(Duration::from_millis(i * 2)));
+        futures::future::join_all(timeouts).await;
+        assert!(start.elapsed() >= Duration::from_millis(1998));
+        assert!(start.elapsed() < Duration::from_secs(4));
+
+        let start = std::time::Instant::now();
+        delay_timer::delay_for(Duration::from_secs(1)).await;
+        assert!(start.elapsed() >= Duration::from_secs(1));
+
+        // A delay dropped before it's over is taken out of the wheel.
+        drop(delay_timer.delay_for(Duration::from_secs(1)));
+
+        // The code taking timeouts is written once, for any source of delays.
+        async fn time_delay(delay_source: &impl DelaySource) -> Duration {
+            let start = std::time::Instant::now();
+            delay_source.delay_for(Duration::from_millis(500)).await;
+            start.elapsed()
+        }
+        assert!(time_delay(&delay_timer).await >= Duration::from_millis(500));
+        assert!(time_delay(&SharedWheel).await >= Duration::from_millis(500));
+    });
+
+    Ok(())
+}
+
 #[test]
 fn tests_countdown() -> AnyResult<()> {
     let delay_timer = DelayTimer::new();